@@ -0,0 +1,97 @@
+//! Self-check of rendered forms against the accessibility expectations the
+//! renderer is meant to meet: every control is named, option groups are a
+//! `<fieldset>` with a `<legend>`, and the page declares its language.
+
+use scraper::{ElementRef, Html, Selector};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// Opening tag of the offending element.
+    pub element: String,
+    pub problem: &'static str,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.element, self.problem)
+    }
+}
+
+fn selector(css: &str) -> Selector {
+    Selector::parse(css).expect("selectors are valid")
+}
+
+/// The start tag of `element`, for reporting.
+fn tag(element: ElementRef) -> String {
+    let element = element.value();
+    let attrs: String = element
+        .attrs()
+        .map(|(name, value)| format!(" {name}=\"{value}\""))
+        .collect();
+    format!("<{}{attrs}>", element.name())
+}
+
+/// The `<fieldset>` around `control`, if it has a `<legend>`.
+fn named_fieldset(control: ElementRef) -> Option<ElementRef> {
+    let legend = selector(":scope > legend");
+    control
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .filter(|ancestor| ancestor.value().name() == "fieldset")
+        .find(|fieldset| fieldset.select(&legend).next().is_some())
+}
+
+/// Whether `control` is named by `aria-label(ledby)`, a `<label>` for or
+/// around it, or the legend of its fieldset.
+fn is_labeled(document: &Html, control: ElementRef) -> bool {
+    let element = control.value();
+    if element.attr("aria-label").is_some() || element.attr("aria-labelledby").is_some() {
+        return true;
+    }
+    if let Some(id) = element.attr("id") {
+        let label = selector(&format!(r#"label[for="{}"]"#, id.replace('"', "\\\"")));
+        if document.select(&label).next().is_some() {
+            return true;
+        }
+    }
+    let in_label = control
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|ancestor| ancestor.value().name() == "label");
+    in_label || named_fieldset(control).is_some()
+}
+
+/// Everything found wrong with the page `html`, in document order.
+pub fn audit(html: &str) -> Vec<Finding> {
+    let document = Html::parse_document(html);
+    let mut findings = Vec::new();
+
+    let root = document.root_element();
+    if root.value().attr("lang").is_none_or(str::is_empty) {
+        findings.push(Finding {
+            element: tag(root),
+            problem: "the page does not declare its language (lang)",
+        });
+    }
+
+    let controls = selector(
+        r#"input:not([type="hidden"]):not([type="submit"]):not([type="button"]), textarea, select, output"#,
+    );
+    for control in document.select(&controls) {
+        if !is_labeled(&document, control) {
+            findings.push(Finding {
+                element: tag(control),
+                problem: "the control has no label",
+            });
+        }
+        let option = matches!(control.value().attr("type"), Some("radio" | "checkbox"));
+        if option && named_fieldset(control).is_none() {
+            findings.push(Finding {
+                element: tag(control),
+                problem: "the option is not in a <fieldset> with a <legend>",
+            });
+        }
+    }
+    findings
+}
@@ -0,0 +1,243 @@
+//! Managing individual responses from the dashboard (admin only).
+
+use axum::body::Bytes;
+use axum::extract::{Extension, Path, Query};
+use axum::http::{header, StatusCode};
+use axum::response::{Html, IntoResponse};
+use axum::routing::{get, post};
+use axum::Router;
+use itertools::Itertools;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::live::Change;
+use crate::server::{internal_error, Admin, AppState};
+use crate::signing::Signer;
+use crate::stats;
+use crate::store::Submission;
+use crate::{escape, Form, QuestionType, ResponseLimit};
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/form/:form_id/admin/responses", get(responses))
+        .route(
+            "/form/:form_id/admin/response/:submission_id/delete",
+            post(delete_response),
+        )
+}
+
+const PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+/// Characters of an answer shown in the table; the rest is in the tooltip.
+const PREVIEW_CHARS: usize = 40;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum Order {
+    Asc,
+    Desc,
+}
+
+#[derive(Deserialize)]
+struct TableQuery {
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+    /// Newest first unless `asc`.
+    order: Option<Order>,
+    /// Response just deleted, confirmed above the table.
+    deleted: Option<String>,
+}
+
+/// A table cell previewing `values`: multiple choice answers comma-joined,
+/// long answers cut off with the whole answer as tooltip, "—" if missing.
+fn preview(question: &QuestionType, values: Option<&Vec<String>>) -> String {
+    let Some(values) = values.filter(|values| !values.is_empty()) else {
+        return "—".to_string();
+    };
+    let text = match question {
+        QuestionType::MultipleChoice { .. } => values.join(", "),
+        _ => values[0].clone(),
+    };
+    if text.chars().count() <= PREVIEW_CHARS {
+        return escape(&text);
+    }
+    let cut: String = text.chars().take(PREVIEW_CHARS).collect();
+    format!(
+        r#"<span title="{}">{}…</span>"#,
+        escape(&text),
+        escape(cut.trim_end())
+    )
+}
+
+fn table_row(state: &AppState, form_id: &str, form: &Form, submission: &Submission) -> String {
+    let submitted_at = submission
+        .submitted_at
+        .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "—".to_string());
+    let cells = form
+        .questions()
+        .map(|(id, question)| {
+            format!(
+                "<td>{}</td>",
+                preview(&question.spec, submission.answers.get(&id))
+            )
+        })
+        .join("");
+    let id = escape(&submission.id);
+    format!(
+        r#"<tr><td>{submitted_at}</td>{cells}<td><a href="/api/form/{form_id}/response/{id}">JSON</a> <form method="post" action="/form/{form_id}/admin/response/{id}/delete"><input type="hidden" name="_csrf" value="{csrf}"><button type="submit">Delete</button></form></td></tr>"#,
+        csrf = delete_token(&state.signer, form_id, &submission.id)
+    )
+}
+
+/// One page of a form's responses, one row each and one column per question
+/// of the current form, with links to the full response and to delete it.
+async fn responses(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    Query(query): Query<TableQuery>,
+) -> Result<Html<String>, (StatusCode, String)> {
+    let form = state
+        .form(&form_id)
+        .ok_or((StatusCode::NOT_FOUND, format!("no form '{form_id}'")))?;
+    let limit = query.limit.unwrap_or(PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let order = query.order.unwrap_or(Order::Desc);
+    let (total, page) = state
+        .store
+        .page(&form_id, query.offset, limit, order == Order::Desc);
+
+    let link = |offset: usize, order: Order| {
+        let order = match order {
+            Order::Asc => "asc",
+            Order::Desc => "desc",
+        };
+        format!(
+            "/form/{form_id}/admin/responses?offset={offset}&amp;limit={limit}&amp;order={order}"
+        )
+    };
+    let (arrow, flipped) = match order {
+        Order::Asc => ("↑", Order::Desc),
+        Order::Desc => ("↓", Order::Asc),
+    };
+    let headers = form
+        .questions()
+        .map(|(_, question)| format!("<th>{}</th>", escape(form.text(&question.title))))
+        .join("");
+    let rows = page
+        .iter()
+        .map(|submission| table_row(&state, &form_id, &form, submission))
+        .join("");
+    let previous = (query.offset > 0).then(|| {
+        format!(
+            r#"<a href="{}" rel="prev">Previous</a>"#,
+            link(query.offset.saturating_sub(limit), order)
+        )
+    });
+    let next = (query.offset + limit < total).then(|| {
+        format!(
+            r#"<a href="{}" rel="next">Next</a>"#,
+            link(query.offset + limit, order)
+        )
+    });
+    let shown = if page.is_empty() {
+        format!("0 of {total}")
+    } else {
+        format!(
+            "{}–{} of {total}",
+            query.offset + 1,
+            query.offset + page.len()
+        )
+    };
+    let flash = query
+        .deleted
+        .map(|id| {
+            format!(
+                r#"<p class="flash" role="status">Deleted response {}.</p>"#,
+                escape(&id)
+            )
+        })
+        .unwrap_or_default();
+    let limit_note = match form.limit {
+        ResponseLimit::Unlimited => "",
+        ResponseLimit::OnePerIp => {
+            r#"<p class="note">One response per IP address (IPv6: per /64). This is best effort: people sharing a network are turned away after the first, and anyone can answer again from another network.</p>"#
+        }
+    };
+    let versions = stats::versions(&state.store.list(&form_id));
+    let version_note = if versions.is_empty() {
+        String::new()
+    } else {
+        let counts = versions
+            .iter()
+            .map(|count| format!("<code>{}</code> ({})", escape(&count.value), count.count))
+            .join(", ");
+        let warning = stats::mixed_versions_warning(&versions)
+            .map(|warning| {
+                format!(
+                    r#"<p class="warning" role="status">{}</p>"#,
+                    escape(&warning)
+                )
+            })
+            .unwrap_or_default();
+        format!(r#"<p class="versions">Form versions: {counts}</p>{warning}"#)
+    };
+    Ok(Html(format!(
+        r#"<html style="font-family=sans-serif"><head><meta charset="utf-8"><title>Responses</title><style>.responses{{overflow-x:auto}}table{{border-collapse:collapse}}th,td{{border:1px solid #ccc;padding:.2em .4em;white-space:nowrap;text-align:left}}th:first-child,td:first-child{{position:sticky;left:0;background:#fff}}td form{{display:inline}}</style></head><body><h1>{title}</h1>{flash}{limit_note}{version_note}<p>Responses {shown}</p><div class="responses"><table><thead><tr><th><a href="{sort}">Submitted {arrow}</a></th>{headers}<th></th></tr></thead><tbody>{rows}</tbody></table></div><nav>{previous} {next}</nav></body></html>"#,
+        title = escape(form.text(&form.title)),
+        sort = link(0, flipped),
+        previous = previous.unwrap_or_default(),
+        next = next.unwrap_or_default(),
+    )))
+}
+
+const DELETE_TOKEN: &str = "admin-delete";
+
+/// The `_csrf` value the delete button of a response has to post, so only the
+/// dashboard's own buttons delete, not forms posted from other pages.
+pub fn delete_token(signer: &Signer, form_id: &str, submission_id: &str) -> String {
+    signer.sign(DELETE_TOKEN, form_id, submission_id)
+}
+
+/// Where the responses table of `form_id` lives; `deleted` is shown there as
+/// a confirmation.
+fn responses_url(form_id: &str, deleted: &str) -> String {
+    let deleted: String = form_urlencoded::byte_serialize(deleted.as_bytes()).collect();
+    format!("/form/{form_id}/admin/responses?deleted={deleted}")
+}
+
+/// Deletes a response (logged to the audit log) and returns to the table.
+async fn delete_response(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+    Path((form_id, submission_id)): Path<(String, String)>,
+    body: Bytes,
+) -> Result<impl IntoResponse, StatusCode> {
+    if state.store.get(&form_id, &submission_id).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let token = form_urlencoded::parse(&body)
+        .find(|(name, _)| name == "_csrf")
+        .map(|(_, value)| value.into_owned());
+    let valid = token.is_some_and(|token| {
+        state.signer.verify(DELETE_TOKEN, &form_id, &token) == Some(submission_id.clone())
+    });
+    if !valid {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let deleted = state
+        .store
+        .delete(&form_id, &submission_id, "admin", state.clock.now())
+        .await
+        .map_err(internal_error)?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    state.counts.lock().unwrap().remove(&form_id);
+    state.changed(Change::Responses(form_id.clone()));
+    Ok((
+        StatusCode::SEE_OTHER,
+        [(header::LOCATION, responses_url(&form_id, &submission_id))],
+    ))
+}
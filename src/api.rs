@@ -0,0 +1,383 @@
+use axum::body::Bytes;
+use axum::extract::{Extension, Path, Query};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::live::Change;
+use crate::server::{not_modified, Admin, AppState};
+use crate::store::{FormVersion, QuestionMeta, Submission};
+use crate::{parse_form, quiz, Form};
+
+type ApiError = (StatusCode, String);
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/api/health", get(health))
+        .route(
+            "/api/form/:form_id",
+            get(get_form).put(put_form).delete(delete_form),
+        )
+        .route("/api/form/:form_id/responses", get(list_responses))
+        .route(
+            "/api/form/:form_id/response/:submission_id",
+            get(get_response).delete(delete_response),
+        )
+}
+
+/// For load balancers and uptime checks: answers as long as the server runs.
+async fn health() -> Json<Value> {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+fn check_form_id(form_id: &str) -> Result<(), ApiError> {
+    if !form_id.is_empty()
+        && form_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::BAD_REQUEST,
+            "form ids may only contain ASCII letters, digits, '-' and '_'".to_string(),
+        ))
+    }
+}
+
+fn io_error(err: std::io::Error) -> ApiError {
+    eprintln!("could not write: {err}");
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "could not write to disk".to_string(),
+    )
+}
+
+/// The definition of a served form, for clients rendering it themselves.
+/// Quizzes are served without their correct answers. The form's version is
+/// its `ETag`, so clients can check with `If-None-Match` whether it changed.
+async fn get_form(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let form = state
+        .form(&form_id)
+        .ok_or((StatusCode::NOT_FOUND, format!("no form '{form_id}'")))?;
+    let etag = format!("\"{}\"", form.version());
+    if not_modified(&headers, &etag) {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
+    Ok(([(header::ETAG, etag)], Json(quiz::public_definition(&form))).into_response())
+}
+
+/// The file changes to `form_id` go to: the one it was loaded from, else a
+/// new one in `dir` with the extension `ext`.
+fn source_file(state: &AppState, dir: &std::path::Path, form_id: &str, ext: &str) -> PathBuf {
+    state
+        .sources
+        .lock()
+        .unwrap()
+        .get(form_id)
+        .cloned()
+        .unwrap_or_else(|| dir.join(format!("{form_id}.{ext}")))
+}
+
+/// `value` without the object members that are `null`, which read back as
+/// absent.
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(members) => members
+            .into_iter()
+            .filter(|(_, member)| !member.is_null())
+            .map(|(key, member)| (key, without_nulls(member)))
+            .collect(),
+        Value::Array(items) => items.into_iter().map(without_nulls).collect(),
+        value => value,
+    }
+}
+
+/// `form` in the format of the extension of `path`.
+fn serialize(form: &Form, path: &std::path::Path) -> Result<String, String> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::to_string_pretty(form).map_err(|err| err.to_string()),
+        // Through JSON, which turns number keys into the strings TOML needs
+        // and leaves the nulls TOML has none for to drop, and a value, which
+        // puts the tables after the plain keys.
+        Some("toml") => serde_json::to_value(form)
+            .map_err(|err| err.to_string())
+            .and_then(|json| {
+                toml::Value::try_from(without_nulls(json)).map_err(|err| err.to_string())
+            })
+            .and_then(|value| toml::to_string(&value).map_err(|err| err.to_string())),
+        _ => serde_yaml::to_string(form).map_err(|err| err.to_string()),
+    }
+}
+
+/// Creates or replaces a form from a JSON, TOML or YAML body (by
+/// `Content-Type`), read like a form file: includes are found next to it and
+/// templates expanded. Answers `201` for a new form and `200` for a replaced
+/// one, with the `warnings` also logged for files.
+///
+/// The definition is written as it was resolved to the file the form was
+/// loaded from, in that file's format, or to a new file in the forms
+/// directory. It is written through a temporary file and a rename, so a
+/// crash never leaves a half-written form behind.
+async fn put_form(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    check_form_id(&form_id)?;
+    let dir = state.forms_dir.as_ref().ok_or((
+        StatusCode::NOT_IMPLEMENTED,
+        "no --forms-dir configured".to_string(),
+    ))?;
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let ext = if content_type.starts_with("application/json") {
+        "json"
+    } else if content_type.starts_with("application/toml") {
+        "toml"
+    } else {
+        "yml"
+    };
+    let content = std::str::from_utf8(&body).map_err(|_| {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "could not parse form: not UTF-8".to_string(),
+        )
+    })?;
+
+    let _guard = state.form_writes.lock().await;
+    let path = source_file(&state, dir, &form_id, ext);
+    let form = parse_form(ext, content, &path, &state.limits)
+        .map_err(|err| (StatusCode::UNPROCESSABLE_ENTITY, err.to_string()))?;
+    let warnings = form.warnings();
+    for warning in &warnings {
+        eprintln!("{}: warning: {warning}", path.display());
+    }
+    let serialized =
+        serialize(&form, &path).map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err))?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{file_name}.tmp"));
+    tokio::fs::write(&tmp, serialized).await.map_err(io_error)?;
+    tokio::fs::rename(&tmp, &path).await.map_err(io_error)?;
+    state.sources.lock().unwrap().insert(form_id.clone(), path);
+    state.log_form_loaded(&form_id, &form);
+    let replaced = state
+        .forms
+        .write()
+        .unwrap()
+        .insert(form_id.clone(), Arc::new(form))
+        .is_some();
+    state.changed(Change::Form(form_id));
+    let status = if replaced {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+    Ok((status, Json(serde_json::json!({ "warnings": warnings }))))
+}
+
+/// Unpublishes a form. The file it was loaded from is kept with an added
+/// `.unpublished` (`lunch.json.unpublished`), and stored submissions are
+/// left untouched.
+async fn delete_form(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    check_form_id(&form_id)?;
+    let _guard = state.form_writes.lock().await;
+    if state.forms.write().unwrap().remove(&form_id).is_none() {
+        return Err((StatusCode::NOT_FOUND, format!("no form '{form_id}'")));
+    }
+    state.changed(Change::Form(form_id.clone()));
+    let source = state.sources.lock().unwrap().remove(&form_id);
+    if let Some(path) = source.filter(|path| path.exists()) {
+        let mut unpublished = path.clone().into_os_string();
+        unpublished.push(".unpublished");
+        tokio::fs::rename(&path, unpublished)
+            .await
+            .map_err(io_error)?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Serialize)]
+struct ResolvedAnswer {
+    question_id: String,
+    /// Title and type as of the form version the answer was given against,
+    /// or of the current form; `None` if neither knows the question.
+    title: Option<String>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    value: Value,
+    /// Whether the served form still asks the question.
+    in_current_form: bool,
+}
+
+/// Numbers for numeric questions, arrays for multiple choice, strings
+/// otherwise; unknown questions keep all their values.
+fn typed_value(kind: Option<&str>, values: &[String]) -> Value {
+    match (kind, values) {
+        (Some("multiple_choice") | None, _) | (_, []) => Value::from(values.to_vec()),
+        (Some("discrete_numeric" | "continous_numeric"), [value, ..]) => value
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map_or_else(|| Value::from(value.clone()), Value::Number),
+        (_, [value, ..]) => Value::from(value.clone()),
+    }
+}
+
+/// The answers of `submission` in question order: the questions of the form
+/// version it was made against, then those only the current form has, then
+/// any left over.
+fn resolve_answers(
+    submission: &Submission,
+    version: Option<FormVersion>,
+    form: Option<&Form>,
+) -> Vec<ResolvedAnswer> {
+    let current: Vec<QuestionMeta> = form
+        .map(|form| FormVersion::of(&submission.form_id, form).questions)
+        .unwrap_or_default();
+    let current_ids: HashSet<&str> = current.iter().map(|meta| meta.id.as_str()).collect();
+    let mut known: Vec<QuestionMeta> = version.map(|v| v.questions).unwrap_or_default();
+    let mut seen: HashSet<String> = known.iter().map(|meta| meta.id.clone()).collect();
+    known.extend(
+        current
+            .iter()
+            .filter(|meta| seen.insert(meta.id.clone()))
+            .cloned(),
+    );
+    let mut unknown: Vec<&String> = submission
+        .answers
+        .keys()
+        .filter(|id| !seen.contains(*id))
+        .collect();
+    unknown.sort();
+
+    let known = known.into_iter().filter_map(|meta| {
+        let values = submission.answers.get(&meta.id)?;
+        Some(ResolvedAnswer {
+            value: typed_value(Some(&meta.kind), values),
+            in_current_form: current_ids.contains(meta.id.as_str()),
+            question_id: meta.id,
+            title: Some(meta.title),
+            kind: Some(meta.kind),
+        })
+    });
+    let unknown = unknown.into_iter().map(|id| ResolvedAnswer {
+        question_id: id.clone(),
+        title: None,
+        kind: None,
+        value: typed_value(None, &submission.answers[id]),
+        in_current_form: false,
+    });
+    known.chain(unknown).collect()
+}
+
+/// One submission with its answers labelled, for following up on a single
+/// respondent without exporting everything.
+async fn get_response(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+    Path((form_id, submission_id)): Path<(String, String)>,
+) -> Result<Json<Value>, ApiError> {
+    let submission = state.store.get(&form_id, &submission_id).ok_or((
+        StatusCode::NOT_FOUND,
+        format!("no submission '{submission_id}' for form '{form_id}'"),
+    ))?;
+    let version = state
+        .store
+        .versions(&form_id)
+        .into_iter()
+        .find(|version| Some(&version.version) == submission.form_version.as_ref());
+    let form = state.form(&form_id);
+    let answers = resolve_answers(&submission, version, form.as_deref());
+    Ok(Json(serde_json::json!({
+        "id": submission.id,
+        "form_id": submission.form_id,
+        "form_version": submission.form_version,
+        "submitted_at": submission.submitted_at,
+        "updated_at": submission.updated_at,
+        "duration_secs": submission.duration_secs,
+        "tracking": submission.tracking,
+        "source": submission.source,
+        "client": submission.client,
+        "score": submission.score,
+        "computed": submission.computed,
+        "choice_score": submission.choice_score,
+        "answers": answers,
+    })))
+}
+
+/// Deletes a single submission, e.g. to honour a data deletion request.
+async fn delete_response(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+    Path((form_id, submission_id)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    let now = state.clock.now();
+    if state
+        .store
+        .delete(&form_id, &submission_id, "admin", now)
+        .await
+        .map_err(io_error)?
+    {
+        state.changed(Change::Responses(form_id));
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            format!("no submission '{submission_id}' for form '{form_id}'"),
+        ))
+    }
+}
+
+const MAX_PAGE_SIZE: usize = 1000;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Order {
+    Asc,
+    Desc,
+}
+
+#[derive(Deserialize)]
+struct PageQuery {
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+    order: Option<Order>,
+}
+
+/// Lists a form's submissions page by page, oldest first unless `order=desc`.
+async fn list_responses(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    Query(query): Query<PageQuery>,
+) -> Json<serde_json::Value> {
+    let limit = query.limit.unwrap_or(50).min(MAX_PAGE_SIZE);
+    let descending = matches!(query.order, Some(Order::Desc));
+    let (total, responses) = state.store.page(&form_id, query.offset, limit, descending);
+    Json(serde_json::json!({
+        "total": total,
+        "offset": query.offset,
+        "limit": limit,
+        "responses": responses,
+    }))
+}
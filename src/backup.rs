@@ -0,0 +1,308 @@
+//! Disaster-recovery archives: every served form, every stored response and
+//! the state behind handed-out links, in one `.tar.gz` with a manifest of
+//! checksums.
+//!
+//! The archive holds the signing secret, so it has to be kept as safe as the
+//! server itself. `open` checks an archive against its manifest before
+//! anything of it is restored.
+
+use axum::extract::Extension;
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use crate::drafts::Draft;
+use crate::server::{internal_error, Admin, AppState};
+use crate::store::StoreExport;
+
+pub fn routes() -> Router {
+    Router::new().route("/admin/backup.tar.gz", get(download))
+}
+
+pub const MANIFEST: &str = "manifest.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ManifestFile {
+    pub path: String,
+    pub size: u64,
+    /// Hex encoded SHA-256 of the contents.
+    pub sha256: String,
+}
+
+/// `manifest.json`, the first file of the archive.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Manifest {
+    /// Version of the crate that wrote the archive.
+    pub crate_version: String,
+    pub created_at: DateTime<Utc>,
+    pub files: Vec<ManifestFile>,
+}
+
+fn jsonl<T: Serialize>(records: &[T]) -> io::Result<Vec<u8>> {
+    let mut content = Vec::new();
+    for record in records {
+        serde_json::to_writer(&mut content, record)?;
+        content.push(b'\n');
+    }
+    Ok(content)
+}
+
+/// The files of a backup of `state` by their path in the archive: the forms
+/// (`forms/<id>.yml`), the responses of each form (`responses/<id>.jsonl`),
+/// the form versions and audit log (`versions.jsonl`, `audit.jsonl`), the
+/// drafts (`drafts.json`) and the signing secret (`secret`).
+pub fn files(state: &AppState) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let mut files = Vec::new();
+    let forms = state.forms.read().unwrap();
+    let mut ids: Vec<&String> = forms.keys().collect();
+    ids.sort();
+    for id in ids {
+        let yaml = serde_yaml::to_string(&*forms[id])
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        files.push((format!("forms/{id}.yml"), yaml.into_bytes()));
+    }
+    drop(forms);
+
+    let export = state.store.export_all()?;
+    for (form_id, submissions) in &export.submissions {
+        files.push((format!("responses/{form_id}.jsonl"), jsonl(submissions)?));
+    }
+    files.push(("versions.jsonl".to_string(), jsonl(&export.versions)?));
+    files.push(("audit.jsonl".to_string(), jsonl(&export.audit)?));
+    files.push((
+        "drafts.json".to_string(),
+        serde_json::to_vec(&state.drafts.all())?,
+    ));
+    files.push(("secret".to_string(), state.signer.secret_hex().into_bytes()));
+    Ok(files)
+}
+
+/// The manifest describing `files`.
+pub fn manifest(files: &[(String, Vec<u8>)], now: DateTime<Utc>) -> Manifest {
+    Manifest {
+        crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: now,
+        files: files
+            .iter()
+            .map(|(path, content)| ManifestFile {
+                path: path.clone(),
+                size: content.len() as u64,
+                sha256: hex::encode(Sha256::digest(content)),
+            })
+            .collect(),
+    }
+}
+
+const BLOCK: usize = 512;
+
+/// Writes `value` as a NUL terminated octal number filling `field`.
+fn octal(field: &mut [u8], value: u64) {
+    let digits = format!("{value:0width$o}", width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+}
+
+/// A ustar header for a regular file.
+fn tar_header(path: &str, size: u64, mtime: i64) -> io::Result<[u8; BLOCK]> {
+    if path.len() > 100 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{path}: name too long for the archive"),
+        ));
+    }
+    let mut header = [0; BLOCK];
+    header[..path.len()].copy_from_slice(path.as_bytes());
+    octal(&mut header[100..108], 0o600);
+    octal(&mut header[108..116], 0);
+    octal(&mut header[116..124], 0);
+    octal(&mut header[124..136], size);
+    octal(&mut header[136..148], mtime.max(0) as u64);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    // The checksum is computed with its own field filled with spaces.
+    header[148..156].fill(b' ');
+    let checksum: u64 = header.iter().map(|byte| *byte as u64).sum();
+    octal(&mut header[148..155], checksum);
+    Ok(header)
+}
+
+/// A `.tar.gz` of the backup `files` of `state`, with the manifest first.
+pub fn create(state: &AppState, now: DateTime<Utc>) -> io::Result<Vec<u8>> {
+    let files = files(state)?;
+    let manifest = serde_json::to_vec_pretty(&manifest(&files, now))?;
+    let mut archive = GzEncoder::new(Vec::new(), Compression::default());
+    for (path, content) in std::iter::once((MANIFEST, &manifest))
+        .chain(files.iter().map(|(path, content)| (path.as_str(), content)))
+    {
+        archive.write_all(&tar_header(path, content.len() as u64, now.timestamp())?)?;
+        archive.write_all(content)?;
+        let padding = (BLOCK - content.len() % BLOCK) % BLOCK;
+        archive.write_all(&vec![0; padding])?;
+    }
+    archive.write_all(&[0; 2 * BLOCK])?;
+    archive.finish()
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// The files of an archive written by `create`, in archive order.
+pub fn read(archive: &[u8]) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let mut tar = Vec::new();
+    GzDecoder::new(archive).read_to_end(&mut tar)?;
+    let mut files = Vec::new();
+    let mut offset = 0;
+    while offset + BLOCK <= tar.len() && tar[offset..offset + BLOCK].iter().any(|b| *b != 0) {
+        let header = &tar[offset..offset + BLOCK];
+        let field = |range: std::ops::Range<usize>| {
+            let bytes = &header[range];
+            let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+        };
+        let path = field(0..100);
+        let size = u64::from_str_radix(&field(124..136), 8)
+            .map_err(|_| invalid(format!("{path}: invalid size in the archive")))?
+            as usize;
+        let start = offset + BLOCK;
+        let content = tar
+            .get(start..start + size)
+            .ok_or_else(|| invalid(format!("{path}: archive ends within the file")))?;
+        files.push((path, content.to_vec()));
+        offset = start + size.div_ceil(BLOCK) * BLOCK;
+    }
+    Ok(files)
+}
+
+/// The contents of an archive that matched its manifest.
+pub struct Backup {
+    pub manifest: Manifest,
+    /// Form definitions (YAML, as written) by form id.
+    pub forms: BTreeMap<String, String>,
+    pub store: StoreExport,
+    /// Drafts by resume token.
+    pub drafts: HashMap<String, Draft>,
+    /// The signing secret, hex encoded.
+    pub secret: String,
+}
+
+fn parse_jsonl<T: serde::de::DeserializeOwned>(path: &str, content: &[u8]) -> io::Result<Vec<T>> {
+    content
+        .split(|byte| *byte == b'\n')
+        .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+        .map(|line| serde_json::from_slice(line).map_err(|err| invalid(format!("{path}: {err}"))))
+        .collect()
+}
+
+/// Reads an archive written by `create` and checks every file against the
+/// size and checksum in its manifest. Files missing from the archive or
+/// from the manifest make it invalid as a whole.
+pub fn open(archive: &[u8]) -> io::Result<Backup> {
+    let mut files = read(archive)?.into_iter();
+    let manifest: Manifest = match files.next() {
+        Some((path, content)) if path == MANIFEST => {
+            serde_json::from_slice(&content).map_err(|err| invalid(format!("{MANIFEST}: {err}")))?
+        }
+        _ => {
+            return Err(invalid(format!(
+                "the archive does not start with {MANIFEST}"
+            )))
+        }
+    };
+    let files: Vec<(String, Vec<u8>)> = files.collect();
+    if files.len() != manifest.files.len() {
+        return Err(invalid(format!(
+            "the archive holds {} files, the manifest lists {}",
+            files.len(),
+            manifest.files.len()
+        )));
+    }
+    for ((path, content), entry) in files.iter().zip(&manifest.files) {
+        if *path != entry.path {
+            return Err(invalid(format!(
+                "{path}: expected {} at this place of the archive",
+                entry.path
+            )));
+        }
+        if content.len() as u64 != entry.size
+            || hex::encode(Sha256::digest(content)) != entry.sha256
+        {
+            return Err(invalid(format!("{path}: checksum mismatch")));
+        }
+    }
+
+    let mut backup = Backup {
+        manifest,
+        forms: BTreeMap::new(),
+        store: StoreExport::default(),
+        drafts: HashMap::new(),
+        secret: String::new(),
+    };
+    for (path, content) in files {
+        match path.as_str() {
+            "versions.jsonl" => backup.store.versions = parse_jsonl(&path, &content)?,
+            "audit.jsonl" => backup.store.audit = parse_jsonl(&path, &content)?,
+            "drafts.json" => {
+                backup.drafts = serde_json::from_slice(&content)
+                    .map_err(|err| invalid(format!("{path}: {err}")))?
+            }
+            "secret" => {
+                backup.secret =
+                    String::from_utf8(content).map_err(|err| invalid(format!("{path}: {err}")))?
+            }
+            _ => {
+                let id = |prefix: &str, suffix: &str| {
+                    path.strip_prefix(prefix)
+                        .and_then(|rest| rest.strip_suffix(suffix))
+                        .filter(|id| !id.is_empty() && !id.contains('/') && !id.starts_with('.'))
+                        .map(str::to_string)
+                };
+                if let Some(id) = id("forms/", ".yml") {
+                    let yaml = String::from_utf8(content)
+                        .map_err(|err| invalid(format!("{path}: {err}")))?;
+                    backup.forms.insert(id, yaml);
+                } else if let Some(id) = id("responses/", ".jsonl") {
+                    backup
+                        .store
+                        .submissions
+                        .insert(id, parse_jsonl(&path, &content)?);
+                } else {
+                    return Err(invalid(format!("{path}: unexpected file in the archive")));
+                }
+            }
+        }
+    }
+    if backup.secret.is_empty() {
+        return Err(invalid("the archive holds no signing secret".to_string()));
+    }
+    Ok(backup)
+}
+
+async fn download(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let now = state.clock.now();
+    let archive = create(&state, now).map_err(internal_error)?;
+    let disposition = format!(
+        r#"attachment; filename="backup-{}.tar.gz""#,
+        now.format("%Y%m%d-%H%M%S")
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/gzip".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        archive,
+    ))
+}
@@ -0,0 +1,102 @@
+//! Public response counts for forms that opt in with `public_count`, as JSON
+//! and as an SVG badge to put on other pages.
+
+use axum::extract::{Extension, Path, Query};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::Duration;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::server::{language, AppState};
+use crate::{escape, i18n, Form};
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/form/:form_id/count", get(count))
+        .route("/form/:form_id/badge.svg", get(badge))
+}
+
+/// How long a count is reused, here and in the browser cache, so embedding
+/// pages do not cause a store scan per view.
+const TTL_SECS: i64 = 60;
+
+fn cache_control() -> (header::HeaderName, String) {
+    (header::CACHE_CONTROL, format!("public, max-age={TTL_SECS}"))
+}
+
+/// The number of responses to `form_id`, at most `TTL_SECS` old.
+fn cached_count(state: &AppState, form_id: &str) -> usize {
+    let now = state.clock.now();
+    let mut counts = state.counts.lock().unwrap();
+    match counts.get(form_id) {
+        Some((taken, count)) if now - *taken < Duration::seconds(TTL_SECS) => *count,
+        _ => {
+            let count = state.store.count(form_id);
+            counts.insert(form_id.to_string(), (now, count));
+            count
+        }
+    }
+}
+
+/// `404` unless the form publishes its count.
+pub(crate) fn published(state: &AppState, form_id: &str) -> Result<Arc<Form>, StatusCode> {
+    state
+        .form(form_id)
+        .filter(|form| form.public_count)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[derive(Serialize)]
+struct Count {
+    count: usize,
+}
+
+async fn count(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    published(&state, &form_id)?;
+    let count = cached_count(&state, &form_id);
+    Ok(([cache_control()], Json(Count { count })))
+}
+
+async fn badge(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let form = published(&state, &form_id)?;
+    let count = cached_count(&state, &form_id);
+    let lang = language(&form, params.get("lang"), &headers);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "image/svg+xml".to_string()),
+            cache_control(),
+        ],
+        svg(i18n::texts(&lang).responses, count),
+    ))
+}
+
+/// Approximate width of `text` in 11px Verdana, the badge font.
+fn text_width(text: &str) -> usize {
+    text.chars().count() * 7
+}
+
+/// A badge reading "`label` | `count`", as wide as its texts need.
+pub fn svg(label: &str, count: usize) -> String {
+    let count = count.to_string();
+    let label_width = text_width(label) + 12;
+    let count_width = text_width(&count) + 12;
+    let width = label_width + count_width;
+    let label = escape(label);
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {count}"><title>{label}: {count}</title><rect width="{label_width}" height="20" fill="#555"/><rect x="{label_width}" width="{count_width}" height="20" fill="#4c1"/><g fill="#fff" text-anchor="middle" font-family="Verdana,sans-serif" font-size="11"><text x="{label_x}" y="14">{label}</text><text x="{count_x}" y="14">{count}</text></g></svg>"##,
+        label_x = label_width / 2,
+        count_x = label_width + count_width / 2,
+    )
+}
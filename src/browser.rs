@@ -0,0 +1,35 @@
+//! Opening the served form in the default browser (`--open`), for authoring
+//! forms locally.
+
+use std::io;
+use std::net::SocketAddr;
+use std::process::Command;
+
+/// The page to open for a server bound to `bound`: the form when there is
+/// only `form_id`, the index of forms otherwise. `None` unless the server
+/// is bound to a loopback address, so a browser is never pointed at a
+/// server reachable from elsewhere.
+pub fn url(bound: SocketAddr, form_id: Option<&str>) -> Option<String> {
+    if !bound.ip().is_loopback() {
+        return None;
+    }
+    Some(match form_id {
+        Some(form_id) => format!("http://{bound}/form/{form_id}"),
+        None => format!("http://{bound}/"),
+    })
+}
+
+/// Hands `url` to the platform's opener, without waiting for the browser.
+pub fn launch(url: &str) -> io::Result<()> {
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = Command::new("xdg-open");
+    command.arg(url).spawn().map(drop)
+}
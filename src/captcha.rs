@@ -0,0 +1,171 @@
+//! CAPTCHA checks for public forms that attract spam: the provider's widget
+//! on the form page, and the token it submits checked with the provider
+//! before a response is accepted.
+
+use axum::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::net::IpAddr;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+
+use crate::escape;
+use crate::i18n::Texts;
+
+/// Key of the CAPTCHA's entry in `RenderContext::errors` and of its field
+/// error in JSON responses.
+pub const FIELD: &str = "_captcha";
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Provider {
+    Hcaptcha,
+    Turnstile,
+}
+
+impl Provider {
+    fn script(self) -> &'static str {
+        match self {
+            Provider::Hcaptcha => "https://js.hcaptcha.com/1/api.js",
+            Provider::Turnstile => "https://challenges.cloudflare.com/turnstile/v0/api.js",
+        }
+    }
+
+    fn widget_class(self) -> &'static str {
+        match self {
+            Provider::Hcaptcha => "h-captcha",
+            Provider::Turnstile => "cf-turnstile",
+        }
+    }
+
+    /// Name of the field the widget posts its token in.
+    pub fn token_field(self) -> &'static str {
+        match self {
+            Provider::Hcaptcha => "h-captcha-response",
+            Provider::Turnstile => "cf-turnstile-response",
+        }
+    }
+
+    pub fn verify_url(self) -> &'static str {
+        match self {
+            Provider::Hcaptcha => "https://api.hcaptcha.com/siteverify",
+            Provider::Turnstile => "https://challenges.cloudflare.com/turnstile/v0/siteverify",
+        }
+    }
+
+    /// Origins the widget loads scripts, frames and styles from, for the
+    /// `Content-Security-Policy` of pages showing it.
+    pub fn origins(self) -> &'static [&'static str] {
+        match self {
+            Provider::Hcaptcha => &["https://hcaptcha.com", "https://*.hcaptcha.com"],
+            Provider::Turnstile => &["https://challenges.cloudflare.com"],
+        }
+    }
+}
+
+/// The `captcha` section of a form.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct Captcha {
+    pub provider: Provider,
+    /// Public key the widget is shown with.
+    pub site_key: String,
+    /// Key the server verifies tokens with; never served with the form.
+    pub secret: String,
+}
+
+impl Captcha {
+    /// The widget with the message of a failed check, and a notice for
+    /// browsers without JavaScript, which cannot show it.
+    pub fn widget(&self, texts: &Texts, error: Option<&String>) -> String {
+        let message = error
+            .map(|message| {
+                format!(
+                    r#"<p class="error-message" id="{FIELD}-message">{}</p>"#,
+                    escape(message)
+                )
+            })
+            .unwrap_or_default();
+        format!(
+            r#"<div class="captcha{error}" id="{FIELD}-error"><div class="{class}" data-sitekey="{key}"></div><script src="{script}" async defer></script><noscript><p class="captcha-notice">{needs_js}</p></noscript>{message}</div>"#,
+            error = if error.is_some() { " error" } else { "" },
+            class = self.provider.widget_class(),
+            key = escape(&self.site_key),
+            script = self.provider.script(),
+            needs_js = texts.captcha_needs_js,
+        )
+    }
+}
+
+/// `policy` letting the widget of `provider` load: its origins are added to
+/// the script, frame, style and connect sources.
+pub fn csp(policy: &str, provider: Provider) -> String {
+    let origins = provider.origins().join(" ");
+    let mut directives: Vec<String> = policy
+        .split(';')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .map(str::to_string)
+        .collect();
+    for name in ["script-src", "frame-src", "style-src", "connect-src"] {
+        match directives
+            .iter_mut()
+            .find(|directive| directive.split(' ').next() == Some(name))
+        {
+            Some(directive) => {
+                directive.push(' ');
+                directive.push_str(&origins);
+            }
+            None => directives.push(format!("{name} 'self' {origins}")),
+        }
+    }
+    directives.join("; ")
+}
+
+/// Asks the provider whether a token posted by the widget is genuine.
+#[async_trait]
+pub trait CaptchaVerifier: Send + Sync {
+    async fn verify(&self, captcha: &Captcha, token: &str, remote_ip: IpAddr) -> io::Result<bool>;
+}
+
+/// The provider's `siteverify` API. The crate has no HTTPS client of its
+/// own, so the request is made with `curl`; the secret goes in on stdin to
+/// stay out of the process list.
+pub struct SiteVerify;
+
+#[derive(Deserialize)]
+struct SiteVerifyResponse {
+    success: bool,
+}
+
+#[async_trait]
+impl CaptchaVerifier for SiteVerify {
+    async fn verify(&self, captcha: &Captcha, token: &str, remote_ip: IpAddr) -> io::Result<bool> {
+        let body = form_urlencoded::Serializer::new(String::new())
+            .append_pair("secret", &captcha.secret)
+            .append_pair("response", token)
+            .append_pair("remoteip", &remote_ip.to_string())
+            .finish();
+        let mut curl = tokio::process::Command::new("curl")
+            .args(["--silent", "--show-error", "--max-time", "10"])
+            .args(["--data-binary", "@-", captcha.provider.verify_url()])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let mut stdin = curl.stdin.take().expect("stdin is piped");
+        stdin.write_all(body.as_bytes()).await?;
+        drop(stdin);
+        let output = curl.wait_with_output().await?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "curl failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        let response: SiteVerifyResponse = serde_json::from_slice(&output.stdout)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(response.success)
+    }
+}
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Draft {
+    pub form_id: String,
+    pub answers: HashMap<String, Vec<String>>,
+    pub expires_at: DateTime<Utc>,
+    /// Seed of the order shuffled questions were shown in, kept for resuming.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+/// Partially filled forms saved for later, keyed by their resume token.
+///
+/// Drafts live apart from submissions so they never show up in counts or
+/// exports. With a path they are persisted as one JSON file that is rewritten
+/// (temp file + rename) on every change.
+#[derive(Default)]
+pub struct DraftStore {
+    path: Option<PathBuf>,
+    drafts: Mutex<HashMap<String, Draft>>,
+    writes: tokio::sync::Mutex<()>,
+}
+
+/// 128 random bits, hex encoded, so resume links cannot be guessed.
+fn new_token() -> String {
+    rand::random::<[u8; 16]>()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+impl DraftStore {
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        let drafts = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => return Err(err),
+        };
+        Ok(DraftStore {
+            path: Some(path),
+            drafts: Mutex::new(drafts),
+            writes: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Stores a draft and returns its resume token.
+    pub async fn save(&self, draft: Draft) -> io::Result<String> {
+        let token = new_token();
+        self.drafts.lock().unwrap().insert(token.clone(), draft);
+        self.persist().await?;
+        Ok(token)
+    }
+
+    /// The unexpired draft for `form_id` behind `token`, if there is one.
+    pub fn get(&self, form_id: &str, token: &str, now: DateTime<Utc>) -> Option<Draft> {
+        self.drafts
+            .lock()
+            .unwrap()
+            .get(token)
+            .filter(|draft| draft.form_id == form_id && draft.expires_at > now)
+            .cloned()
+    }
+
+    pub async fn remove(&self, token: &str) -> io::Result<()> {
+        if self.drafts.lock().unwrap().remove(token).is_some() {
+            self.persist().await?;
+        }
+        Ok(())
+    }
+
+    /// All saved drafts by resume token, e.g. for a backup.
+    pub fn all(&self) -> HashMap<String, Draft> {
+        self.drafts.lock().unwrap().clone()
+    }
+
+    /// Adds `drafts`, e.g. from a backup, keeping any draft already saved
+    /// under the same token. Returns how many were added.
+    pub async fn import(&self, drafts: HashMap<String, Draft>) -> io::Result<usize> {
+        let added = {
+            let mut stored = self.drafts.lock().unwrap();
+            let before = stored.len();
+            for (token, draft) in drafts {
+                stored.entry(token).or_insert(draft);
+            }
+            stored.len() - before
+        };
+        if added > 0 {
+            self.persist().await?;
+        }
+        Ok(added)
+    }
+
+    /// Drops all drafts.
+    pub async fn clear(&self) -> io::Result<()> {
+        self.drafts.lock().unwrap().clear();
+        self.persist().await
+    }
+
+    /// Drops expired drafts.
+    pub async fn purge(&self, now: DateTime<Utc>) -> io::Result<()> {
+        let removed = {
+            let mut drafts = self.drafts.lock().unwrap();
+            let before = drafts.len();
+            drafts.retain(|_, draft| draft.expires_at > now);
+            before - drafts.len()
+        };
+        if removed > 0 {
+            self.persist().await?;
+        }
+        Ok(())
+    }
+
+    async fn persist(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let _guard = self.writes.lock().await;
+        let content = serde_json::to_string(&*self.drafts.lock().unwrap())?;
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, content).await?;
+        tokio::fs::rename(&tmp, path).await
+    }
+}
@@ -0,0 +1,92 @@
+//! The iframe snippet survey owners paste into their pages to embed a form
+//! (admin only).
+
+use axum::extract::{Extension, Path};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use std::sync::Arc;
+
+use crate::server::{Admin, AppState};
+use crate::{escape, Form, Question, QuestionType};
+
+pub fn routes() -> Router {
+    Router::new().route("/form/:form_id/embed.html", get(embed_snippet))
+}
+
+/// Below this the submit button and an error summary would not fit.
+const MIN_HEIGHT: u32 = 200;
+/// Longer forms scroll inside the frame.
+const MAX_HEIGHT: u32 = 2400;
+
+/// Rough height in pixels of a question as the embedded form renders it.
+fn question_height(question: &Question) -> u32 {
+    let controls = match &question.spec {
+        QuestionType::Text { is_long: true, .. } => 110,
+        QuestionType::Text { .. } | QuestionType::ContinousNumeric { .. } => 40,
+        QuestionType::DiscreteNumeric { .. } => 60,
+        QuestionType::SingleChoice {
+            answers,
+            custom_answer,
+            ..
+        }
+        | QuestionType::MultipleChoice {
+            answers,
+            custom_answer,
+            ..
+        } => 28 * (answers.len() as u32 + u32::from(*custom_answer)),
+        QuestionType::Computed { .. } | QuestionType::Unknown { .. } => 30,
+    };
+    let help = if question.help.is_some() { 24 } else { 0 };
+    50 + help + controls
+}
+
+/// Rough height in pixels of `form` in embed mode, so short polls do not get
+/// a frame with lots of empty space and long surveys do not start with a
+/// scrollbar.
+pub fn estimated_height(form: &Form) -> u32 {
+    let groups: u32 = form
+        .groups
+        .iter()
+        .map(|group| 70 + group.questions.iter().map(question_height).sum::<u32>())
+        .sum();
+    let intro = if form.intro.is_some() { 150 } else { 0 };
+    (60 + intro + groups).clamp(MIN_HEIGHT, MAX_HEIGHT)
+}
+
+/// The iframe showing `form_url` in embed mode, and a link to it for
+/// browsers that do not show frames.
+pub fn snippet(form: &Form, form_url: &str) -> String {
+    let title = escape(form.text(&form.title));
+    let url = escape(form_url);
+    format!(
+        r#"<iframe src="{url}?embed=1" title="{title}" width="100%" height="{height}" style="border:0;max-width:48em" loading="lazy"></iframe>
+<noscript><a href="{url}">{title}</a></noscript>
+"#,
+        height = estimated_height(form)
+    )
+}
+
+/// Served as plain text, to be copied rather than rendered. The URL is built
+/// from the `Host` the admin reached the server under.
+async fn embed_snippet(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let form = state
+        .form(&form_id)
+        .ok_or((StatusCode::NOT_FOUND, format!("no form '{form_id}'")))?;
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let host = header("host").ok_or((
+        StatusCode::BAD_REQUEST,
+        "the request has no Host header".to_string(),
+    ))?;
+    let scheme = header("x-forwarded-proto").unwrap_or("http");
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        snippet(&form, &format!("{scheme}://{host}/form/{form_id}")),
+    ))
+}
@@ -0,0 +1,362 @@
+//! Admin-only downloads of the stored submissions.
+
+use axum::extract::{Extension, Path, RawQuery};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook, XlsxError};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::filter::Filter;
+use crate::server::{Admin, AppState};
+use crate::stats;
+use crate::store::{ClientMeta, Submission};
+use crate::{ClientIp, Form, Numbering, QuestionType};
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/export/:file", get(export))
+        .route("/form/:form_id/results.xlsx", get(results_xlsx))
+}
+
+type ExportError = (StatusCode, String);
+
+/// Upper bound for the cells of a spreadsheet export, which is built in memory.
+const MAX_XLSX_CELLS: usize = 2_000_000;
+
+/// `/export/<form_id>.json` or `.xlsx`; the format is taken from the
+/// extension. Any number of `filter=<question id>:<op>:<value>` parameters
+/// restrict the export to the submissions matching all of them. With
+/// `numbered=1`, spreadsheet column headers start with the question number.
+async fn export(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(file): Path<String>,
+    RawQuery(query): RawQuery,
+) -> Result<Response, ExportError> {
+    let not_found = || (StatusCode::NOT_FOUND, format!("no export '{file}'"));
+    let (form_id, format) = file.rsplit_once('.').ok_or_else(not_found)?;
+    match format {
+        "json" => export_json(&state, form_id, query.as_deref()),
+        "xlsx" => export_xlsx(&state, form_id, query.as_deref()),
+        _ => Err(not_found()),
+    }
+}
+
+async fn results_xlsx(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    RawQuery(query): RawQuery,
+) -> Result<Response, ExportError> {
+    export_xlsx(&state, &form_id, query.as_deref())
+}
+
+fn filtered(
+    state: &AppState,
+    form_id: &str,
+    query: Option<&str>,
+) -> Result<Vec<Submission>, ExportError> {
+    let filters = filters(state, form_id, query.unwrap_or_default())
+        .map_err(|err| (StatusCode::BAD_REQUEST, err))?;
+    Ok(state
+        .store
+        .list(form_id)
+        .into_iter()
+        .filter(|submission| filters.iter().all(|f| f.matches(&submission.answers)))
+        .collect())
+}
+
+fn export_json(
+    state: &AppState,
+    form_id: &str,
+    query: Option<&str>,
+) -> Result<Response, ExportError> {
+    let kinds = question_kinds(state, form_id)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("no form '{form_id}'")))?;
+    let submissions = filtered(state, form_id, query)?;
+    let body = Value::Array(
+        submissions
+            .iter()
+            .map(|submission| to_json(submission, &kinds))
+            .collect(),
+    );
+    Ok((
+        [(header::CONTENT_TYPE, "application/json")],
+        body.to_string(),
+    )
+        .into_response())
+}
+
+fn filters(state: &AppState, form_id: &str, query: &str) -> Result<Vec<Filter>, String> {
+    let expressions: Vec<String> = form_urlencoded::parse(query.as_bytes())
+        .filter(|(key, _)| key == "filter")
+        .map(|(_, value)| value.into_owned())
+        .collect();
+    if expressions.is_empty() {
+        return Ok(Vec::new());
+    }
+    let form = state
+        .form(form_id)
+        .ok_or_else(|| format!("form '{form_id}' is not served, so it cannot be filtered"))?;
+    expressions
+        .iter()
+        .map(|expression| Filter::parse(expression, &form))
+        .collect()
+}
+
+/// Question kinds by form version and question id, so submissions made
+/// against older versions are shaped by the structure they were made with.
+/// `None` if the form is neither served nor has stored versions.
+fn question_kinds(
+    state: &AppState,
+    form_id: &str,
+) -> Option<HashMap<Option<String>, HashMap<String, String>>> {
+    let mut kinds = HashMap::new();
+    for version in state.store.versions(form_id) {
+        kinds.insert(
+            Some(version.version),
+            version
+                .questions
+                .into_iter()
+                .map(|question| (question.id, question.kind))
+                .collect(),
+        );
+    }
+    if let Some(form) = state.form(form_id) {
+        kinds.insert(
+            None,
+            form.questions()
+                .map(|(id, question)| (id, question.spec.kind().to_string()))
+                .collect(),
+        );
+    }
+    (!kinds.is_empty()).then_some(kinds)
+}
+
+/// Multiple choice answers stay arrays, everything else becomes a single
+/// string.
+fn to_json(
+    submission: &Submission,
+    kinds: &HashMap<Option<String>, HashMap<String, String>>,
+) -> Value {
+    let kinds = kinds
+        .get(&submission.form_version)
+        .or_else(|| kinds.get(&None));
+    let answers: Map<String, Value> = submission
+        .answers
+        .iter()
+        .map(|(id, values)| {
+            let kind = kinds.and_then(|kinds| kinds.get(id)).map(String::as_str);
+            let value = match (kind, values.as_slice()) {
+                (Some("multiple_choice") | None, _) | (_, []) => Value::from(values.clone()),
+                (_, [value, ..]) => Value::from(value.clone()),
+            };
+            (id.clone(), value)
+        })
+        .collect();
+    serde_json::json!({
+        "id": submission.id,
+        "form_version": submission.form_version,
+        "submitted_at": submission.submitted_at,
+        "updated_at": submission.updated_at,
+        "client": submission.client,
+        "duration_secs": submission.duration_secs,
+        "tracking": submission.tracking,
+        "source": submission.source,
+        "score": submission.score,
+        "computed": submission.computed,
+        "choice_score": submission.choice_score,
+        "answers": answers,
+    })
+}
+
+/// Workbook with a "Responses" sheet (the submission and edit times, what is
+/// kept of the client's address, the time taken, the source and a column per
+/// tracked parameter, one typed column per question of the current form, the
+/// score for quizzes, a column per computed score and the sum of the chosen
+/// options' scores) and a "Summary" sheet with per-question aggregates.
+fn export_xlsx(
+    state: &AppState,
+    form_id: &str,
+    query: Option<&str>,
+) -> Result<Response, ExportError> {
+    let form = state
+        .form(form_id)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("no form '{form_id}'")))?;
+    let submissions = filtered(state, form_id, query)?;
+    let columns = form.questions().count() + form.computed.len() + form.track_params.len() + 8;
+    let cells = (submissions.len() + 1) * columns;
+    if cells > MAX_XLSX_CELLS {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("the export would have {cells} cells, more than {MAX_XLSX_CELLS}; narrow it down with filters or use the JSON export"),
+        ));
+    }
+    let numbered = form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+        .any(|(key, value)| key == "numbered" && value == "1");
+    // Unnumbered forms are numbered through for the export.
+    let numbers = match form.numbering {
+        _ if !numbered => HashMap::new(),
+        Numbering::None => form.question_numbers(Numbering::PerForm),
+        numbering => form.question_numbers(numbering),
+    };
+    let bytes = workbook(&form, &submissions, &numbers).map_err(|err| {
+        eprintln!("could not build workbook: {err}");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "could not build workbook".to_string(),
+        )
+    })?;
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet".to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{form_id}.xlsx\""),
+            ),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+fn workbook(
+    form: &Form,
+    submissions: &[Submission],
+    numbers: &HashMap<String, String>,
+) -> Result<Vec<u8>, XlsxError> {
+    let bold = Format::new().set_bold();
+    let date = Format::new().set_num_format("yyyy-mm-dd hh:mm:ss");
+    let mut workbook = Workbook::new();
+
+    let sheet = workbook.add_worksheet().set_name("Responses")?;
+    // Labelled with what the form's privacy setting keeps of the address.
+    let client = match form.privacy.client_ip {
+        ClientIp::None => None,
+        ClientIp::Hashed => Some("Client IP hash"),
+        ClientIp::Truncated => Some("Client IP prefix"),
+    };
+    let mut meta = vec!["Submission", "Submitted at", "Updated at"];
+    let client_col = meta.len() as u16;
+    meta.extend(client);
+    let duration_col = meta.len() as u16;
+    meta.push("Duration (s)");
+    let source_col = meta.len() as u16;
+    meta.push("Source");
+    let tracking_col = meta.len() as u16;
+    meta.extend(form.track_params.iter().map(String::as_str));
+    for (col, title) in meta.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *title, &bold)?;
+    }
+    let first_question = meta.len() as u16;
+    let questions: Vec<_> = form.questions().collect();
+    for (col, (id, question)) in questions.iter().enumerate() {
+        let title = match numbers.get(id) {
+            Some(number) => format!("{number} {}", form.text(&question.title)),
+            None => form.text(&question.title).to_string(),
+        };
+        sheet.write_string_with_format(0, first_question + col as u16, &title, &bold)?;
+    }
+    let score_col = first_question + questions.len() as u16;
+    if form.quiz {
+        sheet.write_string_with_format(0, score_col, "Score", &bold)?;
+    }
+    let computed_col = score_col + form.quiz as u16;
+    for (col, score) in form.computed.iter().enumerate() {
+        let col = computed_col + col as u16;
+        sheet.write_string_with_format(0, col, form.text(&score.title), &bold)?;
+    }
+    let choice_col = computed_col + form.computed.len() as u16;
+    if form.scores_choices() {
+        sheet.write_string_with_format(0, choice_col, "Choice score", &bold)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    sheet.set_column_range_width(1, 2, 20)?;
+    for (row, submission) in submissions.iter().enumerate() {
+        let row = row as u32 + 1;
+        sheet.write_string(row, 0, &submission.id)?;
+        for (col, at) in [(1, submission.submitted_at), (2, submission.updated_at)] {
+            if let Some(at) = at {
+                sheet.write_datetime_with_format(
+                    row,
+                    col,
+                    ExcelDateTime::from_timestamp(at.timestamp())?,
+                    &date,
+                )?;
+            }
+        }
+        if let Some(ClientMeta::IpHash(value) | ClientMeta::IpPrefix(value)) =
+            submission.client.as_ref().filter(|_| client.is_some())
+        {
+            sheet.write_string(row, client_col, value)?;
+        }
+        if let Some(secs) = submission.duration_secs {
+            sheet.write_number(row, duration_col, secs as f64)?;
+        }
+        sheet.write_string(row, source_col, submission.source.as_str())?;
+        for (col, name) in form.track_params.iter().enumerate() {
+            if let Some(value) = submission.tracking.get(name) {
+                sheet.write_string(row, tracking_col + col as u16, value)?;
+            }
+        }
+        for (col, (id, question)) in questions.iter().enumerate() {
+            let col = first_question + col as u16;
+            let Some(values) = submission.answers.get(id).filter(|v| !v.is_empty()) else {
+                continue;
+            };
+            let numeric = matches!(
+                question.spec,
+                QuestionType::DiscreteNumeric { .. } | QuestionType::ContinousNumeric { .. }
+            );
+            match values[0].parse::<f64>() {
+                Ok(number) if numeric => sheet.write_number(row, col, number)?,
+                _ => sheet.write_string(row, col, values.join("; "))?,
+            };
+        }
+        if let Some(score) = submission.score.filter(|_| form.quiz) {
+            sheet.write_number(row, score_col, score.points as f64)?;
+        }
+        for (col, score) in form.computed.iter().enumerate() {
+            if let Some(value) = submission.computed.get(&score.key) {
+                sheet.write_number(row, computed_col + col as u16, *value as f64)?;
+            }
+        }
+        if let Some(score) = submission.choice_score {
+            sheet.write_number(row, choice_col, score as f64)?;
+        }
+    }
+
+    let sheet = workbook.add_worksheet().set_name("Summary")?;
+    for (col, title) in ["Question", "Answered", "Mean", "Min", "Max", "Counts"]
+        .iter()
+        .enumerate()
+    {
+        sheet.write_string_with_format(0, col as u16, *title, &bold)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    for (row, summary) in stats::summarize(form, submissions).iter().enumerate() {
+        let row = row as u32 + 1;
+        sheet.write_string(row, 0, &summary.title)?;
+        sheet.write_number(row, 1, summary.answered as f64)?;
+        for (col, value) in [summary.mean, summary.min, summary.max].iter().enumerate() {
+            if let Some(value) = value {
+                sheet.write_number(row, col as u16 + 2, *value)?;
+            }
+        }
+        let counts = summary
+            .counts
+            .iter()
+            .map(|count| format!("{}: {}", count.value, count.count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        sheet.write_string(row, 5, counts)?;
+    }
+    workbook.save_to_buffer()
+}
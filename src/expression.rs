@@ -0,0 +1,168 @@
+//! Tiny arithmetic expressions for `QuestionType::Computed`.
+//!
+//! Expressions are parsed on the server and shipped to the browser as a postfix
+//! program, so the rendered page never has to `eval` anything. Supported are
+//! numbers, `+ - * /`, parentheses and field references written as `{id}`.
+
+use itertools::Itertools;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instr {
+    Number(f64),
+    Field(String),
+    Op(char),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Field(String),
+    Op(char),
+    Open,
+    Close,
+}
+
+fn precedence(op: char) -> u8 {
+    match op {
+        '*' | '/' => 2,
+        _ => 1,
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '+' | '-' | '*' | '/' => {
+                chars.next();
+                tokens.push(Token::Op(c));
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '{' => {
+                chars.next();
+                let field: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                if field.is_empty()
+                    || !field
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                {
+                    return Err(format!("invalid field reference '{{{field}}}'"));
+                }
+                tokens.push(Token::Field(field));
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_digit() || c == '.') {
+                        break;
+                    }
+                    number.push(c);
+                    chars.next();
+                }
+                let value = number
+                    .parse()
+                    .map_err(|_| format!("invalid number '{number}'"))?;
+                tokens.push(Token::Number(value));
+            }
+            c => return Err(format!("unexpected character '{c}'")),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parses an expression into a postfix program (shunting-yard).
+pub fn parse(expression: &str) -> Result<Vec<Instr>, String> {
+    let mut program = Vec::new();
+    let mut stack: Vec<Token> = Vec::new();
+    let mut expect_operand = true;
+
+    for token in tokenize(expression)? {
+        match token {
+            Token::Number(_) | Token::Field(_) if !expect_operand => {
+                return Err("missing operator between operands".to_string())
+            }
+            Token::Number(value) => {
+                program.push(Instr::Number(value));
+                expect_operand = false;
+            }
+            Token::Field(field) => {
+                program.push(Instr::Field(field));
+                expect_operand = false;
+            }
+            Token::Op(op) if expect_operand => return Err(format!("unexpected operator '{op}'")),
+            Token::Op(op) => {
+                while let Some(Token::Op(top)) = stack.last() {
+                    if precedence(*top) < precedence(op) {
+                        break;
+                    }
+                    program.push(Instr::Op(*top));
+                    stack.pop();
+                }
+                stack.push(Token::Op(op));
+                expect_operand = true;
+            }
+            Token::Open if !expect_operand => return Err("missing operator before '('".to_string()),
+            Token::Open => stack.push(Token::Open),
+            Token::Close if expect_operand => return Err("unexpected ')'".to_string()),
+            Token::Close => loop {
+                match stack.pop() {
+                    Some(Token::Op(op)) => program.push(Instr::Op(op)),
+                    Some(Token::Open) => break,
+                    _ => return Err("unbalanced ')'".to_string()),
+                }
+            },
+        }
+    }
+
+    if expect_operand {
+        return Err("expression ends without an operand".to_string());
+    }
+    while let Some(token) = stack.pop() {
+        match token {
+            Token::Op(op) => program.push(Instr::Op(op)),
+            _ => return Err("unbalanced '('".to_string()),
+        }
+    }
+    Ok(program)
+}
+
+/// All field ids referenced by a program, in order of first appearance.
+pub fn fields(program: &[Instr]) -> Vec<&str> {
+    program
+        .iter()
+        .filter_map(|instr| match instr {
+            Instr::Field(field) => Some(field.as_str()),
+            _ => None,
+        })
+        .unique()
+        .collect()
+}
+
+/// Inline script evaluating `program` into the `<output>` with id `output_id`.
+///
+/// The script only ever interprets the instruction list; field values are read
+/// from the element with the referenced id or the checked radio of that name.
+pub fn script(program: &[Instr], output_id: &str) -> String {
+    let instrs = program
+        .iter()
+        .map(|instr| match instr {
+            Instr::Number(value) => format!(r#"["n",{value}]"#),
+            Instr::Field(field) => format!(r#"["f","{field}"]"#),
+            Instr::Op(op) => format!(r#"["o","{op}"]"#),
+        })
+        .join(",");
+    format!(
+        r#"<script>(function(){{var p=[{instrs}];var o=document.getElementById("{output_id}");function v(f){{var e=document.getElementById(f)||document.querySelector('[name="'+f+'"]:checked');return e?parseFloat(e.value.replace(",",".")):NaN}}function u(){{var s=[];p.forEach(function(i){{if(i[0]=="n"){{s.push(i[1])}}else if(i[0]=="f"){{s.push(v(i[1]))}}else{{var b=s.pop(),a=s.pop();s.push(i[1]=="+"?a+b:i[1]=="-"?a-b:i[1]=="*"?a*b:a/b)}}}});var r=s.pop();o.value=isFinite(r)?r:""}}document.addEventListener("input",u);u()}})();</script>"#
+    )
+}
@@ -0,0 +1,147 @@
+//! Filters on answer values for exports, written as `<question id>:<op>:<value>`,
+//! e.g. `i-0-3:eq:Pizza` or `i-0-2:gte:4`.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{Form, QuestionType};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    Contains,
+}
+
+impl Op {
+    fn parse(op: &str) -> Option<Op> {
+        Some(match op {
+            "eq" => Op::Eq,
+            "ne" => Op::Ne,
+            "lt" => Op::Lt,
+            "lte" => Op::Lte,
+            "gt" => Op::Gt,
+            "gte" => Op::Gte,
+            "contains" => Op::Contains,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Op::Eq => "eq",
+            Op::Ne => "ne",
+            Op::Lt => "lt",
+            Op::Lte => "lte",
+            Op::Gt => "gt",
+            Op::Gte => "gte",
+            Op::Contains => "contains",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Number(f64),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub id: String,
+    pub op: Op,
+    operand: Operand,
+}
+
+impl Filter {
+    /// Parses a filter and checks it against the question it refers to:
+    /// comparisons need a numeric question, `contains` a text question.
+    pub fn parse(expression: &str, form: &Form) -> Result<Filter, String> {
+        let mut parts = expression.splitn(3, ':');
+        let (Some(id), Some(op), Some(value)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(format!(
+                "filter '{expression}' is not of the form <question id>:<op>:<value>"
+            ));
+        };
+        let op = Op::parse(op).ok_or_else(|| {
+            format!("unknown operator '{op}' in filter '{expression}' (expected eq, ne, lt, lte, gt, gte or contains)")
+        })?;
+        let question = form
+            .questions()
+            .find(|(question_id, _)| question_id == id)
+            .map(|(_, question)| question)
+            .ok_or_else(|| format!("unknown question id '{id}' in filter '{expression}'"))?;
+
+        let numeric = matches!(
+            question.spec,
+            QuestionType::DiscreteNumeric { .. } | QuestionType::ContinousNumeric { .. }
+        );
+        let text = matches!(question.spec, QuestionType::Text { .. });
+        let operand = match op {
+            Op::Lt | Op::Lte | Op::Gt | Op::Gte if !numeric => {
+                return Err(format!(
+                    "operator '{op}' needs a numeric question, {id} is {}",
+                    question.spec.kind()
+                ))
+            }
+            Op::Contains if !text => {
+                return Err(format!(
+                    "operator 'contains' needs a text question, {id} is {}",
+                    question.spec.kind()
+                ))
+            }
+            _ if numeric => Operand::Number(
+                value
+                    .parse()
+                    .map_err(|_| format!("'{value}' is not a number (filter '{expression}')"))?,
+            ),
+            _ => Operand::Text(value.to_string()),
+        };
+        Ok(Filter {
+            id: id.to_string(),
+            op,
+            operand,
+        })
+    }
+
+    /// Whether any of the answers to the filtered question satisfies the
+    /// filter (`ne`: none of them equals the value). Unanswered never matches.
+    pub fn matches(&self, answers: &HashMap<String, Vec<String>>) -> bool {
+        let Some(values) = answers.get(&self.id).filter(|values| !values.is_empty()) else {
+            return false;
+        };
+        if self.op == Op::Ne {
+            return !values.iter().any(|value| self.test(Op::Eq, value));
+        }
+        values.iter().any(|value| self.test(self.op, value))
+    }
+
+    fn test(&self, op: Op, value: &str) -> bool {
+        match &self.operand {
+            Operand::Number(expected) => {
+                let Ok(value) = value.parse::<f64>() else {
+                    return false;
+                };
+                match op {
+                    Op::Eq => value == *expected,
+                    Op::Lt => value < *expected,
+                    Op::Lte => value <= *expected,
+                    Op::Gt => value > *expected,
+                    Op::Gte => value >= *expected,
+                    Op::Ne | Op::Contains => false,
+                }
+            }
+            Operand::Text(expected) => match op {
+                Op::Eq => value == expected,
+                Op::Contains => value.to_lowercase().contains(&expected.to_lowercase()),
+                _ => false,
+            },
+        }
+    }
+}
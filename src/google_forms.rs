@@ -0,0 +1,204 @@
+//! Conversion of Google Forms definitions, as returned by the Forms API
+//! (`forms.get`), into our own form model.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use crate::{Answer, Form, Localized, Question, QuestionSet, QuestionType};
+
+#[derive(Deserialize)]
+struct Export {
+    #[serde(default)]
+    info: Info,
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize, Default)]
+struct Info {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    description: String,
+    question_item: Option<QuestionItem>,
+    page_break_item: Option<serde_json::Value>,
+    /// Everything else; the key names the kind of item (`questionGroupItem`,
+    /// `imageItem`, ...).
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct QuestionItem {
+    question: GoogleQuestion,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleQuestion {
+    #[serde(default)]
+    required: bool,
+    choice_question: Option<ChoiceQuestion>,
+    text_question: Option<TextQuestion>,
+    scale_question: Option<ScaleQuestion>,
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ChoiceQuestion {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    options: Vec<ChoiceOption>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChoiceOption {
+    #[serde(default)]
+    value: String,
+    #[serde(default)]
+    is_other: bool,
+}
+
+#[derive(Deserialize)]
+struct TextQuestion {
+    #[serde(default)]
+    paragraph: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScaleQuestion {
+    low: i8,
+    high: i8,
+    #[serde(default)]
+    low_label: String,
+    #[serde(default)]
+    high_label: String,
+}
+
+/// Converts an export. Items without a counterpart here (grids, file
+/// uploads, dates, media, ...) are left out and reported as warnings.
+pub fn convert(json: &str) -> Result<(Form, Vec<String>), serde_json::Error> {
+    let export: Export = serde_json::from_str(json)?;
+    let mut warnings = Vec::new();
+    let mut groups = vec![QuestionSet {
+        title: Localized::default(),
+        description: Localized::default(),
+        questions: Vec::new(),
+        show_if: None,
+        collapsible: false,
+    }];
+    for item in export.items {
+        if item.page_break_item.is_some() {
+            groups.push(QuestionSet {
+                title: item.title.into(),
+                description: item.description.into(),
+                questions: Vec::new(),
+                show_if: None,
+                collapsible: false,
+            });
+            continue;
+        }
+        let (required, spec) = match item.question_item {
+            Some(question_item) => (
+                question_item.question.required,
+                spec(question_item.question),
+            ),
+            None => (false, Err(kind(&item.other))),
+        };
+        match spec {
+            Ok(spec) => groups.last_mut().unwrap().questions.push(Question {
+                title: item.title.into(),
+                key: None,
+                help: (!item.description.is_empty()).then(|| item.description.into()),
+                required,
+                correct: None,
+                points: None,
+                explanation: None,
+                show_if: None,
+                spec,
+            }),
+            Err(kind) => warnings.push(format!(
+                "'{}': {kind} is not supported, skipped",
+                item.title
+            )),
+        }
+    }
+    groups.retain(|group| !group.questions.is_empty());
+    let form = Form {
+        title: export.info.title.into(),
+        description: export.info.description.into(),
+        groups,
+        ..Default::default()
+    };
+    Ok((form, warnings))
+}
+
+/// Name of the item or question kind present among `fields`.
+fn kind(fields: &serde_json::Map<String, serde_json::Value>) -> String {
+    fields
+        .keys()
+        .find(|key| key.ends_with("Item") || key.ends_with("Question"))
+        .cloned()
+        .unwrap_or_else(|| "unknown item".to_string())
+}
+
+fn spec(question: GoogleQuestion) -> Result<QuestionType, String> {
+    if let Some(choice) = question.choice_question {
+        let custom_answer = choice.options.iter().any(|option| option.is_other);
+        let answers = choice
+            .options
+            .into_iter()
+            .filter(|option| !option.is_other)
+            .map(|option| Answer::from(option.value.as_str()))
+            .collect();
+        // Dropdowns are shown as radio buttons.
+        return match choice.kind.as_str() {
+            "RADIO" | "DROP_DOWN" => Ok(QuestionType::SingleChoice {
+                answers,
+                custom_answer,
+                columns: None,
+            }),
+            "CHECKBOX" => Ok(QuestionType::MultipleChoice {
+                answers,
+                custom_answer,
+                columns: None,
+            }),
+            kind => Err(format!("choiceQuestion of type {kind}")),
+        };
+    }
+    if let Some(text) = question.text_question {
+        return Ok(QuestionType::Text {
+            is_long: text.paragraph,
+            pattern: None,
+            max_length: None,
+            warn_at: None,
+        });
+    }
+    if let Some(scale) = question.scale_question {
+        let num_descriptions = [(scale.low, scale.low_label), (scale.high, scale.high_label)]
+            .into_iter()
+            .filter(|(_, label)| !label.is_empty())
+            .map(|(value, label)| (value, label.into()))
+            .collect::<BTreeMap<_, _>>();
+        return Ok(QuestionType::DiscreteNumeric {
+            bounds: (scale.low, scale.high),
+            num_descriptions,
+            na_option: None,
+            columns: None,
+        });
+    }
+    Err(kind(&question.other))
+}
@@ -0,0 +1,401 @@
+//! Languages: choosing one per request and the built-in texts around the
+//! form's own (see `Localized` for those).
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{Form, Localized};
+
+/// Built-in texts of one language. `{name}` placeholders are filled in with
+/// `fill`.
+pub struct Texts {
+    pub submit: &'static str,
+    pub save: &'static str,
+    pub save_draft: &'static str,
+    /// Labels the button clearing all answers (`show_reset`).
+    pub reset: &'static str,
+    pub start: &'static str,
+    pub invalid_answers: &'static str,
+    pub estimate_one: &'static str,
+    pub estimate_many: &'static str,
+    /// Follow the title of a closed `collapsible` set.
+    pub question_count_one: &'static str,
+    pub question_count: &'static str,
+    pub closes_soon: &'static str,
+    pub in_one_hour: &'static str,
+    pub in_hours: &'static str,
+    pub in_one_minute: &'static str,
+    pub in_minutes: &'static str,
+    /// `strftime` format of dates.
+    pub date_format: &'static str,
+    pub thank_you: &'static str,
+    pub edit_later: &'static str,
+    pub delete_later: &'static str,
+    pub not_open: &'static str,
+    pub closed: &'static str,
+    pub final_count_one: &'static str,
+    pub final_count: &'static str,
+    pub full: &'static str,
+    pub already_submitted: &'static str,
+    pub draft_saved: &'static str,
+    pub confirm_submit: &'static str,
+    pub confirm_submit_button: &'static str,
+    pub change_answers: &'static str,
+    pub confirm_delete: &'static str,
+    pub delete_button: &'static str,
+    pub deleted: &'static str,
+    pub score: &'static str,
+    pub answer_correct: &'static str,
+    pub answer_partly: &'static str,
+    pub answer_wrong: &'static str,
+    pub not_graded: &'static str,
+    pub no_answer: &'static str,
+    pub result_later: &'static str,
+    pub preview: &'static str,
+    pub preview_discarded: &'static str,
+    /// Names the CAPTCHA in the list of problems.
+    pub captcha: &'static str,
+    pub captcha_failed: &'static str,
+    pub captcha_needs_js: &'static str,
+    /// Label of the response count badge.
+    pub responses: &'static str,
+    /// Labels the free-text option of choice questions.
+    pub other: &'static str,
+    pub required: &'static str,
+    pub only_one: &'static str,
+    pub choose_between: &'static str,
+    pub enter_number: &'static str,
+    pub number_between: &'static str,
+    pub not_an_option: &'static str,
+    pub one_custom: &'static str,
+    pub match_format: &'static str,
+    pub too_long: &'static str,
+    pub computed: &'static str,
+    pub unsupported: &'static str,
+    /// Separates the fraction in numbers typed in by respondents.
+    pub decimal_separator: char,
+}
+
+pub const EN: Texts = Texts {
+    submit: "Submit",
+    save: "Save",
+    save_draft: "Save draft",
+    reset: "Clear answers",
+    start: "Start",
+    invalid_answers: "Some answers are invalid:",
+    estimate_one: "Estimated time: ~1 minute",
+    estimate_many: "Estimated time: ~{minutes} minutes",
+    question_count_one: "(1 question)",
+    question_count: "({count} questions)",
+    closes_soon: "This survey closes on {date} at {time} ({remaining}).",
+    in_one_hour: "in 1 hour",
+    in_hours: "in {n} hours",
+    in_one_minute: "in 1 minute",
+    in_minutes: "in {n} minutes",
+    date_format: "%Y-%m-%d",
+    thank_you: "Thank you for your submission.",
+    edit_later: "You can change your answers later at {link}.",
+    delete_later: "You can delete your response at {link}.",
+    not_open: "This survey is not open yet.",
+    closed: "This survey is closed.",
+    final_count_one: "It received 1 response.",
+    final_count: "It received {count} responses.",
+    full: "This survey is full - it has reached its maximum number of responses.",
+    already_submitted: "A response from your network connection has already been submitted.",
+    draft_saved: "Your answers have been saved. Continue later at {link} - keep this link, it is the only way back to your draft.",
+    confirm_submit: "Are you sure you want to submit your answers?",
+    confirm_submit_button: "Yes, submit",
+    change_answers: "Change answers",
+    confirm_delete: "Do you want to delete your response? This cannot be undone.",
+    delete_button: "Delete my response",
+    deleted: "Your response has been deleted.",
+    score: "You scored {points} of {max} points.",
+    answer_correct: "correct",
+    answer_partly: "partly correct",
+    answer_wrong: "wrong",
+    not_graded: "not graded",
+    no_answer: "(no answer)",
+    result_later: "You can look at your result again at {link}.",
+    preview: "PREVIEW: answers are not saved",
+    preview_discarded: "This was a preview, nothing was saved.",
+    captcha: "Spam check",
+    captcha_failed: "the spam check failed, please try it again",
+    captcha_needs_js: "This form can only be sent with JavaScript enabled, which its spam check needs.",
+    responses: "responses",
+    other: "Other:",
+    required: "please answer this question",
+    only_one: "only one answer is allowed",
+    choose_between: "please choose a value between {min} and {max}",
+    enter_number: "please enter a number",
+    number_between: "please enter a number between {min} and {max}",
+    not_an_option: "'{value}' is not one of the options",
+    one_custom: "only one custom answer is allowed",
+    match_format: "please match the requested format",
+    too_long: "please use at most {max} characters",
+    computed: "computed fields cannot be answered",
+    unsupported: "questions of type '{type}' cannot be answered",
+    decimal_separator: '.',
+};
+
+pub const DE: Texts = Texts {
+    submit: "Absenden",
+    save: "Speichern",
+    save_draft: "Entwurf speichern",
+    reset: "Antworten löschen",
+    start: "Starten",
+    invalid_answers: "Einige Antworten sind ungültig:",
+    estimate_one: "Geschätzte Dauer: ~1 Minute",
+    estimate_many: "Geschätzte Dauer: ~{minutes} Minuten",
+    question_count_one: "(1 Frage)",
+    question_count: "({count} Fragen)",
+    closes_soon: "Diese Umfrage schließt am {date} um {time} Uhr ({remaining}).",
+    in_one_hour: "in 1 Stunde",
+    in_hours: "in {n} Stunden",
+    in_one_minute: "in 1 Minute",
+    in_minutes: "in {n} Minuten",
+    date_format: "%d.%m.%Y",
+    thank_you: "Vielen Dank für Ihre Teilnahme.",
+    edit_later: "Sie können Ihre Antworten später unter {link} ändern.",
+    delete_later: "Sie können Ihre Antwort unter {link} löschen.",
+    not_open: "Diese Umfrage ist noch nicht geöffnet.",
+    closed: "Diese Umfrage ist geschlossen.",
+    final_count_one: "Sie hat 1 Antwort erhalten.",
+    final_count: "Sie hat {count} Antworten erhalten.",
+    full: "Diese Umfrage ist voll - die maximale Anzahl an Antworten ist erreicht.",
+    already_submitted: "Von Ihrer Netzwerkverbindung wurde bereits eine Antwort abgeschickt.",
+    draft_saved: "Ihre Antworten wurden gespeichert. Sie können später unter {link} weitermachen - bewahren Sie den Link auf, nur mit ihm kommen Sie zu Ihrem Entwurf zurück.",
+    confirm_submit: "Möchten Sie Ihre Antworten wirklich absenden?",
+    confirm_submit_button: "Ja, absenden",
+    change_answers: "Antworten ändern",
+    confirm_delete: "Möchten Sie Ihre Antwort löschen? Das kann nicht rückgängig gemacht werden.",
+    delete_button: "Meine Antwort löschen",
+    deleted: "Ihre Antwort wurde gelöscht.",
+    score: "Sie haben {points} von {max} Punkten erreicht.",
+    answer_correct: "richtig",
+    answer_partly: "teilweise richtig",
+    answer_wrong: "falsch",
+    not_graded: "nicht bewertet",
+    no_answer: "(keine Antwort)",
+    result_later: "Sie können Ihr Ergebnis später unter {link} noch einmal ansehen.",
+    preview: "VORSCHAU: Antworten werden nicht gespeichert",
+    preview_discarded: "Dies war eine Vorschau, es wurde nichts gespeichert.",
+    captcha: "Spamschutz",
+    captcha_failed: "die Spamschutz-Prüfung ist fehlgeschlagen, bitte versuchen Sie es erneut",
+    captcha_needs_js: "Dieses Formular kann nur mit aktiviertem JavaScript abgeschickt werden, das die Spamschutz-Prüfung benötigt.",
+    responses: "Antworten",
+    other: "Sonstiges:",
+    required: "bitte beantworten Sie diese Frage",
+    only_one: "nur eine Antwort ist erlaubt",
+    choose_between: "bitte wählen Sie einen Wert zwischen {min} und {max}",
+    enter_number: "bitte geben Sie eine Zahl ein",
+    number_between: "bitte geben Sie eine Zahl zwischen {min} und {max} ein",
+    not_an_option: "'{value}' ist keine der Optionen",
+    one_custom: "nur eine eigene Antwort ist erlaubt",
+    match_format: "bitte halten Sie sich an das verlangte Format",
+    too_long: "bitte verwenden Sie höchstens {max} Zeichen",
+    computed: "berechnete Felder können nicht beantwortet werden",
+    unsupported: "Fragen vom Typ '{type}' können nicht beantwortet werden",
+    decimal_separator: ',',
+};
+
+/// The validation messages, which forms can replace with their own
+/// (`messages: { required: ... }`).
+#[derive(
+    Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Message {
+    Required,
+    OnlyOne,
+    /// `{min}` and `{max}` are the question's bounds.
+    ChooseBetween,
+    EnterNumber,
+    /// `{min}` and `{max}` are the question's bounds.
+    NumberBetween,
+    /// `{value}` is the answer given.
+    NotAnOption,
+    OneCustom,
+    MatchFormat,
+    /// `{max}` is the question's `max_length`.
+    TooLong,
+    Computed,
+    /// `{type}` is the question's type.
+    Unsupported,
+}
+
+impl Message {
+    fn builtin(self, texts: &'static Texts) -> &'static str {
+        match self {
+            Message::Required => texts.required,
+            Message::OnlyOne => texts.only_one,
+            Message::ChooseBetween => texts.choose_between,
+            Message::EnterNumber => texts.enter_number,
+            Message::NumberBetween => texts.number_between,
+            Message::NotAnOption => texts.not_an_option,
+            Message::OneCustom => texts.one_custom,
+            Message::MatchFormat => texts.match_format,
+            Message::TooLong => texts.too_long,
+            Message::Computed => texts.computed,
+            Message::Unsupported => texts.unsupported,
+        }
+    }
+}
+
+/// The validation messages of one language: the form's own where it gives
+/// them in that language, else the built-in ones.
+pub struct Messages<'a> {
+    texts: &'static Texts,
+    lang: &'a str,
+    own: Option<&'a BTreeMap<Message, Localized>>,
+}
+
+impl<'a> Messages<'a> {
+    pub fn new(form: &'a Form, lang: &'a str) -> Self {
+        Messages {
+            texts: texts(lang),
+            lang,
+            own: Some(&form.messages),
+        }
+    }
+
+    /// The built-in messages of `lang`.
+    pub fn builtin(lang: &'a str) -> Self {
+        Messages {
+            texts: texts(lang),
+            lang,
+            own: None,
+        }
+    }
+
+    /// The template of `message`; fill it in with `fill`.
+    pub fn get(&self, message: Message) -> &'a str {
+        let own = self.own.and_then(|own| match own.get(&message)? {
+            Localized::Plain(text) => Some(text.as_str()),
+            // A message in another language would not fit the page.
+            Localized::Translated(texts) => texts.get(self.lang).map(String::as_str),
+        });
+        own.unwrap_or_else(|| message.builtin(self.texts))
+    }
+}
+
+/// The built-in texts for `lang`, English for languages without them.
+pub fn texts(lang: &str) -> &'static Texts {
+    match primary(lang).as_str() {
+        "de" => &DE,
+        _ => &EN,
+    }
+}
+
+/// Replaces the `{name}` placeholders of `template`.
+pub fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value)
+        })
+}
+
+/// Reads a number as typed by someone writing `lang`. Both `,` and `.` are
+/// taken as decimal separator; one of them is a thousands separator instead
+/// when the other one follows it (`1.234,5`), when it occurs several times,
+/// or when it is not the language's decimal separator and three digits
+/// follow it (`1,234` in English). Spaces between digit groups are ignored.
+pub fn parse_number(input: &str, lang: &str) -> Option<f32> {
+    let digits: String = input
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '\u{a0}' | '\u{2009}' | '\u{202f}'))
+        .collect();
+    let last = |c: char| digits.rfind(c);
+    let count = |c: char| digits.matches(c).count();
+    let decimal = match (last(','), last('.')) {
+        (Some(comma), Some(dot)) => Some(if comma > dot { ',' } else { '.' }),
+        (None, None) => None,
+        (Some(_), None) if count(',') > 1 => None,
+        (None, Some(_)) if count('.') > 1 => None,
+        (Some(position), None) | (None, Some(position)) => {
+            let separator = digits[position..].chars().next()?;
+            let grouping =
+                separator != texts(lang).decimal_separator && digits[position + 1..].len() == 3;
+            (!grouping).then_some(separator)
+        }
+    };
+    let (integer, fraction) = match decimal {
+        Some(separator) => digits.rsplit_once(separator)?,
+        None => (digits.as_str(), ""),
+    };
+    // Whatever separator is left groups the integer part in threes.
+    let mut groups = integer.split([',', '.']);
+    let first = groups.next()?;
+    let grouped = groups.all(|group| group.len() == 3);
+    let unsigned = first.strip_prefix(['-', '+']).unwrap_or(first);
+    let well_formed = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if !grouped
+        || unsigned.is_empty() && (integer.len() > first.len() || fraction.is_empty())
+        || integer.len() > first.len() && unsigned.len() > 3
+        || !well_formed(unsigned)
+        || !well_formed(fraction)
+    {
+        return None;
+    }
+    let canonical = format!("{}.{fraction}", integer.replace([',', '.'], ""));
+    canonical
+        .trim_end_matches('.')
+        .parse::<f32>()
+        .ok()
+        .filter(|value| value.is_finite())
+}
+
+/// How a language calls itself, for the language switcher.
+pub fn language_name(lang: &str) -> &str {
+    match primary(lang).as_str() {
+        "de" => "Deutsch",
+        "en" => "English",
+        "fr" => "Français",
+        "es" => "Español",
+        "it" => "Italiano",
+        "nl" => "Nederlands",
+        _ => lang,
+    }
+}
+
+/// The primary subtag, lowercased: `de-AT` -> `de`.
+fn primary(tag: &str) -> String {
+    tag.split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// Language to serve `form` in: the `?lang=` parameter if the form has it,
+/// else the best `Accept-Language` match, else the form's default.
+pub fn choose(form: &Form, requested: Option<&str>, accept_language: Option<&str>) -> String {
+    let available = form.languages();
+    let offered = |tag: &str| {
+        let tag = primary(tag);
+        available.iter().find(|lang| primary(lang) == tag).cloned()
+    };
+    if let Some(lang) = requested.and_then(offered) {
+        return lang;
+    }
+    let mut accepted: Vec<(f32, &str)> = accept_language
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.parse().ok())?;
+            (!tag.is_empty() && quality > 0.0).then_some((quality, tag))
+        })
+        .collect();
+    // Stable, so equally weighted languages keep the client's order.
+    accepted.sort_by(|a, b| b.0.total_cmp(&a.0));
+    accepted
+        .into_iter()
+        .find_map(|(_, tag)| offered(tag))
+        .unwrap_or_else(|| form.language().to_string())
+}
@@ -0,0 +1,146 @@
+//! Reading responses collected on paper from a CSV file, one row per
+//! respondent and one column per question.
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use crate::{Form, Localized, Question, QuestionType};
+
+/// Separates the chosen options of a multiple choice question within a cell.
+pub const VALUE_SEPARATOR: char = ';';
+
+pub struct ImportedRow {
+    /// Line of the row in the CSV file, for reporting.
+    pub line: u64,
+    pub answers: HashMap<String, Vec<String>>,
+}
+
+pub struct RowError {
+    pub line: u64,
+    pub message: String,
+}
+
+/// Finds the question a column is about. `mapping` translates headers to
+/// question ids or titles first; a header mapped to `None` is ignored.
+fn resolve_column(
+    form: &Form,
+    header: &str,
+    mapping: &HashMap<String, Option<String>>,
+) -> Result<Option<String>, String> {
+    let name = match mapping.get(header) {
+        Some(None) => return Ok(None),
+        Some(Some(name)) => name.as_str(),
+        None => header,
+    }
+    .trim();
+    if let Some((id, _)) = form.questions().find(|(id, _)| id == name) {
+        return Ok(Some(id));
+    }
+    let matching: Vec<String> = form
+        .questions()
+        .filter(|(_, question)| {
+            in_any_language(form, &question.title, |title| {
+                title.trim().eq_ignore_ascii_case(name)
+            })
+        })
+        .map(|(id, _)| id)
+        .collect();
+    match matching.as_slice() {
+        [id] => Ok(Some(id.clone())),
+        [] => Err(format!("column '{header}' does not match any question")),
+        _ => Err(format!(
+            "column '{header}' matches several questions ({}), map it to a question id",
+            matching.join(", ")
+        )),
+    }
+}
+
+fn in_any_language(form: &Form, text: &Localized, matches: impl Fn(&str) -> bool) -> bool {
+    form.languages()
+        .iter()
+        .any(|lang| matches(text.get(lang, form.language())))
+}
+
+/// Options may be typed in by their label, in any language; they are
+/// stored by value.
+fn option_value(form: &Form, spec: &QuestionType, cell: &str) -> String {
+    let Some(answers) = spec.answers() else {
+        return cell.to_string();
+    };
+    if answers.iter().any(|answer| answer.value() == cell) {
+        return cell.to_string();
+    }
+    answers
+        .iter()
+        .find(|answer| in_any_language(form, &answer.label, |label| label == cell))
+        .map_or(cell, |answer| answer.value())
+        .to_string()
+}
+
+/// Reads and validates every row. Rows that fail validation are reported
+/// and left out; an error in the header fails the whole import.
+pub fn read_responses(
+    form: &Form,
+    input: impl Read,
+    mapping: &HashMap<String, Option<String>>,
+) -> Result<(Vec<ImportedRow>, Vec<RowError>), String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(input);
+    let columns = reader
+        .headers()
+        .map_err(|err| format!("could not read header: {err}"))?
+        .iter()
+        .map(|header| resolve_column(form, header, mapping))
+        .collect::<Result<Vec<_>, _>>()?;
+    let questions: HashMap<String, &Question> = form.questions().collect();
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                errors.push(RowError {
+                    line: err.position().map_or(0, |position| position.line()),
+                    message: err.to_string(),
+                });
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, |position| position.line());
+        let mut answers: HashMap<String, Vec<String>> = HashMap::new();
+        for (id, cell) in columns.iter().zip(record.iter()) {
+            let Some(id) = id else { continue };
+            if cell.is_empty() {
+                continue;
+            }
+            let spec = &questions[id].spec;
+            let values = answers.entry(id.clone()).or_default();
+            if matches!(spec, QuestionType::MultipleChoice { .. }) {
+                values.extend(
+                    cell.split(VALUE_SEPARATOR)
+                        .map(str::trim)
+                        .filter(|value| !value.is_empty())
+                        .map(|value| option_value(form, spec, value)),
+                );
+            } else {
+                values.push(option_value(form, spec, cell));
+            }
+        }
+        form.normalize_answers(&mut answers, form.language());
+        form.drop_hidden_answers(&mut answers);
+        match form.validate_submission(&answers) {
+            Ok(()) => rows.push(ImportedRow { line, answers }),
+            Err(field_errors) => errors.extend(field_errors.into_iter().map(|error| RowError {
+                line,
+                message: format!(
+                    "{}: {}",
+                    form.text(&questions[&error.id].title),
+                    error.message
+                ),
+            })),
+        }
+    }
+    Ok((rows, errors))
+}
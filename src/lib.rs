@@ -0,0 +1,2014 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use itertools::Itertools;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+pub mod a11y;
+pub mod admin;
+pub mod api;
+pub mod backup;
+pub mod badge;
+pub mod browser;
+pub mod captcha;
+pub mod drafts;
+pub mod embed;
+pub mod export;
+pub mod expression;
+pub mod filter;
+pub mod google_forms;
+pub mod i18n;
+pub mod import;
+pub mod live;
+pub mod markdown;
+pub mod merge;
+pub mod openapi;
+pub mod print;
+pub mod quiz;
+pub mod scores;
+pub mod server;
+pub mod signing;
+pub mod sinks;
+pub mod stats;
+pub mod store;
+pub mod templates;
+pub mod validate;
+pub mod watch;
+
+use templates::Templates;
+pub use validate::{FieldError, Limits, ValidationError};
+
+pub const ROOT_PREFIX: &str = "i";
+
+/// Escapes text for use in HTML content and attribute values.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// JSON Schema of form definitions (in their JSON form; YAML and TOML map
+/// onto it), for checking generated definitions before deploying them.
+pub fn form_schema() -> schemars::schema::RootSchema {
+    schemars::schema_for!(Form)
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    /// The file extension does not name a supported format.
+    UnknownFormat(String),
+    Parse(String),
+    Invalid(ValidationError),
+    /// A file the form includes (`groups: [{ include: ... }]`) did not load.
+    Include(PathBuf, Box<LoadError>),
+    /// The files include each other, in this order.
+    IncludeCycle(Vec<PathBuf>),
+    /// A `{ template: name }` entry does not fit its template.
+    Template(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "could not read form: {err}"),
+            LoadError::UnknownFormat(ext) => write!(
+                f,
+                "unsupported form format '{ext}' (expected .yml, .yaml, .toml or .json)"
+            ),
+            LoadError::Parse(err) => write!(f, "could not parse form: {err}"),
+            LoadError::Invalid(err) => write!(f, "{err}"),
+            LoadError::Include(path, err) => write!(f, "in {}: {err}", path.display()),
+            LoadError::IncludeCycle(chain) => write!(
+                f,
+                "files include each other: {}",
+                chain.iter().map(|path| path.display()).join(" -> ")
+            ),
+            LoadError::Template(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// File extensions `load_form` understands.
+pub const FORM_EXTENSIONS: [&str; 4] = ["yml", "yaml", "toml", "json"];
+
+/// Loads a form from YAML, TOML or JSON, chosen by the file extension.
+/// Entries `{ include: path }` in its `groups` are replaced by the question
+/// set, or list of them, in that file (relative to the including one), which
+/// may include further files. References to its `templates` are expanded,
+/// in included files too.
+pub fn load_form(path: &Path, limits: &Limits) -> Result<Form, LoadError> {
+    let (ext, content) = read_definition(path)?;
+    parse_form(&ext, &content, path, limits)
+}
+
+/// Reads a definition in the format of the extension `ext` like `load_form`
+/// would from a file at `path`, which need not exist: includes are found
+/// relative to it.
+pub fn parse_form(
+    ext: &str,
+    content: &str,
+    path: &Path,
+    limits: &Limits,
+) -> Result<Form, LoadError> {
+    let mut value = parse_value(ext, content)?;
+    let templates = Templates::take(&mut value).map_err(LoadError::Template)?;
+    let groups = value
+        .get_mut("groups")
+        .and_then(|groups| match groups {
+            serde_yaml::Value::Sequence(groups) => Some(groups),
+            _ => None,
+        })
+        .filter(|groups| {
+            !templates.is_empty() || groups.iter().any(|group| group.get("include").is_some())
+        });
+    let form: Form = match groups {
+        Some(groups) => {
+            let mut chain = vec![canonical(path).unwrap_or_else(|_| path.to_path_buf())];
+            *groups = resolve_groups(std::mem::take(groups), path, &mut chain, &templates)?;
+            serde_yaml::from_value(value).map_err(|err| LoadError::Parse(err.to_string()))?
+        }
+        // Parsed from the text, for errors with line numbers.
+        None => match ext {
+            // TOML dates are no strings, so forms using them are read from
+            // the converted value, like composed ones.
+            "toml" if has_toml_dates(content) => {
+                serde_yaml::from_value(value).map_err(|err| err.to_string())
+            }
+            "toml" => toml::from_str(content).map_err(|err| err.to_string()),
+            "json" => serde_json::from_str(content).map_err(|err| err.to_string()),
+            _ => serde_yaml::from_str(content).map_err(|err| err.to_string()),
+        }
+        .map_err(LoadError::Parse)?,
+    };
+    form.validate(limits).map_err(LoadError::Invalid)?;
+    Ok(form)
+}
+
+/// The extension and content of a definition file.
+fn read_definition(path: &Path) -> Result<(String, String), LoadError> {
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if !FORM_EXTENSIONS.contains(&ext.as_str()) {
+        return Err(LoadError::UnknownFormat(ext));
+    }
+    let content = std::fs::read_to_string(path).map_err(LoadError::Io)?;
+    Ok((ext, content))
+}
+
+fn canonical(path: &Path) -> Result<PathBuf, LoadError> {
+    path.canonicalize().map_err(LoadError::Io)
+}
+
+/// A definition in any of the formats, as YAML.
+fn parse_value(ext: &str, content: &str) -> Result<serde_yaml::Value, LoadError> {
+    /// TOML has dates of its own; the form reads them from strings.
+    fn from_toml(value: toml::Value) -> serde_yaml::Value {
+        use serde_yaml::Value;
+        match value {
+            toml::Value::String(text) => Value::String(text),
+            toml::Value::Integer(number) => Value::Number(number.into()),
+            toml::Value::Float(number) => Value::Number(number.into()),
+            toml::Value::Boolean(flag) => Value::Bool(flag),
+            toml::Value::Datetime(date) => Value::String(date.to_string()),
+            toml::Value::Array(items) => {
+                Value::Sequence(items.into_iter().map(from_toml).collect())
+            }
+            toml::Value::Table(table) => Value::Mapping(
+                table
+                    .into_iter()
+                    .map(|(key, value)| (Value::String(key), from_toml(value)))
+                    .collect(),
+            ),
+        }
+    }
+    match ext {
+        "toml" => toml::from_str(content)
+            .map(from_toml)
+            .map_err(|err| err.to_string()),
+        "json" => serde_json::from_str(content).map_err(|err| err.to_string()),
+        _ => serde_yaml::from_str(content).map_err(|err| err.to_string()),
+    }
+    .map_err(LoadError::Parse)
+}
+
+/// Whether the TOML `content` holds any of its native dates.
+fn has_toml_dates(content: &str) -> bool {
+    fn any_date(value: &toml::Value) -> bool {
+        match value {
+            toml::Value::Datetime(_) => true,
+            toml::Value::Array(items) => items.iter().any(any_date),
+            toml::Value::Table(table) => table.values().any(any_date),
+            _ => false,
+        }
+    }
+    toml::from_str(content).is_ok_and(|value| any_date(&value))
+}
+
+/// `groups` with the `{ include: path }` entries replaced by the question
+/// sets in those files and `templates` expanded. `chain` holds the files
+/// being included, outermost first, to catch files that include each other.
+fn resolve_groups(
+    groups: Vec<serde_yaml::Value>,
+    from: &Path,
+    chain: &mut Vec<PathBuf>,
+    templates: &Templates,
+) -> Result<Vec<serde_yaml::Value>, LoadError> {
+    let mut resolved = Vec::new();
+    for (index, group) in groups.into_iter().enumerate() {
+        let Some(include) = group.get("include") else {
+            resolved.push(
+                templates
+                    .expand_set(group, &format!("groups[{index}]"))
+                    .map_err(LoadError::Template)?,
+            );
+            continue;
+        };
+        let path = match include.as_str() {
+            Some(include) => from.parent().unwrap_or(Path::new("")).join(include),
+            None => {
+                return Err(LoadError::Parse(
+                    "include must be the path of a file".to_string(),
+                ))
+            }
+        };
+        if group.as_mapping().is_some_and(|group| group.len() > 1) {
+            return Err(LoadError::Parse(format!(
+                "include of {} has other fields next to it",
+                path.display()
+            )));
+        }
+        let in_file = |err| LoadError::Include(path.clone(), Box::new(err));
+        let file = canonical(&path).map_err(in_file)?;
+        if chain.contains(&file) {
+            chain.push(file);
+            return Err(LoadError::IncludeCycle(std::mem::take(chain)));
+        }
+        let (ext, content) = read_definition(&path).map_err(in_file)?;
+        let sets = match parse_value(&ext, &content).map_err(in_file)? {
+            serde_yaml::Value::Sequence(sets) => sets,
+            set => vec![set],
+        };
+        chain.push(file);
+        let sets = resolve_groups(sets, &path, chain, templates).map_err(|err| match err {
+            LoadError::IncludeCycle(_) => err,
+            err => in_file(err),
+        })?;
+        chain.pop();
+        for set in &sets {
+            serde_yaml::from_value::<QuestionSet>(set.clone())
+                .map_err(|err| in_file(LoadError::Parse(err.to_string())))?;
+        }
+        resolved.extend(sets);
+    }
+    Ok(resolved)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RenderContext {
+    pub form_id: String,
+    pub session: String,
+    /// Answers to prefill the inputs with, keyed by question id.
+    pub values: HashMap<String, Vec<String>>,
+    /// Hidden bookkeeping fields (`_draft`, `_edit`, ...) to post along.
+    pub hidden: Vec<(String, String)>,
+    /// Validation messages of the questions to highlight, keyed by question id.
+    pub errors: HashMap<String, String>,
+    /// Question whose first control gets `autofocus`; set by `Form::render`.
+    pub autofocus: Option<String>,
+    /// Language to render in (see `i18n::choose`); the form's default when empty.
+    pub lang: String,
+    /// Fallback for texts missing in `lang`; set by `Form::render`.
+    pub default_lang: String,
+    /// Leave out all inline scripts and event handlers, so pages can be
+    /// served with a `Content-Security-Policy` forbidding them.
+    pub no_js: bool,
+    /// Numbers to prefix question titles with, keyed by question id; set by
+    /// `Form::render`.
+    pub numbers: HashMap<String, String>,
+    /// Mark the page as a preview, whose submissions are not stored.
+    pub preview: bool,
+    /// Render for an iframe on another page, which provides the context:
+    /// no title and description, and small margins.
+    pub embed: bool,
+    /// Show the form (and `values`) without letting it be changed or
+    /// submitted: every control is `disabled` and there are no buttons.
+    pub disabled: bool,
+    /// Time of the request, for the notice of a form closing soon; without
+    /// it there is none.
+    pub now: Option<DateTime<Utc>>,
+    /// Seed of the order a respondent is shown shuffled questions and
+    /// options in (see `shuffled_order`); the declared order without it.
+    pub seed: Option<u64>,
+    /// Shuffle the questions of each set; set by `Form::render`.
+    pub shuffle_questions: bool,
+    /// Shuffle the options of choice questions; set by `Form::render`.
+    pub shuffle_answers: bool,
+    /// Rendering the questions of a set with a `show_if`, so whether they
+    /// have to be answered depends on other answers; set by
+    /// `QuestionSet::render`.
+    pub conditional: bool,
+    /// Render a `collapsible` set open; set by `Form::render` for the first
+    /// set.
+    pub expanded: bool,
+}
+
+impl RenderContext {
+    pub fn text<'a>(&self, text: &'a Localized) -> &'a str {
+        text.get(&self.lang, &self.default_lang)
+    }
+
+    pub fn texts(&self) -> &'static i18n::Texts {
+        i18n::texts(if self.lang.is_empty() {
+            &self.default_lang
+        } else {
+            &self.lang
+        })
+    }
+
+    /// Indices of `len` items of `key` in the order to show them in.
+    fn order(&self, shuffle: bool, key: &str, len: usize) -> Vec<usize> {
+        match self.seed.filter(|_| shuffle) {
+            Some(seed) => shuffled_order(seed, key, len),
+            None => (0..len).collect(),
+        }
+    }
+}
+
+/// The order a respondent with `seed` was shown `len` shuffled items of
+/// `key` in: the questions of the set with id prefix `key` (`i-0`), or the
+/// options of question `key`. Depends on nothing else, so the order can be
+/// reconstructed from the seed stored with a submission.
+pub fn shuffled_order(seed: u64, key: &str, len: usize) -> Vec<usize> {
+    (0..len)
+        .sorted_by_cached_key(|index| {
+            let mut hasher = Sha256::new();
+            hasher.update(seed.to_le_bytes());
+            hasher.update(key.as_bytes());
+            hasher.update([0]);
+            hasher.update((*index as u64).to_le_bytes());
+            hasher.finalize()
+        })
+        .collect()
+}
+
+/// Text given once for all languages, or per language code:
+/// `title: { de: Umfrage, en: Survey }`.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Localized {
+    Plain(String),
+    Translated(BTreeMap<String, String>),
+}
+
+impl Default for Localized {
+    fn default() -> Self {
+        Localized::Plain(String::new())
+    }
+}
+
+impl From<&str> for Localized {
+    fn from(text: &str) -> Self {
+        Localized::Plain(text.to_string())
+    }
+}
+
+impl From<String> for Localized {
+    fn from(text: String) -> Self {
+        Localized::Plain(text)
+    }
+}
+
+impl Localized {
+    /// The text in `lang`, else in `default`, else in any language given.
+    pub fn get(&self, lang: &str, default: &str) -> &str {
+        match self {
+            Localized::Plain(text) => text,
+            Localized::Translated(texts) => texts
+                .get(lang)
+                .or_else(|| texts.get(default))
+                .or_else(|| texts.values().next())
+                .map_or("", String::as_str),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.get("", "").is_empty()
+    }
+
+    fn languages(&self) -> impl Iterator<Item = &String> {
+        match self {
+            Localized::Plain(_) => None,
+            Localized::Translated(texts) => Some(texts.keys()),
+        }
+        .into_iter()
+        .flatten()
+    }
+}
+
+pub trait Renderable {
+    fn render(&self, prefix: String, ctx: &RenderContext) -> String;
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default)]
+pub struct Form {
+    pub title: Localized,
+    pub description: Localized,
+    /// Markdown shown on a page of its own (e.g. consent text), which
+    /// respondents click through before the questions appear.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intro: Option<Localized>,
+    pub groups: Vec<QuestionSet>,
+    /// Language of the canonical texts, used when a translation is missing
+    /// and for everything shown outside the form (results, exports).
+    /// English when not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_language: Option<String>,
+    #[serde(default)]
+    pub per_group_submit: bool,
+    #[serde(default)]
+    pub opens_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub closes_at: Option<DateTime<Utc>>,
+    /// This many hours before `closes_at` the form starts saying when it
+    /// closes; 48 when not given, 0 never.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closing_notice_hours: Option<u32>,
+    /// Shown before `opens_at` instead of the built-in notice.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_open_page: Option<StatusPage>,
+    /// Shown after `closes_at` instead of the built-in notice.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closed_page: Option<StatusPage>,
+    /// Offset from UTC times are shown in, e.g. `+02:00`; UTC when not given.
+    /// It is fixed, so it does not follow daylight saving time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub utc_offset: Option<String>,
+    /// Preview image for link shares (`og:image`).
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Offer a "save draft" button that hands out a resume link.
+    #[serde(default)]
+    pub drafts: bool,
+    /// Show the questions of each set in an order of their own to every
+    /// respondent, kept while they fill in the form (see
+    /// `RenderContext::seed`).
+    #[serde(default)]
+    pub shuffle_questions: bool,
+    /// The same for the options of choice questions. Custom and "not
+    /// applicable" options stay last.
+    #[serde(default)]
+    pub shuffle_answers: bool,
+    /// Ask respondents whether they are sure before submitting: in a
+    /// `confirm()` dialog, or on a page of its own without scripts.
+    #[serde(default)]
+    pub confirm_submit: bool,
+    /// A button next to submit to start over, clearing every answer including
+    /// custom ones. Without scripts it goes back to the answers the page was
+    /// shown with.
+    #[serde(default)]
+    pub show_reset: bool,
+    /// Page respondents are sent to (`303 See Other`) after submitting,
+    /// instead of the built-in thank-you page. Absolute or root-relative.
+    #[serde(default)]
+    pub redirect_after: Option<String>,
+    /// Shown as "Estimated time: ~N minutes" below the description.
+    #[serde(default)]
+    pub estimated_minutes: Option<u32>,
+    /// Words to leave out of keyword summaries, on top of the built-in list.
+    #[serde(default)]
+    pub stopwords: Vec<String>,
+    /// Move to the next single-line field on Enter instead of submitting.
+    #[serde(default)]
+    pub quick_nav: bool,
+    /// Stop accepting new submissions once the form has this many.
+    #[serde(default)]
+    pub max_responses: Option<usize>,
+    /// Hand out a link that lets respondents change their submission.
+    #[serde(default)]
+    pub editable: bool,
+    /// Hand out a link that lets respondents delete their submission.
+    #[serde(default)]
+    pub deletable: bool,
+    #[serde(default)]
+    pub privacy: Privacy,
+    /// Whether question titles are prefixed with their number.
+    #[serde(default)]
+    pub numbering: Numbering,
+    /// Query parameters of the form URL (e.g. `source`, `utm_campaign`) that
+    /// are stored with each submission. All others are ignored.
+    #[serde(default)]
+    pub track_params: Vec<String>,
+    /// Score submissions against the `correct` answers of the questions.
+    #[serde(default)]
+    pub quiz: bool,
+    /// What quiz respondents learn about their result after submitting.
+    #[serde(default)]
+    pub show_score: ShowScore,
+    /// Scores computed from the numeric answers on submit and stored with
+    /// the submission, see [`scores`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub computed: Vec<scores::ComputedScore>,
+    /// Publish the number of responses (`/form/:id/count`, `/form/:id/badge.svg`).
+    #[serde(default)]
+    pub public_count: bool,
+    /// Question whose per-option counts the live view (`/form/:id/live`)
+    /// shows below the count, as for a poll. Needs `public_count`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub live_question: Option<String>,
+    /// Accept submissions only with a solved CAPTCHA of this provider.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub captcha: Option<captcha::Captcha>,
+    /// How many submissions one respondent may make.
+    #[serde(default)]
+    pub limit: ResponseLimit,
+    /// Validation messages replacing the built-in ones, in the browser and
+    /// from the server.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub messages: BTreeMap<i18n::Message, Localized>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseLimit {
+    #[default]
+    Unlimited,
+    /// One submission per client address, recognised by the hash kept under
+    /// `privacy.client_ip: hashed` (IPv6 addresses by their `/64`). Best
+    /// effort only: people behind one NAT share an address, and anyone
+    /// switching networks gets a new one.
+    OnePerIp,
+}
+
+/// A page of its own for a form that is not open: `not_open_page`,
+/// `closed_page`.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct StatusPage {
+    pub title: Localized,
+    /// Markdown; raw HTML in it is shown as text.
+    #[serde(default)]
+    pub body: Localized,
+    /// E.g. to the results or a follow-up survey.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<PageLink>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct PageLink {
+    pub label: Localized,
+    /// Absolute or root-relative.
+    pub url: String,
+}
+
+/// What is stored about the respondent's connection along with a submission.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default, Clone)]
+pub struct Privacy {
+    #[serde(default)]
+    pub client_ip: ClientIp,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientIp {
+    /// Nothing at all.
+    #[default]
+    None,
+    /// A salted hash, to spot duplicates without knowing the address.
+    Hashed,
+    /// The network part only (`/24` for IPv4, `/48` for IPv6).
+    Truncated,
+}
+
+/// Which side of a continuous numeric input its `unit` goes.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitPosition {
+    /// Before the number, as in "$ 5".
+    Prefix,
+    /// After the number, as in "5 km".
+    #[default]
+    Suffix,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Numbering {
+    #[default]
+    None,
+    /// "3." for the third question of the form.
+    PerForm,
+    /// "2.4" for the fourth question of the second set.
+    PerGroup,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShowScore {
+    #[default]
+    Hidden,
+    /// The achieved points.
+    Total,
+    /// The achieved points and, for every question, the answer given,
+    /// whether it was correct and the explanation.
+    PerQuestion,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Availability {
+    NotYetOpen,
+    Open,
+    Closed,
+}
+
+impl Form {
+    /// Content hash identifying this definition. It is computed from the JSON
+    /// value of the form, whose maps are ordered, so it is stable across loads.
+    pub fn version(&self) -> String {
+        let canonical = serde_json::to_value(self)
+            .expect("forms always serialize")
+            .to_string();
+        let digest = Sha256::digest(canonical.as_bytes());
+        digest[..8]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    /// Whether respondents see questions or options in an order of their own.
+    pub fn shuffles(&self) -> bool {
+        self.shuffle_questions || self.shuffle_answers
+    }
+
+    /// Language of the canonical texts.
+    pub fn language(&self) -> &str {
+        self.default_language.as_deref().unwrap_or("en")
+    }
+
+    /// `text` in the default language.
+    pub fn text<'a>(&self, text: &'a Localized) -> &'a str {
+        text.get(self.language(), self.language())
+    }
+
+    /// The default language and every language a text is translated to.
+    pub fn languages(&self) -> Vec<String> {
+        let texts = self.groups.iter().flat_map(|group| {
+            let questions = group.questions.iter().flat_map(|question| {
+                let options = question
+                    .spec
+                    .answers()
+                    .into_iter()
+                    .flatten()
+                    .map(|answer| &answer.label);
+                let descriptions = match &question.spec {
+                    QuestionType::DiscreteNumeric {
+                        num_descriptions,
+                        na_option,
+                        ..
+                    } => Some(num_descriptions.values().chain(na_option)),
+                    _ => None,
+                };
+                std::iter::once(&question.title)
+                    .chain(options)
+                    .chain(descriptions.into_iter().flatten())
+            });
+            [&group.title, &group.description]
+                .into_iter()
+                .chain(questions)
+        });
+        std::iter::once(self.language())
+            .chain(
+                [&self.title, &self.description]
+                    .into_iter()
+                    .chain(&self.intro)
+                    .chain(texts)
+                    .flat_map(Localized::languages)
+                    .map(String::as_str),
+            )
+            .unique()
+            .map(str::to_string)
+            .collect()
+    }
+
+    pub(crate) fn head(&self, lang: &str) -> String {
+        let image = self
+            .image
+            .as_ref()
+            .map(|image| format!(r#"<meta property="og:image" content="{}">"#, escape(image)))
+            .unwrap_or_default();
+        format!(
+            r#"<meta charset="utf-8"><title>{title}</title><link rel="icon" href="/favicon.ico"><meta property="og:title" content="{title}"><meta property="og:description" content="{description}">{image}"#,
+            title = escape(self.title.get(lang, self.language())),
+            description = escape(self.description.get(lang, self.language())),
+        )
+    }
+
+    /// "This survey closes on ... (in 3 hours)", once `closes_at` is less than
+    /// `closing_notice_hours` away.
+    pub fn closing_notice(&self, now: DateTime<Utc>, lang: &str) -> Option<String> {
+        let closes_at = self.closes_at?;
+        let hours = i64::from(self.closing_notice_hours.unwrap_or(48));
+        let remaining = closes_at - now;
+        if remaining <= chrono::Duration::zero() || remaining > chrono::Duration::hours(hours) {
+            return None;
+        }
+        let texts = i18n::texts(lang);
+        let offset = self
+            .utc_offset
+            .as_ref()
+            .and_then(|offset| offset.parse::<FixedOffset>().ok())
+            .unwrap_or(FixedOffset::east_opt(0).expect("UTC is a valid offset"));
+        let local = closes_at.with_timezone(&offset);
+        let minutes = (remaining.num_seconds() + 59) / 60;
+        let remaining = match minutes {
+            1 => texts.in_one_minute.to_string(),
+            2..=59 => i18n::fill(texts.in_minutes, &[("n", &minutes.to_string())]),
+            60..=119 => texts.in_one_hour.to_string(),
+            _ => i18n::fill(texts.in_hours, &[("n", &(minutes / 60).to_string())]),
+        };
+        Some(i18n::fill(
+            texts.closes_soon,
+            &[
+                ("date", &local.format(texts.date_format).to_string()),
+                ("time", &local.format("%H:%M").to_string()),
+                ("remaining", &remaining),
+            ],
+        ))
+    }
+
+    pub fn availability(&self, now: DateTime<Utc>) -> Availability {
+        match (self.opens_at, self.closes_at) {
+            (Some(opens_at), _) if now < opens_at => Availability::NotYetOpen,
+            (_, Some(closes_at)) if now >= closes_at => Availability::Closed,
+            _ => Availability::Open,
+        }
+    }
+
+    pub fn questions(&self) -> impl Iterator<Item = (String, &Question)> {
+        (0..self.groups.len()).flat_map(|g_idx| self.group_questions(g_idx))
+    }
+
+    /// The questions of the set at `index`, with their ids.
+    pub fn group_questions(&self, index: usize) -> impl Iterator<Item = (String, &Question)> {
+        self.groups.get(index).into_iter().flat_map(move |group| {
+            group
+                .questions
+                .iter()
+                .enumerate()
+                .map(move |(q_idx, q)| (q.id_at(format!("{ROOT_PREFIX}-{index}-{q_idx}")), q))
+        })
+    }
+
+    /// The number of each question under `numbering`, keyed by question id.
+    pub fn question_numbers(&self, numbering: Numbering) -> HashMap<String, String> {
+        (0..self.groups.len())
+            .flat_map(|g_idx| {
+                self.group_questions(g_idx)
+                    .enumerate()
+                    .map(move |(q_idx, (id, _))| (g_idx, q_idx, id))
+            })
+            .enumerate()
+            .filter_map(|(running, (g_idx, q_idx, id))| {
+                let number = match numbering {
+                    Numbering::None => return None,
+                    Numbering::PerForm => format!("{}.", running + 1),
+                    Numbering::PerGroup => format!("{}.{}", g_idx + 1, q_idx + 1),
+                };
+                Some((id, number))
+            })
+            .collect()
+    }
+
+    /// Whether any option of the form has a score.
+    pub fn scores_choices(&self) -> bool {
+        self.questions()
+            .filter_map(|(_, q)| q.spec.answers())
+            .flatten()
+            .any(|answer| answer.score.is_some())
+    }
+
+    /// Sum of the scores of the options selected in `answers`.
+    pub fn score(&self, answers: &HashMap<String, Vec<String>>) -> f32 {
+        self.questions()
+            .filter_map(|(id, q)| Some((answers.get(&id)?, q.spec.answers()?)))
+            .flat_map(|(selected, options)| {
+                selected.iter().filter_map(|value| {
+                    options
+                        .iter()
+                        .find(|answer| answer.value() == value)
+                        .and_then(|answer| answer.score)
+                })
+            })
+            .sum()
+    }
+}
+
+impl Form {
+    /// Links to the form in each of its languages. Left out once answers were
+    /// given, as following a link would lose them.
+    fn language_switcher(&self, ctx: &RenderContext) -> String {
+        let languages = self.languages();
+        if languages.len() < 2 || !ctx.values.is_empty() || !ctx.errors.is_empty() || ctx.disabled {
+            return String::new();
+        }
+        // Relative to the current page when the form is rendered on its own.
+        let base = if ctx.form_id.is_empty() {
+            String::new()
+        } else {
+            format!("/form/{}", ctx.form_id)
+        };
+        let links = languages
+            .iter()
+            .map(|lang| {
+                let lang = escape(lang);
+                let current = if lang == ctx.lang {
+                    r#" aria-current="true""#
+                } else {
+                    ""
+                };
+                format!(
+                    r#"<a href="{base}?lang={lang}{embed}" hreflang="{lang}" lang="{lang}"{current}>{name}</a>"#,
+                    embed = if ctx.embed { "&amp;embed=1" } else { "" },
+                    name = escape(i18n::language_name(&lang))
+                )
+            })
+            .join(" | ");
+        format!(r#"<nav class="languages" aria-label="Language">{links}</nav>"#)
+    }
+
+    /// Links to the invalid questions, in form order, and to a failed
+    /// CAPTCHA, and scrolls to the first.
+    fn error_summary(&self, ctx: &RenderContext) -> String {
+        let captcha = ctx.errors.get(captcha::FIELD).map(|message| {
+            (
+                captcha::FIELD.to_string(),
+                ctx.texts().captcha.to_string(),
+                message,
+            )
+        });
+        let invalid: Vec<(String, String, &String)> = self
+            .questions()
+            .filter_map(|(id, question)| {
+                let message = ctx.errors.get(&id)?;
+                Some((id, escape(ctx.text(&question.title)), message))
+            })
+            .chain(captcha)
+            .collect();
+        let Some((first, _, _)) = invalid.first() else {
+            return String::new();
+        };
+        let items = invalid
+            .iter()
+            .map(|(id, title, message)| {
+                format!(
+                    r##"<li><a href="#{id}-error">{title}</a>: {message}</li>"##,
+                    message = escape(message)
+                )
+            })
+            .join("");
+        format!(
+            r#"<style>.error{{border-left:3px solid #c00;padding-left:.5em}}</style><div class="error-summary" role="alert" aria-live="assertive"><p>{invalid}</p><ul>{items}</ul></div>{scroll}"#,
+            invalid = ctx.texts().invalid_answers,
+            scroll = if ctx.no_js {
+                String::new()
+            } else {
+                format!(
+                    r#"<script>document.addEventListener("DOMContentLoaded",function(){{document.getElementById("{first}-error").scrollIntoView()}})</script>"#
+                )
+            }
+        )
+    }
+}
+
+/// Turns reset buttons into clearing every answer, instead of going back to
+/// the values the page was shown with (drafts, error pages). Custom options
+/// are emptied too, and conditional questions hidden again.
+const RESET_SCRIPT: &str = r#"<script>document.addEventListener("reset",function(e){e.preventDefault();Array.prototype.forEach.call(e.target.elements,function(x){if(x.type==="radio"||x.type==="checkbox"){x.checked=false;if(/-c$/.test(x.id))x.setAttribute("value","")}else if(x.tagName==="TEXTAREA"||x.tagName==="INPUT"&&["text","number","range","email","tel","url"].indexOf(x.type)>=0){x.value=""}else return;x.dispatchEvent(new Event("input",{bubbles:true}))})},true)</script>"#;
+
+/// Enter in a single-line input focuses the next field of its form.
+const QUICK_NAV_SCRIPT: &str = r#"<script>document.addEventListener("keydown",function(e){var t=e.target;if(e.key!=="Enter"||t.tagName!=="INPUT"||!t.form||["text","number","range","email","tel","url"].indexOf(t.type)<0)return;var f=Array.prototype.filter.call(t.form.elements,function(el){return !el.disabled&&el.type!=="hidden"&&el.tagName!=="OUTPUT"&&el.tagName!=="FIELDSET"});var i=f.indexOf(t);if(i>=0&&i+1<f.length){e.preventDefault();f[i+1].focus()}})</script>"#;
+
+/// Hides the `data-show-if` questions whose condition (see `filter::Filter`)
+/// the current answers do not meet. Answers in hidden questions do not count.
+const SHOW_IF_SCRIPT: &str = r#"<script>document.addEventListener("DOMContentLoaded",function(){var c=document.querySelectorAll("[data-show-if]");function v(n){return Array.prototype.filter.call(document.getElementsByName(n),function(e){return(e.type!=="radio"&&e.type!=="checkbox"||e.checked)&&e.value!==""&&!e.closest("[hidden]")}).map(function(e){return e.value})}function t(o,x,w){var a=parseFloat(x.replace(",",".")),b=parseFloat(w);switch(o){case"eq":return x===w||a===b;case"lt":return a<b;case"lte":return a<=b;case"gt":return a>b;case"gte":return a>=b;case"contains":return x.toLowerCase().indexOf(w.toLowerCase())>=0}return false}function u(){c.forEach(function(e){var p=e.getAttribute("data-show-if").split(":"),o=p[1],w=p.slice(2).join(":"),x=v(p[0]);e.hidden=!(x.length&&(o==="ne"?!x.some(function(y){return t("eq",y,w)}):x.some(function(y){return t(o,y,w)})))})}document.addEventListener("input",u);document.addEventListener("change",u);u()})</script>"#;
+
+/// Shows the messages of the page's language (from the script's `data-msg-*`
+/// attributes) instead of the browser's when a control is left empty or does
+/// not match its pattern.
+const VALIDITY_SCRIPT: &str = r#"<script data-msg-required="{required}" data-msg-match-format="{match_format}">var d=document.currentScript.dataset;document.addEventListener("invalid",function(e){var t=e.target,m=t.validity.valueMissing?d.msgRequired:t.validity.patternMismatch?d.msgMatchFormat:"";if(m)t.setCustomValidity(m)},true);function c(e){var t=e.target;if(t.form)Array.prototype.forEach.call(t.form.elements,function(x){if(x.name===t.name&&x.setCustomValidity)x.setCustomValidity("")})}document.addEventListener("input",c);document.addEventListener("change",c)</script>"#;
+
+impl Renderable for Form {
+    fn render(&self, prefix: String, ctx: &RenderContext) -> String {
+        let ctx = &RenderContext {
+            autofocus: self
+                .questions()
+                .filter(|_| !ctx.disabled)
+                .find(|(_, question)| {
+                    !matches!(
+                        question.spec,
+                        QuestionType::Computed { .. } | QuestionType::Unknown { .. }
+                    )
+                })
+                .map(|(id, _)| id),
+            lang: if ctx.lang.is_empty() {
+                self.language().to_string()
+            } else {
+                ctx.lang.clone()
+            },
+            default_lang: self.language().to_string(),
+            numbers: self.question_numbers(self.numbering),
+            shuffle_questions: self.shuffle_questions,
+            shuffle_answers: self.shuffle_answers,
+            ..ctx.clone()
+        };
+        let texts = ctx.texts();
+        let form_id = &ctx.form_id;
+        let reset_button = if self.show_reset {
+            format!(r#"<input type="reset" value="{}">"#, texts.reset)
+        } else {
+            String::new()
+        };
+        let hidden = ctx
+            .hidden
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    r#"<input type="hidden" name="{name}" value="{}">"#,
+                    escape(value)
+                )
+            })
+            .join("");
+        let groups = self
+            .groups
+            .iter()
+            .enumerate()
+            .map(|(idx, q)| {
+                let group = q.render(
+                    format!("{prefix}-{idx}"),
+                    &RenderContext {
+                        expanded: idx == 0,
+                        ..ctx.clone()
+                    },
+                );
+                if self.per_group_submit && !ctx.disabled {
+                    // Its save button is hidden along with the set.
+                    let show_if = q
+                        .show_if
+                        .as_ref()
+                        .map(|condition| format!(r#" data-show-if="{}""#, escape(condition)))
+                        .unwrap_or_default();
+                    format!(
+                        r#"<form method="post" action="/submit/{form_id}/{idx}"{show_if}><input type="hidden" name="_session" value="{session}">{hidden}{group}<input type="submit" value="{save}">{reset_button}</form>"#,
+                        session = ctx.session,
+                        save = texts.save
+                    )
+                } else {
+                    group
+                }
+            })
+            .join("\n");
+        let qs = if ctx.disabled {
+            format!("<form>{groups}</form>")
+        } else if self.per_group_submit {
+            groups
+        } else {
+            let save_draft = if self.drafts {
+                format!(
+                    r#"<input type="submit" formaction="/draft/{form_id}" formnovalidate value="{save_draft}">"#,
+                    save_draft = texts.save_draft
+                )
+            } else {
+                String::new()
+            };
+            // Edits come from a link only the respondent has.
+            let editing = ctx.hidden.iter().any(|(name, _)| name == "_edit");
+            let captcha = self
+                .captcha
+                .as_ref()
+                .filter(|_| !editing)
+                .map(|captcha| captcha.widget(texts, ctx.errors.get(captcha::FIELD)))
+                .unwrap_or_default();
+            // The server asks on a page of its own unless the dialog
+            // confirmed; saving a draft is not asked about.
+            let confirm = if self.confirm_submit && !ctx.no_js {
+                let question = escape(&texts.confirm_submit.replace('\'', "\\'"));
+                format!(
+                    r#" onsubmit="if(event.submitter&&event.submitter.hasAttribute('formaction'))return;if(!confirm('{question}'))return false;this.elements._confirmed.value='1'""#
+                )
+            } else {
+                String::new()
+            };
+            let confirmed = if confirm.is_empty() {
+                ""
+            } else {
+                r#"<input type="hidden" name="_confirmed" value="">"#
+            };
+            format!(
+                r#"{summary}<form method="post" action="/submit/{form_id}"{confirm}>{hidden}{confirmed}{groups}{captcha}<input type="submit" value="{submit}">{save_draft}{reset_button}</form>"#,
+                summary = self.error_summary(ctx),
+                submit = texts.submit
+            )
+        };
+        let conditional = self.groups.iter().any(|group| group.show_if.is_some())
+            || self
+                .questions()
+                .any(|(_, question)| question.show_if.is_some());
+        // Coming back to the form (errors, drafts, edits) skips the intro.
+        let intro = self
+            .intro
+            .as_ref()
+            .filter(|_| ctx.errors.is_empty() && ctx.values.is_empty() && !ctx.disabled)
+            // The embedding page introduces the form.
+            .filter(|_| !ctx.embed)
+            .map(|intro| intro_page(ctx.text(intro), texts.start, ctx.no_js));
+        let messages = i18n::Messages::new(self, &ctx.lang);
+        let validity = if ctx.no_js || ctx.disabled {
+            String::new()
+        } else {
+            i18n::fill(
+                VALIDITY_SCRIPT,
+                &[
+                    ("required", &escape(messages.get(i18n::Message::Required))),
+                    (
+                        "match_format",
+                        &escape(messages.get(i18n::Message::MatchFormat)),
+                    ),
+                ],
+            )
+        };
+        format!(
+            "{doctype}<html lang=\"{lang}\" style=\"font-family=sans-serif\"><head>{head}</head><body{margin}>{preview}{switcher}{header}{estimate}{closing}{intro}<div class=\"content\" id=\"questions\"{hidden}><pre>{qs}</pre></div>{quick_nav}{show_if}{reset}{validity}</body></html>",
+            lang=escape(&ctx.lang),
+
+            hidden=if intro.is_some() && !ctx.no_js { " hidden" } else { "" },
+            switcher=self.language_switcher(ctx),
+            preview=if ctx.preview { format!("<p class=\"preview\" role=\"status\">{}</p>", texts.preview) } else { String::new() },
+            intro=intro.as_deref().unwrap_or_default(),
+            head=self.head(&ctx.lang),
+            estimate=match self.estimated_minutes {
+                Some(1) => format!("<p class=\"estimate\">{}</p>", texts.estimate_one),
+                Some(minutes) => format!("<p class=\"estimate\">{}</p>", i18n::fill(texts.estimate_many, &[("minutes", &minutes.to_string())])),
+                None => String::new(),
+            },
+            closing=ctx
+                .now
+                .filter(|_| !ctx.disabled)
+                .and_then(|now| self.closing_notice(now, &ctx.lang))
+                .map(|notice| format!("<p class=\"closes-soon\" role=\"status\">{notice}</p>"))
+                .unwrap_or_default(),
+            quick_nav=if self.quick_nav && !ctx.no_js { QUICK_NAV_SCRIPT } else { "" },
+            show_if=if conditional && !ctx.no_js && !ctx.disabled { SHOW_IF_SCRIPT } else { "" },
+            reset=if self.show_reset && !ctx.no_js && !ctx.disabled { RESET_SCRIPT } else { "" },
+            doctype=if ctx.embed { "<!DOCTYPE html>" } else { "" },
+            margin=if ctx.embed { " style=\"margin:0.5em;background:transparent\"" } else { "" },
+            header=if ctx.embed {
+                String::new()
+            } else {
+                format!("<h1>{}</h1><p>{}</p>", escape(ctx.text(&self.title)), escape(ctx.text(&self.description)))
+            },
+        )
+    }
+}
+
+/// The smallest of 1, 2 and 5 times a power of ten that is at least `value`,
+/// for steps and tick intervals that read well.
+fn nice_ceil(value: f64) -> f64 {
+    let power = 10f64.powf(value.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|factor| factor * power)
+        .find(|candidate| *candidate >= value * (1.0 - 1e-6))
+        .unwrap_or(10.0 * power)
+}
+
+/// `value` as a multiple of `unit`, without the rounding noise of floats.
+fn format_multiple(value: f64, unit: f64) -> String {
+    let decimals = (-unit.log10().floor()).max(0.0) as usize;
+    let formatted = format!("{value:.decimals$}");
+    let formatted = if decimals > 0 {
+        formatted.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &formatted
+    };
+    match formatted {
+        "-0" => "0".to_string(),
+        formatted => formatted.to_string(),
+    }
+}
+
+/// The `step` of a slider from `min` to `max`: about 100 positions, `any`
+/// for a range without any.
+pub fn slider_step(min: f32, max: f32) -> String {
+    let span = max as f64 - min as f64;
+    if !(span.is_finite() && span > 0.0) {
+        return "any".to_string();
+    }
+    let step = nice_ceil(span / 100.0);
+    format_multiple(step, step)
+}
+
+/// Values to mark on a slider from `min` to `max`: 5 to 11 round numbers
+/// evenly spaced between them.
+pub fn slider_ticks(min: f32, max: f32) -> Vec<String> {
+    let (min, max) = (min as f64, max as f64);
+    let span = max - min;
+    if !(span.is_finite() && span > 0.0) {
+        return Vec::new();
+    }
+    let interval = nice_ceil(span / 10.0);
+    let first = (min / interval - 1e-6).ceil() as i64;
+    let last = (max / interval + 1e-6).floor() as i64;
+    (first..=last)
+        .map(|tick| format_multiple(tick as f64 * interval, interval))
+        .collect()
+}
+
+/// Length of a text answer as browsers count it for `maxlength` (UTF-16
+/// code units), so both sides agree on what fits.
+pub fn text_length(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+/// Length from which the counter of a text with `max_length` is marked
+/// `near-limit`.
+fn near_limit(max_length: usize, warn_at: Option<f32>) -> usize {
+    (max_length as f32 * warn_at.unwrap_or(0.9)).ceil() as usize
+}
+
+/// `markdown` as HTML, with raw HTML in it shown as text.
+pub fn markdown_html(markdown: &str) -> String {
+    use pulldown_cmark::{html, Event, Parser};
+    let events = Parser::new(markdown).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        event => event,
+    });
+    let mut body = String::new();
+    html::push_html(&mut body, events);
+    body
+}
+
+/// The intro with a button revealing the questions. Without scripts there is
+/// no button and the questions are shown right away.
+fn intro_page(intro: &str, start: &str, no_js: bool) -> String {
+    let body = markdown_html(intro);
+    if no_js {
+        return format!(r#"<section id="intro">{body}</section>"#);
+    }
+    format!(
+        r#"<section id="intro">{body}<button type="button" class="start" onclick="document.getElementById('intro').hidden=true;document.getElementById('questions').hidden=false">{start}</button></section><noscript><style>#questions{{display:block}}.start{{display:none}}</style></noscript>"#
+    )
+}
+
+/// The whole page, rendered with a default context at the root prefix.
+impl fmt::Display for Form {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(ROOT_PREFIX.to_string(), &RenderContext::default()))
+    }
+}
+
+impl axum::response::IntoResponse for &Form {
+    fn into_response(self) -> axum::response::Response {
+        axum::response::Html(self.to_string()).into_response()
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+pub struct QuestionSet {
+    pub title: Localized,
+    pub description: Localized,
+    pub questions: Vec<Question>,
+    /// Only ask the questions of this set if an answer of an earlier set
+    /// matches, written like a question's `show_if`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub show_if: Option<String>,
+    /// Show the set as a block respondents open and close, closed unless it
+    /// comes first or has a problem to fix.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub collapsible: bool,
+}
+
+impl Renderable for QuestionSet {
+    fn render(&self, prefix: String, ctx: &RenderContext) -> String {
+        let ctx = &RenderContext {
+            conditional: ctx.conditional || self.show_if.is_some(),
+            ..ctx.clone()
+        };
+        let show_if = self
+            .show_if
+            .as_ref()
+            .map(|condition| {
+                format!(
+                    r#" class="conditional" data-show-if="{}""#,
+                    escape(condition)
+                )
+            })
+            .unwrap_or_default();
+        let title = escape(ctx.text(&self.title));
+        let desc = escape(ctx.text(&self.description));
+        let ids: Vec<String> = (0..self.questions.len())
+            .map(|idx| self.questions[idx].id_at(format!("{prefix}-{idx}")))
+            .collect();
+        let qs = ctx
+            .order(ctx.shuffle_questions, &prefix, self.questions.len())
+            .into_iter()
+            .map(|idx| self.questions[idx].render(ids[idx].clone(), ctx))
+            .join("\n</br>\n");
+        if !self.collapsible {
+            return format!(
+                "<section aria-labelledby=\"{prefix}-title\"{show_if}><h2 id=\"{prefix}-title\">{title}</h2><p>{desc}</p><div class=\"group\">{qs}</div></section>"
+            );
+        }
+        // Problems have to be seen, and so do the answers of a read-only view.
+        let open = ctx.expanded || ctx.disabled || ids.iter().any(|id| ctx.errors.contains_key(id));
+        let texts = ctx.texts();
+        let count = match self.questions.len() {
+            1 => texts.question_count_one.to_string(),
+            n => i18n::fill(texts.question_count, &[("count", &n.to_string())]),
+        };
+        format!(
+            "<section aria-labelledby=\"{prefix}-title\"{show_if}><details{open}><summary><h2 id=\"{prefix}-title\">{title}</h2> <span class=\"question-count\">{count}</span></summary><p>{desc}</p><div class=\"group\">{qs}</div></details></section>",
+            open = if open { " open" } else { "" }
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+pub struct Question {
+    pub title: Localized,
+    /// Name answers are submitted and stored under instead of the positional
+    /// `i-<set>-<question>`, so they keep their key when questions move.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Explanation shown below the title.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub help: Option<Localized>,
+    /// Submissions have to answer this question.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub required: bool,
+    /// The right answer, for quiz forms.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correct: Option<quiz::Correct>,
+    /// What a correct answer is worth, one point unless given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub points: Option<f32>,
+    /// Shown with the quiz result, e.g. why the correct answer is right.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<Localized>,
+    /// Only ask this question if an earlier answer matches, written like an
+    /// export filter: `<question id>:<op>:<value>`, e.g. `role:eq:manager`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub show_if: Option<String>,
+    #[serde(deserialize_with = "spec_or_unknown")]
+    pub spec: QuestionType,
+}
+
+/// Accepts numeric keys as well as the string keys JSON and TOML are
+/// limited to.
+fn num_descriptions<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<i8, Localized>, D::Error> {
+    #[derive(Deserialize, PartialEq, Eq, Hash)]
+    #[serde(untagged)]
+    enum Key {
+        Number(i8),
+        Text(String),
+    }
+    HashMap::<Key, Localized>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(key, description)| match key {
+            Key::Number(value) => Ok((value, description)),
+            Key::Text(text) => text
+                .trim()
+                .parse()
+                .map(|value| (value, description))
+                .map_err(|_| {
+                    serde::de::Error::custom(format!(
+                        "'{text}' is not a number between -128 and 127"
+                    ))
+                }),
+        })
+        .collect()
+}
+
+/// Deserializes a question type, turning an unrecognized `type` into
+/// `QuestionType::Unknown` instead of failing the whole form.
+fn spec_or_unknown<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<QuestionType, D::Error> {
+    let value = serde_yaml::Value::deserialize(deserializer)?;
+    match value.get("type").and_then(serde_yaml::Value::as_str) {
+        Some(raw_type) if !QuestionType::KINDS.contains(&raw_type) => Ok(QuestionType::Unknown {
+            raw_type: raw_type.to_string(),
+        }),
+        _ => serde_yaml::from_value(value).map_err(serde::de::Error::custom),
+    }
+}
+
+impl Question {
+    /// The id of this question when placed at `position` (`i-0-3`).
+    pub fn id_at(&self, position: String) -> String {
+        self.key.clone().unwrap_or(position)
+    }
+
+    /// The markup of this question alone, without a surrounding form or
+    /// question set, to be spliced into a page of one's own. The answer is
+    /// posted under `id`; texts are in the default language.
+    ///
+    /// ```
+    /// use umfragetool_rust::Question;
+    ///
+    /// let question: Question =
+    ///     serde_yaml::from_str("{ title: Your name?, spec: { type: text, is_long: false } }")
+    ///         .unwrap();
+    /// let html = question.render_fragment("name");
+    /// assert!(html.contains(r#"<label for="name">Your name?</label>"#));
+    /// assert!(html.contains(r#"name="name""#));
+    /// assert!(!html.contains("<form"));
+    /// ```
+    pub fn render_fragment(&self, id: &str) -> String {
+        self.render(id.to_string(), &RenderContext::default())
+    }
+}
+
+impl Renderable for Question {
+    /// Questions with several controls are a `<fieldset>` named by its
+    /// `<legend>`; those with a single one label it directly.
+    fn render(&self, prefix: String, ctx: &RenderContext) -> String {
+        let marker = if self.required {
+            r#" <span class="required" aria-hidden="true">*</span>"#
+        } else {
+            ""
+        };
+        let number = ctx
+            .numbers
+            .get(&prefix)
+            .map(|number| format!(r#"<span class="number">{number}</span> "#))
+            .unwrap_or_default();
+        let title = format!("{number}{}{marker}", escape(ctx.text(&self.title)));
+        let help = self.help.as_ref().map(|help| {
+            format!(
+                r#"<small class="help" id="{prefix}-help">{}</small>"#,
+                escape(ctx.text(help))
+            )
+        });
+        let message = ctx.errors.get(&prefix);
+        let counted = matches!(
+            self.spec,
+            QuestionType::Text {
+                max_length: Some(_),
+                ..
+            }
+        );
+        let aria = Aria {
+            described_by: [
+                help.as_ref().map(|_| format!("{prefix}-help")),
+                counted.then(|| format!("{prefix}-counter")),
+                message.map(|_| format!("{prefix}-message")),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            invalid: message.is_some(),
+            // Whether a conditional question has to be answered depends on
+            // the other answers; the server checks that.
+            required: self.required && self.show_if.is_none() && !ctx.conditional,
+            disabled: ctx.disabled,
+        };
+        let controls = format!(
+            "{}{}",
+            help.as_deref().unwrap_or_default(),
+            self.spec.render_controls(prefix.clone(), ctx, &aria)
+        );
+        let error = message.map(|message| {
+            format!(
+                r#"<p class="error-message" id="{prefix}-message">{}</p>"#,
+                escape(message)
+            )
+        });
+        let question = match &self.spec {
+            QuestionType::DiscreteNumeric { .. }
+            | QuestionType::SingleChoice { .. }
+            | QuestionType::MultipleChoice { .. } => format!(
+                "<fieldset><legend><h3>{title}</h3></legend>{controls}{}</fieldset>",
+                error.as_deref().unwrap_or_default()
+            ),
+            QuestionType::Unknown { .. } => format!(
+                "<h3>{title}</h3>{controls}{}",
+                error.as_deref().unwrap_or_default()
+            ),
+            _ => format!(
+                r#"<h3><label for="{prefix}">{title}</label></h3>{controls}{}"#,
+                error.as_deref().unwrap_or_default()
+            ),
+        };
+        let question = match error {
+            Some(_) => format!(r#"<div class="error" id="{prefix}-error">{question}</div>"#),
+            None => question,
+        };
+        match &self.show_if {
+            Some(condition) => format!(
+                r#"<div class="conditional" data-show-if="{}">{question}</div>"#,
+                escape(condition)
+            ),
+            None => question,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(from = "AnswerRepr")]
+pub struct Answer {
+    pub label: Localized,
+    /// What is submitted and stored for this option, so the label can be
+    /// reworded without touching collected data. Defaults to the label; needed
+    /// when the label is translated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum AnswerRepr {
+    Full {
+        label: Localized,
+        #[serde(default)]
+        value: Option<String>,
+        #[serde(default)]
+        score: Option<f32>,
+    },
+    Plain(Localized),
+}
+
+impl From<AnswerRepr> for Answer {
+    fn from(repr: AnswerRepr) -> Self {
+        match repr {
+            AnswerRepr::Plain(label) => Answer {
+                label,
+                value: None,
+                score: None,
+            },
+            AnswerRepr::Full {
+                label,
+                value,
+                score,
+            } => Answer {
+                label,
+                value,
+                score,
+            },
+        }
+    }
+}
+
+impl Answer {
+    pub fn value(&self) -> &str {
+        self.value
+            .as_deref()
+            .unwrap_or_else(|| self.label.get("", ""))
+    }
+}
+
+/// Described by the accepted input: a bare label or a label with a value
+/// and score.
+impl JsonSchema for Answer {
+    fn schema_name() -> String {
+        "Answer".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        AnswerRepr::json_schema(gen)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(label: &str) -> Self {
+        Answer {
+            label: label.into(),
+            value: None,
+            score: None,
+        }
+    }
+}
+
+/// Regular expression a text answer has to match as a whole, like the HTML
+/// `pattern` attribute. Compiled once when the form is loaded; a pattern that
+/// does not compile is reported by `Form::validate`.
+#[derive(Deserialize, Debug)]
+#[serde(from = "String")]
+pub struct Pattern {
+    pub source: String,
+    regex: Result<regex::Regex, regex::Error>,
+}
+
+impl From<String> for Pattern {
+    fn from(source: String) -> Self {
+        let regex = regex::Regex::new(&format!("^(?:{source})$"));
+        Pattern { source, regex }
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl JsonSchema for Pattern {
+    fn schema_name() -> String {
+        "Pattern".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(gen)
+    }
+}
+
+impl Pattern {
+    pub fn regex(&self) -> Result<&regex::Regex, &regex::Error> {
+        self.regex.as_ref()
+    }
+}
+
+/// The answer to a discrete question with an `na_option` when it was chosen.
+/// Not a number, so means and numeric filters leave it out.
+pub const NA: &str = "na";
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QuestionType {
+    DiscreteNumeric {
+        bounds: (i8, i8),
+        #[serde(deserialize_with = "num_descriptions")]
+        num_descriptions: BTreeMap<i8, Localized>,
+        /// Label of an extra "not applicable" choice after the numbers,
+        /// submitted as [`NA`].
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        na_option: Option<Localized>,
+        /// Lay the choices out in this many columns.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        columns: Option<u8>,
+    },
+    ContinousNumeric {
+        bounds: Option<(f32, f32)>,
+        /// Shown next to the input, like `€` or `km`. Display only: the
+        /// number is submitted without it.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        unit: Option<String>,
+        #[serde(default)]
+        unit_position: UnitPosition,
+        /// Distance between the values the slider of a bounded question
+        /// moves by, also with the arrow keys; about a hundredth of the range
+        /// when not given.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        step: Option<f32>,
+    },
+    SingleChoice {
+        answers: Vec<Answer>,
+        custom_answer: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        columns: Option<u8>,
+    },
+    MultipleChoice {
+        answers: Vec<Answer>,
+        custom_answer: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        columns: Option<u8>,
+    },
+    Text {
+        is_long: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pattern: Option<Pattern>,
+        /// Longest answer accepted, in characters; counted down below the field.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_length: Option<usize>,
+        /// Share of `max_length` from which the counter is marked
+        /// `near-limit`; 0.9 when not given.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        warn_at: Option<f32>,
+    },
+    Computed {
+        expression: String,
+    },
+    /// A `type` this build does not know (e.g. from a newer version). Kept so
+    /// the rest of the form still loads; rendered as a placeholder.
+    #[serde(skip_deserializing)]
+    Unknown {
+        raw_type: String,
+    },
+}
+
+impl QuestionType {
+    /// The `type` tags this build understands.
+    pub const KINDS: [&'static str; 6] = [
+        "discrete_numeric",
+        "continous_numeric",
+        "single_choice",
+        "multiple_choice",
+        "text",
+        "computed",
+    ];
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            QuestionType::DiscreteNumeric { .. } => "discrete_numeric",
+            QuestionType::ContinousNumeric { .. } => "continous_numeric",
+            QuestionType::SingleChoice { .. } => "single_choice",
+            QuestionType::MultipleChoice { .. } => "multiple_choice",
+            QuestionType::Text { .. } => "text",
+            QuestionType::Computed { .. } => "computed",
+            QuestionType::Unknown { .. } => "unknown",
+        }
+    }
+
+    pub fn answers(&self) -> Option<&[Answer]> {
+        match self {
+            QuestionType::SingleChoice { answers, .. }
+            | QuestionType::MultipleChoice { answers, .. } => Some(answers),
+            _ => None,
+        }
+    }
+
+    /// Columns the choices are laid out in, for choice questions.
+    pub fn columns(&self) -> Option<u8> {
+        match self {
+            QuestionType::DiscreteNumeric { columns, .. }
+            | QuestionType::SingleChoice { columns, .. }
+            | QuestionType::MultipleChoice { columns, .. } => *columns,
+            _ => None,
+        }
+    }
+}
+
+impl Renderable for QuestionType {
+    fn render(&self, id: String, ctx: &RenderContext) -> String {
+        self.render_controls(id, ctx, &Aria::default())
+    }
+}
+
+/// How the controls of a question are described to assistive technology.
+#[derive(Default)]
+struct Aria {
+    /// Ids of the help text and error message, if shown.
+    described_by: Vec<String>,
+    invalid: bool,
+    required: bool,
+    disabled: bool,
+}
+
+impl Aria {
+    /// `required` means "this one has to be checked" on a checkbox, so it is
+    /// left out of those.
+    fn attrs(&self, checkbox: bool) -> String {
+        let mut attrs = String::new();
+        if !self.described_by.is_empty() {
+            attrs += &format!(r#" aria-describedby="{}""#, self.described_by.join(" "));
+        }
+        if self.invalid {
+            attrs += r#" aria-invalid="true""#;
+        }
+        if self.required && !checkbox {
+            attrs += r#" required aria-required="true""#;
+        }
+        if self.disabled {
+            attrs += " disabled";
+        }
+        attrs
+    }
+}
+
+impl QuestionType {
+    /// Only the controls of a question of this type, named `id`; see
+    /// `Question::render_fragment`.
+    pub fn render_fragment(&self, id: &str) -> String {
+        self.render(id.to_string(), &RenderContext::default())
+    }
+
+    fn render_controls(&self, id: String, ctx: &RenderContext, aria: &Aria) -> String {
+        let aria_checkbox = aria.attrs(true);
+        let aria = aria.attrs(false);
+        let values = ctx.values.get(&id).map(Vec::as_slice).unwrap_or_default();
+        let value_attr = values
+            .first()
+            .map(|value| format!(r#" value="{}""#, escape(value)))
+            .unwrap_or_default();
+        let checked = |candidate: &str| {
+            if values.iter().any(|value| value == candidate) {
+                " checked"
+            } else {
+                ""
+            }
+        };
+        let autofocus = if ctx.autofocus.as_deref() == Some(id.as_str()) {
+            " autofocus"
+        } else {
+            ""
+        };
+        let custom_value = |answers: &[Answer]| {
+            values
+                .iter()
+                .find(|value| !answers.iter().any(|answer| answer.value() == *value))
+                .map(|value| escape(value))
+        };
+        // The free-text option. A script copies the text into the value of
+        // its radio button or checkbox; without scripts the text box is
+        // submitted itself, and left empty when another option is meant.
+        let custom_option = |answers: &[Answer], kind: &str, aria: &str| {
+            let custom = custom_value(answers);
+            let other = ctx.texts().other;
+            if ctx.no_js {
+                let custom = custom.as_deref().unwrap_or_default();
+                return format!(
+                    r#"
+<label for="{id}-t">{other}</label><input type="text" name="{id}" id="{id}-t" value="{custom}"{aria}>"#
+                );
+            }
+            let disabled = if ctx.disabled { " disabled" } else { "" };
+            let (custom_checked, custom) = match &custom {
+                Some(custom) => (" checked", custom.as_str()),
+                None => ("", ""),
+            };
+            format!(
+                r#"
+<input type="{kind}" name="{id}" id="{id}-c" value="{custom}"{custom_checked}{aria}><label for="{id}-c">{other}</label><input type="text" id="{id}-t" value="{custom}" aria-label="{other}"{disabled} onkeyup="document.getElementById('{id}-c').setAttribute('value', this.value)">"#
+            )
+        };
+        let controls = match self {
+            QuestionType::Text {
+                is_long,
+                pattern,
+                max_length,
+                warn_at,
+            } => {
+                let (limit_attrs, counter) = match max_length {
+                    Some(max) => {
+                        let length = values.first().map_or(0, |value| text_length(value));
+                        let warn_from = near_limit(*max, *warn_at);
+                        let class = if length >= warn_from { " near-limit" } else { "" };
+                        // Updated as the respondent types; without scripts it
+                        // just states the limit.
+                        let update = if ctx.no_js || ctx.disabled {
+                            String::new()
+                        } else {
+                            format!(
+                                r#" oninput="var c=document.getElementById('{id}-counter'),n=this.value.length;c.textContent=n+'/{max}';c.classList.toggle('near-limit',n>={warn_from})""#
+                            )
+                        };
+                        (
+                            format!(r#" maxlength="{max}"{update}"#),
+                            format!(
+                                r#"<small class="counter{class}" id="{id}-counter">{length}/{max}</small>"#
+                            ),
+                        )
+                    }
+                    None => (String::new(), String::new()),
+                };
+                if *is_long {
+                    let value = values.first().map(|value| escape(value)).unwrap_or_default();
+                    format!(r#"<textarea name="{id}" id="{id}"{autofocus}{aria}{limit_attrs}>{value}</textarea>{counter}"#)
+                } else {
+                    let pattern_attr = pattern
+                        .as_ref()
+                        .map(|pattern| format!(r#" pattern="{}""#, escape(&pattern.source)))
+                        .unwrap_or_default();
+                    format!(
+                        r#"<input type="text" name="{id}" id="{id}"{autofocus}{aria}{pattern_attr}{limit_attrs}{value_attr}>{counter}"#
+                    )
+                }
+            }
+            QuestionType::ContinousNumeric {
+                bounds,
+                unit,
+                unit_position,
+                step,
+            } => {
+                let with_unit = |content: String| match unit {
+                    Some(unit) => {
+                        let unit = format!(r#"<span class="unit">{}</span>"#, escape(unit));
+                        match unit_position {
+                            UnitPosition::Prefix => format!("{unit}{content}"),
+                            UnitPosition::Suffix => format!("{content}{unit}"),
+                        }
+                    }
+                    None => content,
+                };
+                match bounds {
+                    // The ends are labelled, with the unit, and ticks mark
+                    // the way between them.
+                    Some((min, max)) => {
+                        let step = step
+                            .map(|step| step.to_string())
+                            .unwrap_or_else(|| slider_step(*min, *max));
+                        let ticks = slider_ticks(*min, *max)
+                            .into_iter()
+                            .map(|tick| format!(r#"<option value="{tick}"></option>"#))
+                            .join("");
+                        format!(
+                            r#"<span class="slider-min">{}</span><input type="range" min="{min}" max="{max}" step="{step}" list="{id}-ticks" class="slider" name="{id}" id="{id}"{autofocus}{aria}{value_attr}><span class="slider-max">{}</span><datalist id="{id}-ticks">{ticks}</datalist>"#,
+                            with_unit(min.to_string()),
+                            with_unit(max.to_string())
+                        )
+                    }
+                    // A text field, as number fields refuse `3,5` in some
+                    // browsers; `inputmode` still brings up the numeric
+                    // keyboard.
+                    None => {
+                        let lang_attr = if ctx.lang.is_empty() {
+                            String::new()
+                        } else {
+                            format!(r#" lang="{}""#, escape(&ctx.lang))
+                        };
+                        with_unit(format!(r#"<input type="text" inputmode="decimal"{lang_attr} name="{id}" id="{id}"{autofocus}{aria}{value_attr}>"#))
+                    }
+                }
+            }
+            QuestionType::DiscreteNumeric {
+                bounds: (min, max),
+                num_descriptions,
+                na_option,
+                ..
+            } => (*min..=*max)
+                .map(|val| {
+                    let stringified = num_descriptions
+                        .get(&val)
+                        .map(|v| format!("{val} ({})", escape(ctx.text(v))))
+                        .unwrap_or_else(|| val.to_string());
+                    let checked = checked(&val.to_string());
+                    let autofocus = if val == *min { autofocus } else { "" };
+                    format!(r#"<input type="radio" name="{id}" id="{id}-{val}" value="{val}"{checked}{autofocus}{aria}><label for="{id}-{val}">{stringified}</label>"#)
+                })
+                .chain(na_option.iter().map(|label| {
+                    let checked = checked(NA);
+                    format!(r#"<input type="radio" name="{id}" id="{id}-{NA}" value="{NA}"{checked}{aria}><label for="{id}-{NA}">{label}</label>"#, label = escape(ctx.text(label)))
+                }))
+                .join("\n"),
+            QuestionType::SingleChoice {answers, custom_answer, .. } => {
+                let custom_string = custom_option(answers, "radio", &aria);
+                ctx.order(ctx.shuffle_answers, &id, answers.len())
+                .into_iter()
+                .enumerate()
+                .map(|(position, idx)| {
+                    let answer = &answers[idx];
+                    let checked = checked(answer.value());
+                    let autofocus = if position == 0 { autofocus } else { "" };
+                    format!(r#"<input type="radio" name="{id}" id="{id}-{idx}" value="{value}"{checked}{autofocus}{aria}><label for="{id}-{idx}">{label}</label>"#, value = escape(answer.value()), label = escape(ctx.text(&answer.label)))
+                })
+                .join("\n") + if *custom_answer {&custom_string} else {""}},
+            QuestionType::MultipleChoice {answers, custom_answer, .. } => {
+                let custom_string = custom_option(answers, "checkbox", &aria_checkbox);
+                ctx.order(ctx.shuffle_answers, &id, answers.len())
+                .into_iter()
+                .enumerate()
+                .map(|(position, idx)| {
+                    let answer = &answers[idx];
+                    let checked = checked(answer.value());
+                    let autofocus = if position == 0 { autofocus } else { "" };
+                    format!(r#"<input type="checkbox" name="{id}" id="{id}-{idx}" value="{value}"{checked}{autofocus}{aria_checkbox}><label for="{id}-{idx}">{label}</label>"#, value = escape(answer.value()), label = escape(ctx.text(&answer.label)))
+                })
+                .join("\n") + if *custom_answer {&custom_string} else {""}},
+            QuestionType::Computed { expression } => match expression::parse(expression) {
+                Ok(program) => format!(
+                    r#"<output id="{id}" for="{fields}"></output>{script}"#,
+                    fields = expression::fields(&program).join(" "),
+                    script = if ctx.no_js {
+                        String::new()
+                    } else {
+                        expression::script(&program, &id)
+                    }
+                ),
+                Err(err) => format!(r#"<output id="{id}">(invalid expression: {err})</output>"#),
+            },
+            QuestionType::Unknown { raw_type } => format!(
+                r#"<p class="unsupported">(unsupported question type: {})</p>"#,
+                escape(raw_type)
+            ),
+        };
+        match self.columns() {
+            Some(columns) if columns > 1 => {
+                format!(r#"<div class="columns" style="column-count:{columns}">{controls}</div>"#)
+            }
+            _ => controls,
+        }
+    }
+}
+
+/// The form served when no definitions are given.
+pub fn demo_form() -> Form {
+    let questions = vec![
+        Question {
+            title: "Why would you do this?".into(),
+            key: None,
+            help: None,
+            required: false,
+            correct: None,
+            points: None,
+            explanation: None,
+            show_if: None,
+            spec: QuestionType::Text {
+                is_long: true,
+                pattern: None,
+                max_length: None,
+                warn_at: None,
+            },
+        },
+        Question {
+            title: "How much is the fish?".into(),
+            key: None,
+            help: None,
+            required: false,
+            correct: None,
+            points: None,
+            explanation: None,
+            show_if: None,
+            spec: QuestionType::ContinousNumeric {
+                step: None,
+                bounds: None,
+                unit: None,
+                unit_position: UnitPosition::Suffix,
+            },
+        },
+        Question {
+            title: "What do you want?".into(),
+            key: None,
+            help: None,
+            required: false,
+            correct: None,
+            points: None,
+            explanation: None,
+            show_if: None,
+            spec: QuestionType::DiscreteNumeric {
+                bounds: (1, 10),
+                num_descriptions: BTreeMap::from([(1, "NOPE!".into()), (10, "YESSSSH!!!!".into())]),
+                na_option: None,
+                columns: None,
+            },
+        },
+        Question {
+            title: "What do you want?".into(),
+            key: None,
+            help: None,
+            required: false,
+            correct: None,
+            points: None,
+            explanation: None,
+            show_if: None,
+            spec: QuestionType::SingleChoice {
+                answers: ["Pizza", "Ravioli", "MAOAM"]
+                    .iter()
+                    .map(|&v| Answer::from(v))
+                    .collect(),
+                custom_answer: true,
+                columns: None,
+            },
+        },
+        Question {
+            title: "What do you want?".into(),
+            key: None,
+            help: None,
+            required: false,
+            correct: None,
+            points: None,
+            explanation: None,
+            show_if: None,
+            spec: QuestionType::MultipleChoice {
+                answers: ["Pizza", "Ravioli", "MAOAM"]
+                    .iter()
+                    .map(|&v| Answer::from(v))
+                    .collect(),
+                custom_answer: true,
+                columns: None,
+            },
+        },
+    ];
+
+    let groups = vec![QuestionSet {
+        title: "Set 1".into(),
+        description: "".into(),
+        questions,
+        show_if: None,
+        collapsible: false,
+    }];
+
+    Form {
+        title: "This is Survey speaking!".into(),
+        description: "Hello, I am survey.".into(),
+        groups,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,334 @@
+//! Live views of the responses as they come in: a public count for audience
+//! polls and the full aggregates for presenter screens.
+//!
+//! # Count
+//!
+//! Forms with `public_count` stream their number of responses as server-sent
+//! events from `/form/:id/events`, each `data` a JSON object with
+//! `responses` and, for forms with a `live_question`, its summary as
+//! `question`. An event is sent on connect and after every change, with a
+//! comment every [`HEARTBEAT`] in between so proxies keep idle streams open.
+//! Each form serves at most `AppState::max_event_streams` of them at once.
+//! `/form/:id/live` is a page showing the count, for a projector.
+//!
+//! # Presenter
+//!
+//! `/form/:id/stats/live` (admin only) is a WebSocket streaming the per-question
+//! aggregates of [`stats::summarize`] while responses come in. Like every
+//! admin route it needs `Authorization: Bearer <admin token>`, here on the
+//! upgrade request. The server sends JSON text messages, told apart by
+//! their `type`:
+//!
+//! - `{"type": "snapshot", "form_id", "version", "responses", "questions"}`
+//!   with the summary of every question. Sent first, again whenever the form
+//!   is reloaded, and when the client fell behind.
+//! - `{"type": "update", "responses", "questions"}` with only the summaries
+//!   that changed since the last message, after responses were stored or
+//!   deleted.
+//! - `{"type": "removed"}` once the form is no longer served; the server
+//!   closes the connection after it.
+//!
+//! Messages from the client are ignored. Changes are not queued without
+//! bound for a client reading slower than they happen: one more than
+//! [`BACKLOG`] changes behind skips the missed ones and gets a snapshot
+//! instead, and one that takes no message for [`SEND_TIMEOUT`] is dropped.
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Extension, Path, Query};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{Html, Response};
+use axum::routing::get;
+use axum::Router;
+use futures_util::stream::{self, Stream};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+
+use crate::server::{language, Admin, AppState};
+use crate::stats::{self, QuestionSummary};
+use crate::{badge, escape, i18n, Form};
+
+/// Changes the channel of the state keeps for subscribers that are behind.
+pub const BACKLOG: usize = 64;
+/// How long a client gets to take a message.
+pub const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often an idle event stream gets a comment.
+pub const HEARTBEAT: Duration = Duration::from_secs(15);
+
+/// What happened to a form, as told to the live views.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// Responses to the form were stored or deleted.
+    Responses(String),
+    /// The form was replaced or is no longer served.
+    Form(String),
+}
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/form/:form_id/events", get(events))
+        .route("/form/:form_id/live", get(live_page))
+        .route("/form/:form_id/stats/live", get(presenter))
+}
+
+#[derive(Serialize)]
+struct LiveCount {
+    responses: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    question: Option<QuestionSummary>,
+}
+
+fn live_count(state: &AppState, form_id: &str, form: &Form) -> LiveCount {
+    let Some(id) = &form.live_question else {
+        return LiveCount {
+            responses: state.store.count(form_id),
+            question: None,
+        };
+    };
+    let submissions = state.store.list(form_id);
+    LiveCount {
+        responses: submissions.len(),
+        question: stats::summarize(form, &submissions)
+            .into_iter()
+            .find(|question| &question.id == id),
+    }
+}
+
+/// One of the event streams of a form, counted against
+/// `max_event_streams` until it is dropped with the connection.
+struct StreamSlot {
+    state: Arc<AppState>,
+    form_id: String,
+}
+
+impl StreamSlot {
+    fn take(state: &Arc<AppState>, form_id: &str) -> Option<Self> {
+        let mut streams = state.event_streams.lock().unwrap();
+        let open = streams.entry(form_id.to_string()).or_insert(0);
+        if *open >= state.max_event_streams {
+            return None;
+        }
+        *open += 1;
+        Some(StreamSlot {
+            state: state.clone(),
+            form_id: form_id.to_string(),
+        })
+    }
+}
+
+impl Drop for StreamSlot {
+    fn drop(&mut self) {
+        let mut streams = self.state.event_streams.lock().unwrap();
+        if let Some(open) = streams.get_mut(&self.form_id) {
+            *open -= 1;
+            if *open == 0 {
+                streams.remove(&self.form_id);
+            }
+        }
+    }
+}
+
+/// Waits for a change to `form_id`; `false` once there will be none.
+async fn next_change(changes: &mut Receiver<Change>, form_id: &str) -> bool {
+    loop {
+        match changes.recv().await {
+            Ok(Change::Responses(id) | Change::Form(id)) if id == form_id => return true,
+            Ok(_) => {}
+            // The count is sent whole, so missed changes do not matter.
+            Err(RecvError::Lagged(_)) => return true,
+            Err(RecvError::Closed) => return false,
+        }
+    }
+}
+
+/// The count of a form with `public_count`, sent again after every change
+/// until the form is gone or no longer publishes it. `503` while the form
+/// has `max_event_streams` open already.
+async fn events(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, serde_json::Error>>>, StatusCode> {
+    badge::published(&state, &form_id)?;
+    let slot = StreamSlot::take(&state, &form_id).ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    let changes = state.changes.subscribe();
+    let events = stream::unfold(
+        (slot, changes, true),
+        |(slot, mut changes, first)| async move {
+            if !first && !next_change(&mut changes, &slot.form_id).await {
+                return None;
+            }
+            let form = badge::published(&slot.state, &slot.form_id).ok()?;
+            let event = Event::default().json_data(live_count(&slot.state, &slot.form_id, &form));
+            Some((event, (slot, changes, false)))
+        },
+    );
+    Ok(Sse::new(events).keep_alive(KeepAlive::new().interval(HEARTBEAT)))
+}
+
+/// Replaces the count and the options of `live_question` with those of
+/// every event.
+const LIVE_SCRIPT: &str = r#"<script>new EventSource(location.pathname.replace(/live$/,"events")).onmessage=function(e){var d=JSON.parse(e.data);document.getElementById("responses").textContent=d.responses;var l=document.getElementById("counts");if(l&&d.question){l.replaceChildren.apply(l,(d.question.counts||[]).map(function(c){var i=document.createElement("li");i.textContent=c.value+": "+c.count;return i}))}}</script>"#;
+
+/// The count of a form with `public_count` in large, for a projector. It
+/// follows `/form/:id/events`, or reloads every few seconds when pages are
+/// rendered without scripts.
+async fn live_page(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Result<Html<String>, StatusCode> {
+    let form = badge::published(&state, &form_id)?;
+    let lang = language(&form, params.get("lang"), &headers);
+    let title = escape(form.title.get(&lang, form.language()));
+    let count = live_count(&state, &form_id, &form);
+    let question = count
+        .question
+        .map(|question| {
+            let counts: String = question
+                .counts
+                .iter()
+                .map(|count| format!("<li>{}: {}</li>", escape(&count.value), count.count))
+                .collect();
+            format!(
+                r#"<h2>{}</h2><ul id="counts">{counts}</ul>"#,
+                escape(&question.title)
+            )
+        })
+        .unwrap_or_default();
+    let (refresh, script) = if state.no_js {
+        (r#"<meta http-equiv="refresh" content="5">"#, "")
+    } else {
+        ("", LIVE_SCRIPT)
+    };
+    Ok(Html(format!(
+        r#"<html lang="{lang}" style="font-family=sans-serif"><head><meta charset="utf-8">{refresh}<title>{title}</title><style>.count{{font-size:4em}}</style></head><body><h1>{title}</h1><p class="count"><span id="responses">{responses}</span> {label}</p>{question}{script}</body></html>"#,
+        responses = count.responses,
+        label = i18n::texts(&lang).responses,
+    )))
+}
+
+async fn presenter(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, (StatusCode, String)> {
+    if state.form(&form_id).is_none() {
+        return Err((StatusCode::NOT_FOUND, format!("no form '{form_id}'")));
+    }
+    Ok(upgrade.on_upgrade(move |socket| present(state, form_id, socket)))
+}
+
+/// Question summaries last sent to one client, by question id, so updates
+/// can leave out what it already has.
+type Sent = HashMap<String, Value>;
+
+async fn present(state: Arc<AppState>, form_id: String, mut socket: WebSocket) {
+    // Subscribed before the first snapshot is taken, so no response stored
+    // in between is missed.
+    let mut changes = state.changes.subscribe();
+    let mut sent = Sent::new();
+    let mut message = snapshot(&state, &form_id, &mut sent);
+    loop {
+        let delivered = tokio::time::timeout(
+            SEND_TIMEOUT,
+            socket.send(Message::Text(message.to_string())),
+        )
+        .await;
+        if !matches!(delivered, Ok(Ok(()))) || message["type"] == "removed" {
+            return;
+        }
+        message = loop {
+            tokio::select! {
+                change = changes.recv() => match change {
+                    Ok(Change::Responses(id)) if id == form_id => {
+                        break update(&state, &form_id, &mut sent);
+                    }
+                    Ok(Change::Form(id)) if id == form_id => {
+                        break snapshot(&state, &form_id, &mut sent);
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(_)) => break snapshot(&state, &form_id, &mut sent),
+                    Err(RecvError::Closed) => return,
+                },
+                incoming = socket.recv() => match incoming {
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => return,
+                    Some(Ok(_)) => {}
+                },
+            }
+        };
+    }
+}
+
+struct Summaries {
+    version: String,
+    responses: usize,
+    /// Summary of every question as JSON, with the question id.
+    questions: Vec<(String, Value)>,
+}
+
+/// `None` once the form is gone.
+fn summaries(state: &AppState, form_id: &str) -> Option<Summaries> {
+    let form = state.form(form_id)?;
+    let submissions = state.store.list(form_id);
+    let questions = stats::summarize(&form, &submissions)
+        .into_iter()
+        .map(|summary| (summary.id.clone(), json!(summary)))
+        .collect();
+    Some(Summaries {
+        version: form.version(),
+        responses: submissions.len(),
+        questions,
+    })
+}
+
+fn snapshot(state: &AppState, form_id: &str, sent: &mut Sent) -> Value {
+    let Some(Summaries {
+        version,
+        responses,
+        questions,
+    }) = summaries(state, form_id)
+    else {
+        return json!({ "type": "removed" });
+    };
+    sent.clear();
+    let questions: Vec<Value> = questions
+        .into_iter()
+        .map(|(id, summary)| {
+            sent.insert(id, summary.clone());
+            summary
+        })
+        .collect();
+    json!({
+        "type": "snapshot",
+        "form_id": form_id,
+        "version": version,
+        "responses": responses,
+        "questions": questions,
+    })
+}
+
+fn update(state: &AppState, form_id: &str, sent: &mut Sent) -> Value {
+    let Some(Summaries {
+        responses,
+        questions,
+        ..
+    }) = summaries(state, form_id)
+    else {
+        return json!({ "type": "removed" });
+    };
+    let changed: Vec<Value> = questions
+        .into_iter()
+        .filter_map(|(id, summary)| {
+            let previous = sent.insert(id, summary.clone());
+            (previous.as_ref() != Some(&summary)).then_some(summary)
+        })
+        .collect();
+    json!({ "type": "update", "responses": responses, "questions": changed })
+}
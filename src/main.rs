@@ -1,220 +1,719 @@
-use axum::routing::get;
-use axum::Router;
+use clap::{Parser, Subcommand, ValueEnum};
 use itertools::Itertools;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use umfragetool_rust::drafts::DraftStore;
+use umfragetool_rust::server::{self, AppState, Favicon};
+use umfragetool_rust::signing::Signer;
+use umfragetool_rust::sinks::{JsonLinesFile, SubmissionSink, Webhook};
+use umfragetool_rust::store::{FormVersion, ImportMode, Source, Submission, SubmissionStore};
+use umfragetool_rust::watch::FormWatcher;
+use umfragetool_rust::*;
 
-trait Renderable {
-    fn render(&self, prefix: String) -> String;
+#[derive(Parser, Debug)]
+#[command(
+    about = "Serve surveys defined in YAML, TOML or JSON files",
+    args_conflicts_with_subcommands = true
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Form definitions to serve, each under its file name; the built-in
+    /// demo form is used when none are given
+    forms: Vec<PathBuf>,
+    /// Directory whose form files are served and which forms created through
+    /// the API are written to
+    #[arg(long)]
+    forms_dir: Option<PathBuf>,
+    /// JSONL file submissions are appended to
+    #[arg(long, default_value = "submissions.jsonl")]
+    store: PathBuf,
+    /// Maximum number of question sets a form may contain
+    #[arg(long, default_value_t = Limits::default().max_groups)]
+    max_groups: usize,
+    /// Maximum number of questions a form may contain
+    #[arg(long, default_value_t = Limits::default().max_questions)]
+    max_questions: usize,
+    /// Maximum number of options of a discrete numeric question
+    #[arg(long, default_value_t = Limits::default().max_discrete_span)]
+    max_discrete_span: usize,
+    /// Bearer token required by the admin API; the API is disabled without it
+    #[arg(long)]
+    admin_token: Option<String>,
+    /// Icon served as /favicon.ico instead of the bundled one (.ico, .png or .svg)
+    #[arg(long)]
+    favicon: Option<PathBuf>,
+    /// Hours a saved draft can be resumed
+    #[arg(long, default_value_t = 24 * 7)]
+    draft_ttl_hours: i64,
+    /// Origin allowed to submit and read form definitions from another site,
+    /// e.g. https://app.example.org; can be given several times
+    #[arg(long)]
+    cors_origin: Vec<String>,
+    /// Let pages on any origin use the JSON API under /api (not /submit),
+    /// for an API that is meant to be public
+    #[arg(long)]
+    cors_any_origin: bool,
+    /// Origin allowed to show forms in an iframe with ?embed=1, e.g.
+    /// https://intranet.example.org; can be given several times
+    #[arg(long)]
+    embed_origin: Vec<String>,
+    /// Serve pages without inline scripts; custom answers then get a plain
+    /// text box and computed fields stay empty
+    #[arg(long)]
+    no_js: bool,
+    /// Do not store a submission whose answers equal a stored one of the same
+    /// form; the respondent gets the stored submission's links instead
+    #[arg(long)]
+    dedupe: bool,
+    /// Content-Security-Policy header sent with every response; by default
+    /// same-origin only, plus inline scripts unless --no-js is given
+    #[arg(long)]
+    csp: Option<String>,
+    /// Take client addresses from the last X-Forwarded-For entry; only when
+    /// a reverse proxy that sets it is the only way to reach the server
+    #[arg(long)]
+    trust_proxy: bool,
+    /// Address and port to listen on
+    #[arg(long, default_value = "0.0.0.0:3000")]
+    bind: SocketAddr,
+    /// Open the form in the default browser once the server runs; only when
+    /// listening on a loopback address such as 127.0.0.1:3000
+    #[arg(long)]
+    open: bool,
+    /// Serve form files anew when they change; a file that does not load
+    /// keeps its previous version
+    #[arg(long)]
+    watch: bool,
+    /// JSONL file every stored submission is also appended to, e.g. on
+    /// another disk; can be given several times
+    #[arg(long)]
+    sink_file: Vec<PathBuf>,
+    /// URL every stored submission is also posted to as JSON; can be given
+    /// several times
+    #[arg(long)]
+    webhook: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Form {
-    title: String,
-    description: String,
-    groups: Vec<QuestionSet>,
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print a form definition in another format instead of serving it
+    Render {
+        form: PathBuf,
+        #[arg(long, value_enum, default_value_t = RenderFormat::Markdown)]
+        format: RenderFormat,
+        /// Print-optimized HTML for handing the survey out on paper
+        #[arg(long, conflicts_with = "format")]
+        print: bool,
+    },
+    /// Print the JSON Schema form definitions are checked against
+    Schema,
+    /// Load form definitions, with the files they include, and report
+    /// errors and warnings without serving them
+    Validate {
+        #[arg(required = true)]
+        forms: Vec<PathBuf>,
+    },
+    /// Render a form and report controls without labels, option groups
+    /// without a fieldset and legend, and a missing page language
+    A11yCheck { form: PathBuf },
+    /// Add responses typed in from paper forms, one CSV row per respondent
+    ImportResponses {
+        form: PathBuf,
+        /// CSV file whose header names the question of each column, by id or title
+        responses: PathBuf,
+        /// YAML map from CSV headers to question ids or titles; map a header
+        /// to null to ignore its column
+        #[arg(long)]
+        mapping: Option<PathBuf>,
+        /// JSONL file submissions are appended to
+        #[arg(long, default_value = "submissions.jsonl")]
+        store: PathBuf,
+        /// Check the rows without storing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print a link for previewing a form without storing its submissions
+    PreviewLink {
+        /// Id the form is served under
+        form_id: String,
+        /// JSONL file submissions are appended to; its signing secret is used
+        #[arg(long, default_value = "submissions.jsonl")]
+        store: PathBuf,
+    },
+    /// Write the forms, all stored responses, form versions, drafts and the
+    /// signing secret to one .tar.gz archive
+    Backup {
+        /// Archive to write
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Form definitions to include
+        forms: Vec<PathBuf>,
+        /// Directory whose form files are included
+        #[arg(long)]
+        forms_dir: Option<PathBuf>,
+        /// JSONL file submissions are stored in
+        #[arg(long, default_value = "submissions.jsonl")]
+        store: PathBuf,
+    },
+    /// Restore forms, responses and link state from a backup archive
+    Restore {
+        archive: PathBuf,
+        /// Directory the form definitions are written to
+        #[arg(long, default_value = "forms")]
+        forms_dir: PathBuf,
+        /// JSONL file submissions are stored in
+        #[arg(long, default_value = "submissions.jsonl")]
+        store: PathBuf,
+        /// Replace what the store, the forms directory and the secret hold
+        #[arg(long, conflicts_with = "merge")]
+        force: bool,
+        /// Add the backup to a store that is not empty, keeping responses,
+        /// forms and the secret already there
+        #[arg(long)]
+        merge: bool,
+    },
+    /// Convert a survey made with another tool into a form definition
+    Import {
+        #[command(subcommand)]
+        source: ImportSource,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ImportSource {
+    /// Form JSON as returned by the Google Forms API
+    GoogleForms {
+        export: PathBuf,
+        /// YAML file to write; printed when not given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum RenderFormat {
+    Markdown,
+    Html,
 }
 
-impl Renderable for Form {
-    fn render(&self, prefix: String) -> String {
-        format!(
-            "<html style=\"font-family=sans-serif\"><body><h1>{title}</h1><p>{desc}</p><div class=\"content\"><pre>{qs}</pre></div></body></html>",
-            title=self.title,
-            desc=self.description,
-            qs=self.groups
-                .iter()
-                .enumerate()
-                .map(|(idx, q)| q.render(format!("{prefix}-{idx}")))
-                .join("\n")
-        )
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    match &args.command {
+        Some(Command::Render {
+            form,
+            format,
+            print,
+        }) => render(form, format, *print),
+        Some(Command::ImportResponses {
+            form,
+            responses,
+            mapping,
+            store,
+            dry_run,
+        }) => import_responses(form, responses, mapping.as_deref(), store, *dry_run).await,
+        Some(Command::PreviewLink { form_id, store }) => preview_link(form_id, store),
+        Some(Command::Import {
+            source: ImportSource::GoogleForms { export, output },
+        }) => import_google_forms(export, output.as_deref()),
+        Some(Command::A11yCheck { form }) => a11y_check(form),
+        Some(Command::Validate { forms }) => validate(forms),
+        Some(Command::Backup {
+            output,
+            forms,
+            forms_dir,
+            store,
+        }) => backup(output, forms, forms_dir.as_deref(), store),
+        Some(Command::Restore {
+            archive,
+            forms_dir,
+            store,
+            force,
+            merge,
+        }) => restore(archive, forms_dir, store, *force, *merge).await,
+        Some(Command::Schema) => println!(
+            "{}",
+            serde_json::to_string_pretty(&form_schema()).expect("schema serializes")
+        ),
+        None => {
+            let sinks = sinks(&args);
+            run(args, sinks).await
+        }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct QuestionSet {
-    title: String,
-    description: String,
-    questions: Vec<Question>,
+fn preview_link(form_id: &str, store: &Path) {
+    let secret_path = store.with_extension("secret");
+    let signer = Signer::load_or_create(&secret_path).unwrap_or_else(|err| {
+        eprintln!(
+            "{}: could not load signing secret: {err}",
+            secret_path.display()
+        );
+        std::process::exit(1)
+    });
+    println!(
+        "/form/{form_id}?preview=1&token={}",
+        server::preview_token(&signer, form_id)
+    );
 }
 
-impl Renderable for QuestionSet {
-    fn render(&self, prefix: String) -> String {
-        format!(
-            "<h2>{title}</h2><p>{desc}</p><form>{qs}</form>",
-            title = self.title,
-            desc = self.description,
-            qs = self
-                .questions
-                .iter()
-                .enumerate()
-                .map(|(idx, q)| q.render(format!("{prefix}-{idx}")))
-                .join("\n</br>\n")
-        )
+fn validate(paths: &[PathBuf]) {
+    let mut failed = 0;
+    for path in paths {
+        match load_form(path, &Limits::default()) {
+            Ok(form) => {
+                for warning in form.warnings() {
+                    eprintln!("{}: warning: {warning}", path.display());
+                }
+            }
+            Err(err) => {
+                eprintln!("{}: {err}", path.display());
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        eprintln!("{failed} of {} forms do not load", paths.len());
+        std::process::exit(1)
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Question {
-    title: String,
-    spec: QuestionType,
+fn a11y_check(path: &Path) {
+    let form = load_form(path, &Limits::default()).unwrap_or_else(|err| {
+        eprintln!("{}: {err}", path.display());
+        std::process::exit(1)
+    });
+    let findings = a11y::audit(&form.to_string());
+    for finding in &findings {
+        println!("{}: {finding}", path.display());
+    }
+    if !findings.is_empty() {
+        eprintln!("{} accessibility problems", findings.len());
+        std::process::exit(1)
+    }
+}
+
+fn render(path: &std::path::Path, format: &RenderFormat, print: bool) {
+    let form = load_form(path, &Limits::default()).unwrap_or_else(|err| {
+        eprintln!("{}: {err}", path.display());
+        std::process::exit(1)
+    });
+    let output = match format {
+        _ if print => print::to_print_html(&form),
+        RenderFormat::Markdown => markdown::to_markdown(&form),
+        RenderFormat::Html => {
+            let ctx = RenderContext {
+                form_id: path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                ..Default::default()
+            };
+            form.render(ROOT_PREFIX.to_string(), &ctx)
+        }
+    };
+    println!("{output}");
 }
 
-impl Renderable for Question {
-    fn render(&self, prefix: String) -> String {
-        format!("<h3>{}</h3>{}", self.title, self.spec.render(prefix))
+fn import_google_forms(export: &Path, output: Option<&Path>) {
+    let json = std::fs::read_to_string(export).unwrap_or_else(|err| fail(export, err));
+    let (form, warnings) = google_forms::convert(&json).unwrap_or_else(|err| fail(export, err));
+    for warning in warnings.iter().chain(&form.warnings()) {
+        eprintln!("{}: warning: {warning}", export.display());
+    }
+    if let Err(err) = form.validate(&Limits::default()) {
+        fail(export, err);
+    }
+    let yaml = serde_yaml::to_string(&form).expect("form serializes");
+    match output {
+        Some(path) => std::fs::write(path, yaml).unwrap_or_else(|err| fail(path, err)),
+        None => print!("{yaml}"),
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(tag = "type", rename_all = "snake_case")]
-enum QuestionType {
-    DiscreteNumeric {
-        bounds: (i8, i8),
-        num_descriptions: HashMap<i8, String>,
-    },
-    ContinousNumeric {
-        bounds: Option<(f32, f32)>,
-    },
-    SingleChoice {
-        answers: Vec<String>,
-        custom_answer: bool,
-    },
-    MultipleChoice {
-        answers: Vec<String>,
-        custom_answer: bool,
-    },
-    Text {
-        is_long: bool,
-    },
+async fn import_responses(
+    form_path: &Path,
+    responses_path: &Path,
+    mapping_path: Option<&Path>,
+    store_path: &Path,
+    dry_run: bool,
+) {
+    let form = load_form(form_path, &Limits::default()).unwrap_or_else(|err| fail(form_path, err));
+    let form_id = form_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mapping = match mapping_path {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|content| serde_yaml::from_str(&content).map_err(|err| err.to_string()))
+            .unwrap_or_else(|err| fail(path, err)),
+        None => HashMap::new(),
+    };
+    let file = std::fs::File::open(responses_path).unwrap_or_else(|err| fail(responses_path, err));
+    let (rows, errors) = import::read_responses(&form, file, &mapping)
+        .unwrap_or_else(|err| fail(responses_path, err));
+    for error in &errors {
+        eprintln!(
+            "{}:{}: {}",
+            responses_path.display(),
+            error.line,
+            error.message
+        );
+    }
+    let rejected = errors.iter().map(|error| error.line).unique().count();
+
+    if !dry_run && !rows.is_empty() {
+        let store = SubmissionStore::open(store_path).unwrap_or_else(|err| fail(store_path, err));
+        let version = FormVersion::of(&form_id, &form);
+        store
+            .record_version(version.clone())
+            .await
+            .unwrap_or_else(|err| fail(store_path, err));
+        let now = chrono::Utc::now();
+        for row in &rows {
+            let submission = Submission {
+                id: uuid::Uuid::new_v4().to_string(),
+                form_id: form_id.clone(),
+                answers: row.answers.clone(),
+                form_version: Some(version.version.clone()),
+                submitted_at: Some(now),
+                updated_at: None,
+                client: None,
+                duration_secs: None,
+                tracking: BTreeMap::new(),
+                source: Source::Import,
+                score: quiz::score(&form, &row.answers),
+                computed: scores::compute(&form, &row.answers),
+                choice_score: form.scores_choices().then(|| form.score(&row.answers)),
+                shuffle_seed: None,
+            };
+            store
+                .insert(submission, None)
+                .await
+                .unwrap_or_else(|err| fail(store_path, err));
+        }
+    }
+    let verb = if dry_run { "would import" } else { "imported" };
+    println!("{verb} {} responses, rejected {rejected} rows", rows.len());
+    if !errors.is_empty() {
+        std::process::exit(1)
+    }
 }
 
-impl Renderable for QuestionType {
-    fn render(&self, id: String) -> String {
-        match self {
-            QuestionType::Text { is_long } => {
-                if *is_long {
-                    format!(r#"<textarea id="{id}"></textarea>"#)
-                } else {
-                    format!(r#"<input type="text" id="{id}">"#)
-                }
-            }
-            QuestionType::ContinousNumeric {
-                bounds: Some((min, max)),
-            } => format!(r#"<input type="range" min="{min}" max="{max}" class="slider" id="{id}">"#),
-            QuestionType::ContinousNumeric { bounds: None } => format!(r#"<input type="number" id="{id}">"#),
-            QuestionType::DiscreteNumeric {
-                bounds: (min, max),
-                num_descriptions,
-            } => (*min..=*max)
-                .map(|val| {
-                    let stringified = num_descriptions
-                        .get(&val)
-                        .map(|v| format!("{val} ({v})"))
-                        .unwrap_or_else(|| val.to_string());
-                    format!(r#"<input type="radio" name="{id}" id="{id}-{val}" value="{val}"><label for="{id}-{val}">{stringified}</label>"#)
-                })
-                .join("\n"),
-            QuestionType::SingleChoice {answers, custom_answer } => {
-                let custom_string = format!(r#"
-<input type="radio" name="{id}" id="{id}-c" value=""><input type="text" id="{id}-t" onkeyup="document.getElementById('{id}-c').setAttribute('value', this.value)">"#);
-                answers
-                .iter()
-                .enumerate()
-                .map(|(idx, val)| {
-                    format!(r#"<input type="radio" name="{id}" id="{id}-{idx}" value="{val}"><label for="{id}-{idx}">{val}</label>"#)
-                })
-                .join("\n") + if *custom_answer {&custom_string} else {""}},
-            QuestionType::MultipleChoice {answers, custom_answer } => {
-                let custom_string = format!(r#"
-<input type="checkbox" name="{id}" id="{id}-c" value=""><input type="text" id="{id}-t" onkeyup="document.getElementById('{id}-c').setAttribute('value', this.value)">"#);
-                answers
-                .iter()
-                .enumerate()
-                .map(|(idx, val)| {
-                    format!(r#"<input type="checkbox" name="{id}" id="{id}-{idx}" value="{val}"><label for="{id}-{idx}">{val}</label>"#)
-                })
-                .join("\n") + if *custom_answer {&custom_string} else {""}},
+/// The form files given and those in `forms_dir`, in that order, each with
+/// the id it is served under, its file name. Exits if two files would be
+/// served under the same id, as `lunch.yml` and `lunch.json` would.
+fn form_files(paths: &[PathBuf], forms_dir: Option<&Path>) -> Vec<(String, PathBuf)> {
+    let mut paths = paths.to_vec();
+    if let Some(dir) = forms_dir {
+        let entries = std::fs::read_dir(dir).unwrap_or_else(|err| {
+            eprintln!("{}: could not read forms directory: {err}", dir.display());
+            std::process::exit(1)
+        });
+        paths.extend(
+            entries
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|ext| FORM_EXTENSIONS.iter().any(|known| ext == *known))
+                }),
+        );
+    }
+
+    let files: Vec<(String, PathBuf)> = paths
+        .into_iter()
+        .map(|path| {
+            let id = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            (id, path)
+        })
+        .collect();
+    let mut seen: HashMap<&str, &Path> = HashMap::new();
+    for (id, path) in &files {
+        if let Some(other) = seen.insert(id, path) {
+            fail(
+                path,
+                format!("form id '{id}' is already used by {}", other.display()),
+            );
         }
     }
+    files
 }
 
-#[tokio::main]
-async fn main() {
-    run().await
-}
-
-async fn run() {
-    let questions = vec![
-        Question {
-            title: "Why would you do this?".to_string(),
-            spec: QuestionType::Text { is_long: true },
-        },
-        Question {
-            title: "How much is the fish?".to_string(),
-            spec: QuestionType::ContinousNumeric { bounds: None },
-        },
-        Question {
-            title: "What do you want?".to_string(),
-            spec: QuestionType::DiscreteNumeric {
-                bounds: (1, 10),
-                num_descriptions: HashMap::from([
-                    (1, "NOPE!".to_string()),
-                    (10, "YESSSSH!!!!".to_string()),
-                ]),
-            },
-        },
-        Question {
-            title: "What do you want?".to_string(),
-            spec: QuestionType::SingleChoice {
-                answers: vec!["Pizza", "Ravioli", "MAOAM"]
-                    .iter()
-                    .map(|v| v.to_string())
-                    .collect(),
-                custom_answer: true,
-            },
-        },
-        Question {
-            title: "What do you want?".to_string(),
-            spec: QuestionType::MultipleChoice {
-                answers: vec!["Pizza", "Ravioli", "MAOAM"]
-                    .iter()
-                    .map(|v| v.to_string())
-                    .collect(),
-                custom_answer: true,
-            },
-        },
-    ];
-
-    let groups = vec![QuestionSet {
-        title: "Set 1".to_string(),
-        description: "".to_string(),
-        questions,
-    }];
-
-    let html = format!(
-        "{}",
-        Form {
-            title: "This is Survey speaking!".to_string(),
-            description: "Hello, I am survey.".to_string(),
-            groups
+/// The forms of `files`. Exits if one does not load.
+fn load_forms(files: &[(String, PathBuf)], limits: &Limits) -> Vec<(String, Form)> {
+    let mut forms = Vec::new();
+    for (id, path) in files {
+        let form = load_form(path, limits).unwrap_or_else(|err| {
+            eprintln!("{}: {err}", path.display());
+            std::process::exit(1)
+        });
+        for warning in form.warnings() {
+            eprintln!("{}: warning: {warning}", path.display());
         }
-        .render("i".to_string())
-    );
+        forms.push((id.clone(), form));
+    }
+    forms
+}
+
+/// State serving `forms`, with the submissions, drafts and signing secret
+/// kept at `store` and next to it. Exits if one cannot be opened.
+fn open_state(forms: HashMap<String, Form>, store: &Path) -> AppState {
+    let submissions = SubmissionStore::open(store).unwrap_or_else(|err| {
+        eprintln!(
+            "{}: could not open submission store: {err}",
+            store.display()
+        );
+        std::process::exit(1)
+    });
+
+    let drafts_path = store.with_extension("drafts.json");
+    let drafts = DraftStore::open(drafts_path.clone()).unwrap_or_else(|err| {
+        eprintln!(
+            "{}: could not open draft store: {err}",
+            drafts_path.display()
+        );
+        std::process::exit(1)
+    });
+
+    let secret_path = store.with_extension("secret");
+    let signer = Signer::load_or_create(&secret_path).unwrap_or_else(|err| {
+        eprintln!(
+            "{}: could not load signing secret: {err}",
+            secret_path.display()
+        );
+        std::process::exit(1)
+    });
+
+    let mut state = AppState::new(forms, submissions);
+    state.signer = signer;
+    state.drafts = drafts;
+    state
+}
+
+/// Writes a backup archive (see `backup::create`) of the forms and the store.
+fn backup(output: &Path, forms: &[PathBuf], forms_dir: Option<&Path>, store: &Path) {
+    let forms = load_forms(&form_files(forms, forms_dir), &Limits::default());
+    let state = open_state(forms.into_iter().collect(), store);
+    let archive = backup::create(&state, chrono::Utc::now()).unwrap_or_else(|err| {
+        eprintln!("could not create backup: {err}");
+        std::process::exit(1)
+    });
+    std::fs::write(output, archive).unwrap_or_else(|err| {
+        eprintln!("{}: could not write backup: {err}", output.display());
+        std::process::exit(1)
+    });
+}
 
-    //let data = serde_yaml::from_str::<Form>(
-        //&std::fs::read_to_string("test.yml").expect("Could not find testfile"),
-    //)
-    //.expect("Parsing failed!");
+/// Exits with `message` about `path`.
+fn fail(path: &Path, message: impl std::fmt::Display) -> ! {
+    eprintln!("{}: {message}", path.display());
+    std::process::exit(1)
+}
 
-    //println!("{data:#?}");
+/// Writes `content` to `path` with `write` unless a different file is there
+/// and `overwrite` is not set; returns whether it was kept for that reason.
+fn restore_file(
+    path: &Path,
+    content: &str,
+    overwrite: bool,
+    write: impl FnOnce(&Path, &str) -> std::io::Result<()>,
+) -> bool {
+    match std::fs::read_to_string(path) {
+        Ok(existing) if existing.trim() == content.trim() => false,
+        Ok(_) if !overwrite => true,
+        _ => {
+            write(path, content).unwrap_or_else(|err| fail(path, err));
+            false
+        }
+    }
+}
 
-    //let html = data.render("i".to_string());
+async fn restore(archive: &Path, forms_dir: &Path, store: &Path, force: bool, merge: bool) {
+    let content = std::fs::read(archive).unwrap_or_else(|err| fail(archive, err));
+    let backup = backup::open(&content)
+        .unwrap_or_else(|err| fail(archive, format!("not a valid backup: {err}")));
 
-    let app = Router::new().route("/", get(|| async { axum::response::Html::from(html) }));
+    let submissions = SubmissionStore::open(store)
+        .unwrap_or_else(|err| fail(store, format!("could not open submission store: {err}")));
+    let drafts_path = store.with_extension("drafts.json");
+    let drafts = DraftStore::open(drafts_path.clone())
+        .unwrap_or_else(|err| fail(&drafts_path, format!("could not open draft store: {err}")));
+    let empty = submissions.is_empty() && drafts.all().is_empty();
+    if !empty && !force && !merge {
+        fail(
+            store,
+            "the store is not empty; pass --force to replace it or --merge to add the backup",
+        );
+    }
+    let mode = if merge {
+        ImportMode::Merge
+    } else {
+        ImportMode::Replace
+    };
 
-    // run it with hyper on localhost:3000
-    axum::Server::bind(&"0.0.0.0:3000".parse().unwrap())
-        .serve(app.into_make_service())
+    let mut conflicts = Vec::new();
+    std::fs::create_dir_all(forms_dir).unwrap_or_else(|err| fail(forms_dir, err));
+    for (id, yaml) in &backup.forms {
+        let path = forms_dir.join(format!("{id}.yml"));
+        if restore_file(&path, yaml, force, |path, yaml| std::fs::write(path, yaml)) {
+            conflicts.push(format!("{}: differs from the backup, kept", path.display()));
+        }
+    }
+    let secret_path = store.with_extension("secret");
+    if restore_file(&secret_path, &backup.secret, force, Signer::write_secret) {
+        conflicts.push(format!(
+            "{}: differs from the backup, kept; links handed out before the backup no longer verify",
+            secret_path.display()
+        ));
+    }
+
+    let report = submissions
+        .import_all(backup.store, mode)
+        .await
+        .unwrap_or_else(|err| fail(store, format!("could not restore responses: {err}")));
+    if mode == ImportMode::Replace {
+        drafts
+            .clear()
+            .await
+            .unwrap_or_else(|err| fail(&drafts_path, err));
+    }
+    let restored_drafts = drafts
+        .import(backup.drafts)
         .await
-        .unwrap();
+        .unwrap_or_else(|err| fail(&drafts_path, err));
+
+    conflicts.extend(
+        report
+            .conflicts
+            .iter()
+            .map(|id| format!("response {id}: differs from the backup, kept the stored one")),
+    );
+    for conflict in &conflicts {
+        eprintln!("{conflict}");
+    }
+    println!(
+        "restored {} forms, {} responses ({} already stored) and {restored_drafts} drafts from the backup of {}",
+        backup.forms.len(),
+        report.imported,
+        report.unchanged,
+        backup.manifest.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
+    );
+}
+
+/// The sinks given on the command line, files first.
+fn sinks(args: &Args) -> Vec<Box<dyn SubmissionSink>> {
+    let files = args
+        .sink_file
+        .iter()
+        .map(|path| Box::new(JsonLinesFile { path: path.clone() }) as Box<dyn SubmissionSink>);
+    let webhooks = args.webhook.iter().map(|url| {
+        if !(url.starts_with("https://") || url.starts_with("http://")) {
+            eprintln!("{url}: not an http(s) URL");
+            std::process::exit(1)
+        }
+        Box::new(Webhook { url: url.clone() }) as Box<dyn SubmissionSink>
+    });
+    files.chain(webhooks).collect()
+}
+
+async fn run(args: Args, sinks: Vec<Box<dyn SubmissionSink>>) {
+    let limits = Limits {
+        max_groups: args.max_groups,
+        max_questions: args.max_questions,
+        max_discrete_span: args.max_discrete_span,
+    };
+    let files = form_files(&args.forms, args.forms_dir.as_deref());
+    let mut forms = load_forms(&files, &limits);
+    if forms.is_empty() && args.forms_dir.is_none() {
+        forms.push(("demo".to_string(), demo_form()));
+    }
+    // Several forms are opened at the index listing them.
+    let open_id = match forms.as_slice() {
+        [(id, _)] => Some(id.clone()),
+        _ => None,
+    };
+
+    let mut state = open_state(forms.into_iter().collect(), &args.store);
+    state.store.dedupe = args.dedupe;
+    state.draft_ttl = chrono::Duration::hours(args.draft_ttl_hours);
+    state.limits = limits;
+    state.admin_token = args.admin_token;
+    state.forms_dir = args.forms_dir;
+    *state.sources.get_mut().unwrap() = files.iter().cloned().collect();
+    state.no_js = args.no_js;
+    state.csp = args.csp.as_ref().map(|csp| {
+        csp.parse().unwrap_or_else(|_| {
+            eprintln!("{csp}: not a valid header value");
+            std::process::exit(1)
+        })
+    });
+    state.cors_origins = args
+        .cors_origin
+        .iter()
+        .map(|origin| {
+            server::parse_origin(origin).unwrap_or_else(|err| {
+                eprintln!("{err}");
+                std::process::exit(1)
+            })
+        })
+        .collect();
+    state.cors_any_origin = args.cors_any_origin;
+    for origin in &args.embed_origin {
+        if origin.is_empty() || origin.contains(|c: char| c.is_whitespace() || ";,'".contains(c)) {
+            eprintln!("{origin}: not a valid origin");
+            std::process::exit(1)
+        }
+    }
+    state.embed_origins = args.embed_origin;
+    state.trust_proxy = args.trust_proxy;
+    state.sinks = Arc::new(sinks);
+    if let Some(path) = &args.favicon {
+        state.favicon = Favicon::load(path).unwrap_or_else(|err| {
+            eprintln!("{}: could not read favicon: {err}", path.display());
+            std::process::exit(1)
+        });
+    }
+    for (id, form) in state
+        .forms
+        .read()
+        .unwrap()
+        .iter()
+        .sorted_by_key(|(id, _)| *id)
+    {
+        state.log_form_loaded(id, form);
+    }
+    let state = Arc::new(state);
+    if args.watch {
+        tokio::spawn(FormWatcher::new(files).run(state.clone()));
+    }
+    let app = server::router(state);
+
+    let server = axum::Server::try_bind(&args.bind)
+        .unwrap_or_else(|err| {
+            eprintln!("{}: could not listen: {err}", args.bind);
+            std::process::exit(1)
+        })
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>());
+    if args.open {
+        let bound = server.local_addr();
+        match browser::url(bound, open_id.as_deref()) {
+            Some(url) => {
+                if let Err(err) = browser::launch(&url) {
+                    eprintln!("could not open {url} in a browser: {err}");
+                }
+            }
+            None => eprintln!(
+                "not opening a browser: {bound} is not a loopback address (try --bind 127.0.0.1:{})",
+                bound.port()
+            ),
+        }
+    }
+    server.await.unwrap();
 }
@@ -0,0 +1,149 @@
+//! Markdown rendering of form definitions, e.g. for reviewing a survey in a
+//! wiki before it goes live.
+
+use itertools::Itertools;
+
+use crate::{Form, Question, QuestionType};
+
+/// Backslash-escapes characters Markdown would interpret inside a line.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '~'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Like `escape`, for text that starts a line of its own (descriptions), where
+/// a leading `#`, `-`, `+` or `1.` would turn it into a heading or list.
+fn escape_paragraph(text: &str) -> String {
+    let escaped = escape(text);
+    let digits = escaped.chars().take_while(char::is_ascii_digit).count();
+    if escaped.starts_with(['#', '-', '+', '=']) {
+        format!("\\{escaped}")
+    } else if digits > 0 && escaped[digits..].starts_with(['.', ')']) {
+        format!("{}\\{}", &escaped[..digits], &escaped[digits..])
+    } else {
+        escaped
+    }
+}
+
+pub fn to_markdown(form: &Form) -> String {
+    let mut out = format!("# {}\n\n", escape(form.text(&form.title)));
+    if !form.description.is_empty() {
+        out.push_str(&format!(
+            "{}\n\n",
+            escape_paragraph(form.text(&form.description))
+        ));
+    }
+    for group in &form.groups {
+        out.push_str(&format!("## {}\n\n", escape(form.text(&group.title))));
+        if !group.description.is_empty() {
+            out.push_str(&format!(
+                "{}\n\n",
+                escape_paragraph(form.text(&group.description))
+            ));
+        }
+        for (idx, question) in group.questions.iter().enumerate() {
+            out.push_str(&question_markdown(form, idx + 1, question));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn question_markdown(form: &Form, number: usize, question: &Question) -> String {
+    let mut out = format!("{number}. **{}**", escape(form.text(&question.title)));
+    let mut lines = Vec::new();
+    match &question.spec {
+        QuestionType::DiscreteNumeric {
+            bounds: (min, max),
+            num_descriptions,
+            na_option,
+            ..
+        } => {
+            let descriptions = num_descriptions
+                .iter()
+                .map(|(value, description)| format!("{value} = {}", escape(form.text(description))))
+                .join(", ");
+            out.push_str(&format!(" \\[scale {min} to {max}"));
+            if !descriptions.is_empty() {
+                out.push_str(&format!("; {descriptions}"));
+            }
+            if let Some(label) = na_option {
+                out.push_str(&format!("; or {}", escape(form.text(label))));
+            }
+            out.push_str("\\]");
+        }
+        QuestionType::ContinousNumeric { bounds, .. } => match bounds {
+            Some((min, max)) => out.push_str(&format!(" \\[number from {min} to {max}\\]")),
+            None => out.push_str(" \\[number\\]"),
+        },
+        QuestionType::SingleChoice {
+            answers,
+            custom_answer,
+            ..
+        }
+        | QuestionType::MultipleChoice {
+            answers,
+            custom_answer,
+            ..
+        } => {
+            let note = if matches!(question.spec, QuestionType::SingleChoice { .. }) {
+                "pick one"
+            } else {
+                "pick any"
+            };
+            out.push_str(&format!(" \\[{note}\\]"));
+            for answer in answers {
+                let mut line = format!("- {}", escape(form.text(&answer.label)));
+                if let Some(value) = &answer.value {
+                    line.push_str(&format!(" \\[value `{}`\\]", value.replace('`', "'")));
+                }
+                if let Some(score) = answer.score {
+                    line.push_str(&format!(" \\[score {score}\\]"));
+                }
+                lines.push(line);
+            }
+            if *custom_answer {
+                lines.push("- _other \\(free text\\)_".to_string());
+            }
+        }
+        QuestionType::Text {
+            is_long,
+            pattern,
+            max_length,
+            ..
+        } => {
+            out.push_str(if *is_long {
+                " \\[long text\\]"
+            } else {
+                " \\[text\\]"
+            });
+            if let Some(pattern) = pattern {
+                lines.push(format!("- format: `{}`", pattern.source.replace('`', "'")));
+            }
+            if let Some(max) = max_length {
+                lines.push(format!("- at most {max} characters"));
+            }
+        }
+        QuestionType::Computed { expression } => out.push_str(&format!(
+            " \\[computed: `{}`\\]",
+            expression.replace('`', "'")
+        )),
+        QuestionType::Unknown { raw_type } => {
+            out.push_str(&format!(" \\[unsupported type {}\\]", escape(raw_type)))
+        }
+    }
+    out.push('\n');
+    for line in lines {
+        out.push_str(&format!("   {line}\n"));
+    }
+    out
+}
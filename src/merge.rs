@@ -0,0 +1,83 @@
+//! Composing a form from the question sets of others.
+
+use regex::{Captures, Regex};
+use std::collections::{HashMap, HashSet};
+
+use crate::{Form, QuestionType, ROOT_PREFIX};
+
+/// What `Form::merge` does with an appended question whose `key` the form
+/// already has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKey {
+    /// Keep it under the first free `<key>-2`, `<key>-3`, ...
+    Rename,
+    /// Leave it out, as the form asks it already.
+    Skip,
+}
+
+impl Form {
+    /// Appends the question sets of `other`; its title and settings are
+    /// ignored. Appended questions without a key get the positional ids of
+    /// their new place, and references to them in computed expressions and
+    /// `show_if` conditions are updated to match. Sets left without questions are dropped.
+    pub fn merge(&mut self, other: Form, duplicates: DuplicateKey) {
+        let mut taken: HashSet<String> = self.questions().map(|(id, _)| id).collect();
+        let old_ids: Vec<Vec<String>> = (0..other.groups.len())
+            .map(|g_idx| other.group_questions(g_idx).map(|(id, _)| id).collect())
+            .collect();
+        let first_new = self.groups.len();
+        let mut renames = HashMap::new();
+        for (mut group, old_ids) in other.groups.into_iter().zip(old_ids) {
+            let g_idx = self.groups.len();
+            let mut questions = Vec::new();
+            for (mut question, old_id) in group.questions.into_iter().zip(old_ids) {
+                if let Some(key) = question.key.clone().filter(|key| taken.contains(key)) {
+                    if duplicates == DuplicateKey::Skip {
+                        continue;
+                    }
+                    let renamed = (2..)
+                        .map(|n| format!("{key}-{n}"))
+                        .find(|candidate| !taken.contains(candidate))
+                        .expect("some suffix is free");
+                    question.key = Some(renamed);
+                }
+                let id = question.id_at(format!("{ROOT_PREFIX}-{g_idx}-{}", questions.len()));
+                if id != old_id {
+                    renames.insert(old_id, id.clone());
+                }
+                taken.insert(id);
+                questions.push(question);
+            }
+            if !questions.is_empty() {
+                group.questions = questions;
+                self.groups.push(group);
+            }
+        }
+
+        let rename_condition = |condition: &mut Option<String>| {
+            if let Some((id, rest)) = condition.as_ref().and_then(|c| c.split_once(':')) {
+                if let Some(renamed) = renames.get(id) {
+                    *condition = Some(format!("{renamed}:{rest}"));
+                }
+            }
+        };
+        let reference = Regex::new(r"\{([A-Za-z0-9_-]+)\}").expect("the pattern is valid");
+        for group in &mut self.groups[first_new..] {
+            rename_condition(&mut group.show_if);
+        }
+        let appended = self.groups[first_new..]
+            .iter_mut()
+            .flat_map(|group| &mut group.questions);
+        for question in appended {
+            if let QuestionType::Computed { expression } = &mut question.spec {
+                *expression = reference
+                    .replace_all(expression, |captures: &Captures| {
+                        let id = &captures[1];
+                        format!("{{{}}}", renames.get(id).map_or(id, String::as_str))
+                    })
+                    .into_owned();
+            }
+            rename_condition(&mut question.show_if);
+        }
+    }
+}
@@ -0,0 +1,520 @@
+//! OpenAPI 3 description of the JSON routes, served at `/api/openapi.json`
+//! and browsable at `/api/docs` (admin only).
+//!
+//! The document is written out here rather than derived from the handlers;
+//! the schemas of form definitions come from their `JsonSchema`. A test checks
+//! that every route of the router is either described or listed as not part
+//! of the API, so new routes are not left out by accident.
+
+use axum::http::header;
+use axum::response::{Html, IntoResponse, Json};
+use axum::routing::get;
+use axum::Router;
+use schemars::gen::SchemaSettings;
+use serde_json::{json, Map, Value};
+
+use crate::server::Admin;
+use crate::Form;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/api/openapi.json", get(spec))
+        .route("/api/docs", get(docs))
+}
+
+/// The schema of form definitions and those it refers to (`QuestionType`,
+/// `Answer`, ...), keyed by name, with `$ref`s into `components/schemas`.
+fn schemas() -> Map<String, Value> {
+    let root = SchemaSettings::openapi3()
+        .into_generator()
+        .into_root_schema_for::<Form>();
+    let mut schemas: Map<String, Value> = root
+        .definitions
+        .into_iter()
+        .map(|(name, schema)| (name, serde_json::to_value(schema).unwrap()))
+        .collect();
+    schemas.insert(
+        "Form".to_string(),
+        serde_json::to_value(root.schema).unwrap(),
+    );
+    schemas.values_mut().for_each(untuple);
+    schemas.insert(
+        "Submission".to_string(),
+        json!({
+            "type": "object",
+            "description": "A stored submission. Answers are lists of strings by question id: one for most questions, one per option for multiple choice.",
+            "required": ["id", "form_id", "answers"],
+            "properties": {
+                "id": { "type": "string" },
+                "form_id": { "type": "string" },
+                "answers": {
+                    "type": "object",
+                    "additionalProperties": { "type": "array", "items": { "type": "string" } },
+                },
+                "form_version": { "type": "string", "nullable": true },
+                "submitted_at": { "type": "string", "format": "date-time", "nullable": true },
+                "updated_at": { "type": "string", "format": "date-time", "nullable": true },
+                "duration_secs": { "type": "integer", "nullable": true },
+                "tracking": { "type": "object", "additionalProperties": { "type": "string" } },
+                "source": { "type": "string", "enum": ["web", "import"] },
+                "score": { "type": "number", "nullable": true },
+                "computed": { "type": "object", "additionalProperties": { "type": "number" } },
+                "choice_score": { "type": "number", "nullable": true },
+            },
+        }),
+    );
+    schemas.insert(
+        "QuestionSummary".to_string(),
+        json!({
+            "type": "object",
+            "required": ["id", "title", "kind", "answered"],
+            "properties": {
+                "id": { "type": "string" },
+                "title": { "type": "string" },
+                "kind": { "type": "string" },
+                "answered": { "type": "integer" },
+                "mean": { "type": "number" },
+                "min": { "type": "number" },
+                "max": { "type": "number" },
+                "counts": { "type": "array", "items": { "$ref": "#/components/schemas/ValueCount" } },
+            },
+        }),
+    );
+    schemas.insert(
+        "ValueCount".to_string(),
+        json!({
+            "type": "object",
+            "required": ["value", "count"],
+            "properties": { "value": { "type": "string" }, "count": { "type": "integer" } },
+        }),
+    );
+    schemas
+}
+
+/// OpenAPI 3.0 has no tuples (`bounds: [1, 5]`): a list of item schemas
+/// becomes one schema any item may match. `minItems` and `maxItems` still fix
+/// the length.
+fn untuple(schema: &mut Value) {
+    match schema {
+        Value::Object(map) => {
+            if let Some(Value::Array(items)) = map.get_mut("items") {
+                let mut items = std::mem::take(items);
+                items.dedup();
+                let single = match items.len() {
+                    1 => items.remove(0),
+                    _ => json!({ "oneOf": items }),
+                };
+                map.insert("items".to_string(), single);
+            }
+            map.values_mut().for_each(untuple);
+        }
+        Value::Array(values) => values.iter_mut().for_each(untuple),
+        _ => {}
+    }
+}
+
+fn form_id() -> Value {
+    json!({ "name": "form_id", "in": "path", "required": true, "schema": { "type": "string" } })
+}
+
+fn query(name: &str, description: &str, schema: Value) -> Value {
+    json!({ "name": name, "in": "query", "description": description, "schema": schema })
+}
+
+fn json_body(description: &str, schema: Value) -> Value {
+    json!({ "description": description, "content": { "application/json": { "schema": schema } } })
+}
+
+fn schema(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+/// Errors of the JSON API come as a plain text message.
+fn error(description: &str) -> Value {
+    json!({ "description": description, "content": { "text/plain": { "schema": { "type": "string" } } } })
+}
+
+fn object() -> Value {
+    json!({ "type": "object" })
+}
+
+/// `operation`, marked as needing the admin token.
+fn admin(mut operation: Value) -> Value {
+    operation["security"] = json!([{ "admin": [] }]);
+    operation["responses"]["401"] = json!({ "description": "No or the wrong admin token" });
+    operation
+}
+
+/// The OpenAPI document, as served at `/api/openapi.json`.
+pub fn document() -> Value {
+    let stats_query = |summary: &str, params: Vec<Value>| {
+        let mut parameters = vec![form_id()];
+        parameters.extend(params);
+        admin(json!({
+            "tags": ["stats"],
+            "summary": summary,
+            "description": "JSON when asked for with `Accept: application/json`, HTML otherwise.",
+            "parameters": parameters,
+            "responses": {
+                "200": json_body(summary, object()),
+                "400": error("A parameter names no suitable question"),
+                "404": error("No such form"),
+            },
+        }))
+    };
+    let submit_responses = json!({
+        "200": json_body(
+            "Stored; links the respondent may keep",
+            json!({
+                "type": "object",
+                "properties": {
+                    "status": { "type": "string", "enum": ["ok"] },
+                    "submission_id": { "type": "string" },
+                    "edit_url": { "type": "string", "nullable": true },
+                    "delete_url": { "type": "string", "nullable": true },
+                    "result_url": { "type": "string", "nullable": true },
+                    "redirect_url": { "type": "string", "nullable": true },
+                    "score": { "type": "number", "nullable": true },
+                    "computed": object(),
+                },
+            }),
+        ),
+        "403": json_body("The form is not open or full", object()),
+        "404": { "description": "No such form" },
+        "422": json_body(
+            "Answers that do not fit their questions",
+            json!({
+                "type": "object",
+                "properties": {
+                    "status": { "type": "string", "enum": ["error"] },
+                    "fields": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": { "id": { "type": "string" }, "message": { "type": "string" } },
+                        },
+                    },
+                },
+            }),
+        ),
+    });
+    let answers_body = json!({
+        "required": true,
+        "content": {
+            "application/x-www-form-urlencoded": {
+                "schema": {
+                    "type": "object",
+                    "description": "Answers by question id (`i-<set>-<question>`), repeated for each chosen option, plus the hidden fields of the rendered form (`_started`, `_seed`, `_session`, ...).",
+                    "additionalProperties": { "type": "string" },
+                },
+            },
+        },
+    });
+    let warnings = json!({
+        "type": "object",
+        "description": "Problems that do not stop the form from being served",
+        "properties": { "warnings": { "type": "array", "items": { "type": "string" } } },
+    });
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "umfragetool",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Forms, their responses and the statistics over them. Admin routes need `Authorization: Bearer <admin token>`.",
+        },
+        "paths": {
+            "/api/health": {
+                "get": {
+                    "tags": ["server"],
+                    "summary": "Whether the server is up",
+                    "responses": {
+                        "200": json_body("Up", json!({
+                            "type": "object",
+                            "properties": { "status": { "type": "string", "enum": ["ok"] } },
+                        })),
+                    },
+                },
+            },
+            "/api/openapi.json": {
+                "get": {
+                    "tags": ["server"],
+                    "summary": "This document",
+                    "responses": { "200": json_body("OpenAPI 3 document", object()) },
+                },
+            },
+            "/api/docs": {
+                "get": admin(json!({
+                    "tags": ["server"],
+                    "summary": "This document in Swagger UI",
+                    "responses": {
+                        "200": { "description": "HTML page", "content": { "text/html": { "schema": { "type": "string" } } } },
+                    },
+                })),
+            },
+            "/api/form/{form_id}": {
+                "get": {
+                    "tags": ["forms"],
+                    "summary": "The definition of a served form",
+                    "description": "Quizzes come without their correct answers. The `ETag` is the form version.",
+                    "parameters": [
+                        form_id(),
+                        { "name": "If-None-Match", "in": "header", "schema": { "type": "string" } },
+                    ],
+                    "responses": {
+                        "200": json_body("The definition", schema("Form")),
+                        "304": { "description": "The definition has the version given in `If-None-Match`" },
+                        "404": error("No such form"),
+                    },
+                },
+                "put": admin(json!({
+                    "tags": ["forms"],
+                    "summary": "Create or replace a form",
+                    "description": "Read like a form file: includes are found next to it and templates expanded. Written, in its format, to the file the form was loaded from or else to a new one in `--forms-dir`, and served right away.",
+                    "parameters": [form_id()],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": { "schema": schema("Form") },
+                            "application/toml": { "schema": schema("Form") },
+                            "application/yaml": { "schema": schema("Form") },
+                        },
+                    },
+                    "responses": {
+                        "200": json_body("Replaced", warnings.clone()),
+                        "201": json_body("Created", warnings),
+                        "400": error("Not a valid form id"),
+                        "422": error("Not a valid form definition"),
+                        "501": error("No `--forms-dir` configured"),
+                    },
+                })),
+                "delete": admin(json!({
+                    "tags": ["forms"],
+                    "summary": "Unpublish a form",
+                    "description": "The file the form was loaded from is kept with an added `.unpublished`, responses are kept.",
+                    "parameters": [form_id()],
+                    "responses": {
+                        "204": { "description": "Unpublished" },
+                        "400": error("Not a valid form id"),
+                        "404": error("No such form"),
+                    },
+                })),
+            },
+            "/api/form/{form_id}/responses": {
+                "get": admin(json!({
+                    "tags": ["results"],
+                    "summary": "The submissions of a form, page by page",
+                    "parameters": [
+                        form_id(),
+                        query("offset", "Submissions to skip", json!({ "type": "integer", "minimum": 0 })),
+                        query("limit", "Submissions per page, 50 by default, at most 1000", json!({ "type": "integer", "minimum": 0 })),
+                        query("order", "Oldest first unless `desc`", json!({ "type": "string", "enum": ["asc", "desc"] })),
+                    ],
+                    "responses": {
+                        "200": json_body("One page", json!({
+                            "type": "object",
+                            "properties": {
+                                "total": { "type": "integer" },
+                                "offset": { "type": "integer" },
+                                "limit": { "type": "integer" },
+                                "responses": { "type": "array", "items": schema("Submission") },
+                            },
+                        })),
+                    },
+                })),
+            },
+            "/api/form/{form_id}/response/{submission_id}": {
+                "get": admin(json!({
+                    "tags": ["results"],
+                    "summary": "One submission with its answers labelled",
+                    "parameters": [
+                        form_id(),
+                        { "name": "submission_id", "in": "path", "required": true, "schema": { "type": "string" } },
+                    ],
+                    "responses": {
+                        "200": json_body("The submission; `answers` is a list of question id, title, type and typed value", object()),
+                        "404": error("No such submission"),
+                    },
+                })),
+                "delete": admin(json!({
+                    "tags": ["results"],
+                    "summary": "Delete one submission",
+                    "parameters": [
+                        form_id(),
+                        { "name": "submission_id", "in": "path", "required": true, "schema": { "type": "string" } },
+                    ],
+                    "responses": {
+                        "204": { "description": "Deleted" },
+                        "404": error("No such submission"),
+                    },
+                })),
+            },
+            "/submit/{form_id}": {
+                "post": {
+                    "tags": ["responses"],
+                    "summary": "Submit a response",
+                    "description": "Answers JSON when asked for with `Accept: application/json`, the pages of the form otherwise.",
+                    "parameters": [form_id()],
+                    "requestBody": answers_body.clone(),
+                    "responses": submit_responses,
+                },
+            },
+            "/submit/{form_id}/{group_index}": {
+                "post": {
+                    "tags": ["responses"],
+                    "summary": "Save the answers to one question set of a `per_group_submit` form",
+                    "description": "Sets saved with the same `_session` are merged into one submission.",
+                    "parameters": [
+                        form_id(),
+                        { "name": "group_index", "in": "path", "required": true, "schema": { "type": "integer", "minimum": 0 } },
+                    ],
+                    "requestBody": answers_body,
+                    "responses": {
+                        "204": { "description": "Saved" },
+                        "400": { "description": "No session issued by the server" },
+                        "403": { "description": "The form is not open or full" },
+                        "404": { "description": "No such form or set" },
+                        "422": { "description": "Answers of the set that do not fit their questions" },
+                    },
+                },
+            },
+            "/form/{form_id}/version": {
+                "get": {
+                    "tags": ["forms"],
+                    "summary": "The version of the served definition",
+                    "parameters": [form_id()],
+                    "responses": {
+                        "200": json_body("The version", json!({
+                            "type": "object",
+                            "properties": { "form_id": { "type": "string" }, "version": { "type": "string" } },
+                        })),
+                        "404": { "description": "No such form" },
+                    },
+                },
+            },
+            "/form/{form_id}/count": {
+                "get": {
+                    "tags": ["results"],
+                    "summary": "The number of responses of a form with `public_count`",
+                    "parameters": [form_id()],
+                    "responses": {
+                        "200": json_body("At most a minute old", json!({
+                            "type": "object",
+                            "properties": { "count": { "type": "integer" } },
+                        })),
+                        "404": { "description": "No such form, or it does not publish its count" },
+                    },
+                },
+            },
+            "/form/{form_id}/summary.json": {
+                "get": admin(json!({
+                    "tags": ["stats"],
+                    "summary": "Aggregates of every question",
+                    "parameters": [form_id()],
+                    "responses": {
+                        "200": json_body("The summary", json!({
+                            "type": "object",
+                            "properties": {
+                                "form_id": { "type": "string" },
+                                "responses": { "type": "integer" },
+                                "versions": { "type": "array", "items": schema("ValueCount") },
+                                "warning": { "type": "string" },
+                                "questions": { "type": "array", "items": schema("QuestionSummary") },
+                            },
+                        })),
+                        "404": error("No such form"),
+                    },
+                })),
+            },
+            "/form/{form_id}/stats/crosstab": {
+                "get": stats_query("Contingency table of two questions", vec![
+                    query("rows", "Question id of the rows", json!({ "type": "string" })),
+                    query("cols", "Question id of the columns", json!({ "type": "string" })),
+                    query("bins", "Buckets numeric questions are split into", json!({ "type": "integer", "minimum": 1 })),
+                    query("expand_other", "List custom answers individually", json!({ "type": "boolean" })),
+                ]),
+            },
+            "/form/{form_id}/stats/keywords": {
+                "get": stats_query("Most frequent words in the answers to a text question", vec![
+                    query("question", "Question id", json!({ "type": "string" })),
+                    query("top", "How many words and word pairs", json!({ "type": "integer", "minimum": 1 })),
+                ]),
+            },
+            "/form/{form_id}/stats/scores": {
+                "get": stats_query("How many submissions of a quiz achieved which score", vec![]),
+            },
+            "/export/{file}": {
+                "get": admin(json!({
+                    "tags": ["results"],
+                    "summary": "All submissions of a form, as `<form id>.json` or `<form id>.xlsx`",
+                    "parameters": [
+                        { "name": "file", "in": "path", "required": true, "schema": { "type": "string" } },
+                        query("filter", "Only submissions matching this expression, e.g. `i-0-2>=5`; repeatable", json!({ "type": "string" })),
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "The submissions",
+                            "content": {
+                                "application/json": { "schema": { "type": "array", "items": object() } },
+                                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet": {
+                                    "schema": { "type": "string", "format": "binary" },
+                                },
+                            },
+                        },
+                        "400": error("A filter does not parse"),
+                        "404": error("No such form or format"),
+                    },
+                })),
+            },
+            "/form/{form_id}/results.xlsx": {
+                "get": admin(json!({
+                    "tags": ["results"],
+                    "summary": "All submissions of a form as a spreadsheet",
+                    "parameters": [
+                        form_id(),
+                        query("filter", "As for `/export/{file}`", json!({ "type": "string" })),
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "The spreadsheet",
+                            "content": {
+                                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet": {
+                                    "schema": { "type": "string", "format": "binary" },
+                                },
+                            },
+                        },
+                        "400": error("A filter does not parse"),
+                        "404": error("No such form"),
+                    },
+                })),
+            },
+        },
+        "components": {
+            "schemas": schemas(),
+            "securitySchemes": {
+                "admin": { "type": "http", "scheme": "bearer", "description": "The `--admin-token`" },
+            },
+        },
+    })
+}
+
+async fn spec() -> Json<Value> {
+    Json(document())
+}
+
+/// Swagger UI, loaded from a CDN, with the document inlined so the page needs
+/// no second request with the admin token.
+const SWAGGER_UI: &str = "https://cdn.jsdelivr.net/npm/swagger-ui-dist@5";
+
+async fn docs(_: Admin) -> impl IntoResponse {
+    // `</` would end the script early.
+    let spec = document().to_string().replace("</", "<\\/");
+    let csp = format!(
+        "default-src 'self'; script-src 'unsafe-inline' {SWAGGER_UI}/; style-src 'unsafe-inline' {SWAGGER_UI}/; img-src 'self' data: {SWAGGER_UI}/; frame-ancestors 'none'"
+    );
+    (
+        [(header::CONTENT_SECURITY_POLICY, csp)],
+        Html(format!(
+            r##"<html><head><meta charset="utf-8"><title>umfragetool API</title><link rel="stylesheet" href="{SWAGGER_UI}/swagger-ui.css"></head><body><div id="docs"></div><script src="{SWAGGER_UI}/swagger-ui-bundle.js"></script><script>SwaggerUIBundle({{dom_id:"#docs",spec:{spec}}})</script></body></html>"##,
+        )),
+    )
+}
@@ -0,0 +1,133 @@
+//! Print layout of a form, for surveys that are also handed out on paper.
+//! Nothing in it is interactive and it does not point at the submit endpoint.
+
+use itertools::Itertools;
+
+use crate::{escape, Form, Question, QuestionType};
+
+const PRINT_STYLE: &str = r#"<style>
+body{font-family:sans-serif;max-width:48em;margin:auto}
+.group{break-before:page}
+.group:first-of-type{break-before:auto}
+.question{break-inside:avoid;margin-bottom:1.5em}
+.option{margin:.3em 0}
+.box{display:inline-block;width:.9em;height:.9em;border:1px solid #000;vertical-align:middle;margin-right:.5em}
+.circle{border-radius:50%}
+.scale{display:flex;gap:.5em}
+.scale .step{text-align:center}
+.scale .box{width:1.6em;height:1.6em;margin:0}
+.line{border-bottom:1px solid #000;height:2em}
+.short{max-width:24em}
+@media print{body{max-width:none}}
+</style>"#;
+
+pub fn to_print_html(form: &Form) -> String {
+    let groups = form
+        .groups
+        .iter()
+        .map(|group| {
+            let questions = group
+                .questions
+                .iter()
+                .filter(|question| !matches!(question.spec, QuestionType::Computed { .. }))
+                .enumerate()
+                .map(|(idx, question)| question_html(form, idx + 1, question))
+                .join("\n");
+            format!(
+                r#"<section class="group"><h2>{title}</h2><p>{desc}</p>{questions}</section>"#,
+                title = escape(form.text(&group.title)),
+                desc = escape(form.text(&group.description)),
+            )
+        })
+        .join("\n");
+    format!(
+        "<html><head>{head}{PRINT_STYLE}</head><body><h1>{title}</h1><p>{desc}</p>{groups}</body></html>",
+        head = form.head(form.language()),
+        title = escape(form.text(&form.title)),
+        desc = escape(form.text(&form.description)),
+    )
+}
+
+/// Writing lines for an answer, sized for a word or a paragraph.
+fn lines(count: usize, short: bool) -> String {
+    let class = if short { "line short" } else { "line" };
+    format!(r#"<div class="{class}"></div>"#).repeat(count)
+}
+
+fn question_html(form: &Form, number: usize, question: &Question) -> String {
+    let body = match &question.spec {
+        QuestionType::DiscreteNumeric {
+            bounds: (min, max),
+            num_descriptions,
+            na_option,
+            ..
+        } => {
+            let steps = (*min..=*max)
+                .map(|value| {
+                    let description = num_descriptions
+                        .get(&value)
+                        .map(|description| format!("<br>{}", escape(form.text(description))))
+                        .unwrap_or_default();
+                    format!(
+                        r#"<div class="step"><span class="box"></span><br>{value}{description}</div>"#
+                    )
+                })
+                .join("");
+            let na = na_option
+                .as_ref()
+                .map(|label| {
+                    format!(
+                        r#"<div class="step"><span class="box"></span><br>{}</div>"#,
+                        escape(form.text(label))
+                    )
+                })
+                .unwrap_or_default();
+            format!(r#"<div class="scale">{steps}{na}</div>"#)
+        }
+        QuestionType::ContinousNumeric { bounds, .. } => {
+            let range = bounds
+                .map(|(min, max)| format!("<p>A number from {min} to {max}</p>"))
+                .unwrap_or_default();
+            format!("{range}{}", lines(1, true))
+        }
+        QuestionType::SingleChoice {
+            answers,
+            custom_answer,
+            ..
+        }
+        | QuestionType::MultipleChoice {
+            answers,
+            custom_answer,
+            ..
+        } => {
+            let mark = if matches!(question.spec, QuestionType::SingleChoice { .. }) {
+                "box circle"
+            } else {
+                "box"
+            };
+            let mut options = answers
+                .iter()
+                .map(|answer| {
+                    format!(
+                        r#"<div class="option"><span class="{mark}"></span>{}</div>"#,
+                        escape(form.text(&answer.label))
+                    )
+                })
+                .join("");
+            if *custom_answer {
+                options.push_str(&format!(
+                    r#"<div class="option"><span class="{mark}"></span>Other:{}</div>"#,
+                    lines(1, true)
+                ));
+            }
+            options
+        }
+        QuestionType::Text { is_long, .. } => lines(if *is_long { 5 } else { 1 }, !is_long),
+        QuestionType::Computed { .. } => String::new(),
+        QuestionType::Unknown { .. } => lines(2, false),
+    };
+    format!(
+        r#"<div class="question"><h3>{number}. {title}</h3>{body}</div>"#,
+        title = escape(form.text(&question.title))
+    )
+}
@@ -0,0 +1,212 @@
+//! Quiz forms: questions with a `correct` answer are scored on submit.
+
+use itertools::Itertools;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{escape, i18n, Form, Localized, Question, QuestionType, ShowScore};
+
+/// Points a question with a correct answer is worth unless it sets `points`.
+pub const DEFAULT_POINTS: f32 = 1.0;
+
+/// The expected answer of a question: a number for numeric questions, an
+/// option value for single choice and the set of right options for multiple
+/// choice questions. A single choice question may accept several options.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Correct {
+    Number(f32),
+    Value(String),
+    Values(Vec<String>),
+}
+
+impl Correct {
+    /// The accepted option values.
+    pub fn values(&self) -> Vec<String> {
+        match self {
+            Correct::Number(number) => vec![number.to_string()],
+            Correct::Value(value) => vec![value.clone()],
+            Correct::Values(values) => values.clone(),
+        }
+    }
+}
+
+/// Achieved and achievable points of a submission, stored with it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Score {
+    pub points: f32,
+    pub max: f32,
+}
+
+/// How one question of a quiz was answered.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct QuestionResult {
+    pub id: String,
+    pub points: f32,
+    pub max: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Correctness {
+    Correct,
+    Partly,
+    Wrong,
+}
+
+impl QuestionResult {
+    pub fn correctness(&self) -> Correctness {
+        if self.points >= self.max {
+            Correctness::Correct
+        } else if self.points > 0.0 {
+            Correctness::Partly
+        } else {
+            Correctness::Wrong
+        }
+    }
+}
+
+/// The score of `answers` and the result of every question with a correct
+/// answer, in form order.
+pub fn grade(form: &Form, answers: &HashMap<String, Vec<String>>) -> (Score, Vec<QuestionResult>) {
+    let results: Vec<QuestionResult> = form
+        .questions()
+        .filter_map(|(id, question)| {
+            let correct = question.correct.as_ref()?;
+            let max = question.points.unwrap_or(DEFAULT_POINTS);
+            let given = answers.get(&id).map(Vec::as_slice).unwrap_or_default();
+            Some(QuestionResult {
+                points: max * credit(question, correct, given),
+                max,
+                id,
+            })
+        })
+        .collect();
+    let score = Score {
+        points: results.iter().map(|result| result.points).sum(),
+        max: results.iter().map(|result| result.max).sum(),
+    };
+    (score, results)
+}
+
+/// The score stored with a submission, for quiz forms only.
+pub fn score(form: &Form, answers: &HashMap<String, Vec<String>>) -> Option<Score> {
+    form.quiz.then(|| grade(form, answers).0)
+}
+
+/// Share of the question's points `given` earns. Multiple choice answers get
+/// partial credit: every right option chosen counts for, every wrong option
+/// chosen against, and the sum never goes below zero.
+fn credit(question: &Question, correct: &Correct, given: &[String]) -> f32 {
+    let right = |value: &String| match (correct, &question.spec) {
+        (
+            Correct::Number(expected),
+            QuestionType::DiscreteNumeric { .. } | QuestionType::ContinousNumeric { .. },
+        ) => value
+            .parse::<f32>()
+            .is_ok_and(|number| (number - expected).abs() < 1e-6),
+        _ => correct.values().contains(value),
+    };
+    match &question.spec {
+        QuestionType::MultipleChoice { .. } => {
+            let expected = correct.values().len().max(1) as f32;
+            let hits = given.iter().filter(|value| right(value)).count() as f32;
+            let misses = given.len() as f32 - hits;
+            ((hits - misses) / expected).clamp(0.0, 1.0)
+        }
+        _ => match given {
+            [value] if right(value) => 1.0,
+            _ => 0.0,
+        },
+    }
+}
+
+/// The result shown to a respondent, as much of it as the form's
+/// `show_score` allows. Empty for forms that are no quiz.
+pub fn render_result(form: &Form, answers: &HashMap<String, Vec<String>>, lang: &str) -> String {
+    if !form.quiz || form.show_score == ShowScore::Hidden {
+        return String::new();
+    }
+    let texts = i18n::texts(lang);
+    let points = |points: f32| {
+        points
+            .to_string()
+            .replace('.', &texts.decimal_separator.to_string())
+    };
+    let (score, results) = grade(form, answers);
+    let total = i18n::fill(
+        texts.score,
+        &[
+            ("points", &points(score.points)),
+            ("max", &points(score.max)),
+        ],
+    );
+    if form.show_score != ShowScore::PerQuestion {
+        return format!("<p class=\"score\">{total}</p>");
+    }
+    let text = |text: &Localized| escape(text.get(lang, form.language()));
+    let items = form
+        .questions()
+        .filter(|(_, question)| !matches!(question.spec, QuestionType::Unknown { .. }))
+        .map(|(id, question)| {
+            let given = answers.get(&id).filter(|values| !values.is_empty());
+            let answer = match given {
+                Some(values) => values
+                    .iter()
+                    .map(|value| {
+                        let option = question
+                            .spec
+                            .answers()
+                            .and_then(|options| options.iter().find(|o| o.value() == value));
+                        match option {
+                            Some(option) => text(&option.label),
+                            None => escape(value),
+                        }
+                    })
+                    .join(", "),
+                None => texts.no_answer.to_string(),
+            };
+            let (class, verdict) = match results.iter().find(|result| result.id == id) {
+                None => ("ungraded", texts.not_graded),
+                Some(result) => match result.correctness() {
+                    Correctness::Correct => ("correct", texts.answer_correct),
+                    Correctness::Partly => ("partly", texts.answer_partly),
+                    Correctness::Wrong => ("wrong", texts.answer_wrong),
+                },
+            };
+            let explanation = question
+                .explanation
+                .as_ref()
+                .map(|explanation| format!("<p class=\"explanation\">{}</p>", text(explanation)))
+                .unwrap_or_default();
+            format!(
+                "<li class=\"{class}\"><h3>{title}</h3><p class=\"answer\">{answer}</p><p class=\"verdict\">{verdict}</p>{explanation}</li>",
+                title = text(&question.title)
+            )
+        })
+        .join("");
+    format!("<p class=\"score\">{total}</p><ol class=\"quiz-result\">{items}</ol>")
+}
+
+/// The form definition as served to respondents: quizzes leave out the
+/// correct answers, and a CAPTCHA its secret.
+pub fn public_definition(form: &Form) -> serde_json::Value {
+    let mut definition = serde_json::to_value(form).expect("forms always serialize");
+    if let Some(captcha) = definition
+        .get_mut("captcha")
+        .and_then(serde_json::Value::as_object_mut)
+    {
+        captcha.remove("secret");
+    }
+    if form.quiz {
+        for group in definition["groups"].as_array_mut().into_iter().flatten() {
+            for question in group["questions"].as_array_mut().into_iter().flatten() {
+                if let Some(question) = question.as_object_mut() {
+                    question.remove("correct");
+                    question.remove("explanation");
+                }
+            }
+        }
+    }
+    definition
+}
@@ -0,0 +1,165 @@
+//! Scores computed from numeric answers on submit, e.g. a burnout score as
+//! the weighted sum of several scale questions. They are stored with the
+//! submission and can be shown to the respondent afterwards.
+
+use itertools::Itertools;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{escape, i18n, Form, Localized, QuestionType};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+pub struct ComputedScore {
+    /// Name the value is stored and exported under.
+    pub key: String,
+    /// Shown with the value, and the header of its export column.
+    pub title: Localized,
+    #[serde(default)]
+    pub aggregate: Aggregate,
+    pub items: Vec<ScoreItem>,
+    /// Show the value on the page after submitting.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub show: bool,
+    /// Labels for ranges of values, e.g. "low", "moderate" and "high". Each
+    /// applies from its `from` up to the next one, lowest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub thresholds: Vec<Threshold>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregate {
+    /// The weighted answers added up.
+    #[default]
+    Sum,
+    /// Their weighted mean, over the questions that were answered.
+    Mean,
+}
+
+/// A numeric question counted towards a score.
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+pub struct ScoreItem {
+    /// Id of the question, its `key` or positional id.
+    pub question: String,
+    #[serde(default = "unit_weight")]
+    pub weight: f32,
+    /// Count the answer from the other end of the question's bounds, for
+    /// items worded the other way round: 1 counts as 5 on a scale from 1 to 5.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reverse: bool,
+}
+
+fn unit_weight() -> f32 {
+    1.0
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+pub struct Threshold {
+    pub from: f32,
+    pub label: Localized,
+}
+
+/// The bounds answers to `spec` lie within, if it is numeric and has them.
+pub fn bounds(spec: &QuestionType) -> Option<(f32, f32)> {
+    match spec {
+        QuestionType::DiscreteNumeric {
+            bounds: (min, max), ..
+        } => Some((*min as f32, *max as f32)),
+        QuestionType::ContinousNumeric { bounds, .. } => *bounds,
+        _ => None,
+    }
+}
+
+impl ComputedScore {
+    /// The score of `answers`, `None` if none of its questions was answered
+    /// with a number ("not applicable" does not count).
+    pub fn value(&self, form: &Form, answers: &HashMap<String, Vec<String>>) -> Option<f32> {
+        let questions: HashMap<String, _> = form.questions().collect();
+        let mut total = 0.0;
+        let mut weights = 0.0;
+        let mut answered = false;
+        for item in &self.items {
+            let Some(question) = questions.get(&item.question) else {
+                continue;
+            };
+            let Some(value) = answers
+                .get(&item.question)
+                .and_then(|values| values.first())
+                .and_then(|value| value.parse::<f32>().ok())
+            else {
+                continue;
+            };
+            let value = match bounds(&question.spec) {
+                Some((min, max)) if item.reverse => min + max - value,
+                _ => value,
+            };
+            total += item.weight * value;
+            weights += item.weight;
+            answered = true;
+        }
+        let value = match self.aggregate {
+            Aggregate::Sum => total,
+            Aggregate::Mean => total / weights,
+        };
+        Some(value).filter(|value| answered && value.is_finite())
+    }
+
+    /// The label of the range `value` falls into.
+    pub fn label(&self, value: f32) -> Option<&Localized> {
+        self.thresholds
+            .iter()
+            .rev()
+            .find(|threshold| value >= threshold.from)
+            .map(|threshold| &threshold.label)
+    }
+}
+
+/// The values of the form's computed scores for `answers`, by key, as stored
+/// with the submission.
+pub fn compute(form: &Form, answers: &HashMap<String, Vec<String>>) -> BTreeMap<String, f32> {
+    form.computed
+        .iter()
+        .filter_map(|score| Some((score.key.clone(), score.value(form, answers)?)))
+        .collect()
+}
+
+/// The values of the scores respondents are shown.
+pub fn shown(form: &Form, values: &BTreeMap<String, f32>) -> BTreeMap<String, f32> {
+    form.computed
+        .iter()
+        .filter(|score| score.show)
+        .filter_map(|score| Some((score.key.clone(), *values.get(&score.key)?)))
+        .collect()
+}
+
+/// The scores shown to the respondent after submitting, empty if there are
+/// none.
+pub fn render_values(form: &Form, values: &BTreeMap<String, f32>, lang: &str) -> String {
+    let texts = i18n::texts(lang);
+    let text = |text: &Localized| escape(text.get(lang, form.language()));
+    let items = form
+        .computed
+        .iter()
+        .filter(|score| score.show)
+        .filter_map(|score| {
+            let value = values.get(&score.key)?;
+            let number = ((value * 100.0).round() / 100.0)
+                .to_string()
+                .replace('.', &texts.decimal_separator.to_string());
+            let label = score
+                .label(*value)
+                .map(|label| format!(" <span class=\"label\">{}</span>", text(label)))
+                .unwrap_or_default();
+            Some(format!(
+                "<li><span class=\"title\">{}</span>: <span class=\"value\">{number}</span>{label}</li>",
+                text(&score.title)
+            ))
+        })
+        .join("");
+    if items.is_empty() {
+        String::new()
+    } else {
+        format!("<ul class=\"computed-scores\">{items}</ul>")
+    }
+}
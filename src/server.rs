@@ -0,0 +1,1598 @@
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::{ConnectInfo, Extension, FromRequest, Path, Query, RequestParts};
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::Json;
+use axum::Router;
+use chrono::{DateTime, SecondsFormat, Utc};
+use itertools::Itertools;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::set_header::SetResponseHeaderLayer;
+use uuid::Uuid;
+
+use crate::captcha::{self, Captcha, CaptchaVerifier, Provider, SiteVerify};
+use crate::drafts::{Draft, DraftStore};
+use crate::live::{self, Change};
+use crate::signing::Signer;
+use crate::sinks::SubmissionSink;
+use crate::store::{ClientMeta, FormVersion, Inserted, Source, Submission, SubmissionStore};
+use crate::{
+    admin, api, backup, badge, embed, escape, export, i18n, markdown_html, openapi, print, quiz,
+    scores, stats, Availability, ClientIp, FieldError, Form, Limits, RenderContext, Renderable,
+    ResponseLimit, ShowScore, ROOT_PREFIX,
+};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+pub struct AppState {
+    pub forms: RwLock<HashMap<String, Arc<Form>>>,
+    pub store: SubmissionStore,
+    pub clock: Box<dyn Clock>,
+    pub limits: Limits,
+    /// Bearer token for the admin routes; they reject everything when unset.
+    pub admin_token: Option<String>,
+    /// Directory forms managed through the API are written to.
+    pub forms_dir: Option<PathBuf>,
+    /// The file each form was loaded from, by id, which changes through the
+    /// API are written to.
+    pub sources: Mutex<HashMap<String, PathBuf>>,
+    pub favicon: Favicon,
+    pub drafts: DraftStore,
+    /// How long a saved draft can be resumed.
+    pub draft_ttl: chrono::Duration,
+    pub(crate) resume_limiter: RateLimiter,
+    pub signer: Signer,
+    /// Origins whose pages may post to `/submit` and read form definitions
+    /// from `/api/form/:id` in the browser; without any, only same-origin
+    /// requests work. See `parse_origin`.
+    pub cors_origins: Vec<HeaderValue>,
+    /// Let pages on any origin use the `/api/*` routes, as for a public API.
+    /// `/submit` stays limited to `cors_origins`.
+    pub cors_any_origin: bool,
+    /// Render pages without inline scripts (see `RenderContext::no_js`).
+    pub no_js: bool,
+    /// `Content-Security-Policy` sent with every response; `default_csp`
+    /// when unset.
+    pub csp: Option<HeaderValue>,
+    /// Origins whose pages may show forms in an iframe (`?embed=1`). Other
+    /// pages are never framed.
+    pub embed_origins: Vec<String>,
+    /// Checks the tokens submitted with forms that have a `captcha`.
+    pub captcha: Box<dyn CaptchaVerifier>,
+    /// Handed every stored submission in the background, so a slow
+    /// destination does not hold up the response (see `deliver`).
+    pub sinks: Arc<Vec<Box<dyn SubmissionSink>>>,
+    /// Take the client address from the last `X-Forwarded-For` entry, as
+    /// added by a reverse proxy in front of the server. Anyone can send the
+    /// header, so this is only safe when the proxy is the only way in.
+    pub trust_proxy: bool,
+    /// Response counts of the public badges, with the time they were taken.
+    pub(crate) counts: Mutex<HashMap<String, (DateTime<Utc>, usize)>>,
+    pub(crate) form_writes: tokio::sync::Mutex<()>,
+    /// Tells the live views what changed (see `live`).
+    pub(crate) changes: tokio::sync::broadcast::Sender<Change>,
+    /// Most `/form/:id/events` streams open at once per form.
+    pub max_event_streams: usize,
+    pub(crate) event_streams: Mutex<HashMap<String, usize>>,
+}
+
+impl AppState {
+    pub fn new(forms: HashMap<String, Form>, store: SubmissionStore) -> Self {
+        AppState {
+            forms: RwLock::new(
+                forms
+                    .into_iter()
+                    .map(|(id, form)| (id, Arc::new(form)))
+                    .collect(),
+            ),
+            store,
+            clock: Box::new(SystemClock),
+            limits: Limits::default(),
+            admin_token: None,
+            forms_dir: None,
+            sources: Mutex::default(),
+            favicon: Favicon::default(),
+            drafts: DraftStore::default(),
+            draft_ttl: chrono::Duration::days(7),
+            resume_limiter: RateLimiter::new(30, Duration::from_secs(60)),
+            signer: Signer::default(),
+            cors_origins: Vec::new(),
+            cors_any_origin: false,
+            no_js: false,
+            csp: None,
+            embed_origins: Vec::new(),
+            captcha: Box::new(SiteVerify),
+            sinks: Arc::default(),
+            trust_proxy: false,
+            counts: Mutex::default(),
+            form_writes: tokio::sync::Mutex::new(()),
+            changes: tokio::sync::broadcast::channel(live::BACKLOG).0,
+            max_event_streams: 100,
+            event_streams: Mutex::default(),
+        }
+    }
+
+    pub fn form(&self, form_id: &str) -> Option<Arc<Form>> {
+        self.forms.read().unwrap().get(form_id).cloned()
+    }
+
+    /// Logs that `form` is now served under `form_id`, with its version, to
+    /// tell later which definition was live when.
+    pub fn log_form_loaded(&self, form_id: &str, form: &Form) {
+        eprintln!(
+            "{} loaded form {form_id} -> {}",
+            self.clock.now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            form.version()
+        );
+    }
+
+    /// Tells the live views about `change`; nobody may be listening.
+    pub(crate) fn changed(&self, change: Change) {
+        let _ = self.changes.send(change);
+    }
+
+    /// Tells the live views about a stored submission and hands it to every
+    /// sink, in a task of its own: the sinks get it in order, but submissions
+    /// stored at the same time may reach them in either order. Failures are
+    /// only logged: the submission is in the store either way.
+    pub fn deliver(&self, submission: &Submission) {
+        self.changed(Change::Responses(submission.form_id.clone()));
+        if self.sinks.is_empty() {
+            return;
+        }
+        let sinks = self.sinks.clone();
+        let submission = submission.clone();
+        tokio::spawn(async move {
+            for sink in sinks.iter() {
+                if let Err(err) = sink.handle(&submission).await {
+                    eprintln!(
+                        "could not hand submission {} of form {} to {}: {err}",
+                        submission.id,
+                        submission.form_id,
+                        sink.name()
+                    );
+                }
+            }
+        });
+    }
+
+    /// Address of the client a request came from: the peer, or with
+    /// `trust_proxy` the address the proxy says it forwarded for.
+    pub(crate) fn client_ip(&self, peer: SocketAddr, headers: &HeaderMap) -> IpAddr {
+        let forwarded = || {
+            let value = headers.get_all("x-forwarded-for").iter().next_back()?;
+            value.to_str().ok()?.rsplit(',').next()?.trim().parse().ok()
+        };
+        self.trust_proxy
+            .then(forwarded)
+            .flatten()
+            .unwrap_or(peer.ip())
+    }
+}
+
+/// Fixed-window limit on requests per client address.
+pub(crate) struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    hits: Mutex<HashMap<IpAddr, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: u32, window: Duration) -> Self {
+        RateLimiter {
+            limit,
+            window,
+            hits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a request from `ip` and tells whether it is still allowed.
+    pub(crate) fn check(&self, ip: IpAddr) -> bool {
+        let now = Instant::now();
+        let mut hits = self.hits.lock().unwrap();
+        hits.retain(|_, (start, _)| now.duration_since(*start) < self.window);
+        let (_, count) = hits.entry(ip).or_insert((now, 0));
+        *count += 1;
+        *count <= self.limit
+    }
+}
+
+pub struct Favicon {
+    pub content_type: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+impl Default for Favicon {
+    fn default() -> Self {
+        Favicon {
+            content_type: "image/x-icon",
+            bytes: include_bytes!("../assets/favicon.ico").to_vec(),
+        }
+    }
+}
+
+impl Favicon {
+    pub fn load(path: &std::path::Path) -> std::io::Result<Self> {
+        let content_type = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("png") => "image/png",
+            Some("svg") => "image/svg+xml",
+            _ => "image/x-icon",
+        };
+        Ok(Favicon {
+            content_type,
+            bytes: std::fs::read(path)?,
+        })
+    }
+}
+
+/// Extractor guarding admin routes with `Authorization: Bearer <admin token>`.
+pub struct Admin;
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for Admin {
+    type Rejection = StatusCode;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Extension(state) = Extension::<Arc<AppState>>::from_request(req)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let expected = state
+            .admin_token
+            .as_deref()
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+        let given = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+        if constant_time_eq(given.as_bytes(), expected.as_bytes()) {
+            Ok(Admin)
+        } else {
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Only same-origin resources, plus the inline scripts pages carry unless
+/// rendered without them. Inline styles are allowed either way.
+pub fn default_csp(no_js: bool) -> &'static str {
+    if no_js {
+        "default-src 'self'; style-src 'self' 'unsafe-inline'; frame-ancestors 'none'"
+    } else {
+        "default-src 'self'; style-src 'self' 'unsafe-inline'; script-src 'self' 'unsafe-inline'; frame-ancestors 'none'"
+    }
+}
+
+/// Marks responses meant to be shown in an iframe, which are sent without
+/// `X-Frame-Options`.
+#[derive(Clone, Copy)]
+struct Embedded;
+
+/// The usual policy, but letting the embedding origins frame the page.
+fn embed_csp(state: &AppState) -> HeaderValue {
+    let base = state
+        .csp
+        .as_ref()
+        .and_then(|csp| csp.to_str().ok())
+        .unwrap_or(default_csp(state.no_js));
+    let ancestors = std::iter::once("'self'")
+        .chain(state.embed_origins.iter().map(String::as_str))
+        .join(" ");
+    let policy = base
+        .split(';')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty() && !directive.starts_with("frame-ancestors"))
+        .map(str::to_string)
+        .chain(std::iter::once(format!("frame-ancestors {ancestors}")))
+        .join("; ");
+    HeaderValue::from_str(&policy).expect("policy is built from header values")
+}
+
+/// Lets `response` be framed by the embedding origins if `embed` is set.
+fn framed(state: &AppState, embed: bool, mut response: Response) -> Response {
+    if embed {
+        let mut policy = embed_csp(state);
+        if let Some(CaptchaWidget(provider)) = response.extensions().get() {
+            policy = captcha_csp(&policy, *provider);
+        }
+        response
+            .headers_mut()
+            .insert(header::CONTENT_SECURITY_POLICY, policy);
+        response.extensions_mut().insert(Embedded);
+    }
+    response
+}
+
+/// Marks pages showing the CAPTCHA widget of a provider, which are sent with
+/// a policy allowing it to load.
+#[derive(Clone, Copy)]
+struct CaptchaWidget(Provider);
+
+fn captcha_csp(policy: &HeaderValue, provider: Provider) -> HeaderValue {
+    let policy = captcha::csp(policy.to_str().unwrap_or_default(), provider);
+    HeaderValue::from_str(&policy).expect("policy is built from header values")
+}
+
+/// `page` of `form`, marked if it shows a CAPTCHA.
+fn with_captcha(form: &Form, page: impl IntoResponse) -> Response {
+    let mut response = page.into_response();
+    if let Some(captcha) = &form.captcha {
+        response
+            .extensions_mut()
+            .insert(CaptchaWidget(captcha.provider));
+    }
+    response
+}
+
+/// Checks that `origin` is exactly `scheme://host[:port]`, as browsers send
+/// it in `Origin`; anything else, like a path or a trailing slash, would
+/// never match.
+pub fn parse_origin(origin: &str) -> Result<HeaderValue, String> {
+    let pattern = regex::Regex::new(
+        r"^(?i)https?://([a-z0-9-]+(\.[a-z0-9-]+)*|\[[0-9a-f:.]+\])(:[0-9]{1,5})?$",
+    )
+    .expect("pattern is valid");
+    if !pattern.is_match(origin) {
+        return Err(format!(
+            "{origin}: not an origin, expected scheme://host[:port] without a path or trailing slash"
+        ));
+    }
+    HeaderValue::from_str(origin).map_err(|_| format!("{origin}: not a valid origin"))
+}
+
+pub fn router(state: Arc<AppState>) -> Router {
+    let csp = state
+        .csp
+        .clone()
+        .unwrap_or_else(|| HeaderValue::from_static(default_csp(state.no_js)));
+    let mut submit_routes = Router::new()
+        .route("/submit/:form_id", post(submit))
+        .route("/submit/:form_id/:group_index", post(submit_group));
+    let mut api_routes = api::routes();
+    if !state.cors_origins.is_empty() {
+        submit_routes = submit_routes.layer(
+            CorsLayer::new()
+                .allow_origin(AllowOrigin::list(state.cors_origins.clone()))
+                .allow_methods([Method::POST])
+                .allow_headers([header::CONTENT_TYPE, header::ACCEPT]),
+        );
+    }
+    if state.cors_any_origin || !state.cors_origins.is_empty() {
+        let origins = if state.cors_any_origin {
+            AllowOrigin::any()
+        } else {
+            AllowOrigin::list(state.cors_origins.clone())
+        };
+        api_routes = api_routes.layer(
+            CorsLayer::new()
+                .allow_origin(origins)
+                .allow_methods([Method::GET, Method::PUT, Method::DELETE])
+                .allow_headers([
+                    header::CONTENT_TYPE,
+                    header::ACCEPT,
+                    header::AUTHORIZATION,
+                    header::IF_NONE_MATCH,
+                ])
+                .expose_headers([header::ETAG]),
+        );
+    }
+    Router::new()
+        .route("/", get(index))
+        .route("/favicon.ico", get(favicon))
+        .route("/form/:form_id", get(show_form))
+        .route("/form/:form_id/embed", get(show_embedded_form))
+        .route("/form/:form_id/resume/:token", get(resume_draft))
+        .route("/form/:form_id/edit/:token", get(edit_submission))
+        .route("/form/:form_id/view", get(view_form))
+        .route("/form/:form_id/version", get(form_version))
+        .route("/form/:form_id/result/:token", get(quiz_result))
+        .route(
+            "/form/:form_id/delete/:token",
+            get(confirm_delete).post(delete_submission),
+        )
+        .route("/draft/:form_id", post(save_draft))
+        .merge(submit_routes)
+        .merge(admin::routes())
+        .merge(api_routes)
+        .merge(backup::routes())
+        .merge(badge::routes())
+        .merge(embed::routes())
+        .merge(export::routes())
+        .merge(live::routes())
+        .merge(openapi::routes())
+        .merge(stats::routes())
+        .layer(Extension(state))
+        .layer(SetResponseHeaderLayer::if_not_present(
+            header::X_FRAME_OPTIONS,
+            |response: &Response| {
+                (response.extensions().get::<Embedded>().is_none())
+                    .then(|| HeaderValue::from_static("DENY"))
+            },
+        ))
+        .layer(SetResponseHeaderLayer::if_not_present(
+            header::CONTENT_SECURITY_POLICY,
+            move |response: &Response| {
+                Some(match response.extensions().get() {
+                    Some(CaptchaWidget(provider)) => captcha_csp(&csp, *provider),
+                    None => csp.clone(),
+                })
+            },
+        ))
+}
+
+async fn index(Extension(state): Extension<Arc<AppState>>) -> Html<String> {
+    let links = state
+        .forms
+        .read()
+        .unwrap()
+        .iter()
+        .sorted_by_key(|(id, _)| *id)
+        .map(|(id, form)| {
+            format!(
+                r#"<li><a href="/form/{id}">{}</a></li>"#,
+                escape(form.text(&form.title))
+            )
+        })
+        .join("\n");
+    Html(format!(
+        "<html style=\"font-family=sans-serif\"><body><ul>{links}</ul></body></html>"
+    ))
+}
+
+/// `"<hex>"` of the first bytes of the SHA-256 of `parts`.
+fn etag<'a>(parts: impl IntoIterator<Item = &'a [u8]>) -> String {
+    let mut digest = Sha256::new();
+    for part in parts {
+        digest.update(part);
+        digest.update([0]);
+    }
+    format!("\"{}\"", hex::encode(&digest.finalize()[..8]))
+}
+
+/// Whether the client's `If-None-Match` lists `etag`, compared weakly.
+pub(crate) fn not_modified(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+}
+
+/// A day, after which browsers check for a new icon.
+const FAVICON_CACHE: &str = "public, max-age=86400";
+
+async fn favicon(Extension(state): Extension<Arc<AppState>>, headers: HeaderMap) -> Response {
+    let etag = etag([state.favicon.bytes.as_ref()]);
+    let cache = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, FAVICON_CACHE.to_string()),
+    ];
+    if not_modified(&headers, &etag) {
+        return (StatusCode::NOT_MODIFIED, cache).into_response();
+    }
+    (
+        [(header::CONTENT_TYPE, state.favicon.content_type)],
+        cache,
+        state.favicon.bytes.clone(),
+    )
+        .into_response()
+}
+
+/// Language for a page of `form`: an explicit choice (`?lang=`, `_lang`)
+/// before the browser's `Accept-Language`.
+pub(crate) fn language(form: &Form, requested: Option<&String>, headers: &HeaderMap) -> String {
+    let accept_language = headers
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok());
+    i18n::choose(form, requested.map(String::as_str), accept_language)
+}
+
+async fn show_form(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    admin: Option<Admin>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let form = state.form(&form_id).ok_or(StatusCode::NOT_FOUND)?;
+    // The paper version cannot submit, so it is available regardless.
+    if params.get("print").is_some_and(|print| print == "1") {
+        return Ok(Html(print::to_print_html(&form)).into_response());
+    }
+    let embed = params.get("embed").is_some_and(|embed| embed == "1");
+    let preview = params.get("preview").is_some_and(|preview| preview == "1");
+    if preview
+        && admin.is_none()
+        && !params
+            .get("token")
+            .is_some_and(|token| is_preview_token(&state, &form_id, token))
+    {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    let lang = language(&form, params.get("lang"), &headers);
+    let now = state.clock.now();
+    // Authors preview forms before they open and after they filled up.
+    if !preview {
+        if let Some(page) = unavailable_page(&state, &form_id, &form, now, &lang)
+            .or_else(|| full_page(&state, &form_id, &form, &lang))
+        {
+            return Ok(framed(&state, embed, Html(page).into_response()));
+        }
+    }
+    // Shuffled forms keep their order for the session.
+    let cookie = cookie_seed(&state, &headers);
+    let seed = form.shuffles().then(|| cookie.unwrap_or_else(rand::random));
+    // A page taken from the cache keeps its `_started` time. Pages of
+    // previews carry a token, and those of `per_group_submit` forms the
+    // session their sets are joined under, so these are always rendered.
+    let cacheable = !preview && !form.per_group_submit;
+    let etag = cacheable.then(|| {
+        let query = params
+            .iter()
+            .sorted()
+            .map(|(name, value)| format!("{name}={value}"))
+            .join("&");
+        let version = form.version();
+        // The time left before a form closes changes the page too.
+        let closing = form.closing_notice(now, &lang).unwrap_or_default();
+        format!(
+            "W/{}",
+            etag([
+                version.as_bytes(),
+                lang.as_bytes(),
+                query.as_bytes(),
+                &[u8::from(state.no_js)],
+                closing.as_bytes(),
+                &seed.unwrap_or_default().to_le_bytes(),
+            ])
+        )
+    });
+    if let Some(etag) = etag.as_ref().filter(|etag| not_modified(&headers, etag)) {
+        let headers = [
+            (header::ETAG, etag.clone()),
+            (header::CACHE_CONTROL, "no-cache".to_string()),
+        ];
+        let response = with_captcha(&form, (StatusCode::NOT_MODIFIED, headers));
+        return Ok(framed(&state, embed, response));
+    }
+    let tracking = form.track_params.iter().filter_map(|name| {
+        let value = params.get(name)?;
+        Some((format!("{TRACK_PREFIX}{name}"), truncate(value)))
+    });
+    let preview_field = preview.then(|| {
+        (
+            "_preview".to_string(),
+            preview_token(&state.signer, &form_id),
+        )
+    });
+    let embed_field = embed.then(|| ("_embed".to_string(), "1".to_string()));
+    let ctx = RenderContext {
+        hidden: [started_field(&state, &form_id, now), lang_field(&lang)]
+            .into_iter()
+            .chain(preview_field)
+            .chain(embed_field)
+            .chain(seed.map(|seed| seed_field(&state, seed)))
+            .chain(tracking)
+            .collect(),
+        session: session_token(&state, &form_id),
+        form_id,
+        lang,
+        no_js: state.no_js,
+        preview,
+        embed,
+        now: Some(now),
+        seed,
+        ..Default::default()
+    };
+    let mut page = with_captcha(&form, Html(form.render(ROOT_PREFIX.to_string(), &ctx)));
+    if let Some(seed) = seed.filter(|_| cookie.is_none()) {
+        let cookie = format!(
+            "{SEED_COOKIE}={}; Path=/; HttpOnly; SameSite=Lax",
+            seed_field(&state, seed).1
+        );
+        page.headers_mut().insert(
+            header::SET_COOKIE,
+            HeaderValue::from_str(&cookie).expect("tokens are hex"),
+        );
+    }
+    if let Some(etag) = etag {
+        let headers = page.headers_mut();
+        headers.insert(
+            header::ETAG,
+            HeaderValue::from_str(&etag).expect("tags are hex"),
+        );
+        // Checked on every visit, so a changed form is seen right away.
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    }
+    Ok(framed(&state, embed, page))
+}
+
+/// `/form/:id/embed`, the same as `/form/:id?embed=1`.
+async fn show_embedded_form(
+    state: Extension<Arc<AppState>>,
+    form_id: Path<String>,
+    Query(mut params): Query<HashMap<String, String>>,
+    admin: Option<Admin>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    params.insert("embed".to_string(), "1".to_string());
+    show_form(state, form_id, Query(params), admin, headers).await
+}
+
+async fn save_draft(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, StatusCode> {
+    let form = state
+        .form(&form_id)
+        .filter(|form| form.drafts)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let (mut answers, fields) = parse_answers(&form, &body, |_| true);
+    let lang = language(&form, fields.get("_lang"), &headers);
+    form.normalize_answers(&mut answers, &lang);
+    let now = state.clock.now();
+    if let Some(page) = unavailable_page(&state, &form_id, &form, now, &lang) {
+        return Ok((StatusCode::FORBIDDEN, Html(page)).into_response());
+    }
+    let draft = Draft {
+        form_id: form_id.clone(),
+        answers,
+        expires_at: now + state.draft_ttl,
+        seed: posted_seed(&state, &form, &fields, &headers),
+    };
+    state.drafts.purge(now).await.map_err(internal_error)?;
+    let token = state.drafts.save(draft).await.map_err(internal_error)?;
+    // A draft saved from a resumed draft replaces it.
+    if let Some(previous) = fields.get("_draft") {
+        state
+            .drafts
+            .remove(previous)
+            .await
+            .map_err(internal_error)?;
+    }
+    let resume_url = format!("/form/{form_id}/resume/{token}?lang={lang}");
+    Ok(Html(draft_saved(&form, &lang, &resume_url)).into_response())
+}
+
+async fn resume_draft(
+    Extension(state): Extension<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path((form_id, token)): Path<(String, String)>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    if !state.resume_limiter.check(state.client_ip(addr, &headers)) {
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+    let form = state.form(&form_id).ok_or(StatusCode::NOT_FOUND)?;
+    let lang = language(&form, params.get("lang"), &headers);
+    let now = state.clock.now();
+    if let Some(page) = unavailable_page(&state, &form_id, &form, now, &lang)
+        .or_else(|| full_page(&state, &form_id, &form, &lang))
+    {
+        return Ok(Html(page).into_response());
+    }
+    let draft = state
+        .drafts
+        .get(&form_id, &token, now)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let seed = draft
+        .seed
+        .or_else(|| cookie_seed(&state, &headers))
+        .filter(|_| form.shuffles());
+    let ctx = RenderContext {
+        session: session_token(&state, &form_id),
+        values: draft.answers,
+        hidden: [
+            ("_draft".to_string(), token),
+            started_field(&state, &form_id, now),
+            lang_field(&lang),
+        ]
+        .into_iter()
+        .chain(seed.map(|seed| seed_field(&state, seed)))
+        .collect(),
+        form_id,
+        lang,
+        no_js: state.no_js,
+        now: Some(now),
+        seed,
+        ..Default::default()
+    };
+    Ok(with_captcha(
+        &form,
+        Html(form.render(ROOT_PREFIX.to_string(), &ctx)),
+    ))
+}
+
+/// Hidden fields carrying the tracked query parameters: `_track.<name>`.
+const TRACK_PREFIX: &str = "_track.";
+const MAX_TRACK_VALUE: usize = 100;
+
+fn truncate(value: &str) -> String {
+    value.chars().take(MAX_TRACK_VALUE).collect()
+}
+
+/// The tracked parameters posted back, restricted to the form's allowlist.
+fn tracking(form: &Form, fields: &HashMap<String, String>) -> BTreeMap<String, String> {
+    form.track_params
+        .iter()
+        .filter_map(|name| {
+            let value = fields.get(&format!("{TRACK_PREFIX}{name}"))?;
+            Some((name.clone(), truncate(value)))
+        })
+        .collect()
+}
+
+fn lang_field(lang: &str) -> (String, String) {
+    ("_lang".to_string(), lang.to_string())
+}
+
+const STARTED_TOKEN: &str = "started";
+
+/// Signed time the form was served, posted back as `_started`.
+fn started_field(state: &AppState, form_id: &str, now: DateTime<Utc>) -> (String, String) {
+    let token = state
+        .signer
+        .sign(STARTED_TOKEN, form_id, &now.timestamp().to_string());
+    ("_started".to_string(), token)
+}
+
+/// Time spent on the form, or `None` if the `_started` value was tampered
+/// with or is implausible (in the future, or before the form opened).
+fn duration_secs(
+    state: &AppState,
+    form: &Form,
+    form_id: &str,
+    started: &str,
+    now: DateTime<Utc>,
+) -> Option<i64> {
+    let started = state.signer.verify(STARTED_TOKEN, form_id, started)?;
+    let started = DateTime::from_timestamp(started.parse().ok()?, 0)?;
+    if form.opens_at.is_some_and(|opens_at| started < opens_at) {
+        return None;
+    }
+    let duration = (now - started).num_seconds();
+    (duration >= 0).then_some(duration)
+}
+
+const SESSION_TOKEN: &str = "session";
+
+/// Signed id the sets of a `per_group_submit` form are merged under, posted
+/// back as `_session`. Bound to the form, so it cannot name the submission of
+/// another form or one the respondent only knows the id of.
+fn session_token(state: &AppState, form_id: &str) -> String {
+    state
+        .signer
+        .sign(SESSION_TOKEN, form_id, &Uuid::new_v4().to_string())
+}
+
+const SEED_TOKEN: &str = "seed";
+/// Cookie keeping the shuffle seed of a respondent across page loads.
+const SEED_COOKIE: &str = "umfragetool_seed";
+
+/// Signed shuffle seed, posted back as `_seed`; also the cookie's value.
+fn seed_field(state: &AppState, seed: u64) -> (String, String) {
+    let token = state.signer.sign(SEED_TOKEN, "", &seed.to_string());
+    ("_seed".to_string(), token)
+}
+
+fn verify_seed(state: &AppState, token: &str) -> Option<u64> {
+    state.signer.verify(SEED_TOKEN, "", token)?.parse().ok()
+}
+
+/// The seed of the cookie sent with a request, if it is validly signed.
+fn cookie_seed(state: &AppState, headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == SEED_COOKIE)
+        .and_then(|(_, token)| verify_seed(state, token))
+}
+
+/// The seed the posted page was shown with: its `_seed` field, or the cookie
+/// for pages that lack one.
+fn posted_seed(
+    state: &AppState,
+    form: &Form,
+    fields: &HashMap<String, String>,
+    headers: &HeaderMap,
+) -> Option<u64> {
+    fields
+        .get("_seed")
+        .and_then(|token| verify_seed(state, token))
+        .or_else(|| cookie_seed(state, headers))
+        .filter(|_| form.shuffles())
+}
+
+const EDIT_TOKEN: &str = "edit";
+const DELETE_TOKEN: &str = "delete";
+const RESULT_TOKEN: &str = "result";
+const PREVIEW_TOKEN: &str = "preview";
+
+/// Token that lets whoever holds it preview `form_id`, without being an
+/// admin. Posted along with preview submissions, so only pages served as a
+/// preview can submit without being stored.
+pub fn preview_token(signer: &Signer, form_id: &str) -> String {
+    signer.sign(PREVIEW_TOKEN, form_id, PREVIEW_TOKEN)
+}
+
+fn is_preview_token(state: &AppState, form_id: &str, token: &str) -> bool {
+    state
+        .signer
+        .verify(PREVIEW_TOKEN, form_id, token)
+        .is_some_and(|id| id == PREVIEW_TOKEN)
+}
+
+/// Whether a submission comes from a preview. A `_preview` field that is not
+/// validly signed is refused rather than counted.
+fn preview_submission(
+    state: &AppState,
+    form_id: &str,
+    fields: &HashMap<String, String>,
+) -> Result<bool, StatusCode> {
+    match fields.get("_preview") {
+        None => Ok(false),
+        Some(token) if is_preview_token(state, form_id, token) => Ok(true),
+        Some(_) => Err(StatusCode::FORBIDDEN),
+    }
+}
+
+async fn edit_submission(
+    Extension(state): Extension<Arc<AppState>>,
+    Path((form_id, token)): Path<(String, String)>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Result<Html<String>, StatusCode> {
+    let form = state
+        .form(&form_id)
+        .filter(|form| form.editable)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let lang = language(&form, params.get("lang"), &headers);
+    if let Some(page) = unavailable_page(&state, &form_id, &form, state.clock.now(), &lang) {
+        return Ok(Html(page));
+    }
+    let submission = state
+        .signer
+        .verify(EDIT_TOKEN, &form_id, &token)
+        .and_then(|id| state.store.get(&form_id, &id))
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let ctx = RenderContext {
+        session: session_token(&state, &form_id),
+        form_id,
+        values: submission.answers,
+        hidden: vec![("_edit".to_string(), token), lang_field(&lang)],
+        lang,
+        no_js: state.no_js,
+        seed: submission.shuffle_seed,
+        ..Default::default()
+    };
+    Ok(Html(form.render(ROOT_PREFIX.to_string(), &ctx)))
+}
+
+/// The form read-only, blank or (for admins, `?response=<id>`) showing a
+/// stored submission. Served regardless of availability, as it cannot submit.
+async fn view_form(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    admin: Option<Admin>,
+    headers: HeaderMap,
+) -> Result<Html<String>, StatusCode> {
+    let form = state.form(&form_id).ok_or(StatusCode::NOT_FOUND)?;
+    let lang = language(&form, params.get("lang"), &headers);
+    // A stored submission is shown in the order its respondent saw.
+    let (values, seed) = match params.get("response") {
+        None => (HashMap::new(), None),
+        Some(_) if admin.is_none() => return Err(StatusCode::UNAUTHORIZED),
+        Some(id) => {
+            let submission = state.store.get(&form_id, id).ok_or(StatusCode::NOT_FOUND)?;
+            (submission.answers, submission.shuffle_seed)
+        }
+    };
+    let ctx = RenderContext {
+        form_id,
+        values,
+        seed,
+        lang,
+        no_js: state.no_js,
+        disabled: true,
+        ..Default::default()
+    };
+    Ok(Html(form.render(ROOT_PREFIX.to_string(), &ctx)))
+}
+
+/// Clients asking for `application/json` (e.g. `fetch` based frontends) get
+/// JSON results from `/submit` instead of HTML pages.
+pub(crate) fn wants_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("application/json"))
+}
+
+async fn submit(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, StatusCode> {
+    // Forms shown in an iframe keep every following page in it.
+    let embed = form_urlencoded::parse(&body).any(|(name, _)| name == "_embed");
+    let ip = state.client_ip(addr, &headers);
+    let response = handle_submission(&state, form_id, ip, &headers, &body).await?;
+    Ok(framed(&state, embed, response))
+}
+
+async fn handle_submission(
+    state: &AppState,
+    form_id: String,
+    ip: IpAddr,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<Response, StatusCode> {
+    let form = state.form(&form_id).ok_or(StatusCode::NOT_FOUND)?;
+    let json = wants_json(headers);
+    let now = state.clock.now();
+    let (mut answers, fields) = parse_answers(&form, body, |_| true);
+    let lang = language(&form, fields.get("_lang"), headers);
+    let preview = preview_submission(state, &form_id, &fields)?;
+    let embed = fields.contains_key("_embed");
+    form.normalize_answers(&mut answers, &lang);
+    form.drop_hidden_answers(&mut answers);
+    let refuse = |page: String| {
+        if json {
+            let body = serde_json::json!({ "status": "error", "fields": [] });
+            (StatusCode::FORBIDDEN, Json(body)).into_response()
+        } else {
+            (StatusCode::FORBIDDEN, Html(page)).into_response()
+        }
+    };
+    if let Some(page) = unavailable_page(state, &form_id, &form, now, &lang).filter(|_| !preview) {
+        return Ok(refuse(page));
+    }
+    // Only a link handed out for an editable form continues a submission.
+    let edited = match fields.get("_edit") {
+        Some(token) => Some(
+            state
+                .signer
+                .verify(EDIT_TOKEN, &form_id, token)
+                .filter(|_| form.editable)
+                .and_then(|id| state.store.get(&form_id, &id))
+                .ok_or(StatusCode::FORBIDDEN)?,
+        ),
+        None => None,
+    };
+    // A full form still accepts changes to existing submissions.
+    if edited.is_none() && !preview {
+        if let Some(page) = full_page(state, &form_id, &form, &lang) {
+            return Ok(refuse(page));
+        }
+    }
+    let seed = posted_seed(state, &form, &fields, headers);
+    // Serves the form again with the input kept, and the problems marked.
+    let form_again =
+        |values: HashMap<String, Vec<String>>, errors: Vec<FieldError>| RenderContext {
+            form_id: form_id.clone(),
+            session: session_token(state, &form_id),
+            values,
+            hidden: fields
+                .iter()
+                .filter(|(name, _)| {
+                    [
+                        "_draft", "_edit", "_started", "_lang", "_preview", "_embed", "_seed",
+                    ]
+                    .contains(&name.as_str())
+                        || name.starts_with(TRACK_PREFIX)
+                })
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            errors: errors
+                .into_iter()
+                .map(|error| (error.id, error.message))
+                .collect(),
+            lang: lang.clone(),
+            no_js: state.no_js,
+            preview,
+            embed,
+            now: Some(now),
+            seed,
+            ..Default::default()
+        };
+    if form.confirm_submit && fields.contains_key("_back") {
+        let ctx = form_again(answers, Vec::new());
+        return Ok(with_captcha(
+            &form,
+            Html(form.render(ROOT_PREFIX.to_string(), &ctx)),
+        ));
+    }
+    let mut errors = match form.validate_submission_in(&answers, &lang) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors,
+    };
+    // Asked before the CAPTCHA is checked, whose token only counts once.
+    let unconfirmed = fields.get("_confirmed").map(String::as_str) != Some("1");
+    if form.confirm_submit && unconfirmed && !json && errors.is_empty() {
+        return Ok(Html(confirm_page(&form, &form_id, &lang, body)).into_response());
+    }
+    // Previews store nothing and edits come from the respondent's own link.
+    if errors.is_empty() && !preview && edited.is_none() {
+        if let Some(captcha) = &form.captcha {
+            if !captcha_solved(state, captcha, body, ip).await {
+                errors.push(FieldError {
+                    id: captcha::FIELD.to_string(),
+                    message: i18n::texts(&lang).captcha_failed.to_string(),
+                });
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Ok(if json {
+            let body = serde_json::json!({ "status": "error", "fields": errors });
+            (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response()
+        } else {
+            let ctx = form_again(answers, errors);
+            with_captcha(
+                &form,
+                (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    Html(form.render(ROOT_PREFIX.to_string(), &ctx)),
+                ),
+            )
+        });
+    }
+    if preview {
+        return Ok(if json {
+            Json(serde_json::json!({ "status": "ok", "preview": true })).into_response()
+        } else {
+            Html(preview_submitted(&form, &lang, &answers)).into_response()
+        });
+    }
+    let version = FormVersion::of(&form_id, &form);
+    state
+        .store
+        .record_version(version.clone())
+        .await
+        .map_err(internal_error)?;
+
+    let score = quiz::score(&form, &answers);
+    let choice_score = form.scores_choices().then(|| form.score(&answers));
+    let result = quiz::render_result(&form, &answers, &lang);
+    let computed = scores::compute(&form, &answers);
+    let computed_html = scores::render_values(&form, &computed, &lang);
+    let shown_computed = scores::shown(&form, &computed);
+    let submission_id = match edited {
+        // Edits keep the id and original submission time.
+        Some(original) => {
+            let submission = Submission {
+                score,
+                computed,
+                choice_score,
+                answers,
+                form_version: Some(version.version),
+                updated_at: Some(now),
+                ..original
+            };
+            let id = submission.id.clone();
+            state
+                .store
+                .update(submission.clone())
+                .await
+                .map_err(internal_error)?;
+            state.deliver(&submission);
+            id
+        }
+        None => {
+            let submission = Submission {
+                id: Uuid::new_v4().to_string(),
+                form_id: form_id.clone(),
+                score,
+                computed,
+                choice_score,
+                answers,
+                form_version: Some(version.version),
+                submitted_at: Some(now),
+                updated_at: None,
+                client: client_meta(state, &form, &form_id, ip),
+                duration_secs: fields
+                    .get("_started")
+                    .and_then(|started| duration_secs(state, &form, &form_id, started, now)),
+                tracking: tracking(&form, &fields),
+                source: Source::Web,
+                shuffle_seed: seed,
+            };
+            let id = submission.id.clone();
+            let inserted = match form.limit {
+                ResponseLimit::Unlimited => {
+                    state
+                        .store
+                        .insert(submission.clone(), form.max_responses)
+                        .await
+                }
+                ResponseLimit::OnePerIp => {
+                    state
+                        .store
+                        .insert_one_per_client(submission.clone(), form.max_responses)
+                        .await
+                }
+            };
+            match inserted.map_err(internal_error)? {
+                Inserted::Stored => {
+                    state.deliver(&submission);
+                    id
+                }
+                Inserted::Duplicate(existing) => existing,
+                Inserted::Full => return Ok(refuse(full_message(&form, &lang))),
+                Inserted::SameClient => {
+                    let message = format!("<p>{}</p>", i18n::texts(&lang).already_submitted);
+                    return Ok(refuse(page(&form, &lang, &message)));
+                }
+            }
+        }
+    };
+    if let Some(token) = fields.get("_draft") {
+        state.drafts.remove(token).await.map_err(internal_error)?;
+    }
+
+    let link = |purpose: &str, action: &str| {
+        let token = state.signer.sign(purpose, &form_id, &submission_id);
+        format!("/form/{form_id}/{action}/{token}")
+    };
+    let edit_url = form.editable.then(|| link(EDIT_TOKEN, "edit"));
+    let delete_url = form.deletable.then(|| link(DELETE_TOKEN, "delete"));
+    let result_url = (!result.is_empty()).then(|| link(RESULT_TOKEN, "result"));
+    Ok(if json {
+        Json(serde_json::json!({
+            "status": "ok",
+            "submission_id": submission_id,
+            "edit_url": edit_url,
+            "delete_url": delete_url,
+            "result_url": result_url,
+            "redirect_url": form.redirect_after,
+            "score": score.filter(|_| form.show_score != ShowScore::Hidden),
+            "computed": shown_computed,
+        }))
+        .into_response()
+    } else if let Some(url) = &form.redirect_after {
+        (StatusCode::SEE_OTHER, [(header::LOCATION, url.clone())]).into_response()
+    } else {
+        Html(thank_you(
+            &form,
+            &lang,
+            &format!("{result}{computed_html}"),
+            result_url.as_deref(),
+            edit_url.as_deref(),
+            delete_url.as_deref(),
+        ))
+        .into_response()
+    })
+}
+
+/// Version of the definition currently served, the hash of
+/// `log_form_loaded`.
+async fn form_version(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let form = state.form(&form_id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(
+        serde_json::json!({ "form_id": form_id, "version": form.version() }),
+    ))
+}
+
+/// The quiz result of a submission, again, through the link handed out
+/// after submitting. Graded against the current form.
+async fn quiz_result(
+    Extension(state): Extension<Arc<AppState>>,
+    Path((form_id, token)): Path<(String, String)>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Result<Html<String>, StatusCode> {
+    let form = state.form(&form_id).ok_or(StatusCode::NOT_FOUND)?;
+    let submission = state
+        .signer
+        .verify(RESULT_TOKEN, &form_id, &token)
+        .and_then(|id| state.store.get(&form_id, &id))
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let lang = language(&form, params.get("lang"), &headers);
+    let result = quiz::render_result(&form, &submission.answers, &lang);
+    if result.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(Html(page(&form, &lang, &result)))
+}
+
+/// Asks for confirmation, so link previews and prefetching cannot delete.
+async fn confirm_delete(
+    Extension(state): Extension<Arc<AppState>>,
+    Path((form_id, token)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Result<Html<String>, StatusCode> {
+    let form = state
+        .form(&form_id)
+        .filter(|form| form.deletable)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    state
+        .signer
+        .verify(DELETE_TOKEN, &form_id, &token)
+        .and_then(|id| state.store.get(&form_id, &id))
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let lang = language(&form, None, &headers);
+    let texts = i18n::texts(&lang);
+    Ok(Html(page(
+        &form,
+        &lang,
+        &format!(
+            "<p>{}</p><form method=\"post\"><input type=\"submit\" value=\"{}\"></form>",
+            texts.confirm_delete, texts.delete_button
+        ),
+    )))
+}
+
+async fn delete_submission(
+    Extension(state): Extension<Arc<AppState>>,
+    Path((form_id, token)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Result<Html<String>, StatusCode> {
+    let form = state
+        .form(&form_id)
+        .filter(|form| form.deletable)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let id = state
+        .signer
+        .verify(DELETE_TOKEN, &form_id, &token)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let deleted = state
+        .store
+        .delete(&form_id, &id, "respondent", state.clock.now())
+        .await
+        .map_err(internal_error)?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    state.changed(Change::Responses(form_id));
+    let lang = language(&form, None, &headers);
+    let message = format!("<p>{}</p>", i18n::texts(&lang).deleted);
+    Ok(Html(page(&form, &lang, &message)))
+}
+
+async fn submit_group(
+    Extension(state): Extension<Arc<AppState>>,
+    Path((form_id, group_index)): Path<(String, usize)>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, StatusCode> {
+    let form = state
+        .form(&form_id)
+        .filter(|form| form.per_group_submit && group_index < form.groups.len())
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let group_ids: Vec<String> = form
+        .group_questions(group_index)
+        .map(|(id, _)| id)
+        .collect();
+    let (mut answers, fields) = parse_answers(&form, &body, |id| {
+        group_ids.iter().any(|group_id| group_id == id)
+    });
+    let lang = language(&form, fields.get("_lang"), &headers);
+    let preview = preview_submission(&state, &form_id, &fields)?;
+    if !preview && unavailable_page(&state, &form_id, &form, state.clock.now(), &lang).is_some() {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    form.normalize_answers(&mut answers, &lang);
+    let session = fields
+        .get("_session")
+        .and_then(|token| state.signer.verify(SESSION_TOKEN, &form_id, token))
+        .and_then(|session| Uuid::parse_str(&session).ok())
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let saved = state.store.get(&form_id, &session.to_string());
+    // Conditions may depend on the answers saved with other groups.
+    let mut combined = saved.map(|saved| saved.answers).unwrap_or_default();
+    combined.extend(answers.clone());
+    // Required questions of the other groups are answered with those.
+    if let Err(errors) = form.validate_submission(&combined) {
+        if errors.iter().any(|error| group_ids.contains(&error.id)) {
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+    if preview {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    let version = FormVersion::of(&form_id, &form);
+    state
+        .store
+        .record_version(version.clone())
+        .await
+        .map_err(internal_error)?;
+    let merged = state
+        .store
+        .merge(
+            &form,
+            &form_id,
+            &session.to_string(),
+            &version.version,
+            answers,
+            state.clock.now(),
+            client_meta(&state, &form, &form_id, state.client_ip(addr, &headers)),
+            posted_seed(&state, &form, &fields, &headers),
+            form.max_responses,
+        )
+        .await
+        .map_err(internal_error)?
+        // The cap is checked under the store's lock, like for single submits.
+        .ok_or(StatusCode::FORBIDDEN)?;
+    state.deliver(&merged);
+    // No content keeps the respondent on the page with the other groups intact.
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Whether the provider confirms the token the widget posted in `body`. A
+/// provider that cannot be reached counts as a failed check.
+async fn captcha_solved(state: &AppState, captcha: &Captcha, body: &[u8], ip: IpAddr) -> bool {
+    let Some(token) = form_urlencoded::parse(body)
+        .find(|(name, _)| name == captcha.provider.token_field())
+        .map(|(_, token)| token.into_owned())
+        .filter(|token| !token.is_empty())
+    else {
+        return false;
+    };
+    state
+        .captcha
+        .verify(captcha, &token, ip)
+        .await
+        .unwrap_or_else(|err| {
+            eprintln!("could not verify the captcha: {err}");
+            false
+        })
+}
+
+/// Collects the submitted values of the form's questions accepted by `filter`,
+/// plus the hidden bookkeeping fields (names starting with `_`, e.g. the
+/// `_session` used to merge partial submissions).
+fn parse_answers(
+    form: &Form,
+    body: &[u8],
+    filter: impl Fn(&str) -> bool,
+) -> (HashMap<String, Vec<String>>, HashMap<String, String>) {
+    let ids: Vec<String> = form
+        .questions()
+        .map(|(id, _)| id)
+        .filter(|id| filter(id))
+        .collect();
+    let mut answers: HashMap<String, Vec<String>> = HashMap::new();
+    let mut fields = HashMap::new();
+    for (key, value) in form_urlencoded::parse(body) {
+        if key.starts_with('_') {
+            fields.insert(key.into_owned(), value.into_owned());
+        } else if !value.is_empty() && ids.iter().any(|id| *id == key) {
+            answers
+                .entry(key.into_owned())
+                .or_default()
+                .push(value.into_owned());
+        }
+    }
+    (answers, fields)
+}
+
+/// Asks whether the answers in `body` are to be submitted, for forms with
+/// `confirm_submit` submitted without the dialog. Both buttons post the
+/// answers again: to be stored, or to be shown in the form for changes.
+fn confirm_page(form: &Form, form_id: &str, lang: &str, body: &[u8]) -> String {
+    let texts = i18n::texts(lang);
+    let hidden = form_urlencoded::parse(body)
+        .filter(|(name, _)| name != "_confirmed" && name != "_back")
+        .map(|(name, value)| {
+            format!(
+                r#"<input type="hidden" name="{}" value="{}">"#,
+                escape(&name),
+                escape(&value)
+            )
+        })
+        .join("");
+    page(
+        form,
+        lang,
+        &format!(
+            r#"<p>{question}</p><form method="post" action="/submit/{form_id}">{hidden}<input type="hidden" name="_confirmed" value="1"><input type="submit" value="{yes}"> <button type="submit" name="_back" value="1">{back}</button></form>"#,
+            question = texts.confirm_submit,
+            yes = texts.confirm_submit_button,
+            back = texts.change_answers,
+        ),
+    )
+}
+
+/// A page of its own about `form`, titled in `lang`.
+fn page(form: &Form, lang: &str, body: &str) -> String {
+    format!(
+        "<html lang=\"{lang}\" style=\"font-family=sans-serif\"><body><h1>{title}</h1>{body}</body></html>",
+        title = escape(form.title.get(lang, form.language()))
+    )
+}
+
+/// The page shown instead of the form before it opens or after it closed:
+/// the form's own `not_open_page` or `closed_page`, else a built-in notice.
+/// Closed forms with a `public_count` say how many responses they got.
+fn unavailable_page(
+    state: &AppState,
+    form_id: &str,
+    form: &Form,
+    now: DateTime<Utc>,
+    lang: &str,
+) -> Option<String> {
+    let texts = i18n::texts(lang);
+    let (custom, message) = match form.availability(now) {
+        Availability::Open => return None,
+        Availability::NotYetOpen => (&form.not_open_page, texts.not_open),
+        Availability::Closed => (&form.closed_page, texts.closed),
+    };
+    let count = if form.public_count && form.availability(now) == Availability::Closed {
+        let message = match state.store.count(form_id) {
+            1 => texts.final_count_one.to_string(),
+            count => i18n::fill(texts.final_count, &[("count", &count.to_string())]),
+        };
+        format!(r#"<p class="count">{message}</p>"#)
+    } else {
+        String::new()
+    };
+    let default = form.language();
+    Some(match custom {
+        Some(custom) => {
+            let link = custom
+                .link
+                .as_ref()
+                .map(|link| {
+                    format!(
+                        r#"<p><a href="{}">{}</a></p>"#,
+                        escape(&link.url),
+                        escape(link.label.get(lang, default))
+                    )
+                })
+                .unwrap_or_default();
+            format!(
+                "<html lang=\"{lang}\" style=\"font-family=sans-serif\"><body><h1>{title}</h1>{body}{count}{link}</body></html>",
+                title = escape(custom.title.get(lang, default)),
+                body = markdown_html(custom.body.get(lang, default)),
+            )
+        }
+        None => page(form, lang, &format!("<p>{message}</p>{count}")),
+    })
+}
+
+/// The page shown instead of the form once it reached `max_responses`.
+fn full_page(state: &AppState, form_id: &str, form: &Form, lang: &str) -> Option<String> {
+    let max = form.max_responses?;
+    (state.store.count(form_id) >= max).then(|| full_message(form, lang))
+}
+
+fn full_message(form: &Form, lang: &str) -> String {
+    page(form, lang, &format!("<p>{}</p>", i18n::texts(lang).full))
+}
+
+fn link(url: &str) -> String {
+    format!("<a href=\"{url}\">{url}</a>")
+}
+
+fn draft_saved(form: &Form, lang: &str, resume_url: &str) -> String {
+    let message = i18n::fill(
+        i18n::texts(lang).draft_saved,
+        &[("link", &link(resume_url))],
+    );
+    page(form, lang, &format!("<p>{message}</p>"))
+}
+
+fn thank_you(
+    form: &Form,
+    lang: &str,
+    quiz_result: &str,
+    result_url: Option<&str>,
+    edit_url: Option<&str>,
+    delete_url: Option<&str>,
+) -> String {
+    let texts = i18n::texts(lang);
+    let later = |template: &str, url: &str| {
+        format!("<p>{}</p>", i18n::fill(template, &[("link", &link(url))]))
+    };
+    let result = result_url
+        .map(|url| later(texts.result_later, url))
+        .unwrap_or_default();
+    let edit = edit_url
+        .map(|url| later(texts.edit_later, url))
+        .unwrap_or_default();
+    let delete = delete_url
+        .map(|url| later(texts.delete_later, url))
+        .unwrap_or_default();
+    page(
+        form,
+        lang,
+        &format!(
+            "<p>{}</p>{quiz_result}{result}{edit}{delete}",
+            texts.thank_you
+        ),
+    )
+}
+
+/// The normal confirmation, and that it was not stored.
+fn preview_submitted(form: &Form, lang: &str, answers: &HashMap<String, Vec<String>>) -> String {
+    let texts = i18n::texts(lang);
+    page(
+        form,
+        lang,
+        &format!(
+            "<p>{}</p>{}{}<p class=\"preview\">{}</p>",
+            texts.thank_you,
+            quiz::render_result(form, answers, lang),
+            scores::render_values(form, &scores::compute(form, answers), lang),
+            texts.preview_discarded
+        ),
+    )
+}
+
+/// The only place connection metadata for a submission is derived, so the
+/// raw address never gets past the form's privacy setting.
+fn client_meta(state: &AppState, form: &Form, form_id: &str, ip: IpAddr) -> Option<ClientMeta> {
+    match form.privacy.client_ip {
+        ClientIp::None => None,
+        ClientIp::Hashed => {
+            // One respondent's IPv6 addresses share their /64, and mapped
+            // IPv4 addresses are the plain ones.
+            let key = match (form.limit, ip.to_canonical()) {
+                (ResponseLimit::OnePerIp, IpAddr::V6(ip)) => {
+                    let [a, b, c, d, ..] = ip.segments();
+                    format!("{a:x}:{b:x}:{c:x}:{d:x}::/64")
+                }
+                (ResponseLimit::OnePerIp, ip) => ip.to_string(),
+                (ResponseLimit::Unlimited, _) => ip.to_string(),
+            };
+            Some(ClientMeta::IpHash(state.signer.digest(
+                "client-ip",
+                form_id,
+                &key,
+            )))
+        }
+        ClientIp::Truncated => Some(ClientMeta::IpPrefix(match ip {
+            IpAddr::V4(ip) => {
+                let [a, b, c, _] = ip.octets();
+                format!("{a}.{b}.{c}.0/24")
+            }
+            IpAddr::V6(ip) => {
+                let [a, b, c, ..] = ip.segments();
+                format!("{a:x}:{b:x}:{c:x}::/48")
+            }
+        })),
+    }
+}
+
+pub(crate) fn internal_error(err: std::io::Error) -> StatusCode {
+    eprintln!("could not store submission: {err}");
+    StatusCode::INTERNAL_SERVER_ERROR
+}
@@ -0,0 +1,95 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io;
+use std::path::Path;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Writes `key` hex encoded, readable by the owner only.
+fn write_key(mut options: std::fs::OpenOptions, path: &Path, key: &[u8]) -> io::Result<()> {
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    io::Write::write_all(&mut options.open(path)?, hex::encode(key).as_bytes())
+}
+
+/// Signs the tokens handed out to respondents (e.g. edit links) with a server
+/// secret, binding each token to its purpose, form and response id.
+pub struct Signer {
+    key: Vec<u8>,
+}
+
+impl Default for Signer {
+    fn default() -> Self {
+        Signer {
+            key: rand::random::<[u8; 32]>().to_vec(),
+        }
+    }
+}
+
+impl Signer {
+    /// Reads the hex encoded secret from `path`, creating a new one if the
+    /// file does not exist yet so links survive restarts.
+    pub fn load_or_create(path: &Path) -> io::Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                let key = hex::decode(content.trim())
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                Ok(Signer { key })
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                let signer = Signer::default();
+                let mut options = std::fs::OpenOptions::new();
+                options.write(true).create_new(true);
+                write_key(options, path, &signer.key)?;
+                Ok(signer)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Replaces the secret in `path` with the hex encoded `secret`, e.g. one
+    /// restored from a backup.
+    pub fn write_secret(path: &Path, secret: &str) -> io::Result<()> {
+        let key = hex::decode(secret.trim())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        write_key(options, path, &key)
+    }
+
+    /// The secret, hex encoded as in its file, e.g. for a backup.
+    pub fn secret_hex(&self) -> String {
+        hex::encode(&self.key)
+    }
+
+    fn mac(&self, purpose: &str, form_id: &str, id: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        for part in [purpose, form_id, id] {
+            mac.update(part.as_bytes());
+            mac.update(&[0]);
+        }
+        mac
+    }
+
+    /// Keyed hash of `value`, e.g. to recognize a value without storing it.
+    /// Each form gets its own salt, derived from the secret and the form id.
+    pub fn digest(&self, purpose: &str, form_id: &str, value: &str) -> String {
+        hex::encode(self.mac(purpose, form_id, value).finalize().into_bytes())
+    }
+
+    /// Token of the form `<id>.<signature>`.
+    pub fn sign(&self, purpose: &str, form_id: &str, id: &str) -> String {
+        let signature = self.mac(purpose, form_id, id).finalize().into_bytes();
+        format!("{id}.{}", hex::encode(signature))
+    }
+
+    /// Returns the id carried by `token` if its signature is valid.
+    pub fn verify(&self, purpose: &str, form_id: &str, token: &str) -> Option<String> {
+        let (id, signature) = token.split_once('.')?;
+        let signature = hex::decode(signature).ok()?;
+        self.mac(purpose, form_id, id)
+            .verify_slice(&signature)
+            .ok()
+            .map(|_| id.to_string())
+    }
+}
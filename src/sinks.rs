@@ -0,0 +1,113 @@
+//! Destinations every stored submission is handed to as well, e.g. a copy
+//! on another disk or a webhook. The `SubmissionStore` stays the record the
+//! server reads back (limits, edits, exports); sinks only receive.
+//!
+//! Implement [`SubmissionSink`] to send submissions elsewhere and add it to
+//! `AppState::sinks`.
+
+use axum::async_trait;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+
+use crate::store::Submission;
+
+#[derive(Debug)]
+pub enum SinkError {
+    Io(io::Error),
+    /// The destination turned the submission down.
+    Rejected(String),
+}
+
+impl fmt::Display for SinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SinkError::Io(err) => write!(f, "{err}"),
+            SinkError::Rejected(reason) => write!(f, "rejected: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for SinkError {}
+
+impl From<io::Error> for SinkError {
+    fn from(err: io::Error) -> Self {
+        SinkError::Io(err)
+    }
+}
+
+/// Receives each submission once it is stored: new ones, edits and the
+/// merged state of partial submissions. Sinks run in the background, not
+/// holding up the response; a failing sink is logged, the submission is
+/// stored regardless.
+#[async_trait]
+pub trait SubmissionSink: Send + Sync {
+    /// Names the sink in log messages.
+    fn name(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
+
+    async fn handle(&self, submission: &Submission) -> Result<(), SinkError>;
+}
+
+/// Appends every submission as a JSON line to a file, in the format of the
+/// store.
+pub struct JsonLinesFile {
+    pub path: PathBuf,
+}
+
+#[async_trait]
+impl SubmissionSink for JsonLinesFile {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    async fn handle(&self, submission: &Submission) -> Result<(), SinkError> {
+        let mut line = serde_json::to_vec(submission).map_err(io::Error::from)?;
+        line.push(b'\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(&line).await?;
+        Ok(file.sync_data().await?)
+    }
+}
+
+/// Posts every submission as JSON to a URL. Like the CAPTCHA check, the
+/// request is made with `curl`; any status but 2xx counts as a failure.
+pub struct Webhook {
+    pub url: String,
+}
+
+#[async_trait]
+impl SubmissionSink for Webhook {
+    fn name(&self) -> String {
+        self.url.clone()
+    }
+
+    async fn handle(&self, submission: &Submission) -> Result<(), SinkError> {
+        let body = serde_json::to_vec(submission).map_err(io::Error::from)?;
+        let mut curl = tokio::process::Command::new("curl")
+            .args(["--silent", "--show-error", "--fail", "--max-time", "10"])
+            .args(["--header", "Content-Type: application/json"])
+            .args(["--data-binary", "@-", "--output", "/dev/null", &self.url])
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let mut stdin = curl.stdin.take().expect("stdin is piped");
+        stdin.write_all(&body).await?;
+        drop(stdin);
+        let output = curl.wait_with_output().await?;
+        if !output.status.success() {
+            return Err(SinkError::Rejected(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,755 @@
+//! Aggregated views over the stored submissions (admin only).
+
+use axum::extract::{Extension, Path, Query};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::server::{wants_json, Admin, AppState};
+use crate::store::Submission;
+use crate::{escape, quiz, Form, Question, QuestionType, NA};
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/form/:form_id/stats/crosstab", get(crosstab))
+        .route("/form/:form_id/stats/keywords", get(keywords))
+        .route("/form/:form_id/stats/scores", get(scores))
+        .route("/form/:form_id/summary.json", get(summary))
+}
+
+const OTHER: &str = "Other";
+/// Most buckets a numeric crosstab axis is split into.
+const MAX_BINS: usize = 100;
+
+#[derive(Deserialize)]
+struct CrosstabQuery {
+    rows: String,
+    cols: String,
+    /// Number of equal-width buckets for numeric questions.
+    bins: Option<usize>,
+    /// List custom answers individually instead of as "Other".
+    #[serde(default)]
+    expand_other: bool,
+}
+
+#[derive(Serialize)]
+struct Axis {
+    id: String,
+    title: String,
+    labels: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Crosstab {
+    rows: Axis,
+    cols: Axis,
+    /// `counts[row][col]`; a multiple choice answer counts once per option.
+    counts: Vec<Vec<usize>>,
+    row_totals: Vec<usize>,
+    col_totals: Vec<usize>,
+    total: usize,
+    /// Share of `total` per cell, in percent.
+    percentages: Vec<Vec<f64>>,
+}
+
+/// Contingency table of two questions over all submissions answering both.
+async fn crosstab(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    Query(query): Query<CrosstabQuery>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
+    let form = state
+        .form(&form_id)
+        .ok_or((StatusCode::NOT_FOUND, format!("no form '{form_id}'")))?;
+    if query.bins.is_some_and(|bins| bins > MAX_BINS) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("bins can be at most {MAX_BINS}"),
+        ));
+    }
+    let question = |id: &str| {
+        form.questions()
+            .find(|(question_id, _)| question_id == id)
+            .map(|(_, question)| question)
+            .ok_or((
+                StatusCode::BAD_REQUEST,
+                format!("unknown question id '{id}'"),
+            ))
+    };
+    let submissions = state.store.list(&form_id);
+    let rows = Categories::new(
+        &form,
+        &query.rows,
+        question(&query.rows)?,
+        &query,
+        &submissions,
+    );
+    let cols = Categories::new(
+        &form,
+        &query.cols,
+        question(&query.cols)?,
+        &query,
+        &submissions,
+    );
+
+    let mut counts = vec![vec![0; cols.axis.labels.len()]; rows.axis.labels.len()];
+    for submission in &submissions {
+        for row in rows.of(submission) {
+            for col in cols.of(submission) {
+                counts[row][col] += 1;
+            }
+        }
+    }
+    let row_totals: Vec<usize> = counts.iter().map(|row| row.iter().sum()).collect();
+    let col_totals: Vec<usize> = (0..cols.axis.labels.len())
+        .map(|col| counts.iter().map(|row| row[col]).sum())
+        .collect();
+    let total: usize = row_totals.iter().sum();
+    let percentages = counts
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&count| {
+                    if total == 0 {
+                        0.0
+                    } else {
+                        100.0 * count as f64 / total as f64
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let table = Crosstab {
+        rows: rows.axis,
+        cols: cols.axis,
+        counts,
+        row_totals,
+        col_totals,
+        total,
+        percentages,
+    };
+    Ok(if wants_json(&headers) {
+        Json(table).into_response()
+    } else {
+        Html(crosstab_page(form.text(&form.title), &table)).into_response()
+    })
+}
+
+/// Maps the answers of one question to the labels of a crosstab axis.
+struct Categories<'a> {
+    id: &'a str,
+    question: &'a Question,
+    bins: Option<(f64, f64, usize)>,
+    expand_other: bool,
+    axis: Axis,
+}
+
+impl<'a> Categories<'a> {
+    fn new(
+        form: &Form,
+        id: &'a str,
+        question: &'a Question,
+        query: &CrosstabQuery,
+        submissions: &[Submission],
+    ) -> Self {
+        let numbers = || {
+            submissions
+                .iter()
+                .filter_map(|s| s.answers.get(id))
+                .flatten()
+                .filter_map(|value| value.parse::<f64>().ok())
+        };
+        let bins = match (&question.spec, query.bins) {
+            (_, None | Some(0)) => None,
+            (QuestionType::DiscreteNumeric { bounds, .. }, Some(n)) => {
+                Some((bounds.0 as f64, bounds.1 as f64, n))
+            }
+            (
+                QuestionType::ContinousNumeric {
+                    bounds: Some(b), ..
+                },
+                Some(n),
+            ) => Some((b.0 as f64, b.1 as f64, n)),
+            (QuestionType::ContinousNumeric { bounds: None, .. }, Some(n)) => numbers()
+                .minmax()
+                .into_option()
+                .map(|(min, max)| (min, max, n)),
+            _ => None,
+        };
+        let mut categories = Categories {
+            id,
+            question,
+            bins,
+            expand_other: query.expand_other,
+            axis: Axis {
+                id: id.to_string(),
+                title: form.text(&question.title).to_string(),
+                labels: Vec::new(),
+            },
+        };
+
+        let mut labels: Vec<String> = match (&question.spec, bins) {
+            (_, Some((min, max, n))) => (0..n).map(|i| bin_label(min, max, n, i)).collect(),
+            (QuestionType::DiscreteNumeric { bounds, .. }, None) => {
+                (bounds.0..=bounds.1).map(|v| v.to_string()).collect()
+            }
+            _ => question
+                .spec
+                .answers()
+                .map(|answers| answers.iter().map(|a| a.value().to_string()).collect())
+                .unwrap_or_default(),
+        };
+        let seen: Vec<String> = submissions
+            .iter()
+            .filter_map(|s| s.answers.get(id))
+            .flatten()
+            .filter_map(|value| categories.label(value))
+            .unique()
+            .collect();
+        let mut extra: Vec<String> = seen.into_iter().filter(|l| !labels.contains(l)).collect();
+        if matches!(question.spec, QuestionType::ContinousNumeric { .. }) {
+            extra.sort_by(|a, b| {
+                let (a, b) = (a.parse::<f64>(), b.parse::<f64>());
+                a.ok()
+                    .partial_cmp(&b.ok())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        }
+        // "Other" goes last, after the individual values.
+        extra.sort_by_key(|label| label == OTHER);
+        labels.extend(extra);
+        categories.axis.labels = labels;
+        categories
+    }
+
+    fn label(&self, value: &str) -> Option<String> {
+        if let Some((min, max, n)) = self.bins {
+            let value: f64 = value.parse().ok()?;
+            let width = (max - min) / n as f64;
+            let bin = if width > 0.0 {
+                (((value - min) / width).floor() as usize).min(n - 1)
+            } else {
+                0
+            };
+            return Some(bin_label(min, max, n, bin));
+        }
+        match self.question.spec.answers() {
+            Some(answers)
+                if !self.expand_other && !answers.iter().any(|answer| answer.value() == value) =>
+            {
+                Some(OTHER.to_string())
+            }
+            _ => Some(value.to_string()),
+        }
+    }
+
+    /// Label indices a submission falls into.
+    fn of(&self, submission: &Submission) -> Vec<usize> {
+        submission
+            .answers
+            .get(self.id)
+            .into_iter()
+            .flatten()
+            .filter_map(|value| self.label(value))
+            .filter_map(|label| self.axis.labels.iter().position(|l| *l == label))
+            .unique()
+            .collect()
+    }
+}
+
+fn bin_label(min: f64, max: f64, n: usize, bin: usize) -> String {
+    let width = (max - min) / n as f64;
+    format!(
+        "{}–{}",
+        min + width * bin as f64,
+        min + width * (bin + 1) as f64
+    )
+}
+
+fn crosstab_page(title: &str, table: &Crosstab) -> String {
+    let cell = |count: usize, percentage: f64| format!("<td>{count} ({percentage:.1}%)</td>");
+    let header = table
+        .cols
+        .labels
+        .iter()
+        .map(|label| format!("<th>{}</th>", escape(label)))
+        .join("");
+    let rows = table
+        .rows
+        .labels
+        .iter()
+        .enumerate()
+        .map(|(r, label)| {
+            let cells = (0..table.cols.labels.len())
+                .map(|c| cell(table.counts[r][c], table.percentages[r][c]))
+                .join("");
+            format!(
+                "<tr><th>{}</th>{cells}<td>{}</td></tr>",
+                escape(label),
+                table.row_totals[r]
+            )
+        })
+        .join("");
+    let totals = table
+        .col_totals
+        .iter()
+        .map(|total| format!("<td>{total}</td>"))
+        .join("");
+    format!(
+        "<html style=\"font-family=sans-serif\"><body><h1>{title}</h1><h2>{row_title} / {col_title}</h2><table><tr><th></th>{header}<th>Total</th></tr>{rows}<tr><th>Total</th>{totals}<td>{total}</td></tr></table></body></html>",
+        title = escape(title),
+        row_title = escape(&table.rows.title),
+        col_title = escape(&table.cols.title),
+        total = table.total
+    )
+}
+
+/// Words too common to say anything about an answer, in English and German.
+const STOPWORDS: &[&str] = &[
+    // English
+    "a", "about", "after", "all", "also", "am", "an", "and", "any", "are", "as", "at", "be",
+    "because", "been", "but", "by", "can", "could", "did", "do", "does", "for", "from", "had",
+    "has", "have", "he", "her", "him", "his", "how", "i", "if", "in", "into", "is", "it", "its",
+    "just", "me", "more", "my", "no", "not", "of", "on", "or", "our", "out", "she", "so", "some",
+    "than", "that", "the", "their", "them", "then", "there", "these", "they", "this", "to", "too",
+    "up", "us", "very", "was", "we", "were", "what", "when", "which", "who", "why", "will", "with",
+    "would", "you", "your", // German
+    "aber", "alle", "als", "also", "am", "an", "auch", "auf", "aus", "bei", "bin", "bis", "bist",
+    "da", "das", "dass", "dem", "den", "der", "des", "die", "dies", "diese", "doch", "du", "durch",
+    "ein", "eine", "einem", "einen", "einer", "es", "für", "gibt", "hat", "hatte", "ich", "ihr",
+    "im", "in", "ist", "ja", "kann", "kein", "keine", "man", "mehr", "mich", "mit", "nach",
+    "nicht", "noch", "nur", "ob", "oder", "schon", "sehr", "sich", "sie", "sind", "so", "um",
+    "und", "uns", "unter", "vom", "von", "vor", "war", "was", "wenn", "wie", "wir", "wird", "zu",
+    "zum", "zur",
+];
+
+const DEFAULT_TOP: usize = 20;
+const MAX_TOP: usize = 200;
+
+#[derive(Deserialize)]
+struct KeywordQuery {
+    question: String,
+    top: Option<usize>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct Term {
+    term: String,
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct Keywords {
+    id: String,
+    title: String,
+    answers: usize,
+    terms: Vec<Term>,
+    bigrams: Vec<Term>,
+}
+
+/// Most frequent words and word pairs in the answers to a text question.
+async fn keywords(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    Query(query): Query<KeywordQuery>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
+    let form = state
+        .form(&form_id)
+        .ok_or((StatusCode::NOT_FOUND, format!("no form '{form_id}'")))?;
+    let question = form
+        .questions()
+        .find(|(id, _)| *id == query.question)
+        .map(|(_, question)| question)
+        .ok_or((
+            StatusCode::BAD_REQUEST,
+            format!("unknown question id '{}'", query.question),
+        ))?;
+    if !matches!(question.spec, QuestionType::Text { .. }) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("{} is not a text question", query.question),
+        ));
+    }
+    let stopwords: HashSet<String> = STOPWORDS
+        .iter()
+        .map(|word| word.to_string())
+        .chain(form.stopwords.iter().map(|word| word.to_lowercase()))
+        .collect();
+    let top = query.top.unwrap_or(DEFAULT_TOP).min(MAX_TOP);
+
+    let mut terms: HashMap<String, usize> = HashMap::new();
+    let mut bigrams: HashMap<String, usize> = HashMap::new();
+    let mut answers = 0;
+    for value in state
+        .store
+        .list(&form_id)
+        .iter()
+        .filter_map(|s| s.answers.get(&query.question))
+        .flatten()
+    {
+        let tokens = tokenize(value);
+        if tokens.is_empty() {
+            continue;
+        }
+        answers += 1;
+        let relevant = |token: &String| !stopwords.contains(token);
+        for token in tokens.iter().filter(|token| relevant(token)) {
+            *terms.entry(token.clone()).or_default() += 1;
+        }
+        for (first, second) in tokens.iter().tuple_windows() {
+            if relevant(first) && relevant(second) {
+                *bigrams.entry(format!("{first} {second}")).or_default() += 1;
+            }
+        }
+    }
+    let keywords = Keywords {
+        id: query.question.clone(),
+        title: form.text(&question.title).to_string(),
+        answers,
+        terms: ranked(terms, top),
+        bigrams: ranked(bigrams, top),
+    };
+    Ok(if wants_json(&headers) {
+        Json(keywords).into_response()
+    } else {
+        Html(keywords_page(form.text(&form.title), &keywords)).into_response()
+    })
+}
+
+/// Lowercased words; anything that is not a letter or digit (punctuation,
+/// emoji, ...) separates words, and single characters are dropped.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().nth(1).is_some())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn ranked(counts: HashMap<String, usize>, top: usize) -> Vec<Term> {
+    counts
+        .into_iter()
+        .sorted_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)))
+        .take(top)
+        .map(|(term, count)| Term { term, count })
+        .collect()
+}
+
+fn keywords_page(title: &str, keywords: &Keywords) -> String {
+    let list = |terms: &[Term]| {
+        terms
+            .iter()
+            .map(|term| format!("<li>{} ({})</li>", escape(&term.term), term.count))
+            .join("")
+    };
+    format!(
+        "<html style=\"font-family=sans-serif\"><body><h1>{title}</h1><h2>{question}</h2><p>{answers} answers</p><h3>Terms</h3><ol>{terms}</ol><h3>Word pairs</h3><ol>{bigrams}</ol></body></html>",
+        title = escape(title),
+        question = escape(&keywords.title),
+        answers = keywords.answers,
+        terms = list(&keywords.terms),
+        bigrams = list(&keywords.bigrams)
+    )
+}
+
+#[derive(Serialize)]
+struct ScoreDistribution {
+    /// Points achievable with the current form.
+    max: f32,
+    scored: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mean: Option<f32>,
+    /// Submissions per achieved score, lowest first.
+    counts: Vec<ValueCount>,
+}
+
+/// How many submissions of a quiz achieved which score.
+async fn scores(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
+    let form = state
+        .form(&form_id)
+        .filter(|form| form.quiz)
+        .ok_or((StatusCode::NOT_FOUND, format!("no quiz '{form_id}'")))?;
+    let points: Vec<f32> = state
+        .store
+        .list(&form_id)
+        .iter()
+        .filter_map(|submission| submission.score)
+        .map(|score| score.points)
+        .collect();
+    let counts = points
+        .iter()
+        .sorted_by(|a, b| a.total_cmp(b))
+        .dedup_with_count()
+        .map(|(count, points)| ValueCount {
+            value: points.to_string(),
+            count,
+        })
+        .collect();
+    let distribution = ScoreDistribution {
+        max: quiz::grade(&form, &HashMap::new()).0.max,
+        scored: points.len(),
+        mean: (!points.is_empty()).then(|| points.iter().sum::<f32>() / points.len() as f32),
+        counts,
+    };
+    Ok(if wants_json(&headers) {
+        Json(distribution).into_response()
+    } else {
+        Html(scores_page(form.text(&form.title), &distribution)).into_response()
+    })
+}
+
+fn scores_page(title: &str, distribution: &ScoreDistribution) -> String {
+    let rows = distribution
+        .counts
+        .iter()
+        .map(|count| format!("<tr><td>{}</td><td>{}</td></tr>", count.value, count.count))
+        .join("");
+    let mean = distribution
+        .mean
+        .map(|mean| format!(", mean {mean:.2}"))
+        .unwrap_or_default();
+    format!(
+        "<html style=\"font-family=sans-serif\"><body><h1>{title}</h1><p>{scored} scored submissions of {max} points{mean}</p><table><tr><th>Points</th><th>Submissions</th></tr>{rows}</table></body></html>",
+        title = escape(title),
+        scored = distribution.scored,
+        max = distribution.max,
+    )
+}
+
+#[derive(Serialize, Debug)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Aggregates of one question over a set of submissions.
+#[derive(Serialize, Debug)]
+pub struct QuestionSummary {
+    pub id: String,
+    pub title: String,
+    pub kind: &'static str,
+    pub answered: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// Answers per option (choices) or value (discrete scales), options in
+    /// form order followed by custom answers.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub counts: Vec<ValueCount>,
+}
+
+#[derive(Serialize)]
+struct Summary {
+    form_id: String,
+    responses: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration: Option<Durations>,
+    /// Submissions per value of each of the form's `track_params`, e.g. per
+    /// channel the link was shared through.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    tracking: BTreeMap<String, Vec<ValueCount>>,
+    /// Submissions per way into the store, e.g. imported from paper.
+    sources: Vec<ValueCount>,
+    /// Submissions per form version they answered.
+    versions: Vec<ValueCount>,
+    /// Set when the aggregates span more than one form version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warning: Option<String>,
+    questions: Vec<QuestionSummary>,
+}
+
+/// Submissions per value of each tracked parameter, most frequent first.
+/// Submissions opened without the parameter are left out of its counts.
+pub fn tracking(form: &Form, submissions: &[Submission]) -> BTreeMap<String, Vec<ValueCount>> {
+    form.track_params
+        .iter()
+        .map(|name| {
+            let counts = submissions
+                .iter()
+                .filter_map(|submission| submission.tracking.get(name))
+                .counts();
+            (name.clone(), most_frequent(counts))
+        })
+        .collect()
+}
+
+/// Submissions per `Source`, most frequent first.
+pub fn sources(submissions: &[Submission]) -> Vec<ValueCount> {
+    most_frequent(submissions.iter().map(|s| s.source.as_str()).counts())
+}
+
+/// Submissions per form version, most frequent first. Submissions stored
+/// before versions were recorded count as `unversioned`.
+pub fn versions(submissions: &[Submission]) -> Vec<ValueCount> {
+    most_frequent(
+        submissions
+            .iter()
+            .map(|s| s.form_version.as_deref().unwrap_or("unversioned"))
+            .counts(),
+    )
+}
+
+/// Warns that `versions` (see `versions`) mixes answers to different forms,
+/// whose questions or options may have changed in between.
+pub fn mixed_versions_warning(versions: &[ValueCount]) -> Option<String> {
+    (versions.len() > 1).then(|| {
+        format!(
+            "These responses answered {} versions of the form; questions may have changed between them.",
+            versions.len()
+        )
+    })
+}
+
+fn most_frequent(counts: HashMap<impl ToString, usize>) -> Vec<ValueCount> {
+    counts
+        .into_iter()
+        .map(|(value, count)| ValueCount {
+            value: value.to_string(),
+            count,
+        })
+        .sorted_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)))
+        .collect()
+}
+
+/// How long respondents took, in seconds, over the submissions that were
+/// timed.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Durations {
+    pub timed: usize,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    pub p90: f64,
+}
+
+/// `None` if no submission was timed.
+pub fn durations(submissions: &[Submission]) -> Option<Durations> {
+    let mut secs: Vec<f64> = submissions
+        .iter()
+        .filter_map(|submission| submission.duration_secs)
+        .map(|secs| secs as f64)
+        .collect();
+    if secs.is_empty() {
+        return None;
+    }
+    secs.sort_by(f64::total_cmp);
+    // Interpolated between the closest ranks, to a tenth of a second.
+    let quantile = |q: f64| {
+        let rank = q * (secs.len() - 1) as f64;
+        let (low, high) = (secs[rank.floor() as usize], secs[rank.ceil() as usize]);
+        ((low + (high - low) * rank.fract()) * 10.0).round() / 10.0
+    };
+    Some(Durations {
+        timed: secs.len(),
+        p25: quantile(0.25),
+        median: quantile(0.5),
+        p75: quantile(0.75),
+        p90: quantile(0.9),
+    })
+}
+
+/// `summarize` over all stored submissions of a form, for a quick look
+/// without an export.
+async fn summary(
+    _: Admin,
+    Extension(state): Extension<Arc<AppState>>,
+    Path(form_id): Path<String>,
+) -> Result<Json<Summary>, (StatusCode, String)> {
+    let form = state
+        .form(&form_id)
+        .ok_or((StatusCode::NOT_FOUND, format!("no form '{form_id}'")))?;
+    let submissions = state.store.list(&form_id);
+    let versions = versions(&submissions);
+    Ok(Json(Summary {
+        responses: submissions.len(),
+        duration: durations(&submissions),
+        tracking: tracking(&form, &submissions),
+        sources: sources(&submissions),
+        warning: mixed_versions_warning(&versions),
+        versions,
+        questions: summarize(&form, &submissions),
+        form_id,
+    }))
+}
+
+pub fn summarize(form: &Form, submissions: &[Submission]) -> Vec<QuestionSummary> {
+    form.questions()
+        .map(|(id, question)| {
+            let answers: Vec<&Vec<String>> = submissions
+                .iter()
+                .filter_map(|s| s.answers.get(&id))
+                .filter(|values| !values.is_empty())
+                .collect();
+            let mut summary = QuestionSummary {
+                title: form.text(&question.title).to_string(),
+                kind: question.spec.kind(),
+                answered: answers.len(),
+                mean: None,
+                min: None,
+                max: None,
+                counts: Vec::new(),
+                id,
+            };
+            let values = answers.iter().copied().flatten();
+            let options: Vec<String> = match &question.spec {
+                QuestionType::DiscreteNumeric {
+                    bounds, na_option, ..
+                } => (bounds.0..=bounds.1)
+                    .map(|v| v.to_string())
+                    .chain(na_option.as_ref().map(|_| NA.to_string()))
+                    .collect(),
+                spec => match spec.answers() {
+                    Some(answers) => answers.iter().map(|a| a.value().to_string()).collect(),
+                    None => Vec::new(),
+                },
+            };
+            if matches!(
+                question.spec,
+                QuestionType::DiscreteNumeric { .. } | QuestionType::ContinousNumeric { .. }
+            ) {
+                let numbers: Vec<f64> = values
+                    .clone()
+                    .filter_map(|value| value.parse().ok())
+                    .collect();
+                if !numbers.is_empty() {
+                    summary.mean = Some(numbers.iter().sum::<f64>() / numbers.len() as f64);
+                    summary.min = numbers.iter().copied().reduce(f64::min);
+                    summary.max = numbers.iter().copied().reduce(f64::max);
+                }
+            }
+            if !options.is_empty() {
+                let mut counts: Vec<ValueCount> = options
+                    .into_iter()
+                    .map(|value| ValueCount { value, count: 0 })
+                    .collect();
+                for value in values {
+                    match counts.iter_mut().find(|count| count.value == *value) {
+                        Some(count) => count.count += 1,
+                        None => counts.push(ValueCount {
+                            value: value.clone(),
+                            count: 1,
+                        }),
+                    }
+                }
+                summary.counts = counts;
+            }
+            summary
+        })
+        .collect()
+}
@@ -0,0 +1,635 @@
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::io::AsyncWriteExt;
+
+use crate::quiz::Score;
+use crate::Form;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Submission {
+    pub id: String,
+    pub form_id: String,
+    pub answers: HashMap<String, Vec<String>>,
+    /// Version of the form definition the submission was made against.
+    #[serde(default)]
+    pub form_version: Option<String>,
+    #[serde(default)]
+    pub submitted_at: Option<DateTime<Utc>>,
+    /// Set when the respondent changed the submission afterwards.
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<ClientMeta>,
+    /// Seconds between serving the form and submitting it.
+    #[serde(default)]
+    pub duration_secs: Option<i64>,
+    /// Values of the form's `track_params` the form was opened with.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tracking: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Source::is_web")]
+    pub source: Source,
+    /// Points achieved, for quiz forms.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<Score>,
+    /// Values of the form's `computed` scores, by key.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub computed: BTreeMap<String, f32>,
+    /// Sum of the scores of the chosen options, for forms whose options have
+    /// scores; see `Form::score`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub choice_score: Option<f32>,
+    /// Seed of the order shuffled questions and options were shown in, see
+    /// `shuffled_order`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shuffle_seed: Option<u64>,
+}
+
+impl Submission {
+    /// Hash of the form and the answers, independent of the order answers
+    /// were given in and of surrounding whitespace. Unanswered questions do
+    /// not count.
+    pub fn content_hash(&self) -> String {
+        let answers: BTreeMap<&String, Vec<&str>> = self
+            .answers
+            .iter()
+            .map(|(id, values)| {
+                let mut values: Vec<&str> = values
+                    .iter()
+                    .map(|value| value.trim())
+                    .filter(|value| !value.is_empty())
+                    .collect();
+                values.sort_unstable();
+                (id, values)
+            })
+            .filter(|(_, values)| !values.is_empty())
+            .collect();
+        let canonical = serde_json::json!([self.form_id, answers]).to_string();
+        hex::encode(Sha256::digest(canonical.as_bytes()))
+    }
+}
+
+/// Outcome of `SubmissionStore::insert`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inserted {
+    Stored,
+    /// The form has reached its `max_responses`.
+    Full,
+    /// A submission from the same client is already stored.
+    SameClient,
+    /// Identical to the stored submission with this id, which is kept.
+    Duplicate(String),
+}
+
+/// How a submission got into the store.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// Submitted through the form.
+    #[default]
+    Web,
+    /// Typed in from paper and loaded with `import-responses`.
+    Import,
+}
+
+impl Source {
+    fn is_web(&self) -> bool {
+        *self == Source::Web
+    }
+
+    /// The name it is stored under.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Source::Web => "web",
+            Source::Import => "import",
+        }
+    }
+}
+
+/// Connection metadata as allowed by the form's `privacy` settings. There is
+/// deliberately no variant that could hold a raw client address.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientMeta {
+    IpHash(String),
+    IpPrefix(String),
+}
+
+/// Who did what to which submission - never the submission's content.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub action: String,
+    pub form_id: String,
+    pub submission_id: String,
+    pub by: String,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QuestionMeta {
+    pub id: String,
+    pub title: String,
+    pub kind: String,
+}
+
+/// Structure of one version of a form, kept so answers given against older
+/// versions can still be labelled after the definition changed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FormVersion {
+    pub form_id: String,
+    pub version: String,
+    pub questions: Vec<QuestionMeta>,
+}
+
+impl FormVersion {
+    pub fn of(form_id: &str, form: &Form) -> Self {
+        FormVersion {
+            form_id: form_id.to_string(),
+            version: form.version(),
+            questions: form
+                .questions()
+                .map(|(id, question)| QuestionMeta {
+                    id,
+                    title: form.text(&question.title).to_string(),
+                    kind: question.spec.kind().to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+fn read_jsonl<T: DeserializeOwned>(path: &Path) -> io::Result<Vec<T>> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut records = Vec::new();
+    for line in io::BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        records.push(
+            serde_json::from_str(&line)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+        );
+    }
+    Ok(records)
+}
+
+/// Append-only JSONL file shared by all request tasks.
+///
+/// The handle sits behind an async mutex and every record is serialized
+/// up front and written with a single `write_all` + `flush` while the lock is
+/// held, so lines from concurrent writers never interleave or tear.
+struct JsonlWriter {
+    path: PathBuf,
+    file: tokio::sync::Mutex<tokio::fs::File>,
+}
+
+impl JsonlWriter {
+    fn open(path: &Path) -> io::Result<Self> {
+        Ok(JsonlWriter {
+            path: path.to_path_buf(),
+            file: tokio::sync::Mutex::new(Self::open_append(path)?),
+        })
+    }
+
+    fn open_append(path: &Path) -> io::Result<tokio::fs::File> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(tokio::fs::File::from_std(file))
+    }
+
+    /// Replaces the whole file with `records` through a temporary file and a
+    /// rename; `file` is the locked handle, which is reopened afterwards.
+    async fn rewrite<T: Serialize>(
+        &self,
+        file: &mut tokio::fs::File,
+        records: &[T],
+    ) -> io::Result<()> {
+        let mut content = String::new();
+        for record in records {
+            content.push_str(&serde_json::to_string(record)?);
+            content.push('\n');
+        }
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        tokio::fs::write(&tmp, content).await?;
+        tokio::fs::rename(&tmp, &self.path).await?;
+        *file = Self::open_append(&self.path)?;
+        Ok(())
+    }
+
+    async fn write(file: &mut tokio::fs::File, record: &impl Serialize) -> io::Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await
+    }
+}
+
+/// The contents of a `SubmissionStore`, see `export_all`.
+#[derive(Debug, Default, PartialEq)]
+pub struct StoreExport {
+    pub submissions: BTreeMap<String, Vec<Submission>>,
+    pub versions: Vec<FormVersion>,
+    pub audit: Vec<AuditEntry>,
+}
+
+/// How `import_all` treats what the store already holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Drop it, so the store holds exactly the import afterwards.
+    Replace,
+    /// Keep it and add what is new; submissions are matched by form and id.
+    Merge,
+}
+
+/// What `import_all` did.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub imported: usize,
+    /// Submissions that were already stored as they are in the import.
+    pub unchanged: usize,
+    /// `<form id>/<id>` of submissions stored with other content than in the
+    /// import; the stored one was kept.
+    pub conflicts: Vec<String>,
+}
+
+/// Submissions of all forms, kept in memory and appended to a JSONL file.
+///
+/// Every write appends the full submission; when a submission is written
+/// several times (partial saves), the last line with its id wins on load.
+/// The form versions submissions refer to are kept in a second file next to
+/// it (`<name>.versions.jsonl`), deletions are logged to `<name>.audit.jsonl`.
+///
+/// Ordering: writes are applied in the order they acquire the file lock, and
+/// the in-memory state is updated while holding it, so the file order, the
+/// order of `list` and the state rebuilt on the next `open` always agree.
+pub struct SubmissionStore {
+    /// Answer an exact repeat of a stored submission (e.g. a retried POST)
+    /// with the stored one instead of storing it again.
+    pub dedupe: bool,
+    log: JsonlWriter,
+    versions_log: JsonlWriter,
+    audit_log: JsonlWriter,
+    submissions: Mutex<Vec<Submission>>,
+    versions: Mutex<Vec<FormVersion>>,
+}
+
+impl SubmissionStore {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut submissions: Vec<Submission> = Vec::new();
+        // Position of each submission in `submissions` by form and id, as
+        // partial saves repeat ids.
+        let mut positions: HashMap<(String, String), usize> = HashMap::new();
+        for submission in read_jsonl::<Submission>(path)? {
+            let key = (submission.form_id.clone(), submission.id.clone());
+            match positions.get(&key) {
+                Some(&idx) => submissions[idx] = submission,
+                None => {
+                    positions.insert(key, submissions.len());
+                    submissions.push(submission);
+                }
+            }
+        }
+        let versions_path = path.with_extension("versions.jsonl");
+        let versions = read_jsonl(&versions_path)?;
+        Ok(SubmissionStore {
+            dedupe: false,
+            log: JsonlWriter::open(path)?,
+            versions_log: JsonlWriter::open(&versions_path)?,
+            audit_log: JsonlWriter::open(&path.with_extension("audit.jsonl"))?,
+            submissions: Mutex::new(submissions),
+            versions: Mutex::new(versions),
+        })
+    }
+
+    /// Remembers the structure of a form version unless it is already known.
+    pub async fn record_version(&self, version: FormVersion) -> io::Result<()> {
+        let mut file = self.versions_log.file.lock().await;
+        {
+            let mut versions = self.versions.lock().unwrap();
+            if versions.contains(&version) {
+                return Ok(());
+            }
+            versions.push(version.clone());
+        }
+        JsonlWriter::write(&mut file, &version).await
+    }
+
+    pub fn versions(&self, form_id: &str) -> Vec<FormVersion> {
+        self.versions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|v| v.form_id == form_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Stores a new submission unless its form already has `max` of them or,
+    /// with `dedupe`, the same answers are already stored.
+    pub async fn insert(&self, submission: Submission, max: Option<usize>) -> io::Result<Inserted> {
+        self.insert_checked(submission, max, false).await
+    }
+
+    /// Like `insert`, but also refuses a submission whose `client` matches
+    /// that of a stored submission of the form. Checked under the same lock
+    /// as the insert, so two concurrent submissions cannot both get in.
+    pub async fn insert_one_per_client(
+        &self,
+        submission: Submission,
+        max: Option<usize>,
+    ) -> io::Result<Inserted> {
+        self.insert_checked(submission, max, true).await
+    }
+
+    async fn insert_checked(
+        &self,
+        submission: Submission,
+        max: Option<usize>,
+        one_per_client: bool,
+    ) -> io::Result<Inserted> {
+        let mut file = self.log.file.lock().await;
+        {
+            let mut submissions = self.submissions.lock().unwrap();
+            let same_form = submissions
+                .iter()
+                .filter(|s| s.form_id == submission.form_id);
+            if one_per_client
+                && submission.client.is_some()
+                && same_form.clone().any(|s| s.client == submission.client)
+            {
+                return Ok(Inserted::SameClient);
+            }
+            if self.dedupe {
+                let hash = submission.content_hash();
+                if let Some(existing) = same_form.clone().find(|s| s.content_hash() == hash) {
+                    return Ok(Inserted::Duplicate(existing.id.clone()));
+                }
+            }
+            if max.is_some_and(|max| same_form.count() >= max) {
+                return Ok(Inserted::Full);
+            }
+            submissions.push(submission.clone());
+        }
+        JsonlWriter::write(&mut file, &submission).await?;
+        Ok(Inserted::Stored)
+    }
+
+    /// Adds `answers` to the submission `id`, creating it if necessary, and
+    /// drops the answers `form` no longer asks for with the merged ones. A
+    /// `shuffle_seed` replaces the one stored. `None` if it would be new and
+    /// the form already has `max`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn merge(
+        &self,
+        form: &Form,
+        form_id: &str,
+        id: &str,
+        form_version: &str,
+        answers: HashMap<String, Vec<String>>,
+        now: DateTime<Utc>,
+        client: Option<ClientMeta>,
+        shuffle_seed: Option<u64>,
+        max: Option<usize>,
+    ) -> io::Result<Option<Submission>> {
+        let mut file = self.log.file.lock().await;
+        let merged = {
+            let mut submissions = self.submissions.lock().unwrap();
+            let submission = match submissions
+                .iter()
+                .position(|s| s.id == id && s.form_id == form_id)
+            {
+                Some(idx) => &mut submissions[idx],
+                None => {
+                    let count = submissions.iter().filter(|s| s.form_id == form_id).count();
+                    if max.is_some_and(|max| count >= max) {
+                        return Ok(None);
+                    }
+                    submissions.push(Submission {
+                        id: id.to_string(),
+                        form_id: form_id.to_string(),
+                        answers: HashMap::new(),
+                        form_version: None,
+                        submitted_at: Some(now),
+                        updated_at: None,
+                        client,
+                        duration_secs: None,
+                        tracking: BTreeMap::new(),
+                        source: Source::Web,
+                        score: None,
+                        computed: BTreeMap::new(),
+                        choice_score: None,
+                        shuffle_seed: None,
+                    });
+                    submissions.last_mut().unwrap()
+                }
+            };
+            submission.answers.extend(answers);
+            if shuffle_seed.is_some() {
+                submission.shuffle_seed = shuffle_seed;
+            }
+            form.drop_hidden_answers(&mut submission.answers);
+            submission.form_version = Some(form_version.to_string());
+            submission.clone()
+        };
+        JsonlWriter::write(&mut file, &merged).await?;
+        Ok(Some(merged))
+    }
+
+    /// Replaces the stored submission with the same id.
+    pub async fn update(&self, submission: Submission) -> io::Result<()> {
+        let mut file = self.log.file.lock().await;
+        {
+            let mut submissions = self.submissions.lock().unwrap();
+            let existing = submissions
+                .iter_mut()
+                .find(|s| s.id == submission.id && s.form_id == submission.form_id)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such submission"))?;
+            *existing = submission.clone();
+        }
+        JsonlWriter::write(&mut file, &submission).await
+    }
+
+    /// Removes a submission for good. The log is rewritten without it, so
+    /// neither the current nor earlier partial saves stay on disk; only the
+    /// fact of the deletion goes to the audit log. Returns whether it existed.
+    pub async fn delete(
+        &self,
+        form_id: &str,
+        id: &str,
+        by: &str,
+        now: DateTime<Utc>,
+    ) -> io::Result<bool> {
+        let mut file = self.log.file.lock().await;
+        let remaining: Vec<Submission> = {
+            let submissions = self.submissions.lock().unwrap();
+            let remaining: Vec<Submission> = submissions
+                .iter()
+                .filter(|s| !(s.id == id && s.form_id == form_id))
+                .cloned()
+                .collect();
+            if remaining.len() == submissions.len() {
+                return Ok(false);
+            }
+            remaining
+        };
+        // Memory follows the file only once it is written, so a failed
+        // rewrite leaves both as they were. Other writers wait for the lock.
+        self.log.rewrite(&mut file, &remaining).await?;
+        *self.submissions.lock().unwrap() = remaining;
+        drop(file);
+
+        let entry = AuditEntry {
+            action: "delete".to_string(),
+            form_id: form_id.to_string(),
+            submission_id: id.to_string(),
+            by: by.to_string(),
+            at: now,
+        };
+        let mut audit = self.audit_log.file.lock().await;
+        JsonlWriter::write(&mut audit, &entry).await?;
+        Ok(true)
+    }
+
+    pub fn get(&self, form_id: &str, id: &str) -> Option<Submission> {
+        self.submissions
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|s| s.form_id == form_id && s.id == id)
+            .cloned()
+    }
+
+    /// One page of a form's submissions in storage order (or reversed), plus
+    /// the total number of submissions of the form.
+    pub fn page(
+        &self,
+        form_id: &str,
+        offset: usize,
+        limit: usize,
+        descending: bool,
+    ) -> (usize, Vec<Submission>) {
+        let submissions = self.submissions.lock().unwrap();
+        let matching = submissions.iter().filter(|s| s.form_id == form_id);
+        let total = matching.clone().count();
+        let page = if descending {
+            matching.rev().skip(offset).take(limit).cloned().collect()
+        } else {
+            matching.skip(offset).take(limit).cloned().collect()
+        };
+        (total, page)
+    }
+
+    pub fn count(&self, form_id: &str) -> usize {
+        self.submissions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| s.form_id == form_id)
+            .count()
+    }
+
+    /// Everything the store holds, e.g. for a backup: the submissions of each
+    /// form in storage order, the form versions and the audit log.
+    pub fn export_all(&self) -> io::Result<StoreExport> {
+        let mut submissions: BTreeMap<String, Vec<Submission>> = BTreeMap::new();
+        for submission in self.submissions.lock().unwrap().iter() {
+            submissions
+                .entry(submission.form_id.clone())
+                .or_default()
+                .push(submission.clone());
+        }
+        Ok(StoreExport {
+            submissions,
+            versions: self.versions.lock().unwrap().clone(),
+            audit: read_jsonl(&self.audit_log.path)?,
+        })
+    }
+
+    /// Whether the store holds neither submissions nor form versions.
+    pub fn is_empty(&self) -> bool {
+        self.submissions.lock().unwrap().is_empty() && self.versions.lock().unwrap().is_empty()
+    }
+
+    /// Loads an `export_all` of this or another store, e.g. from a backup.
+    /// All three files are rewritten, so the result is the same as opening
+    /// a store written by the exporting one.
+    pub async fn import_all(
+        &self,
+        import: StoreExport,
+        mode: ImportMode,
+    ) -> io::Result<ImportReport> {
+        let mut file = self.log.file.lock().await;
+        let mut versions_file = self.versions_log.file.lock().await;
+        let mut audit_file = self.audit_log.file.lock().await;
+        let mut report = ImportReport::default();
+        let (submissions, versions, audit) = {
+            let (mut submissions, mut versions, mut audit) = match mode {
+                ImportMode::Replace => (Vec::new(), Vec::new(), Vec::new()),
+                ImportMode::Merge => (
+                    self.submissions.lock().unwrap().clone(),
+                    self.versions.lock().unwrap().clone(),
+                    read_jsonl(&self.audit_log.path)?,
+                ),
+            };
+            for submission in import.submissions.into_values().flatten() {
+                match submissions
+                    .iter()
+                    .find(|s| s.id == submission.id && s.form_id == submission.form_id)
+                {
+                    Some(stored) if *stored == submission => report.unchanged += 1,
+                    Some(_) => report
+                        .conflicts
+                        .push(format!("{}/{}", submission.form_id, submission.id)),
+                    None => {
+                        submissions.push(submission);
+                        report.imported += 1;
+                    }
+                }
+            }
+            for version in import.versions {
+                if !versions.contains(&version) {
+                    versions.push(version);
+                }
+            }
+            for entry in import.audit {
+                if !audit.contains(&entry) {
+                    audit.push(entry);
+                }
+            }
+            (submissions, versions, audit)
+        };
+        // As for `delete`, memory only takes the import once all three files
+        // are written. Other writers wait for the locks until then.
+        self.log.rewrite(&mut file, &submissions).await?;
+        self.versions_log
+            .rewrite(&mut versions_file, &versions)
+            .await?;
+        self.audit_log.rewrite(&mut audit_file, &audit).await?;
+        *self.submissions.lock().unwrap() = submissions;
+        *self.versions.lock().unwrap() = versions;
+        Ok(report)
+    }
+
+    pub fn list(&self, form_id: &str) -> Vec<Submission> {
+        self.submissions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| s.form_id == form_id)
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,135 @@
+//! The `templates` section of a form definition: questions and question sets
+//! declared once by name and used in `groups` as `{ template: name }`, with
+//! fields of the use replacing those of the template.
+//!
+//! References are expanded on the definition as parsed, before it becomes a
+//! [`Form`](crate::Form), so the loaded form is the same as one with the
+//! questions written out.
+
+use serde_yaml::{Mapping, Value};
+
+/// The templates of a form, by name. A template with `questions` is a
+/// question set, any other a question.
+#[derive(Default)]
+pub struct Templates(Mapping);
+
+impl Templates {
+    /// Takes the `templates` section out of a parsed definition.
+    pub fn take(definition: &mut Value) -> Result<Self, String> {
+        let section = match definition.as_mapping_mut() {
+            Some(definition) => definition.remove(&key("templates")),
+            None => None,
+        };
+        match section {
+            None => Ok(Templates::default()),
+            Some(Value::Mapping(templates)) => {
+                if let Some((name, _)) = templates.iter().find(|(name, template)| {
+                    !name.is_string()
+                        || !template.is_mapping()
+                        || template.get("template").is_some()
+                }) {
+                    return Err(format!(
+                        "templates.{}: must be a question or question set, and not refer to another template",
+                        name.as_str().unwrap_or("?")
+                    ));
+                }
+                Ok(Templates(templates))
+            }
+            Some(_) => Err("templates: must map names to questions or question sets".to_string()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// `group` with template references expanded, itself and its questions.
+    /// `at` locates it for errors, e.g. `groups[1]`.
+    pub fn expand_set(&self, group: Value, at: &str) -> Result<Value, String> {
+        let mut group = match group.get("template") {
+            Some(_) => self.instantiate(group, true, at)?,
+            None => group,
+        };
+        if let Some(Value::Sequence(questions)) = group.get_mut("questions") {
+            for (index, question) in questions.iter_mut().enumerate() {
+                if question.get("template").is_some() {
+                    let at = format!("{at}.questions[{index}]");
+                    *question = self.instantiate(std::mem::take(question), false, &at)?;
+                }
+            }
+        }
+        Ok(group)
+    }
+
+    /// The template `reference` names, with the other fields of `reference`
+    /// in place of the template's. `spec` is updated field by field and may
+    /// only set fields the template's question type has.
+    fn instantiate(&self, reference: Value, set: bool, at: &str) -> Result<Value, String> {
+        let Value::Mapping(mut overrides) = reference else {
+            unreachable!("only mappings have a template field")
+        };
+        let name = match overrides.remove(&key("template")) {
+            Some(Value::String(name)) => name,
+            _ => return Err(format!("{at}.template: must be the name of a template")),
+        };
+        let Some(Value::Mapping(template)) = self.0.get(&key(&name)) else {
+            return Err(format!("{at}: unknown template '{name}'"));
+        };
+        let is_set = template.contains_key(&key("questions"));
+        if is_set != set {
+            return Err(format!(
+                "{at}: template '{name}' is a {}, not a {}",
+                if is_set { "question set" } else { "question" },
+                if set { "question set" } else { "question" }
+            ));
+        }
+        let mut instance = template.clone();
+        if let Some(spec) = overrides.remove(&key("spec")) {
+            let Some(Value::Mapping(template_spec)) = instance.get_mut(&key("spec")) else {
+                return Err(format!("{at}: template '{name}' has no spec to override"));
+            };
+            let Value::Mapping(spec) = spec else {
+                return Err(format!("{at}.spec: must be a map of fields to override"));
+            };
+            let kind = template_spec
+                .get(&key("type"))
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            let fields = spec_fields(&kind);
+            for (field, value) in spec {
+                let field_name = field.as_str().unwrap_or_default();
+                if field_name == "type" && value.as_str() != Some(&kind) {
+                    return Err(format!(
+                        "{at}.spec.type: cannot change the type of template '{name}' ({kind})"
+                    ));
+                }
+                if !fields.iter().any(|known| known == field_name) {
+                    return Err(format!(
+                        "{at}.spec.{field_name}: {kind} questions have no such field to override"
+                    ));
+                }
+                template_spec.insert(field, value);
+            }
+        }
+        instance.extend(overrides);
+        Ok(Value::Mapping(instance))
+    }
+}
+
+fn key(name: &str) -> Value {
+    Value::String(name.to_string())
+}
+
+/// Fields of the question type tagged `kind`, from its schema.
+fn spec_fields(kind: &str) -> Vec<String> {
+    let schema = serde_json::to_value(schemars::schema_for!(crate::QuestionType))
+        .expect("schemas serialize");
+    let variants = schema["oneOf"].as_array().cloned().unwrap_or_default();
+    variants
+        .iter()
+        .find(|variant| variant["properties"]["type"]["enum"][0] == kind)
+        .and_then(|variant| variant["properties"].as_object())
+        .map(|properties| properties.keys().cloned().collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,637 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::filter::Filter;
+use crate::i18n::{self, fill, Message, Messages};
+use crate::quiz::Correct;
+use crate::scores::{self, Aggregate};
+use crate::{
+    text_length, Answer, ClientIp, Form, Localized, QuestionType, ResponseLimit, NA, ROOT_PREFIX,
+};
+
+#[derive(Debug, Clone)]
+pub struct Limits {
+    pub max_groups: usize,
+    pub max_questions: usize,
+    /// Most options a `discrete_numeric` question may have.
+    pub max_discrete_span: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_groups: 1000,
+            max_questions: 1000,
+            max_discrete_span: 20,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ValidationError {
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid form:")?;
+        for problem in &self.problems {
+            write!(f, "\n  - {problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FieldError {
+    pub id: String,
+    pub message: String,
+}
+
+impl Form {
+    pub fn validate(&self, limits: &Limits) -> Result<(), ValidationError> {
+        let mut problems = Vec::new();
+
+        let groups = self.groups.len();
+        if groups > limits.max_groups {
+            problems.push(format!(
+                "form has {groups} question sets, the limit is {} (see --max-groups)",
+                limits.max_groups
+            ));
+        }
+        let questions = self.questions().count();
+        if questions > limits.max_questions {
+            problems.push(format!(
+                "form has {questions} questions, the limit is {} (see --max-questions)",
+                limits.max_questions
+            ));
+        }
+
+        if let (Some(opens_at), Some(closes_at)) = (self.opens_at, self.closes_at) {
+            if closes_at <= opens_at {
+                problems.push(format!(
+                    "closes_at ({closes_at}) must be later than opens_at ({opens_at})"
+                ));
+            }
+        }
+
+        if let Some(offset) = &self.utc_offset {
+            if offset.parse::<chrono::FixedOffset>().is_err() {
+                problems.push(format!(
+                    "utc_offset ({offset}) must be an offset like +02:00 or -05:00"
+                ));
+            }
+        }
+
+        let links = [("redirect_after", self.redirect_after.as_ref())]
+            .into_iter()
+            .chain(
+                [
+                    ("not_open_page.link.url", &self.not_open_page),
+                    ("closed_page.link.url", &self.closed_page),
+                ]
+                .map(|(name, page)| {
+                    (
+                        name,
+                        page.as_ref()
+                            .and_then(|page| Some(&page.link.as_ref()?.url)),
+                    )
+                }),
+            );
+        for (name, url) in links {
+            let Some(url) = url else { continue };
+            let absolute = url.starts_with("https://") || url.starts_with("http://");
+            let root_relative = url.starts_with('/') && !url.starts_with("//");
+            if !(absolute || root_relative) {
+                problems.push(format!(
+                    "{name} ({url}) must be an absolute http(s) URL or start with '/'"
+                ));
+            }
+        }
+
+        if self.limit == ResponseLimit::OnePerIp && self.privacy.client_ip != ClientIp::Hashed {
+            problems.push(
+                "limit: one_per_ip needs privacy.client_ip: hashed, the addresses are told apart by their hash"
+                    .to_string(),
+            );
+        }
+
+        if let Some(captcha) = &self.captcha {
+            if captcha.site_key.trim().is_empty() || captcha.secret.trim().is_empty() {
+                problems.push("captcha needs a site_key and a secret".to_string());
+            }
+            if self.per_group_submit {
+                problems.push(
+                    "captcha cannot be combined with per_group_submit, which sends every question set on its own"
+                        .to_string(),
+                );
+            }
+        }
+        if self.limit == ResponseLimit::OnePerIp && self.per_group_submit {
+            problems.push(
+                "limit: one_per_ip cannot be combined with per_group_submit, whose sets are saved one by one"
+                    .to_string(),
+            );
+        }
+        if self.confirm_submit && self.per_group_submit {
+            problems.push(
+                "confirm_submit cannot be combined with per_group_submit, which has no final submit"
+                    .to_string(),
+            );
+        }
+
+        let mut keys = HashMap::new();
+        let mut asked_before = HashSet::new();
+        for (id, question) in self.questions() {
+            if let Some(key) = &question.key {
+                let valid = !key.is_empty()
+                    && !key.starts_with('_')
+                    && !key.starts_with(&format!("{ROOT_PREFIX}-"))
+                    && key
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+                if !valid {
+                    problems.push(format!(
+                        "question ({}) has the key '{key}'; keys consist of letters, digits, '-' and '_', and must not start with '_' or '{ROOT_PREFIX}-'",
+                        self.text(&question.title)
+                    ));
+                }
+                if let Some(other) = keys.insert(key, &question.title) {
+                    problems.push(format!(
+                        "questions ({}) and ({}) have the same key '{key}'",
+                        self.text(other),
+                        self.text(&question.title)
+                    ));
+                }
+            }
+            // Stored answers have to stay the same whatever language was shown.
+            let translated_without_value =
+                question
+                    .spec
+                    .answers()
+                    .into_iter()
+                    .flatten()
+                    .find(|answer| {
+                        matches!(answer.label, Localized::Translated(_)) && answer.value.is_none()
+                    });
+            if let Some(answer) = translated_without_value {
+                problems.push(format!(
+                    "question {id} ({}) has the translated option '{}' without a value",
+                    self.text(&question.title),
+                    self.text(&answer.label)
+                ));
+            }
+            if let QuestionType::DiscreteNumeric {
+                bounds: (min, max), ..
+            } = &question.spec
+            {
+                let span = (*max as i16 - *min as i16 + 1).max(0) as usize;
+                if span > limits.max_discrete_span {
+                    problems.push(format!(
+                        "question {id} ({}) has {span} options, the limit is {} (see --max-discrete-span); use a continous_numeric question with bounds for a slider instead",
+                        self.text(&question.title), limits.max_discrete_span
+                    ));
+                }
+            }
+            if let QuestionType::ContinousNumeric { bounds, step, .. } = &question.spec {
+                if let Some((min, max)) =
+                    bounds.filter(|(min, max)| min >= max || min.is_nan() || max.is_nan())
+                {
+                    problems.push(format!(
+                        "question {id} ({}) has the bounds {min} to {max}, the first has to be below the second",
+                        self.text(&question.title)
+                    ));
+                }
+                if let Some(step) = step.filter(|step| !(step.is_finite() && *step > 0.0)) {
+                    problems.push(format!(
+                        "question {id} ({}) has step {step}, it must be more than 0",
+                        self.text(&question.title)
+                    ));
+                }
+            }
+            if question.spec.columns() == Some(0) {
+                problems.push(format!(
+                    "question {id} ({}) has columns: 0, its choices need at least one",
+                    self.text(&question.title)
+                ));
+            }
+            if let QuestionType::Text {
+                pattern,
+                max_length,
+                warn_at,
+                ..
+            } = &question.spec
+            {
+                if let Some(Err(err)) = pattern.as_ref().map(|pattern| pattern.regex()) {
+                    problems.push(format!(
+                        "question {id} ({}) has an invalid pattern: {err}",
+                        self.text(&question.title)
+                    ));
+                }
+                if *max_length == Some(0) {
+                    problems.push(format!(
+                        "question {id} ({}) has max_length 0, no answer would fit",
+                        self.text(&question.title)
+                    ));
+                }
+                if let Some(warn_at) =
+                    warn_at.filter(|warn_at| !(*warn_at > 0.0 && *warn_at <= 1.0))
+                {
+                    problems.push(format!(
+                        "question {id} ({}) has warn_at {warn_at}, it must be a share of max_length between 0 and 1",
+                        self.text(&question.title)
+                    ));
+                }
+            }
+            if question.required
+                && matches!(
+                    question.spec,
+                    QuestionType::Computed { .. } | QuestionType::Unknown { .. }
+                )
+            {
+                problems.push(format!(
+                    "question {id} ({}) is required but cannot be answered",
+                    self.text(&question.title)
+                ));
+            }
+            if let Some(correct) = &question.correct {
+                if let Some(problem) = correct_problem(&question.spec, correct) {
+                    problems.push(format!(
+                        "question {id} ({}) {problem}",
+                        self.text(&question.title)
+                    ));
+                }
+            }
+            if let Some(points) = question.points {
+                if !(points.is_finite() && points > 0.0) {
+                    problems.push(format!(
+                        "question {id} ({}) is worth {points} points, which must be more than 0",
+                        self.text(&question.title)
+                    ));
+                }
+            }
+            if let Some(condition) = &question.show_if {
+                match Filter::parse(condition, self) {
+                    Err(err) => problems.push(format!(
+                        "question {id} ({}) has an invalid show_if: {err}",
+                        self.text(&question.title)
+                    )),
+                    Ok(filter) if !asked_before.contains(&filter.id) => problems.push(format!(
+                        "question {id} ({}) depends on {}, which is not asked before it",
+                        self.text(&question.title),
+                        filter.id
+                    )),
+                    Ok(_) => {}
+                }
+            }
+            asked_before.insert(id);
+        }
+
+        let mut in_earlier_sets = HashSet::new();
+        for (g_idx, group) in self.groups.iter().enumerate() {
+            if let Some(condition) = &group.show_if {
+                match Filter::parse(condition, self) {
+                    Err(err) => problems.push(format!(
+                        "question set {g_idx} ({}) has an invalid show_if: {err}",
+                        self.text(&group.title)
+                    )),
+                    Ok(filter) if !in_earlier_sets.contains(&filter.id) => problems.push(format!(
+                        "question set {g_idx} ({}) depends on {}, which is not asked in an earlier set",
+                        self.text(&group.title),
+                        filter.id
+                    )),
+                    Ok(_) => {}
+                }
+            }
+            in_earlier_sets.extend(self.group_questions(g_idx).map(|(id, _)| id));
+        }
+
+        let questions: HashMap<String, _> = self.questions().collect();
+        let mut score_keys = HashSet::new();
+        for score in &self.computed {
+            let key = &score.key;
+            if key.is_empty() {
+                problems.push(format!(
+                    "computed score ({}) has no key",
+                    self.text(&score.title)
+                ));
+            } else if !score_keys.insert(key) {
+                problems.push(format!("computed score {key} is defined twice"));
+            }
+            if score.items.is_empty() {
+                problems.push(format!("computed score {key} has no items"));
+            }
+            for item in &score.items {
+                let id = &item.question;
+                let Some(question) = questions.get(id) else {
+                    problems.push(format!(
+                        "computed score {key} counts {id}, which is no question of the form"
+                    ));
+                    continue;
+                };
+                let numeric = matches!(
+                    question.spec,
+                    QuestionType::DiscreteNumeric { .. } | QuestionType::ContinousNumeric { .. }
+                );
+                if !numeric {
+                    problems.push(format!(
+                        "computed score {key} counts {id} ({}), a {} question; only numeric questions can be counted",
+                        self.text(&question.title),
+                        question.spec.kind()
+                    ));
+                } else if item.reverse && scores::bounds(&question.spec).is_none() {
+                    problems.push(format!(
+                        "computed score {key} reverses {id} ({}), which has no bounds to reverse it within",
+                        self.text(&question.title)
+                    ));
+                }
+                let positive = item.weight > 0.0 || score.aggregate == Aggregate::Sum;
+                if !item.weight.is_finite() || !positive {
+                    problems.push(format!(
+                        "computed score {key} weighs {id} with {}, weights must be finite numbers, and more than 0 for a mean",
+                        item.weight
+                    ));
+                }
+            }
+            let ascending = score
+                .thresholds
+                .windows(2)
+                .all(|pair| pair[0].from < pair[1].from);
+            if !ascending {
+                problems.push(format!(
+                    "computed score {key} has thresholds out of order, they go from the lowest value up"
+                ));
+            }
+        }
+
+        if let Some(id) = &self.live_question {
+            match questions.get(id) {
+                None => problems.push(format!(
+                    "live_question {id} is no question of the form"
+                )),
+                Some(question)
+                    if !matches!(
+                        question.spec,
+                        QuestionType::SingleChoice { .. }
+                            | QuestionType::MultipleChoice { .. }
+                            | QuestionType::DiscreteNumeric { .. }
+                    ) =>
+                {
+                    problems.push(format!(
+                        "live_question {id} ({}) is a {} question; only choices and scales have options to count",
+                        self.text(&question.title),
+                        question.spec.kind()
+                    ))
+                }
+                Some(_) => {}
+            }
+            if !self.public_count {
+                problems.push(format!(
+                    "live_question {id} is only shown with public_count"
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError { problems })
+        }
+    }
+}
+
+/// Why `correct` cannot be the answer to a question of type `spec`.
+fn correct_problem(spec: &QuestionType, correct: &Correct) -> Option<String> {
+    match spec {
+        QuestionType::DiscreteNumeric { .. } | QuestionType::ContinousNumeric { .. } => {
+            (!matches!(correct, Correct::Number(_)))
+                .then(|| "needs a number as its correct answer".to_string())
+        }
+        QuestionType::SingleChoice { answers, .. }
+        | QuestionType::MultipleChoice { answers, .. } => {
+            let values = correct.values();
+            if values.is_empty() {
+                return Some("has no correct option".to_string());
+            }
+            values
+                .into_iter()
+                .find(|value| !answers.iter().any(|answer| answer.value() == value))
+                .map(|value| {
+                    format!("has the correct answer '{value}', which is not one of its options")
+                })
+        }
+        _ => Some(format!(
+            "has a correct answer, but {} questions cannot be scored",
+            spec.kind()
+        )),
+    }
+}
+
+impl Form {
+    /// Problems that do not stop the form from being served.
+    pub fn warnings(&self) -> Vec<String> {
+        self.questions()
+            .filter_map(|(id, question)| match &question.spec {
+                QuestionType::Unknown { raw_type } => Some(format!(
+                    "question {id} ({}) has the unsupported type '{raw_type}' and is shown as a placeholder",
+                    self.text(&question.title)
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Form {
+    /// Rewrites numbers typed in `lang` (`3,5`, `1.234,5`) to the canonical
+    /// form they are validated and stored in. Values that are no numbers are
+    /// kept for validation to report.
+    pub fn normalize_answers(&self, answers: &mut HashMap<String, Vec<String>>, lang: &str) {
+        for (id, question) in self.questions() {
+            let QuestionType::ContinousNumeric { .. } = question.spec else {
+                continue;
+            };
+            for value in answers.get_mut(&id).into_iter().flatten() {
+                if let Some(number) = i18n::parse_number(value, lang) {
+                    *value = number.to_string();
+                }
+            }
+        }
+    }
+
+    /// The questions whose `show_if`, or that of their set, does not match
+    /// `answers`, so they were not asked. A question depending on one that
+    /// was not asked is not asked either.
+    pub fn hidden_questions(&self, answers: &HashMap<String, Vec<String>>) -> HashSet<String> {
+        let mut hidden = HashSet::new();
+        let mut asked = answers.clone();
+        // Conditions are checked when the form is loaded.
+        let shown = |condition: &Option<String>, asked: &HashMap<String, Vec<String>>| {
+            condition.as_ref().is_none_or(|condition| {
+                Filter::parse(condition, self).map_or(true, |filter| filter.matches(asked))
+            })
+        };
+        for (g_idx, group) in self.groups.iter().enumerate() {
+            let group_shown = shown(&group.show_if, &asked);
+            for (id, question) in self.group_questions(g_idx) {
+                if !group_shown || !shown(&question.show_if, &asked) {
+                    asked.remove(&id);
+                    hidden.insert(id);
+                }
+            }
+        }
+        hidden
+    }
+
+    /// Removes the answers to questions that were not asked (see
+    /// `hidden_questions`), e.g. left over from before the respondent changed
+    /// an earlier answer, so they are not stored.
+    pub fn drop_hidden_answers(&self, answers: &mut HashMap<String, Vec<String>>) {
+        for id in self.hidden_questions(answers) {
+            answers.remove(&id);
+        }
+    }
+
+    /// Checks submitted answers against their question types. Unanswered
+    /// questions are fine unless required; whatever was answered has to fit
+    /// the question. Questions that were not asked are ignored.
+    pub fn validate_submission(
+        &self,
+        answers: &HashMap<String, Vec<String>>,
+    ) -> Result<(), Vec<FieldError>> {
+        self.validate_submission_in(answers, "en")
+    }
+
+    /// Like `validate_submission`, with the messages in `lang`.
+    pub fn validate_submission_in(
+        &self,
+        answers: &HashMap<String, Vec<String>>,
+        lang: &str,
+    ) -> Result<(), Vec<FieldError>> {
+        let messages = Messages::new(self, lang);
+        let hidden = self.hidden_questions(answers);
+        let errors: Vec<FieldError> = self
+            .questions()
+            .filter(|(id, _)| !hidden.contains(id))
+            .filter_map(|(id, question)| {
+                let message = match answers.get(&id) {
+                    Some(values) => question.spec.validate_answer_in(values, &messages).err()?,
+                    None if question.required => messages.get(Message::Required).to_string(),
+                    None => return None,
+                };
+                Some(FieldError { id, message })
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl QuestionType {
+    pub fn validate_answer(&self, values: &[String]) -> Result<(), String> {
+        self.validate_answer_in(values, &Messages::builtin("en"))
+    }
+
+    pub fn validate_answer_in(&self, values: &[String], messages: &Messages) -> Result<(), String> {
+        let message = |message| messages.get(message).to_string();
+        let single = || match values {
+            [value] => Ok(value.as_str()),
+            _ => Err(message(Message::OnlyOne)),
+        };
+        let between = |template, min: &dyn fmt::Display, max: &dyn fmt::Display| {
+            fill(
+                messages.get(template),
+                &[("min", &min.to_string()), ("max", &max.to_string())],
+            )
+        };
+        let not_an_option =
+            |value: &str| fill(messages.get(Message::NotAnOption), &[("value", value)]);
+        let is_option =
+            |answers: &[Answer], value: &str| answers.iter().any(|answer| answer.value() == value);
+        match self {
+            QuestionType::DiscreteNumeric {
+                bounds: (min, max),
+                na_option,
+                ..
+            } => match single()? {
+                value if na_option.is_some() && value == NA => Ok(()),
+                value => match value.parse::<i8>() {
+                    Ok(value) if (*min..=*max).contains(&value) => Ok(()),
+                    _ => Err(between(Message::ChooseBetween, min, max)),
+                },
+            },
+            QuestionType::ContinousNumeric { bounds, .. } => {
+                let value = single()?
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|value| value.is_finite())
+                    .ok_or_else(|| message(Message::EnterNumber))?;
+                match bounds {
+                    Some((min, max)) if value < *min || value > *max => {
+                        Err(between(Message::NumberBetween, min, max))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            QuestionType::SingleChoice {
+                answers,
+                custom_answer,
+                ..
+            } => {
+                let value = single()?;
+                if *custom_answer || is_option(answers, value) {
+                    Ok(())
+                } else {
+                    Err(not_an_option(value))
+                }
+            }
+            QuestionType::MultipleChoice {
+                answers,
+                custom_answer,
+                ..
+            } => {
+                let unknown: Vec<&String> = values
+                    .iter()
+                    .filter(|value| !is_option(answers, value))
+                    .collect();
+                match unknown.as_slice() {
+                    [] => Ok(()),
+                    [_] if *custom_answer => Ok(()),
+                    [value, ..] if !custom_answer => Err(not_an_option(value)),
+                    _ => Err(message(Message::OneCustom)),
+                }
+            }
+            QuestionType::Text {
+                pattern,
+                max_length,
+                ..
+            } => {
+                let value = single()?;
+                if let Some(max) = max_length.filter(|max| text_length(value) > *max) {
+                    return Err(fill(
+                        messages.get(Message::TooLong),
+                        &[("max", &max.to_string())],
+                    ));
+                }
+                match pattern.as_ref().map(|pattern| pattern.regex()) {
+                    // Like the HTML attribute, the pattern only constrains non-empty answers.
+                    Some(Ok(regex)) if !value.is_empty() && !regex.is_match(value) => {
+                        Err(message(Message::MatchFormat))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            QuestionType::Computed { .. } => Err(message(Message::Computed)),
+            QuestionType::Unknown { raw_type } => Err(fill(
+                messages.get(Message::Unsupported),
+                &[("type", raw_type)],
+            )),
+        }
+    }
+}
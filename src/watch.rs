@@ -0,0 +1,91 @@
+//! `--watch`: form files are reloaded when they change, for editing a form
+//! while it is served.
+//!
+//! Files are read again every [`PERIOD`] and compared by content hash, which
+//! needs no file system notifications and also sees edits made on network
+//! drives. Only the files forms were loaded from are watched, not the files
+//! they include.
+
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::live::Change;
+use crate::server::AppState;
+use crate::{load_form, Form};
+
+/// How often the files are checked.
+pub const PERIOD: Duration = Duration::from_secs(1);
+
+struct WatchedFile {
+    form_id: String,
+    path: PathBuf,
+    digest: Option<Vec<u8>>,
+}
+
+pub struct FormWatcher {
+    files: Vec<WatchedFile>,
+}
+
+fn digest(path: &Path) -> Option<Vec<u8>> {
+    let content = std::fs::read(path).ok()?;
+    Some(Sha256::digest(content).to_vec())
+}
+
+impl FormWatcher {
+    /// Watches the files of `forms`, given with the id each is served under,
+    /// for changes from their content now.
+    pub fn new(forms: Vec<(String, PathBuf)>) -> Self {
+        let files = forms
+            .into_iter()
+            .map(|(form_id, path)| WatchedFile {
+                digest: digest(&path),
+                form_id,
+                path,
+            })
+            .collect();
+        FormWatcher { files }
+    }
+
+    /// Serves the files changed since the last check in place of their
+    /// forms. `on_reload` is called with each form served anew; a file that
+    /// no longer loads is reported and its previous version kept.
+    pub fn poll(&mut self, state: &AppState, mut on_reload: impl FnMut(&str, &Form)) {
+        for file in &mut self.files {
+            let current = digest(&file.path);
+            if current.is_none() || current == file.digest {
+                continue;
+            }
+            file.digest = current;
+            match load_form(&file.path, &state.limits) {
+                Ok(form) => {
+                    for warning in form.warnings() {
+                        eprintln!("{}: warning: {warning}", file.path.display());
+                    }
+                    state.log_form_loaded(&file.form_id, &form);
+                    on_reload(&file.form_id, &form);
+                    state
+                        .forms
+                        .write()
+                        .unwrap()
+                        .insert(file.form_id.clone(), Arc::new(form));
+                    state.changed(Change::Form(file.form_id.clone()));
+                }
+                Err(err) => eprintln!(
+                    "{}: {err}; still serving the previous version",
+                    file.path.display()
+                ),
+            }
+        }
+    }
+
+    /// Checks the files every [`PERIOD`], for as long as the server runs.
+    pub async fn run(mut self, state: Arc<AppState>) {
+        let mut interval = tokio::time::interval(PERIOD);
+        loop {
+            interval.tick().await;
+            self.poll(&state, |_, _| ());
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Every control of a rendered form has to be named for assistive technology.
+
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashMap;
+use umfragetool_rust::*;
+
+const FORM: &str = r#"
+title: Everything
+description: ""
+groups:
+  - title: Choices
+    description: ""
+    questions:
+      - title: One
+        spec: { type: single_choice, answers: [A, B], custom_answer: true }
+      - title: Many
+        spec: { type: multiple_choice, answers: [A, B], custom_answer: true }
+      - title: Scale
+        spec: { type: discrete_numeric, bounds: [1, 3], num_descriptions: { 1: low } }
+  - title: Free input
+    description: ""
+    questions:
+      - title: Short
+        spec: { type: text, is_long: false }
+      - title: Long
+        spec: { type: text, is_long: true }
+      - title: Number
+        spec: { type: continous_numeric }
+      - title: Slider
+        spec: { type: continous_numeric, bounds: [0, 10] }
+      - title: Double the number
+        spec: { type: computed, expression: "i-1-2 * 2" }
+"#;
+
+/// Whether `control` is named by a `<label for>`, an `aria-label` or the
+/// `<legend>` of a fieldset around it.
+fn is_labeled(document: &Html, control: ElementRef) -> bool {
+    let element = control.value();
+    if element.attr("aria-label").is_some() {
+        return true;
+    }
+    if let Some(id) = element.attr("id") {
+        let label = Selector::parse(&format!(r#"label[for="{id}"]"#)).unwrap();
+        if document.select(&label).next().is_some() {
+            return true;
+        }
+    }
+    let legend = Selector::parse(":scope > legend").unwrap();
+    control
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .filter(|ancestor| ancestor.value().name() == "fieldset")
+        .any(|fieldset| fieldset.select(&legend).next().is_some())
+}
+
+fn assert_all_labeled(html: &str) {
+    let document = Html::parse_document(html);
+    let controls = Selector::parse(
+        r#"input:not([type="hidden"]):not([type="submit"]), textarea, select, output"#,
+    )
+    .unwrap();
+    let mut count = 0;
+    for control in document.select(&controls) {
+        count += 1;
+        assert!(
+            is_labeled(&document, control),
+            "unlabeled control: {}",
+            control.html()
+        );
+    }
+    assert!(count > 0, "no controls rendered");
+}
+
+#[test]
+fn every_control_is_labeled() {
+    let form: Form = serde_yaml::from_str(FORM).unwrap();
+    assert_all_labeled(&form.to_string());
+    assert_all_labeled(&demo_form().to_string());
+}
+
+#[test]
+fn controls_stay_labeled_with_errors() {
+    let form: Form = serde_yaml::from_str(FORM).unwrap();
+    let ctx = RenderContext {
+        errors: form
+            .questions()
+            .map(|(id, _)| (id, "wrong".to_string()))
+            .collect::<HashMap<_, _>>(),
+        ..Default::default()
+    };
+    assert_all_labeled(&form.render(ROOT_PREFIX.to_string(), &ctx));
+}
+
+#[test]
+fn question_sets_are_named_sections() {
+    let form: Form = serde_yaml::from_str(FORM).unwrap();
+    let document = Html::parse_document(&form.to_string());
+    let sections = Selector::parse("section").unwrap();
+    let names: Vec<String> = document
+        .select(&sections)
+        .map(|section| {
+            let id = section.value().attr("aria-labelledby").unwrap();
+            let heading = Selector::parse(&format!("#{id}")).unwrap();
+            document.select(&heading).next().unwrap().text().collect()
+        })
+        .collect();
+    assert_eq!(names, ["Choices", "Free input"]);
+}
+
+#[test]
+fn errors_and_help_describe_their_controls() {
+    let mut form: Form = serde_yaml::from_str(FORM).unwrap();
+    for question in form
+        .groups
+        .iter_mut()
+        .flat_map(|group| &mut group.questions)
+    {
+        question.required = !matches!(question.spec, QuestionType::Computed { .. });
+        question.help = Some("Help".into());
+    }
+    let answers = HashMap::new();
+    let errors = form.validate_submission(&answers).unwrap_err();
+    assert_eq!(errors.len(), 7);
+    let ctx = RenderContext {
+        errors: errors
+            .into_iter()
+            .map(|error| (error.id, error.message))
+            .collect(),
+        ..Default::default()
+    };
+    let document = Html::parse_document(&form.render(ROOT_PREFIX.to_string(), &ctx));
+
+    let controls = Selector::parse(r#"input[name^="i-"]:not([type="hidden"]), textarea"#).unwrap();
+    for control in document.select(&controls) {
+        let element = control.value();
+        let name = element.attr("name").unwrap();
+        let described_by: Vec<&str> = element
+            .attr("aria-describedby")
+            .unwrap_or_default()
+            .split(' ')
+            .collect();
+        assert_eq!(
+            described_by,
+            [format!("{name}-help"), format!("{name}-message")],
+            "{}",
+            control.html()
+        );
+        for id in described_by {
+            let target = Selector::parse(&format!("#{id}")).unwrap();
+            assert!(document.select(&target).next().is_some(), "no #{id}");
+        }
+        assert_eq!(element.attr("aria-invalid"), Some("true"));
+        let checkbox = element.attr("type") == Some("checkbox");
+        assert_eq!(element.attr("required").is_some(), !checkbox);
+        assert_eq!(element.attr("aria-required").is_some(), !checkbox);
+    }
+
+    let summary = Selector::parse(r#".error-summary[aria-live] a"#).unwrap();
+    let links: Vec<&str> = document
+        .select(&summary)
+        .map(|link| link.value().attr("href").unwrap())
+        .collect();
+    assert_eq!(links.len(), 7);
+    for link in links {
+        let target = Selector::parse(link).unwrap();
+        assert!(document.select(&target).next().is_some(), "no {link}");
+    }
+}
+
+#[test]
+fn audit_passes_rendered_forms() {
+    let form: Form = serde_yaml::from_str(FORM).unwrap();
+    assert_eq!(a11y::audit(&form.to_string()), []);
+    assert_eq!(a11y::audit(&demo_form().to_string()), []);
+}
+
+#[test]
+fn audit_reports_missing_labels_legends_and_language() {
+    let form: Form = serde_yaml::from_str(FORM).unwrap();
+    let broken = form
+        .to_string()
+        .replace(r#"<html lang="en""#, "<html")
+        .replace(" for=\"", " data-for=\"")
+        .replace("<legend", "<div")
+        .replace("</legend>", "</div>");
+    let findings = a11y::audit(&broken);
+    let problems = |problem: &str| {
+        findings
+            .iter()
+            .filter(|f| f.problem.contains(problem))
+            .count()
+    };
+    assert_eq!(problems("language"), 1);
+    assert!(problems("no label") > 0, "{findings:?}");
+    assert!(problems("<fieldset>") > 0, "{findings:?}");
+    assert!(findings
+        .iter()
+        .any(|finding| finding.element.contains(r#"name="i-1-0""#)));
+}
+
+#[test]
+fn help_describes_its_control_only_when_given() {
+    let mut question = Question {
+        title: "Name".into(),
+        key: None,
+        help: Some("As on your badge.".into()),
+        required: false,
+        correct: None,
+        points: None,
+        explanation: None,
+        show_if: None,
+        spec: QuestionType::Text {
+            is_long: false,
+            pattern: None,
+            max_length: None,
+            warn_at: None,
+        },
+    };
+    let html = question.render_fragment("name");
+    assert!(html.contains(r#"<small class="help" id="name-help">As on your badge.</small>"#));
+    assert!(
+        html.contains(r#"<input type="text" name="name" id="name" aria-describedby="name-help">"#)
+    );
+
+    question.help = None;
+    let html = question.render_fragment("name");
+    assert!(!html.contains("aria-describedby"));
+    assert!(!html.contains("name-help"));
+}
@@ -0,0 +1,228 @@
+//! The dashboard's table of responses, and deleting single responses from it.
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::{as_admin, fetch, post, store_path, with_admin};
+use std::path::PathBuf;
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::store::SubmissionStore;
+use umfragetool_rust::{admin, demo_form};
+
+fn state(name: &str) -> (Arc<AppState>, PathBuf) {
+    let name = format!("admin-{name}");
+    let state = with_admin(common::state(
+        &name,
+        [("demo", demo_form()), ("other", demo_form())],
+    ));
+    (Arc::new(state), store_path(&name))
+}
+
+async fn send(
+    state: &Arc<AppState>,
+    request: Request<Body>,
+) -> (StatusCode, Option<String>, String) {
+    let (status, headers, body) = fetch(state, request).await;
+    let location = headers
+        .get(header::LOCATION)
+        .map(|value| value.to_str().unwrap().to_string());
+    (status, location, body)
+}
+
+/// Submits `body` to `form_id` and returns the id it was stored under.
+async fn submit_body(state: &Arc<AppState>, form_id: &str, body: &str) -> String {
+    let (status, _) = common::submit(state, form_id, body).await;
+    assert_eq!(status, StatusCode::OK);
+    state.store.list(form_id).last().unwrap().id.clone()
+}
+
+async fn submit(state: &Arc<AppState>, form_id: &str) -> String {
+    submit_body(state, form_id, "i-0-0=Because").await
+}
+
+fn admin_get(uri: &str) -> Request<Body> {
+    as_admin(common::get(uri))
+}
+
+fn delete(form_id: &str, submission_id: &str, csrf: &str) -> Request<Body> {
+    as_admin(post(
+        &format!("/form/{form_id}/admin/response/{submission_id}/delete"),
+        &format!("_csrf={csrf}"),
+    ))
+}
+
+#[tokio::test]
+async fn deleting_a_response_redirects_to_the_table() {
+    let (state, path) = state("delete");
+    let id = submit(&state, "demo").await;
+    let kept = submit(&state, "demo").await;
+
+    let token = admin::delete_token(&state.signer, "demo", &id);
+    let (status, location, _) = send(&state, delete("demo", &id, &token)).await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    assert_eq!(
+        location.unwrap(),
+        format!("/form/demo/admin/responses?deleted={id}")
+    );
+
+    let export = admin_get("/export/demo.json");
+    let (_, _, json) = send(&state, export).await;
+    assert!(!json.contains(&id));
+    assert!(json.contains(&kept));
+
+    let reopened = SubmissionStore::open(&path).unwrap();
+    assert_eq!(reopened.list("demo").len(), 1);
+    let audit = std::fs::read_to_string(path.with_extension("audit.jsonl")).unwrap();
+    assert!(
+        audit.contains(&id) && audit.contains(r#""by":"admin""#),
+        "{audit}"
+    );
+}
+
+#[tokio::test]
+async fn unknown_or_foreign_responses_are_not_found() {
+    let (state, _) = state("foreign");
+    let id = submit(&state, "other").await;
+
+    let token = admin::delete_token(&state.signer, "demo", &id);
+    let (status, _, _) = send(&state, delete("demo", &id, &token)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let token = admin::delete_token(&state.signer, "other", "nope");
+    let (status, _, _) = send(&state, delete("other", "nope", &token)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(state.store.list("other").len(), 1);
+}
+
+#[tokio::test]
+async fn deleting_needs_admin_and_token() {
+    let (state, _) = state("csrf");
+    let id = submit(&state, "demo").await;
+
+    let foreign = admin::delete_token(&state.signer, "other", &id);
+    for csrf in ["", "forged", &foreign] {
+        let (status, _, _) = send(&state, delete("demo", &id, csrf)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{csrf}");
+    }
+    let mut anonymous = delete(
+        "demo",
+        &id,
+        &admin::delete_token(&state.signer, "demo", &id),
+    );
+    anonymous.headers_mut().remove(header::AUTHORIZATION);
+    let (status, _, _) = send(&state, anonymous).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(state.store.list("demo").len(), 1);
+}
+
+#[tokio::test]
+async fn table_previews_every_answer() {
+    let (state, _) = state("table");
+    let long = "word ".repeat(20);
+    let id = submit_body(
+        &state,
+        "demo",
+        &format!("i-0-0={long}&i-0-4=Pizza&i-0-4=MAOAM"),
+    )
+    .await;
+
+    let (status, _, html) = send(&state, admin_get("/form/demo/admin/responses")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains(&format!(
+        r#"<td><span title="{long}">{}…</span></td>"#,
+        long[..40].trim_end()
+    )));
+    assert!(html.contains("<td>Pizza, MAOAM</td>"));
+    assert!(html.contains("<td>—</td>"));
+    assert!(html.contains(&format!(r#"href="/api/form/demo/response/{id}""#)));
+    assert!(html.contains(&format!(
+        r#"action="/form/demo/admin/response/{id}/delete"><input type="hidden" name="_csrf" value="{}">"#,
+        admin::delete_token(&state.signer, "demo", &id)
+    )));
+    assert!(html.contains("position:sticky"));
+
+    let (status, _, _) = send(&state, common::get("/form/demo/admin/responses")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn table_pages_newest_first() {
+    let (state, _) = state("pages");
+    for answer in ["first", "second", "third"] {
+        submit_body(&state, "demo", &format!("i-0-0={answer}")).await;
+    }
+    let rows = |html: &str| -> Vec<String> {
+        ["first", "second", "third"]
+            .into_iter()
+            .filter(|answer| html.contains(&format!("<td>{answer}</td>")))
+            .map(str::to_string)
+            .collect()
+    };
+
+    let (_, _, html) = send(&state, admin_get("/form/demo/admin/responses?limit=2")).await;
+    assert_eq!(rows(&html), ["second", "third"]);
+    assert!(html.find("<td>third</td>") < html.find("<td>second</td>"));
+    assert!(html.contains("Responses 1–2 of 3"));
+    assert!(html.contains(
+        r#"href="/form/demo/admin/responses?offset=2&amp;limit=2&amp;order=desc" rel="next""#
+    ));
+
+    let (_, _, html) = send(
+        &state,
+        admin_get("/form/demo/admin/responses?offset=2&limit=2"),
+    )
+    .await;
+    assert_eq!(rows(&html), ["first"]);
+    assert!(html.contains(r#"rel="prev""#) && !html.contains(r#"rel="next""#));
+
+    let (_, _, html) = send(
+        &state,
+        admin_get("/form/demo/admin/responses?order=asc&limit=1"),
+    )
+    .await;
+    assert_eq!(rows(&html), ["first"]);
+}
+
+#[tokio::test]
+async fn table_confirms_deletions() {
+    let (state, _) = state("flash");
+    let id = submit(&state, "demo").await;
+    let token = admin::delete_token(&state.signer, "demo", &id);
+    let (_, location, _) = send(&state, delete("demo", &id, &token)).await;
+
+    let (status, _, html) = send(&state, admin_get(&location.unwrap())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains(&format!(
+        r#"<p class="flash" role="status">Deleted response {id}.</p>"#
+    )));
+    assert!(html.contains("Responses 0 of 0"));
+}
+
+#[tokio::test]
+async fn table_warns_when_responses_span_form_versions() {
+    let (state, _) = state("versions");
+    submit(&state, "demo").await;
+    let (_, _, html) = send(&state, admin_get("/form/demo/admin/responses")).await;
+    let version = demo_form().version();
+    assert!(
+        html.contains(&format!(
+            r#"<p class="versions">Form versions: <code>{version}</code> (1)</p><p>"#
+        )),
+        "{html}"
+    );
+
+    let mut edited = demo_form();
+    edited.groups[0].questions[0].title = "Why, really?".into();
+    state
+        .forms
+        .write()
+        .unwrap()
+        .insert("demo".to_string(), Arc::new(edited));
+    submit(&state, "demo").await;
+    let (_, _, html) = send(&state, admin_get("/form/demo/admin/responses")).await;
+    assert!(
+        html.contains(r#"<p class="warning" role="status">These responses answered 2 versions"#),
+        "{html}"
+    );
+}
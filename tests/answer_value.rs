@@ -0,0 +1,40 @@
+//! Choice options with a stored `value` distinct from the displayed `label`.
+
+use umfragetool_rust::*;
+
+#[test]
+fn value_round_trips() {
+    let answer: Answer = serde_yaml::from_str("{ label: Pizza, value: pz }").unwrap();
+    assert_eq!(answer.label, Localized::from("Pizza"));
+    assert_eq!(answer.value(), "pz");
+
+    let json = serde_json::to_value(&answer).unwrap();
+    assert_eq!(json, serde_json::json!({ "label": "Pizza", "value": "pz" }));
+    let again: Answer = serde_json::from_value(json).unwrap();
+    assert_eq!(again.value(), "pz");
+}
+
+#[test]
+fn value_defaults_to_label() {
+    let answer: Answer = serde_yaml::from_str("Pizza").unwrap();
+    assert_eq!(answer.value(), "Pizza");
+    assert_eq!(
+        serde_json::to_value(&answer).unwrap(),
+        serde_json::json!({ "label": "Pizza" })
+    );
+}
+
+#[test]
+fn value_is_submitted_and_validated() {
+    let spec = QuestionType::SingleChoice {
+        answers: vec![serde_yaml::from_str("{ label: Pizza, value: pz }").unwrap()],
+        custom_answer: false,
+        columns: None,
+    };
+    let html = spec.render("i-0-0".to_string(), &RenderContext::default());
+    assert!(html.contains(r#"value="pz""#), "{html}");
+    assert!(html.contains(">Pizza</label>"), "{html}");
+
+    assert!(spec.validate_answer(&["pz".to_string()]).is_ok());
+    assert!(spec.validate_answer(&["Pizza".to_string()]).is_err());
+}
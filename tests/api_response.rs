@@ -0,0 +1,87 @@
+//! `GET /api/form/:id/response/:id` labels a single submission's answers.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{as_admin, fresh_store, send, submission, with_admin};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::store::{FormVersion, QuestionMeta, Submission};
+
+async fn state(name: &str) -> Arc<AppState> {
+    let store = fresh_store(&format!("api-response-{name}"));
+    let meta = |id: &str, title: &str, kind: &str| QuestionMeta {
+        id: id.to_string(),
+        title: title.to_string(),
+        kind: kind.to_string(),
+    };
+    store
+        .record_version(FormVersion {
+            form_id: "demo".to_string(),
+            version: "old".to_string(),
+            questions: vec![
+                meta("i-0-0", "Why?", "text"),
+                meta("gone", "Removed since", "multiple_choice"),
+            ],
+        })
+        .await
+        .unwrap();
+    let answers: [(&str, &[&str]); 4] = [
+        ("i-0-0", &["Because"]),
+        ("i-0-1", &["3.5"]),
+        ("gone", &["a", "b"]),
+        ("stray", &["?"]),
+    ];
+    let submission = Submission {
+        form_version: Some("old".to_string()),
+        duration_secs: Some(42),
+        tracking: BTreeMap::from([("utm_source".to_string(), "mail".to_string())]),
+        ..submission("demo", "s1", &answers)
+    };
+    store.insert(submission, None).await.unwrap();
+    let forms = HashMap::from([("demo".to_string(), umfragetool_rust::demo_form())]);
+    Arc::new(with_admin(AppState::new(forms, store)))
+}
+
+async fn get(state: Arc<AppState>, uri: &str, admin: bool) -> (StatusCode, String) {
+    let request = common::get(uri);
+    let request = if admin { as_admin(request) } else { request };
+    send(&state, request).await
+}
+
+#[tokio::test]
+async fn response_answers_are_labelled() {
+    let (status, body) = get(state("labelled").await, "/api/form/demo/response/s1", true).await;
+    assert_eq!(status, StatusCode::OK);
+    let response: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(response["id"], "s1");
+    assert_eq!(response["form_version"], "old");
+    assert_eq!(response["duration_secs"], 42);
+    assert_eq!(response["tracking"], json!({ "utm_source": "mail" }));
+    assert_eq!(
+        response["answers"],
+        json!([
+            { "question_id": "i-0-0", "title": "Why?", "type": "text", "value": "Because", "in_current_form": true },
+            { "question_id": "gone", "title": "Removed since", "type": "multiple_choice", "value": ["a", "b"], "in_current_form": false },
+            { "question_id": "i-0-1", "title": "How much is the fish?", "type": "continous_numeric", "value": 3.5, "in_current_form": true },
+            { "question_id": "stray", "title": null, "type": null, "value": ["?"], "in_current_form": false },
+        ])
+    );
+}
+
+#[tokio::test]
+async fn unknown_responses_are_not_found() {
+    let (status, _) = get(state("unknown").await, "/api/form/demo/response/nope", true).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = get(state("other").await, "/api/form/other/response/s1", true).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = get(
+        state("anonymous").await,
+        "/api/form/demo/response/s1",
+        false,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,237 @@
+//! Backups hold every form, response and piece of link state, with checksums.
+
+mod common;
+
+use axum::http::{header, StatusCode};
+use chrono::{TimeZone, Utc};
+use common::{as_admin, fresh_store, get, store_path, submission, with_admin};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tower::ServiceExt;
+use umfragetool_rust::backup::{self, Manifest, MANIFEST};
+use umfragetool_rust::drafts::Draft;
+use umfragetool_rust::server::{self, AppState};
+use umfragetool_rust::store::{FormVersion, ImportMode, ImportReport, Submission, SubmissionStore};
+
+fn empty_store(name: &str) -> SubmissionStore {
+    fresh_store(&format!("backup-{name}"))
+}
+
+async fn state(name: &str) -> AppState {
+    let form = umfragetool_rust::demo_form();
+    let store = empty_store(name);
+    store
+        .record_version(FormVersion::of("demo", &form))
+        .await
+        .unwrap();
+    for id in ["a", "b"] {
+        let submission = Submission {
+            form_version: Some(form.version()),
+            ..submission("demo", id, &[("i-0-0", &[&format!("answer {id}")])])
+        };
+        store.insert(submission, None).await.unwrap();
+    }
+    store
+        .delete("demo", "b", "admin", Utc::now())
+        .await
+        .unwrap();
+    let state = AppState::new(HashMap::from([("demo".to_string(), form)]), store);
+    state
+        .drafts
+        .save(Draft {
+            form_id: "demo".to_string(),
+            answers: HashMap::new(),
+            expires_at: Utc::now(),
+            seed: None,
+        })
+        .await
+        .unwrap();
+    with_admin(state)
+}
+
+#[tokio::test]
+async fn backup_holds_everything_with_checksums() {
+    let state = state("contents").await;
+    let now = Utc.with_ymd_and_hms(2026, 10, 14, 12, 0, 0).unwrap();
+    let files = backup::read(&backup::create(&state, now).unwrap()).unwrap();
+
+    let paths: Vec<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+    assert_eq!(
+        paths,
+        [
+            MANIFEST,
+            "forms/demo.yml",
+            "responses/demo.jsonl",
+            "versions.jsonl",
+            "audit.jsonl",
+            "drafts.json",
+            "secret"
+        ]
+    );
+    let manifest: Manifest = serde_json::from_slice(&files[0].1).unwrap();
+    assert_eq!(manifest.crate_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(manifest.created_at, now);
+    assert_eq!(manifest.files.len(), files.len() - 1);
+    for (entry, (path, content)) in manifest.files.iter().zip(&files[1..]) {
+        assert_eq!(&entry.path, path);
+        assert_eq!(entry.size, content.len() as u64);
+        assert_eq!(entry.sha256, hex::encode(Sha256::digest(content)));
+    }
+
+    let file = |path: &str| {
+        let (_, content) = files.iter().find(|(name, _)| name == path).unwrap();
+        String::from_utf8(content.clone()).unwrap()
+    };
+    let responses = file("responses/demo.jsonl");
+    assert_eq!(responses.lines().count(), 1);
+    assert!(responses.contains("answer a"));
+    assert!(file("audit.jsonl").contains(r#""submission_id":"b""#));
+    assert_eq!(file("secret"), state.signer.secret_hex());
+    let form: umfragetool_rust::Form = serde_yaml::from_str(&file("forms/demo.yml")).unwrap();
+    assert_eq!(form.version(), umfragetool_rust::demo_form().version());
+}
+
+#[tokio::test]
+async fn backup_endpoint_needs_admin() {
+    let router = server::router(Arc::new(state("endpoint").await));
+    let anonymous = get("/admin/backup.tar.gz");
+    let response = router.clone().oneshot(anonymous).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let admin = as_admin(get("/admin/backup.tar.gz"));
+    let response = router.oneshot(admin).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/gzip");
+    let disposition = response.headers()[header::CONTENT_DISPOSITION]
+        .to_str()
+        .unwrap();
+    assert!(disposition.starts_with(r#"attachment; filename="backup-"#));
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let files = backup::read(&body).unwrap();
+    assert_eq!(files[0].0, MANIFEST);
+}
+
+#[tokio::test]
+async fn restore_round_trips() {
+    let state = state("original").await;
+    let archive = backup::create(&state, Utc::now()).unwrap();
+    let restored = backup::open(&archive).unwrap();
+    assert_eq!(restored.secret, state.signer.secret_hex());
+    let form: umfragetool_rust::Form = serde_yaml::from_str(&restored.forms["demo"]).unwrap();
+    assert_eq!(form.version(), umfragetool_rust::demo_form().version());
+
+    let wiped = AppState::new(HashMap::new(), empty_store("restored"));
+    let report = wiped
+        .store
+        .import_all(restored.store, ImportMode::Replace)
+        .await
+        .unwrap();
+    assert_eq!(report.imported, 1);
+    wiped.drafts.import(restored.drafts).await.unwrap();
+
+    assert_eq!(
+        wiped.store.export_all().unwrap(),
+        state.store.export_all().unwrap()
+    );
+    let reopened = store_path("backup-restored");
+    assert_eq!(
+        SubmissionStore::open(&reopened)
+            .unwrap()
+            .export_all()
+            .unwrap(),
+        state.store.export_all().unwrap()
+    );
+    let tokens = |state: &AppState| {
+        let mut tokens: Vec<String> = state.drafts.all().into_keys().collect();
+        tokens.sort();
+        tokens
+    };
+    assert_eq!(tokens(&wiped), tokens(&state));
+}
+
+#[tokio::test]
+async fn merge_skips_stored_responses_and_reports_conflicts() {
+    let state = state("merge-source").await;
+    let archive = backup::create(&state, Utc::now()).unwrap();
+    let target = empty_store("merge-target");
+
+    let first = target
+        .import_all(backup::open(&archive).unwrap().store, ImportMode::Merge)
+        .await
+        .unwrap();
+    assert_eq!(first.imported, 1);
+    let again = target
+        .import_all(backup::open(&archive).unwrap().store, ImportMode::Merge)
+        .await
+        .unwrap();
+    assert_eq!(
+        again,
+        ImportReport {
+            imported: 0,
+            unchanged: 1,
+            conflicts: Vec::new(),
+        }
+    );
+    assert_eq!(
+        target.export_all().unwrap(),
+        state.store.export_all().unwrap()
+    );
+
+    let mut changed = target.get("demo", "a").unwrap();
+    changed
+        .answers
+        .insert("i-0-0".to_string(), vec!["edited".to_string()]);
+    target.update(changed).await.unwrap();
+    let report = target
+        .import_all(backup::open(&archive).unwrap().store, ImportMode::Merge)
+        .await
+        .unwrap();
+    assert_eq!(report.conflicts, ["demo/a"]);
+    assert_eq!(
+        target.get("demo", "a").unwrap().answers["i-0-0"],
+        ["edited"]
+    );
+    assert_eq!(target.export_all().unwrap().audit.len(), 1);
+}
+
+#[tokio::test]
+async fn tampered_archive_is_refused() {
+    use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+    use std::io::{Read, Write};
+
+    let state = state("tampered").await;
+    let archive = backup::create(&state, Utc::now()).unwrap();
+    let mut tar = Vec::new();
+    GzDecoder::new(&archive[..]).read_to_end(&mut tar).unwrap();
+    let at = tar.windows(8).position(|w| w == b"answer a").unwrap();
+    tar[at + 7] = b'x';
+    let mut tampered = GzEncoder::new(Vec::new(), Compression::default());
+    tampered.write_all(&tar).unwrap();
+
+    let err = backup::open(&tampered.finish().unwrap()).err().unwrap();
+    assert!(
+        err.to_string()
+            .contains("responses/demo.jsonl: checksum mismatch"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn failed_import_leaves_the_store_as_it_was() {
+    let source = state("failed-source").await;
+    let archive = backup::create(&source, Utc::now()).unwrap();
+    let target = state("failed-target").await;
+    let before = target.store.export_all().unwrap();
+    // The temporary file cannot be written where a directory is in the way.
+    let tmp = std::env::temp_dir().join("umfragetool-backup-failed-target.jsonl.tmp");
+    let _ = std::fs::remove_dir(&tmp);
+    std::fs::create_dir(&tmp).unwrap();
+
+    for mode in [ImportMode::Replace, ImportMode::Merge] {
+        let import = backup::open(&archive).unwrap().store;
+        assert!(target.store.import_all(import, mode).await.is_err());
+        assert_eq!(target.store.export_all().unwrap(), before);
+    }
+    std::fs::remove_dir(&tmp).unwrap();
+}
@@ -0,0 +1,77 @@
+//! Forms can publish their response count, which is cached briefly.
+
+mod common;
+
+use axum::http::{header, StatusCode};
+use chrono::{DateTime, Duration, Utc};
+use common::{fetch, get, stop_clock, submission};
+use std::sync::{Arc, Mutex};
+use umfragetool_rust::badge;
+use umfragetool_rust::server::AppState;
+
+fn state(name: &str, public_count: bool) -> (Arc<AppState>, Arc<Mutex<DateTime<Utc>>>) {
+    let mut form = umfragetool_rust::demo_form();
+    form.public_count = public_count;
+    let mut state = common::state(&format!("badge-{name}"), [("demo", form)]);
+    let now = stop_clock(&mut state, Utc::now());
+    (Arc::new(state), now)
+}
+
+async fn add_submission(state: &AppState, id: &str) {
+    let submission = submission("demo", id, &[("i-0-0", &[id])]);
+    state.store.insert(submission, None).await.unwrap();
+}
+
+#[tokio::test]
+async fn counts_are_private_by_default() {
+    let (state, _) = state("private", false);
+    for uri in ["/form/demo/count", "/form/demo/badge.svg"] {
+        let (status, _, _) = fetch(&state, get(uri)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn count_is_cached_for_a_minute() {
+    let (state, now) = state("cached", true);
+    add_submission(&state, "a").await;
+
+    let (status, headers, body) = fetch(&state, get("/form/demo/count")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, r#"{"count":1}"#);
+    assert_eq!(headers[header::CACHE_CONTROL], "public, max-age=60");
+
+    add_submission(&state, "b").await;
+    let (_, _, body) = fetch(&state, get("/form/demo/count")).await;
+    assert_eq!(body, r#"{"count":1}"#);
+
+    *now.lock().unwrap() += Duration::seconds(61);
+    let (_, _, body) = fetch(&state, get("/form/demo/count")).await;
+    assert_eq!(body, r#"{"count":2}"#);
+}
+
+#[tokio::test]
+async fn badge_is_an_svg() {
+    let (state, _) = state("svg", true);
+    add_submission(&state, "a").await;
+    let (status, headers, svg) = fetch(&state, get("/form/demo/badge.svg")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::CONTENT_TYPE], "image/svg+xml");
+    assert_eq!(headers[header::CACHE_CONTROL], "public, max-age=60");
+    assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg""#));
+    assert!(svg.contains("<title>responses: 1</title>"));
+}
+
+#[test]
+fn badge_grows_with_the_count() {
+    let width = |count| {
+        let svg = badge::svg("responses", count);
+        let width = svg.split(r#"width=""#).nth(1).unwrap();
+        let width: usize = width[..width.find('"').unwrap()].parse().unwrap();
+        assert!(svg.contains(&format!(">{count}</text>")));
+        width
+    };
+    let (one, three, six) = (width(7), width(137), width(123_456));
+    assert!(one < three && three < six, "{one} {three} {six}");
+    assert_eq!(six - one, 5 * (three - one) / 2);
+}
@@ -0,0 +1,90 @@
+//! Form pages and the favicon carry an `ETag`, and a client that has the
+//! current version gets `304 Not Modified`.
+
+mod common;
+
+use axum::http::{header, HeaderMap, StatusCode};
+use std::sync::Arc;
+use tower::ServiceExt;
+use umfragetool_rust::server::{self, AppState};
+
+fn state(name: &str) -> Arc<AppState> {
+    Arc::new(common::state(
+        &format!("caching-{name}"),
+        [("demo", umfragetool_rust::demo_form())],
+    ))
+}
+
+async fn get(
+    state: &Arc<AppState>,
+    uri: &str,
+    if_none_match: Option<&str>,
+) -> (StatusCode, HeaderMap, usize) {
+    let mut request = common::get(uri);
+    if let Some(etag) = if_none_match {
+        let etag = etag.parse().unwrap();
+        request.headers_mut().insert(header::IF_NONE_MATCH, etag);
+    }
+    let response = server::router(state.clone())
+        .oneshot(request)
+        .await
+        .unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, headers, body.len())
+}
+
+#[tokio::test]
+async fn unchanged_form_is_not_sent_again() {
+    let state = state("form");
+    let (status, headers, _) = get(&state, "/form/demo", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::CACHE_CONTROL], "no-cache");
+    let etag = headers[header::ETAG].to_str().unwrap().to_string();
+    assert!(etag.starts_with("W/\""), "{etag}");
+
+    let (status, headers, body) = get(&state, "/form/demo", Some(&etag)).await;
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+    assert_eq!(headers[header::ETAG], etag.as_str());
+    assert_eq!(body, 0);
+
+    let (status, _, _) = get(&state, "/form/demo?embed=1", Some(&etag)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let mut form = umfragetool_rust::demo_form();
+    form.title = "Changed".into();
+    state
+        .forms
+        .write()
+        .unwrap()
+        .insert("demo".to_string(), Arc::new(form));
+    let (status, headers, _) = get(&state, "/form/demo", Some(&etag)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(headers[header::ETAG], etag.as_str());
+}
+
+#[tokio::test]
+async fn previews_are_always_rendered() {
+    let state = state("preview");
+    let token = server::preview_token(&state.signer, "demo");
+    let uri = format!("/form/demo?preview=1&token={token}");
+    let (status, headers, _) = get(&state, &uri, Some("*")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(headers.get(header::ETAG).is_none());
+}
+
+#[tokio::test]
+async fn favicon_is_cached() {
+    let state = state("favicon");
+    let (status, headers, _) = get(&state, "/favicon.ico", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(headers[header::CACHE_CONTROL]
+        .to_str()
+        .unwrap()
+        .contains("max-age"));
+    let etag = headers[header::ETAG].to_str().unwrap().to_string();
+    let (status, _, body) = get(&state, "/favicon.ico", Some(&etag)).await;
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+    assert_eq!(body, 0);
+}
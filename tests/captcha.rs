@@ -0,0 +1,198 @@
+//! Forms with a `captcha` show the provider's widget and only accept
+//! submissions whose token the provider confirms.
+
+mod common;
+
+use axum::async_trait;
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::{fetch, get, post};
+use std::io;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use umfragetool_rust::captcha::{Captcha, CaptchaVerifier, Provider};
+use umfragetool_rust::server::{self, AppState};
+use umfragetool_rust::{demo_form, Form, Limits};
+
+/// Accepts the token "solved" and remembers what it was asked.
+#[derive(Clone, Default)]
+struct MockProvider {
+    calls: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+#[async_trait]
+impl CaptchaVerifier for MockProvider {
+    async fn verify(&self, captcha: &Captcha, token: &str, _: IpAddr) -> io::Result<bool> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push((captcha.secret.clone(), token.to_string()));
+        Ok(token == "solved")
+    }
+}
+
+fn protected() -> Form {
+    Form {
+        captcha: Some(Captcha {
+            provider: Provider::Hcaptcha,
+            site_key: "site-key".to_string(),
+            secret: "top-secret".to_string(),
+        }),
+        ..demo_form()
+    }
+}
+
+fn state(name: &str, form: Form, provider: &MockProvider) -> Arc<AppState> {
+    let mut state = common::state(&format!("captcha-{name}"), [("demo", form)]);
+    state.captcha = Box::new(provider.clone());
+    Arc::new(state)
+}
+
+fn submit(body: &str, json: bool) -> Request<Body> {
+    let mut request = post("/submit/demo", body);
+    if json {
+        request
+            .headers_mut()
+            .insert(header::ACCEPT, "application/json".parse().unwrap());
+    }
+    request
+}
+
+#[tokio::test]
+async fn forms_without_captcha_are_unaffected() {
+    let provider = MockProvider::default();
+    let state = state("none", demo_form(), &provider);
+    let (_, headers, html) = fetch(&state, get("/form/demo")).await;
+    assert!(!html.contains("captcha"));
+    assert_eq!(
+        headers[header::CONTENT_SECURITY_POLICY],
+        server::default_csp(false)
+    );
+    let (status, _, _) = fetch(&state, submit("i-0-0=Because", false)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(provider.calls.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn widget_is_shown_and_allowed_to_load() {
+    let provider = MockProvider::default();
+    let state = state("widget", protected(), &provider);
+    let (_, headers, html) = fetch(&state, get("/form/demo")).await;
+    assert!(html.contains(r#"<div class="h-captcha" data-sitekey="site-key"></div>"#));
+    assert!(html.contains(r#"<script src="https://js.hcaptcha.com/1/api.js" async defer>"#));
+    assert!(html.contains("<noscript><p class=\"captcha-notice\">"));
+    assert!(!html.contains("top-secret"));
+    let policy = headers[header::CONTENT_SECURITY_POLICY].to_str().unwrap();
+    assert!(
+        policy.contains(
+            "script-src 'self' 'unsafe-inline' https://hcaptcha.com https://*.hcaptcha.com"
+        ),
+        "{policy}"
+    );
+    assert!(
+        policy.contains("frame-src 'self' https://hcaptcha.com"),
+        "{policy}"
+    );
+
+    let (_, _, definition) = fetch(&state, get("/api/form/demo")).await;
+    let definition: serde_json::Value = serde_json::from_str(&definition).unwrap();
+    assert_eq!(
+        definition["captcha"],
+        serde_json::json!({ "provider": "hcaptcha", "site_key": "site-key" })
+    );
+}
+
+#[tokio::test]
+async fn confirmed_token_is_accepted() {
+    let provider = MockProvider::default();
+    let state = state("solved", protected(), &provider);
+    let (status, _, html) = fetch(
+        &state,
+        submit("i-0-0=Because&h-captcha-response=solved", false),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains("Thank you"));
+    assert_eq!(state.store.list("demo").len(), 1);
+    assert_eq!(
+        *provider.calls.lock().unwrap(),
+        [("top-secret".to_string(), "solved".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn failed_check_serves_the_form_again() {
+    let provider = MockProvider::default();
+    let state = state("failed", protected(), &provider);
+    let (status, _, html) = fetch(
+        &state,
+        submit("i-0-0=Because&h-captcha-response=forged", false),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(html.contains(r##"<a href="#_captcha-error">Spam check</a>"##));
+    assert!(html.contains(r#"<div class="captcha error" id="_captcha-error">"#));
+    assert!(html.contains(">Because</textarea>"), "{html}");
+
+    let (status, _, body) = fetch(&state, submit("i-0-0=Because", true)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["fields"][0]["id"], "_captcha");
+    assert!(state.store.list("demo").is_empty());
+    // Without a token the provider is not asked at all.
+    assert_eq!(provider.calls.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn captcha_needs_a_single_submit() {
+    let form = Form {
+        per_group_submit: true,
+        ..protected()
+    };
+    let err = form.validate(&Limits::default()).unwrap_err();
+    assert!(err.to_string().contains("per_group_submit"), "{err}");
+    assert!(protected().validate(&Limits::default()).is_ok());
+}
+
+#[tokio::test]
+async fn edit_field_does_not_skip_the_check() {
+    let provider = MockProvider::default();
+    let closed = state("edit", protected(), &provider);
+    let (status, _, _) = fetch(&closed, submit("i-0-0=Because&_edit=bogus", false)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let state = state(
+        "edit-editable",
+        Form {
+            editable: true,
+            ..protected()
+        },
+        &provider,
+    );
+    let (status, _, _) = fetch(&state, submit("i-0-0=Because&_edit=bogus", false)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert!(state.store.list("demo").is_empty());
+
+    // A link handed out after submitting continues without a new token.
+    let (_, _, body) = fetch(
+        &state,
+        submit("i-0-0=Because&h-captcha-response=solved", true),
+    )
+    .await;
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let token = body["edit_url"]
+        .as_str()
+        .unwrap()
+        .rsplit('/')
+        .next()
+        .unwrap();
+    let (status, _, _) = fetch(
+        &state,
+        submit(&format!("i-0-0=Changed&_edit={token}"), false),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let stored = state.store.list("demo");
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].answers["i-0-0"], ["Changed"]);
+}
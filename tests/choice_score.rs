@@ -0,0 +1,80 @@
+//! Options with a `score` add up to a total stored with each submission and
+//! exported with it.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{answered, as_admin, get, send, submit, with_admin};
+use std::collections::HashMap;
+use std::sync::Arc;
+use umfragetool_rust::*;
+
+const FORM: &str = r#"
+title: Assessment
+description: ""
+groups:
+  - title: Habits
+    description: ""
+    questions:
+      - title: Which do you do?
+        key: habits
+        spec:
+          type: multiple_choice
+          custom_answer: false
+          answers:
+            - { label: Sport, score: 2 }
+            - { label: Reading, score: 1.5 }
+            - Nothing
+      - title: How often?
+        key: often
+        spec:
+          type: single_choice
+          custom_answer: false
+          answers:
+            - { label: Daily, score: 3 }
+            - { label: Never, score: 0 }
+"#;
+
+fn form() -> Form {
+    serde_yaml::from_str(FORM).unwrap()
+}
+
+#[test]
+fn selected_options_add_up() {
+    let form = form();
+    assert!(form.scores_choices());
+    assert_eq!(
+        form.score(&answered(&[("habits", &["Sport", "Reading"])])),
+        3.5
+    );
+    assert_eq!(
+        form.score(&answered(&[
+            ("habits", &["Sport", "Nothing"]),
+            ("often", &["Daily"])
+        ])),
+        5.0
+    );
+    assert_eq!(form.score(&HashMap::new()), 0.0);
+    assert!(!demo_form().scores_choices());
+}
+
+#[tokio::test]
+async fn stored_and_exported() {
+    let state = Arc::new(with_admin(common::state(
+        "choice-score",
+        [("assessment", form())],
+    )));
+
+    let (status, _) = submit(
+        &state,
+        "assessment",
+        "habits=Sport&habits=Reading&often=Never",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(state.store.list("assessment")[0].choice_score, Some(3.5));
+
+    let (_, body) = send(&state, as_admin(get("/export/assessment.json"))).await;
+    let export: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(export[0]["choice_score"], 3.5);
+}
@@ -0,0 +1,116 @@
+//! Shortly before `closes_at` the form says when it closes and how long that
+//! is from now.
+
+mod common;
+
+use axum::http::{header, HeaderValue, StatusCode};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use common::{get, send, stop_clock};
+use std::sync::{Arc, Mutex};
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::{demo_form, Form, Limits};
+
+fn closes_at() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 5, 1, 16, 0, 0).unwrap()
+}
+
+fn closing() -> Form {
+    Form {
+        closes_at: Some(closes_at()),
+        utc_offset: Some("+02:00".to_string()),
+        ..demo_form()
+    }
+}
+
+fn state(name: &str, form: Form) -> (Arc<AppState>, Arc<Mutex<DateTime<Utc>>>) {
+    let mut state = common::state(&format!("closing-{name}"), [("demo", form)]);
+    let now = stop_clock(&mut state, closes_at() - Duration::hours(3));
+    (Arc::new(state), now)
+}
+
+async fn page(state: &Arc<AppState>, uri: &str) -> (StatusCode, String) {
+    let mut request = get(uri);
+    let german = HeaderValue::from_static("de");
+    request
+        .headers_mut()
+        .insert(header::ACCEPT_LANGUAGE, german);
+    send(state, request).await
+}
+
+#[tokio::test]
+async fn notice_counts_down_to_the_close() {
+    let (state, now) = state("countdown", closing());
+    let (status, html) = page(&state, "/form/demo").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(
+        html.contains(r#"<p class="closes-soon" role="status">This survey closes on 2024-05-01 at 18:00 (in 3 hours).</p>"#),
+        "{html}"
+    );
+
+    *now.lock().unwrap() = closes_at() - Duration::minutes(90);
+    let (_, html) = page(&state, "/form/demo").await;
+    assert!(html.contains("(in 1 hour)"), "{html}");
+    *now.lock().unwrap() = closes_at() - Duration::minutes(42);
+    let (_, html) = page(&state, "/form/demo").await;
+    assert!(html.contains("(in 42 minutes)"), "{html}");
+    *now.lock().unwrap() = closes_at() - Duration::seconds(30);
+    let (_, html) = page(&state, "/form/demo").await;
+    assert!(html.contains("(in 1 minute)"), "{html}");
+
+    *now.lock().unwrap() = closes_at();
+    let (status, html) = page(&state, "/form/demo").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains("This survey is closed."), "{html}");
+    assert!(!html.contains("closes-soon"));
+}
+
+#[tokio::test]
+async fn notice_follows_the_language() {
+    let form = Form {
+        title: serde_yaml::from_str("{ de: Umfrage, en: Survey }").unwrap(),
+        default_language: Some("de".to_string()),
+        ..closing()
+    };
+    let (state, now) = state("german", form);
+    let (_, html) = page(&state, "/form/demo").await;
+    assert!(
+        html.contains("Diese Umfrage schließt am 01.05.2024 um 18:00 Uhr (in 3 Stunden)."),
+        "{html}"
+    );
+    *now.lock().unwrap() = closes_at() - Duration::minutes(61);
+    let (_, html) = page(&state, "/form/demo").await;
+    assert!(html.contains("(in 1 Stunde)"), "{html}");
+    let (_, html) = page(&state, "/form/demo?lang=en").await;
+    assert!(html.contains("(in 1 hour)"), "{html}");
+}
+
+#[tokio::test]
+async fn notice_only_within_the_window() {
+    let (state, now) = state("window", closing());
+    *now.lock().unwrap() = closes_at() - Duration::hours(49);
+    let (_, html) = page(&state, "/form/demo").await;
+    assert!(!html.contains("closes-soon"));
+
+    let (state, _) = self::state("unset", demo_form());
+    let (_, html) = page(&state, "/form/demo").await;
+    assert!(!html.contains("closes-soon"));
+
+    let form = Form {
+        closing_notice_hours: Some(0),
+        ..closing()
+    };
+    let (state, _) = self::state("off", form);
+    let (_, html) = page(&state, "/form/demo").await;
+    assert!(!html.contains("closes-soon"));
+}
+
+#[test]
+fn offset_must_parse() {
+    let form = Form {
+        utc_offset: Some("Europe/Berlin".to_string()),
+        ..closing()
+    };
+    let err = form.validate(&Limits::default()).unwrap_err();
+    assert!(err.to_string().contains("utc_offset"), "{err}");
+    assert!(closing().validate(&Limits::default()).is_ok());
+}
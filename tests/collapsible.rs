@@ -0,0 +1,106 @@
+//! `collapsible` question sets are native `<details>` blocks: the first one
+//! open, the others closed unless they have problems to fix.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::submit;
+use regex::Regex;
+use std::sync::Arc;
+use umfragetool_rust::*;
+
+const FORM: &str = r#"
+title: Long
+description: ""
+groups:
+  - title: Intro
+    description: ""
+    collapsible: true
+    questions:
+      - title: Name
+        spec: { type: text, is_long: false }
+  - title: Work
+    description: ""
+    collapsible: true
+    questions:
+      - title: Role
+        required: true
+        spec: { type: text, is_long: false }
+      - title: Team
+        spec: { type: text, is_long: false }
+  - title: Plain
+    description: ""
+    questions:
+      - title: Else
+        spec: { type: text, is_long: false }
+"#;
+
+fn form() -> Form {
+    serde_yaml::from_str(FORM).unwrap()
+}
+
+/// For every `<details>` block, whether it is open and its summary.
+fn blocks(html: &str) -> Vec<(bool, String)> {
+    Regex::new(r"<details( open)?><summary>(.*?)</summary>")
+        .unwrap()
+        .captures_iter(html)
+        .map(|captures| (captures.get(1).is_some(), captures[2].to_string()))
+        .collect()
+}
+
+#[test]
+fn first_set_open_the_others_closed() {
+    let html = form().render(ROOT_PREFIX.to_string(), &RenderContext::default());
+    assert_eq!(
+        blocks(&html),
+        [
+            (
+                true,
+                r#"<h2 id="i-0-title">Intro</h2> <span class="question-count">(1 question)</span>"#
+                    .to_string()
+            ),
+            (
+                false,
+                r#"<h2 id="i-1-title">Work</h2> <span class="question-count">(2 questions)</span>"#
+                    .to_string()
+            ),
+        ]
+    );
+    // Sets without the flag stay as they were, and no script is needed.
+    assert!(html.contains(r#"<section aria-labelledby="i-2-title"><h2 id="i-2-title">Plain</h2>"#));
+    let no_js = RenderContext {
+        no_js: true,
+        ..Default::default()
+    };
+    let plain = form().render(ROOT_PREFIX.to_string(), &no_js);
+    assert!(!plain.contains("<script"));
+    assert_eq!(blocks(&plain), blocks(&html));
+
+    let html = form().render(
+        ROOT_PREFIX.to_string(),
+        &RenderContext {
+            lang: "de".to_string(),
+            ..Default::default()
+        },
+    );
+    assert!(html.contains("(2 Fragen)"), "{html}");
+}
+
+#[test]
+fn read_only_views_show_every_set() {
+    let ctx = RenderContext {
+        disabled: true,
+        ..Default::default()
+    };
+    let html = form().render(ROOT_PREFIX.to_string(), &ctx);
+    assert!(blocks(&html).iter().all(|(open, _)| *open), "{html}");
+}
+
+#[tokio::test]
+async fn sets_with_problems_are_open_after_submitting() {
+    let state = Arc::new(common::state("collapsible", [("long", form())]));
+    let (status, html) = submit(&state, "long", "i-0-0=Kim").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let open: Vec<bool> = blocks(&html).into_iter().map(|(open, _)| open).collect();
+    assert_eq!(open, [true, true], "{html}");
+}
@@ -0,0 +1,38 @@
+//! `columns` lays out the options of choices and scales side by side.
+
+mod common;
+
+use common::{answers, render_question};
+use std::collections::BTreeMap;
+use umfragetool_rust::*;
+
+#[test]
+fn columns_wrap_the_choices() {
+    let single = |columns| QuestionType::SingleChoice {
+        answers: answers(),
+        custom_answer: false,
+        columns,
+    };
+    let html = render_question(single(Some(3)));
+    assert!(
+        html.contains(r#"<div class="columns" style="column-count:3"><input type="radio""#),
+        "{html}"
+    );
+    assert!(html.contains("</label></div>"));
+    for html in [
+        render_question(single(None)),
+        render_question(single(Some(1))),
+    ] {
+        assert!(!html.contains("column-count"), "{html}");
+    }
+
+    let scale = render_question(QuestionType::DiscreteNumeric {
+        bounds: (1, 10),
+        num_descriptions: BTreeMap::new(),
+        na_option: None,
+        columns: Some(2),
+    });
+    assert!(scale.contains(r#"style="column-count:2""#), "{scale}");
+    // Only the layout changes: the same values are posted.
+    assert_eq!(scale.matches(r#"type="radio""#).count(), 10);
+}
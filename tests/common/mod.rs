@@ -0,0 +1,210 @@
+//! Fixtures shared by the integration tests: states to serve forms from,
+//! requests sent through the router, and questions and submissions to start
+//! from. Each test file compiles its own copy and uses only some of it.
+#![allow(dead_code)]
+
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{header, HeaderMap, Request, StatusCode};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tower::ServiceExt;
+use umfragetool_rust::server::{self, AppState, Clock};
+use umfragetool_rust::store::{Source, Submission, SubmissionStore};
+use umfragetool_rust::{
+    Answer, Form, Question, QuestionType, RenderContext, Renderable, ROOT_PREFIX,
+};
+
+/// The admin token of states made with [`with_admin`].
+pub const TOKEN: &str = "secret";
+
+/// A store file in the temporary directory, named after `name`, which has
+/// to be unique across all tests.
+pub fn store_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("umfragetool-{name}.jsonl"))
+}
+
+/// An empty store at [`store_path`], with the files of earlier runs removed.
+pub fn fresh_store(name: &str) -> SubmissionStore {
+    let path = store_path(name);
+    for path in [
+        path.with_extension("versions.jsonl"),
+        path.with_extension("audit.jsonl"),
+        path.clone(),
+    ] {
+        let _ = std::fs::remove_file(path);
+    }
+    SubmissionStore::open(&path).unwrap()
+}
+
+/// A state serving `forms` by id, with a [`fresh_store`].
+pub fn state<'a>(name: &str, forms: impl IntoIterator<Item = (&'a str, Form)>) -> AppState {
+    AppState::new(
+        forms
+            .into_iter()
+            .map(|(id, form)| (id.to_string(), form))
+            .collect(),
+        fresh_store(name),
+    )
+}
+
+/// `state` with [`TOKEN`] as its admin token.
+pub fn with_admin(mut state: AppState) -> AppState {
+    state.admin_token = Some(TOKEN.to_string());
+    state
+}
+
+/// A clock that stands still until a test moves it.
+pub struct TestClock(pub Arc<Mutex<DateTime<Utc>>>);
+
+impl Clock for TestClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.0.lock().unwrap()
+    }
+}
+
+/// Stops the clock of `state` at `now`; the handle returned moves it.
+pub fn stop_clock(state: &mut AppState, now: DateTime<Utc>) -> Arc<Mutex<DateTime<Utc>>> {
+    let now = Arc::new(Mutex::new(now));
+    state.clock = Box::new(TestClock(now.clone()));
+    now
+}
+
+/// `request` as it comes from a client at 127.0.0.1, like axum's
+/// `into_make_service_with_connect_info` would hand it on.
+pub fn from_client(mut request: Request<Body>) -> Request<Body> {
+    if request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .is_none()
+    {
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 1234))));
+    }
+    request
+}
+
+/// The status, headers and body `state` answers `request` with; bodies that
+/// are not text, like icons, come back with replacement characters.
+pub async fn fetch(
+    state: &Arc<AppState>,
+    request: Request<Body>,
+) -> (StatusCode, HeaderMap, String) {
+    let response = server::router(state.clone())
+        .oneshot(from_client(request))
+        .await
+        .unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, headers, String::from_utf8_lossy(&body).into_owned())
+}
+
+/// The status and body `state` answers `request` with.
+pub async fn send(state: &Arc<AppState>, request: Request<Body>) -> (StatusCode, String) {
+    let (status, _, body) = fetch(state, request).await;
+    (status, body)
+}
+
+pub fn get(uri: &str) -> Request<Body> {
+    Request::get(uri).body(Body::empty()).unwrap()
+}
+
+/// `body` posted to `uri` as a browser posts a form.
+pub fn post(uri: &str, body: &str) -> Request<Body> {
+    Request::post(uri)
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// `request` from a script that wants the JSON answer.
+pub fn accept_json(mut request: Request<Body>) -> Request<Body> {
+    request
+        .headers_mut()
+        .insert(header::ACCEPT, "application/json".parse().unwrap());
+    request
+}
+
+/// `request` with the admin [`TOKEN`].
+pub fn as_admin(request: Request<Body>) -> Request<Body> {
+    with_token(request, Some(TOKEN))
+}
+
+/// `request` with `token` as its bearer token, if there is one.
+pub fn with_token(mut request: Request<Body>, token: Option<&str>) -> Request<Body> {
+    if let Some(token) = token {
+        let value = format!("Bearer {token}").parse().unwrap();
+        request.headers_mut().insert(header::AUTHORIZATION, value);
+    }
+    request
+}
+
+/// Answers `body` to the form `form_id`, as posted from its page.
+pub async fn submit(state: &Arc<AppState>, form_id: &str, body: &str) -> (StatusCode, String) {
+    send(state, post(&format!("/submit/{form_id}"), body)).await
+}
+
+/// Answers by question id, from `(id, values)` pairs.
+pub fn answered(answers: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+    answers
+        .iter()
+        .map(|(id, values)| {
+            (
+                id.to_string(),
+                values.iter().map(|value| value.to_string()).collect(),
+            )
+        })
+        .collect()
+}
+
+/// A web submission `id` to `form_id` with `answers` and nothing else set,
+/// to store directly or to set more on with `..submission(…)`.
+pub fn submission(form_id: &str, id: &str, answers: &[(&str, &[&str])]) -> Submission {
+    Submission {
+        id: id.to_string(),
+        form_id: form_id.to_string(),
+        answers: answered(answers),
+        form_version: None,
+        submitted_at: None,
+        updated_at: None,
+        client: None,
+        duration_secs: None,
+        tracking: BTreeMap::new(),
+        source: Source::Web,
+        score: None,
+        computed: BTreeMap::new(),
+        choice_score: None,
+        shuffle_seed: None,
+    }
+}
+
+/// A question titled "Question" of type `spec`, with nothing else set.
+pub fn question(spec: QuestionType) -> Question {
+    Question {
+        title: "Question".into(),
+        key: None,
+        help: None,
+        required: false,
+        correct: None,
+        points: None,
+        explanation: None,
+        show_if: None,
+        spec,
+    }
+}
+
+/// Renders a single question of type `spec` under a fixed id (`i-0-0`), so
+/// every variant can be looked at on its own.
+pub fn render_question(spec: QuestionType) -> String {
+    question(spec).render(format!("{ROOT_PREFIX}-0-0"), &RenderContext::default())
+}
+
+/// The options of the choice questions: Pizza and Ravioli.
+pub fn answers() -> Vec<Answer> {
+    ["Pizza", "Ravioli"].into_iter().map(Answer::from).collect()
+}
@@ -0,0 +1,26 @@
+//! Computed questions show a value worked out from other answers, in the
+//! browser and without `eval`.
+
+mod common;
+
+use common::render_question;
+use umfragetool_rust::*;
+
+#[test]
+fn computed_output_is_filled_without_eval() {
+    let html = render_question(QuestionType::Computed {
+        expression: "({i-0-1} + {i-0-2}) / 2".to_string(),
+    });
+    assert!(
+        html.contains(r#"<output id="i-0-0" for="i-0-1 i-0-2"></output>"#),
+        "{html}"
+    );
+    assert!(
+        html.contains(r#"var p=[["f","i-0-1"],["f","i-0-2"],["o","+"],["n",2],["o","/"]]"#),
+        "{html}"
+    );
+    assert!(
+        !html.contains("eval") && !html.contains("Function("),
+        "{html}"
+    );
+}
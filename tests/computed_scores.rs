@@ -0,0 +1,151 @@
+//! Scores computed from weighted numeric answers: stored with the
+//! submission, exported, and shown after submitting if the form says so.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{as_admin, get, send, submit, with_admin};
+use std::collections::HashMap;
+use std::sync::Arc;
+use umfragetool_rust::*;
+
+const FORM: &str = r#"
+title: Burnout
+description: ""
+groups:
+  - title: Last month
+    description: ""
+    questions:
+      - title: I feel drained
+        key: drained
+        spec: { type: discrete_numeric, bounds: [1, 5], num_descriptions: {}, na_option: n/a }
+      - title: I look forward to work
+        key: forward
+        spec: { type: discrete_numeric, bounds: [1, 5], num_descriptions: {} }
+      - title: Overtime hours
+        key: overtime
+        spec: { type: continous_numeric }
+computed:
+  - key: burnout
+    title: Burnout score
+    items:
+      - question: drained
+        weight: 2
+      - question: forward
+        reverse: true
+    show: true
+    thresholds:
+      - { from: 0, label: low }
+      - { from: 8, label: moderate }
+      - { from: 12, label: high }
+  - key: mean
+    title: Mean
+    aggregate: mean
+    items:
+      - question: drained
+      - question: overtime
+"#;
+
+fn form() -> Form {
+    let form: Form = serde_yaml::from_str(FORM).unwrap();
+    form.validate(&Limits::default()).unwrap();
+    form
+}
+
+fn answers(pairs: &[(&str, &str)]) -> HashMap<String, Vec<String>> {
+    pairs
+        .iter()
+        .map(|(id, value)| (id.to_string(), vec![value.to_string()]))
+        .collect()
+}
+
+#[test]
+fn weighted_and_reversed_items() {
+    let form = form();
+    let values = scores::compute(
+        &form,
+        &answers(&[("drained", "4"), ("forward", "2"), ("overtime", "10")]),
+    );
+    // 2 * 4 + (6 - 2), and (4 + 10) / 2.
+    assert_eq!(values["burnout"], 12.0);
+    assert_eq!(values["mean"], 7.0);
+    assert_eq!(
+        form.computed[0]
+            .label(values["burnout"])
+            .map(|l| form.text(l)),
+        Some("high")
+    );
+    assert_eq!(
+        form.computed[0].label(9.5).map(|l| form.text(l)),
+        Some("moderate")
+    );
+
+    // Unanswered and "not applicable" items count for nothing.
+    let values = scores::compute(&form, &answers(&[("drained", "na"), ("forward", "5")]));
+    assert_eq!(values["burnout"], 1.0);
+    assert!(!values.contains_key("mean"));
+}
+
+#[test]
+fn definitions_are_checked_against_the_questions() {
+    let broken = FORM
+        .replace("question: forward\n", "question: nope\n")
+        .replace(
+            "      - question: overtime\n",
+            "      - question: overtime\n        reverse: true\n",
+        )
+        .replace("{ from: 12,", "{ from: 4,");
+    let broken: Form = serde_yaml::from_str(&broken).unwrap();
+    let problems = broken.validate(&Limits::default()).unwrap_err().problems;
+    assert_eq!(problems.len(), 3, "{problems:?}");
+    assert!(problems[0]
+        .contains("computed score burnout counts nope, which is no question of the form"));
+    assert!(problems[1].contains("computed score burnout has thresholds out of order"));
+    assert!(problems[2]
+        .contains("computed score mean reverses overtime (Overtime hours), which has no bounds"));
+
+    let mut form = form();
+    form.computed[1].items[0].question = "i-0-0".to_string();
+    form.groups[0].questions[0].key = None;
+    form.groups[0].questions[0].spec = QuestionType::Text {
+        is_long: false,
+        pattern: None,
+        max_length: None,
+        warn_at: None,
+    };
+    let problems = form.validate(&Limits::default()).unwrap_err().problems;
+    assert!(
+        problems
+            .iter()
+            .any(|p| p.contains("counts i-0-0 (I feel drained), a text question")),
+        "{problems:?}"
+    );
+}
+
+#[tokio::test]
+async fn stored_exported_and_shown() {
+    let state = Arc::new(with_admin(common::state(
+        "computed-scores",
+        [("burnout", form())],
+    )));
+
+    let (status, html) = submit(&state, "burnout", "drained=3&forward=4&overtime=5").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(
+        html.contains(r#"<ul class="computed-scores"><li><span class="title">Burnout score</span>: <span class="value">8</span> <span class="label">moderate</span></li></ul>"#),
+        "{html}"
+    );
+    // Only scores marked `show` are.
+    assert!(!html.contains("Mean"));
+
+    let stored = &state.store.list("burnout")[0];
+    assert_eq!(stored.computed["burnout"], 8.0);
+    assert_eq!(stored.computed["mean"], 4.0);
+
+    let (_, body) = send(&state, as_admin(get("/export/burnout.json"))).await;
+    let export: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        export[0]["computed"],
+        serde_json::json!({ "burnout": 8.0, "mean": 4.0 })
+    );
+}
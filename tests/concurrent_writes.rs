@@ -0,0 +1,44 @@
+//! Submissions stored from many tasks at once each end up on a line of their
+//! own, and the file loads back with all of them.
+
+mod common;
+
+use common::store_path;
+use std::sync::Arc;
+use umfragetool_rust::store::{Submission, SubmissionStore};
+
+fn submission(index: usize) -> Submission {
+    // Long enough for a write to be split if it were not serialized.
+    let answer = index.to_string().repeat(4096);
+    common::submission("demo", &index.to_string(), &[("i-0-0", &[&answer])])
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn every_line_is_a_whole_submission() {
+    let path = store_path("concurrent-writes");
+    let store = Arc::new(common::fresh_store("concurrent-writes"));
+    let tasks: Vec<_> = (0..64)
+        .map(|index| {
+            let store = store.clone();
+            tokio::spawn(async move { store.insert(submission(index), None).await.unwrap() })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    let mut ids: Vec<usize> = content
+        .lines()
+        .map(|line| {
+            let stored: Submission = serde_json::from_str(line).unwrap();
+            assert_eq!(stored.answers["i-0-0"][0], stored.id.repeat(4096));
+            stored.id.parse().unwrap()
+        })
+        .collect();
+    ids.sort();
+    assert_eq!(ids, (0..64).collect::<Vec<_>>());
+
+    let reopened = SubmissionStore::open(&path).unwrap();
+    assert_eq!(reopened.list("demo").len(), 64);
+}
@@ -0,0 +1,114 @@
+//! Forms with `confirm_submit` ask before storing a submission; a browser
+//! without the dialog gets a page asking instead.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{accept_json, post, send};
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::{demo_form, Form, Limits, RenderContext, Renderable, ROOT_PREFIX};
+
+fn confirming() -> Form {
+    Form {
+        confirm_submit: true,
+        ..demo_form()
+    }
+}
+
+fn state(name: &str) -> Arc<AppState> {
+    let mut state = common::state(&format!("confirm-{name}"), [("demo", confirming())]);
+    state.no_js = true;
+    Arc::new(state)
+}
+
+async fn submit(state: &Arc<AppState>, body: &str, json: bool) -> (StatusCode, String) {
+    let request = post("/submit/demo", body);
+    send(state, if json { accept_json(request) } else { request }).await
+}
+
+#[tokio::test]
+async fn unconfirmed_submissions_get_a_confirm_page() {
+    let state = state("page");
+    let (status, html) = submit(
+        &state,
+        "i-0-0=Because+%3Cb%3E&i-0-4=Pizza&i-0-4=MAOAM",
+        false,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(
+        html.contains("Are you sure you want to submit your answers?"),
+        "{html}"
+    );
+    assert!(html.contains(r#"<input type="hidden" name="i-0-0" value="Because &lt;b&gt;">"#));
+    assert!(html.contains(r#"<input type="hidden" name="i-0-4" value="Pizza"><input type="hidden" name="i-0-4" value="MAOAM">"#));
+    assert!(html.contains(r#"<input type="hidden" name="_confirmed" value="1">"#));
+    assert!(state.store.list("demo").is_empty());
+
+    let (status, html) = submit(&state, "i-0-0=Because&_confirmed=1", false).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains("Thank you"), "{html}");
+    assert_eq!(state.store.list("demo").len(), 1);
+}
+
+#[tokio::test]
+async fn back_shows_the_form_with_the_answers() {
+    let state = state("back");
+    let (status, html) = submit(&state, "i-0-0=Because&_confirmed=1&_back=1", false).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains(">Because</textarea>"), "{html}");
+    assert!(!html.contains("error"));
+    assert!(state.store.list("demo").is_empty());
+}
+
+#[tokio::test]
+async fn invalid_and_json_submissions_are_not_asked() {
+    let state = state("skip");
+    let (status, html) = submit(&state, "i-0-1=abc", false).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(!html.contains("Are you sure"));
+
+    let (status, _) = submit(&state, "i-0-0=Because", true).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(state.store.list("demo").len(), 1);
+}
+
+#[test]
+fn confirm_needs_a_single_submit() {
+    let form = Form {
+        per_group_submit: true,
+        ..confirming()
+    };
+    let err = form.validate(&Limits::default()).unwrap_err();
+    assert!(err.to_string().contains("confirm_submit"), "{err}");
+    assert!(confirming().validate(&Limits::default()).is_ok());
+}
+
+#[test]
+fn confirm_dialog_only_when_enabled() {
+    let render = |confirm_submit, no_js| {
+        let form = Form {
+            confirm_submit,
+            drafts: true,
+            ..demo_form()
+        };
+        let ctx = RenderContext {
+            form_id: "demo".to_string(),
+            no_js,
+            ..Default::default()
+        };
+        form.render(ROOT_PREFIX.to_string(), &ctx)
+    };
+    let html = render(true, false);
+    assert!(
+        html.contains(r#"<form method="post" action="/submit/demo" onsubmit="if(event.submitter&&event.submitter.hasAttribute('formaction'))return;if(!confirm('Are you sure you want to submit your answers?'))return false;this.elements._confirmed.value='1'">"#),
+        "{html}"
+    );
+    assert!(html.contains(r#"<input type="hidden" name="_confirmed" value="">"#));
+
+    for html in [render(false, false), render(true, true)] {
+        assert!(!html.contains("onsubmit"), "{html}");
+        assert!(!html.contains("_confirmed"));
+    }
+}
@@ -0,0 +1,197 @@
+//! `--cors-origin` lets pages on the listed origins submit and use the JSON
+//! API from the browser, `--cors-any-origin` opens the API to every page;
+//! everyone else stays same-origin only.
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode};
+use common::{accept_json, fetch, post};
+use std::sync::Arc;
+use umfragetool_rust::demo_form;
+use umfragetool_rust::server::{parse_origin, AppState};
+
+fn state(name: &str, origins: &[&'static str]) -> Arc<AppState> {
+    let mut state = common::state(&format!("cors-{name}"), [("demo", demo_form())]);
+    state.cors_origins = origins
+        .iter()
+        .map(|origin| HeaderValue::from_static(origin))
+        .collect();
+    Arc::new(state)
+}
+
+async fn preflight(state: &Arc<AppState>, origin: &str) -> (StatusCode, HeaderMap) {
+    preflight_for(state, "/submit/demo", "POST", origin).await
+}
+
+async fn preflight_for(
+    state: &Arc<AppState>,
+    uri: &str,
+    method: &str,
+    origin: &str,
+) -> (StatusCode, HeaderMap) {
+    let request = Request::builder()
+        .method(Method::OPTIONS)
+        .uri(uri)
+        .header(header::ORIGIN, origin)
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, method)
+        .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+        .body(Body::empty())
+        .unwrap();
+    let (status, headers, _) = fetch(state, request).await;
+    (status, headers)
+}
+
+#[tokio::test]
+async fn preflight_allows_configured_origins() {
+    let state = state(
+        "allowed",
+        &["https://app.example.org", "https://other.example"],
+    );
+    let (status, headers) = preflight(&state, "https://app.example.org").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        "https://app.example.org"
+    );
+    let methods = headers[header::ACCESS_CONTROL_ALLOW_METHODS]
+        .to_str()
+        .unwrap();
+    assert!(methods.contains("POST"), "{methods}");
+
+    let (_, headers) = preflight(&state, "https://evil.example").await;
+    assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+}
+
+#[tokio::test]
+async fn submissions_from_configured_origins_can_be_read() {
+    let state = state("submit", &["https://app.example.org"]);
+    let mut request = accept_json(post("/submit/demo", "i-0-0=Because"));
+    request.headers_mut().insert(
+        header::ORIGIN,
+        HeaderValue::from_static("https://app.example.org"),
+    );
+    let (status, headers, _) = fetch(&state, request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        "https://app.example.org"
+    );
+}
+
+#[tokio::test]
+async fn same_origin_only_by_default() {
+    let state = state("default", &[]);
+    let (_, headers) = preflight(&state, "https://app.example.org").await;
+    assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+}
+
+#[tokio::test]
+async fn form_definitions_can_be_read_from_configured_origins() {
+    let state = state("api", &["https://app.example.org"]);
+    let (status, headers) =
+        preflight_for(&state, "/api/form/demo", "GET", "https://app.example.org").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        "https://app.example.org"
+    );
+
+    let request = Request::get("/api/form/demo")
+        .header(header::ORIGIN, "https://app.example.org")
+        .body(Body::empty())
+        .unwrap();
+    let (status, headers, _) = fetch(&state, request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        "https://app.example.org"
+    );
+}
+
+#[test]
+fn origins_are_scheme_host_and_port_only() {
+    for origin in [
+        "https://app.example.org",
+        "http://localhost:8080",
+        "http://127.0.0.1:3000",
+        "http://[::1]:3000",
+    ] {
+        assert!(parse_origin(origin).is_ok(), "{origin}");
+    }
+    for origin in [
+        "https://app.example.org/",
+        "https://app.example.org/survey",
+        "app.example.org",
+        "ftp://app.example.org",
+        "https://app.example.org:port",
+        "*",
+    ] {
+        let err = parse_origin(origin).unwrap_err();
+        assert!(err.contains("expected scheme://host[:port]"), "{err}");
+    }
+}
+
+#[tokio::test]
+async fn api_allows_its_methods_and_exposes_the_etag() {
+    let state = state("api-methods", &["https://app.example.org"]);
+    let request = Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/api/form/demo")
+        .header(header::ORIGIN, "https://app.example.org")
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+        .header(
+            header::ACCESS_CONTROL_REQUEST_HEADERS,
+            "authorization,content-type",
+        )
+        .body(Body::empty())
+        .unwrap();
+    let (_, headers, _) = fetch(&state, request).await;
+    let methods = headers[header::ACCESS_CONTROL_ALLOW_METHODS]
+        .to_str()
+        .unwrap();
+    assert!(
+        methods.contains("PUT") && methods.contains("DELETE"),
+        "{methods}"
+    );
+    let allowed = headers[header::ACCESS_CONTROL_ALLOW_HEADERS]
+        .to_str()
+        .unwrap();
+    assert!(allowed.contains("authorization"), "{allowed}");
+
+    let request = Request::get("/api/form/demo")
+        .header(header::ORIGIN, "https://app.example.org")
+        .body(Body::empty())
+        .unwrap();
+    let (_, headers, _) = fetch(&state, request).await;
+    assert_eq!(headers[header::ACCESS_CONTROL_EXPOSE_HEADERS], "etag");
+    let etag = headers[header::ETAG].clone();
+    assert_eq!(etag, format!("\"{}\"", demo_form().version()).as_str());
+
+    let request = Request::get("/api/form/demo")
+        .header(header::IF_NONE_MATCH, etag)
+        .body(Body::empty())
+        .unwrap();
+    let (status, _, _) = fetch(&state, request).await;
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+}
+
+#[tokio::test]
+async fn any_origin_opens_only_the_api() {
+    let mut state = common::state("cors-any", [("demo", demo_form())]);
+    state.cors_any_origin = true;
+    let state = Arc::new(state);
+    let (_, headers) =
+        preflight_for(&state, "/api/form/demo", "GET", "https://anyone.example").await;
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+    let (_, headers) = preflight(&state, "https://anyone.example").await;
+    assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+
+    // Form pages are no API.
+    let request = Request::get("/form/demo")
+        .header(header::ORIGIN, "https://anyone.example")
+        .body(Body::empty())
+        .unwrap();
+    let (_, headers, _) = fetch(&state, request).await;
+    assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+}
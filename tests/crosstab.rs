@@ -0,0 +1,79 @@
+//! `stats/crosstab` counts the submissions for each combination of the
+//! answers to two questions.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{accept_json, as_admin, get, send, submit, with_admin};
+use std::sync::Arc;
+use umfragetool_rust::demo_form;
+use umfragetool_rust::server::AppState;
+
+async fn state(name: &str) -> Arc<AppState> {
+    let state = Arc::new(with_admin(common::state(
+        &format!("crosstab-{name}"),
+        [("demo", demo_form())],
+    )));
+    for body in [
+        "i-0-2=1&i-0-3=Pizza",
+        "i-0-2=4&i-0-3=Pizza",
+        "i-0-2=10&i-0-3=MAOAM",
+        "i-0-2=2&i-0-3=Lasagne",
+        "i-0-2=5&i-0-3=Risotto",
+        "",
+    ] {
+        let (status, _) = submit(&state, "demo", &format!("i-0-0=Because&{body}")).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    state
+}
+
+async fn crosstab(state: &Arc<AppState>, query: &str) -> (StatusCode, serde_json::Value) {
+    let request = accept_json(as_admin(get(&format!("/form/demo/stats/crosstab?{query}"))));
+    let (status, body) = send(state, request).await;
+    (
+        status,
+        serde_json::from_str(&body).unwrap_or(serde_json::Value::Null),
+    )
+}
+
+#[tokio::test]
+async fn numbers_are_binned_against_choices() {
+    let state = state("bins").await;
+    let (status, table) = crosstab(&state, "rows=i-0-3&cols=i-0-2&bins=3").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        table["rows"]["labels"],
+        serde_json::json!(["Pizza", "Ravioli", "MAOAM", "Other"])
+    );
+    assert_eq!(
+        table["cols"]["labels"],
+        serde_json::json!(["1–4", "4–7", "7–10"])
+    );
+    assert_eq!(
+        table["counts"],
+        serde_json::json!([[1, 1, 0], [0, 0, 0], [0, 0, 1], [1, 1, 0]])
+    );
+    assert_eq!(table["row_totals"], serde_json::json!([2, 0, 1, 2]));
+    assert_eq!(table["col_totals"], serde_json::json!([2, 2, 1]));
+    // The submission answering neither question is not counted.
+    assert_eq!(table["total"], 5);
+    assert_eq!(table["percentages"][0][0], 20.0);
+
+    let (_, table) = crosstab(&state, "rows=i-0-3&cols=i-0-2&bins=3&expand_other=true").await;
+    assert_eq!(
+        table["rows"]["labels"],
+        serde_json::json!(["Pizza", "Ravioli", "MAOAM", "Lasagne", "Risotto"])
+    );
+}
+
+#[tokio::test]
+async fn unknown_questions_and_too_many_bins_are_refused() {
+    let state = state("refused").await;
+    let (status, _) = crosstab(&state, "rows=nope&cols=i-0-2").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = crosstab(&state, "rows=i-0-3&cols=i-0-2&bins=1000000000").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = crosstab(&state, "rows=i-0-3&cols=i-0-2&bins=100").await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,43 @@
+//! The `Content-Security-Policy` header on served pages.
+
+mod common;
+
+use axum::http::{header, HeaderValue};
+use common::{fetch, get};
+use std::sync::Arc;
+use umfragetool_rust::server::{self, AppState};
+
+fn state(name: &str) -> AppState {
+    common::state(
+        &format!("csp-{name}"),
+        [("demo", umfragetool_rust::demo_form())],
+    )
+}
+
+async fn csp(state: AppState, uri: &str) -> Option<HeaderValue> {
+    let (_, headers, _) = fetch(&Arc::new(state), get(uri)).await;
+    headers.get(header::CONTENT_SECURITY_POLICY).cloned()
+}
+
+#[tokio::test]
+async fn default_policy_depends_on_inline_scripts() {
+    assert_eq!(
+        csp(state("default"), "/form/demo").await.unwrap(),
+        server::default_csp(false)
+    );
+    let mut no_js = state("no-js");
+    no_js.no_js = true;
+    let policy = csp(no_js, "/form/demo").await.unwrap();
+    assert_eq!(policy, server::default_csp(true));
+    assert!(!policy.to_str().unwrap().contains("script-src"));
+}
+
+#[tokio::test]
+async fn configured_policy_is_sent_everywhere() {
+    let configured = "default-src 'none'";
+    for uri in ["/", "/form/demo", "/favicon.ico", "/form/missing"] {
+        let mut state = state("configured");
+        state.csp = Some(HeaderValue::from_static(configured));
+        assert_eq!(csp(state, uri).await.unwrap(), configured, "{uri}");
+    }
+}
@@ -0,0 +1,65 @@
+//! With `dedupe`, the store keeps only one of several identical submissions.
+
+mod common;
+
+use umfragetool_rust::store::{Inserted, Submission, SubmissionStore};
+
+fn submission(id: &str, answers: &[(&str, &[&str])]) -> Submission {
+    common::submission("f", id, answers)
+}
+
+fn store(name: &str, dedupe: bool) -> SubmissionStore {
+    let mut store = common::fresh_store(&format!("dedupe-{name}"));
+    store.dedupe = dedupe;
+    store
+}
+
+#[tokio::test]
+async fn identical_answers_are_stored_once() {
+    let store = store("on", true);
+    let first = submission("a", &[("i-0-0", &["x", "y"]), ("i-0-1", &["Ada"])]);
+    assert_eq!(store.insert(first, None).await.unwrap(), Inserted::Stored);
+    let again = submission(
+        "b",
+        &[
+            ("i-0-1", &[" Ada "]),
+            ("i-0-0", &["y", "x"]),
+            ("i-0-2", &[]),
+        ],
+    );
+    assert_eq!(
+        store.insert(again, None).await.unwrap(),
+        Inserted::Duplicate("a".to_string())
+    );
+    assert_eq!(store.list("f").len(), 1);
+
+    let different = submission("c", &[("i-0-0", &["x"]), ("i-0-1", &["Ada"])]);
+    assert_eq!(
+        store.insert(different, None).await.unwrap(),
+        Inserted::Stored
+    );
+    assert_eq!(store.list("f").len(), 2);
+}
+
+#[tokio::test]
+async fn duplicates_are_kept_without_dedupe() {
+    let store = store("off", false);
+    for id in ["a", "b"] {
+        let inserted = store
+            .insert(submission(id, &[("i-0-0", &["x"])]), None)
+            .await
+            .unwrap();
+        assert_eq!(inserted, Inserted::Stored);
+    }
+    assert_eq!(store.list("f").len(), 2);
+}
+
+#[test]
+fn content_hash_ignores_order_and_whitespace() {
+    let a = submission("a", &[("i-0-0", &["x", "y"]), ("i-0-1", &["z"])]);
+    let b = submission("b", &[("i-0-1", &["z "]), ("i-0-0", &["y", "x"])]);
+    assert_eq!(a.content_hash(), b.content_hash());
+    let mut other_form = b.clone();
+    other_form.form_id = "g".to_string();
+    assert_ne!(a.content_hash(), other_form.content_hash());
+}
@@ -0,0 +1,116 @@
+//! Single submissions can be deleted by an admin or through the respondent's
+//! link; the deletion reaches the file and leaves only an audit entry.
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{accept_json, as_admin, get, post, send, with_admin};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::store::SubmissionStore;
+use umfragetool_rust::{demo_form, Form};
+
+fn path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("umfragetool-deletion-{name}"));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("submissions.jsonl")
+}
+
+fn state(path: &Path) -> Arc<AppState> {
+    let form = Form {
+        deletable: true,
+        ..demo_form()
+    };
+    Arc::new(with_admin(AppState::new(
+        HashMap::from([("demo".to_string(), form)]),
+        SubmissionStore::open(path).unwrap(),
+    )))
+}
+
+/// Submits an answer and returns the JSON response.
+async fn submit(state: &Arc<AppState>, answer: &str) -> serde_json::Value {
+    let request = accept_json(post("/submit/demo", &format!("i-0-0={answer}")));
+    let (status, body) = send(state, request).await;
+    assert_eq!(status, StatusCode::OK);
+    serde_json::from_str(&body).unwrap()
+}
+
+fn delete(id: &str) -> Request<Body> {
+    as_admin(
+        Request::delete(format!("/api/form/demo/response/{id}"))
+            .body(Body::empty())
+            .unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn admins_delete_for_good() {
+    let path = path("admin");
+    let state = state(&path);
+    let gone = submit(&state, "Secret").await["submission_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    submit(&state, "Kept").await;
+
+    assert_eq!(send(&state, delete(&gone)).await.0, StatusCode::NO_CONTENT);
+    assert_eq!(send(&state, delete(&gone)).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(state.store.list("demo").len(), 1);
+    let log = std::fs::read_to_string(&path).unwrap();
+    assert!(!log.contains("Secret") && !log.contains(&gone), "{log}");
+    let audit = std::fs::read_to_string(path.with_extension("audit.jsonl")).unwrap();
+    assert!(audit.contains(&gone) && audit.contains(r#""by":"admin""#));
+    assert!(!audit.contains("Secret"));
+
+    // Still gone after a restart.
+    let reopened = SubmissionStore::open(&path).unwrap();
+    assert_eq!(reopened.list("demo"), state.store.list("demo"));
+}
+
+#[tokio::test]
+async fn respondents_delete_through_their_link() {
+    let path = path("respondent");
+    let state = state(&path);
+    let url = submit(&state, "Mine").await["delete_url"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    // Opening the link only asks.
+    let (status, html) = send(&state, get(&url)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains(r#"<form method="post">"#), "{html}");
+    assert_eq!(state.store.list("demo").len(), 1);
+
+    let (status, _) = send(&state, post(&url, "")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(state.store.list("demo").is_empty());
+    let forged = url.replace("/delete/", "/delete/0");
+    let (status, _) = send(&state, post(&forged, "")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn failed_rewrite_keeps_the_submission() {
+    let path = path("failed");
+    let state = state(&path);
+    let id = submit(&state, "Because").await["submission_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    // The temporary file cannot be written where a directory is in the way.
+    let mut tmp = path.clone().into_os_string();
+    tmp.push(".tmp");
+    std::fs::create_dir(&tmp).unwrap();
+
+    let (status, _) = send(&state, delete(&id)).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(
+        state.store.list("demo"),
+        SubmissionStore::open(&path).unwrap().list("demo")
+    );
+    assert_eq!(state.store.list("demo").len(), 1);
+}
@@ -0,0 +1,18 @@
+//! `Form` renders through `Display`, and answers axum requests as a page
+//! through `IntoResponse`.
+
+use umfragetool_rust::*;
+
+#[test]
+fn display_matches_render() {
+    let form = demo_form();
+    assert_eq!(
+        form.to_string(),
+        form.render(ROOT_PREFIX.to_string(), &RenderContext::default())
+    );
+    let response = axum::response::IntoResponse::into_response(&form);
+    assert_eq!(
+        response.headers()[axum::http::header::CONTENT_TYPE],
+        "text/html; charset=utf-8"
+    );
+}
@@ -0,0 +1,95 @@
+//! Forms with `drafts` save unfinished answers behind a resume link; drafts
+//! expire, never count as responses and go away once submitted.
+
+mod common;
+
+use axum::http::StatusCode;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use common::{get, post, send, stop_clock};
+use std::sync::{Arc, Mutex};
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::{demo_form, Form};
+
+fn saved_at() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap()
+}
+
+fn state(name: &str) -> (Arc<AppState>, Arc<Mutex<DateTime<Utc>>>) {
+    let form = Form {
+        drafts: true,
+        ..demo_form()
+    };
+    let mut state = common::state(&format!("drafts-{name}"), [("demo", form)]);
+    let now = stop_clock(&mut state, saved_at());
+    (Arc::new(state), now)
+}
+
+/// Saves a draft and returns its resume token.
+async fn save(state: &Arc<AppState>, body: &str) -> String {
+    let (status, html) = send(state, post("/draft/demo", body)).await;
+    assert_eq!(status, StatusCode::OK);
+    let token = regex::Regex::new("/form/demo/resume/([0-9a-f]+)")
+        .unwrap()
+        .captures(&html)
+        .unwrap_or_else(|| panic!("no resume link in {html}"))[1]
+        .to_string();
+    // 128 random bits.
+    assert_eq!(token.len(), 32);
+    token
+}
+
+#[tokio::test]
+async fn resumed_drafts_are_prefilled_and_not_counted() {
+    let (state, _) = state("resume");
+    let token = save(&state, "i-0-0=Half+an+answer&i-0-3=Ravioli").await;
+    assert!(state.store.list("demo").is_empty());
+
+    let (status, html) = send(&state, get(&format!("/form/demo/resume/{token}"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains(">Half an answer</textarea>"), "{html}");
+    assert!(html.contains(r#"value="Ravioli" checked"#), "{html}");
+    assert!(html.contains(&format!(
+        r#"<input type="hidden" name="_draft" value="{token}">"#
+    )));
+
+    let body = format!("i-0-0=Whole&_draft={token}");
+    assert_eq!(
+        send(&state, post("/submit/demo", &body)).await.0,
+        StatusCode::OK
+    );
+    assert_eq!(state.store.list("demo").len(), 1);
+    let (status, _) = send(&state, get(&format!("/form/demo/resume/{token}"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn drafts_expire() {
+    let (state, now) = state("expire");
+    let token = save(&state, "i-0-0=Later").await;
+    *now.lock().unwrap() = saved_at() + state.draft_ttl - Duration::minutes(1);
+    let uri = format!("/form/demo/resume/{token}");
+    assert_eq!(send(&state, get(&uri)).await.0, StatusCode::OK);
+    *now.lock().unwrap() = saved_at() + state.draft_ttl;
+    assert_eq!(send(&state, get(&uri)).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn guessing_is_rate_limited() {
+    let (state, _) = state("guess");
+    for guess in 0..30 {
+        let uri = format!("/form/demo/resume/{guess:032x}");
+        assert_eq!(send(&state, get(&uri)).await.0, StatusCode::NOT_FOUND);
+    }
+    let uri = format!("/form/demo/resume/{:032x}", 30);
+    assert_eq!(
+        send(&state, get(&uri)).await.0,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+}
+
+#[tokio::test]
+async fn forms_without_drafts_take_none() {
+    let state = Arc::new(common::state("drafts-off", [("demo", demo_form())]));
+    let (status, _) = send(&state, post("/draft/demo", "i-0-0=Later")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,90 @@
+//! The time between serving the form and submitting it is stored with each
+//! submission, from a signed `_started` field, and summarised in the stats.
+
+mod common;
+
+use axum::http::StatusCode;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use common::{as_admin, get, send, stop_clock, with_admin};
+use regex::Regex;
+use std::sync::{Arc, Mutex};
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::stats;
+use umfragetool_rust::{demo_form, Form};
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap()
+}
+
+fn state(name: &str, form: Form) -> (Arc<AppState>, Arc<Mutex<DateTime<Utc>>>) {
+    let mut state = with_admin(common::state(&format!("duration-{name}"), [("demo", form)]));
+    let now = stop_clock(&mut state, start());
+    (Arc::new(state), now)
+}
+
+/// The `_started` value of a freshly served form.
+async fn started(state: &Arc<AppState>) -> String {
+    let (_, html) = send(state, get("/form/demo")).await;
+    Regex::new(r#"name="_started" value="([^"]+)""#)
+        .unwrap()
+        .captures(&html)
+        .unwrap()[1]
+        .to_string()
+}
+
+async fn submit(state: &Arc<AppState>, started: &str) {
+    let body = format!("i-0-0=Because&_started={started}");
+    assert_eq!(common::submit(state, "demo", &body).await.0, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn time_on_the_form_is_stored() {
+    let (state, now) = state("stored", demo_form());
+    let token = started(&state).await;
+    *now.lock().unwrap() += Duration::seconds(90);
+    submit(&state, &token).await;
+    assert_eq!(state.store.list("demo")[0].duration_secs, Some(90));
+}
+
+#[tokio::test]
+async fn forged_start_times_are_ignored() {
+    let (state, _) = state("forged", demo_form());
+    let token = started(&state).await;
+    // Changing the signed value breaks the signature.
+    let forged = token.replacen(&start().timestamp().to_string(), "1", 1);
+    submit(&state, &forged).await;
+    submit(&state, "1714550000").await;
+    assert!(state
+        .store
+        .list("demo")
+        .iter()
+        .all(|submission| submission.duration_secs.is_none()));
+}
+
+#[tokio::test]
+async fn stats_report_the_spread() {
+    let (state, now) = state("stats", demo_form());
+    for secs in [30, 60, 90, 300] {
+        *now.lock().unwrap() = start();
+        let token = started(&state).await;
+        *now.lock().unwrap() += Duration::seconds(secs);
+        submit(&state, &token).await;
+    }
+    // Untimed submissions do not count.
+    submit(&state, "").await;
+
+    let durations = stats::durations(&state.store.list("demo")).unwrap();
+    assert_eq!(durations.timed, 4);
+    assert_eq!(durations.median, 75.0);
+    assert_eq!(durations.p25, 52.5);
+    assert_eq!(durations.p90, 237.0);
+
+    let request = as_admin(get("/form/demo/summary.json"));
+    let (_, body) = send(&state, request).await;
+    let summary: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        summary["duration"],
+        serde_json::json!({ "timed": 4, "p25": 52.5, "median": 75.0, "p75": 142.5, "p90": 237.0 })
+    );
+    assert!(stats::durations(&[]).is_none());
+}
@@ -0,0 +1,130 @@
+//! `editable` forms hand out a signed link through which the respondent
+//! corrects their submission in place.
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use common::{accept_json, as_admin, fresh_store, get, post, send, stop_clock, with_admin};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::{demo_form, Form};
+
+fn submitted_at() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap()
+}
+
+fn editable() -> Form {
+    Form {
+        editable: true,
+        closes_at: Some(submitted_at() + Duration::days(1)),
+        ..demo_form()
+    }
+}
+
+fn state(name: &str, forms: HashMap<String, Form>) -> (Arc<AppState>, Arc<Mutex<DateTime<Utc>>>) {
+    let mut state = with_admin(AppState::new(
+        forms,
+        fresh_store(&format!("editing-{name}")),
+    ));
+    let now = stop_clock(&mut state, submitted_at());
+    (Arc::new(state), now)
+}
+
+fn submit(form_id: &str, body: &str) -> Request<Body> {
+    accept_json(post(&format!("/submit/{form_id}"), body))
+}
+
+/// Submits to `demo` and returns the edit link.
+async fn edit_url(state: &Arc<AppState>) -> String {
+    let (status, body) = send(state, submit("demo", "i-0-0=Because")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    body["edit_url"].as_str().unwrap().to_string()
+}
+
+fn token(edit_url: &str) -> &str {
+    edit_url.rsplit('/').next().unwrap()
+}
+
+#[tokio::test]
+async fn edits_replace_the_answers_and_keep_the_submission_time() {
+    let (state, now) = state("edit", HashMap::from([("demo".to_string(), editable())]));
+    let url = edit_url(&state).await;
+    let (status, html) = send(&state, get(&url)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains(">Because</textarea>"), "{html}");
+    assert!(
+        html.contains(&format!(
+            r#"<input type="hidden" name="_edit" value="{}">"#,
+            token(&url)
+        )),
+        "{html}"
+    );
+
+    let edited_at = submitted_at() + Duration::hours(2);
+    *now.lock().unwrap() = edited_at;
+    let body = format!("i-0-0=Changed&_edit={}", token(&url));
+    let (status, _) = send(&state, submit("demo", &body)).await;
+    assert_eq!(status, StatusCode::OK);
+    let stored = state.store.list("demo");
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].answers["i-0-0"], ["Changed"]);
+    assert_eq!(stored[0].submitted_at, Some(submitted_at()));
+    assert_eq!(stored[0].updated_at, Some(edited_at));
+
+    let export = as_admin(get("/export/demo.json"));
+    let (_, body) = send(&state, export).await;
+    let export: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(export.as_array().unwrap().len(), 1);
+    assert_eq!(export[0]["answers"]["i-0-0"], "Changed");
+    assert_eq!(export[0]["updated_at"], "2024-05-01T10:00:00Z");
+}
+
+#[tokio::test]
+async fn tokens_only_open_their_own_submission() {
+    let (state, _) = state(
+        "bound",
+        HashMap::from([
+            ("demo".to_string(), editable()),
+            ("other".to_string(), editable()),
+        ]),
+    );
+    let url = edit_url(&state).await;
+    let foreign = format!("/form/other/edit/{}", token(&url));
+    assert_eq!(send(&state, get(&foreign)).await.0, StatusCode::NOT_FOUND);
+    let body = format!("i-0-0=Changed&_edit={}", token(&url));
+    assert_eq!(
+        send(&state, submit("other", &body)).await.0,
+        StatusCode::FORBIDDEN
+    );
+    let tampered = format!("{url}0");
+    assert_eq!(send(&state, get(&tampered)).await.0, StatusCode::NOT_FOUND);
+    assert!(state.store.list("other").is_empty());
+    assert_eq!(state.store.list("demo")[0].answers["i-0-0"], ["Because"]);
+}
+
+#[tokio::test]
+async fn closed_forms_take_no_edits() {
+    let (state, now) = state("closed", HashMap::from([("demo".to_string(), editable())]));
+    let url = edit_url(&state).await;
+    *now.lock().unwrap() = submitted_at() + Duration::days(2);
+    let (status, html) = send(&state, get(&url)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains("This survey is closed."), "{html}");
+    assert!(!html.contains("Because"), "{html}");
+    let body = format!("i-0-0=Changed&_edit={}", token(&url));
+    let (status, _) = send(&state, submit("demo", &body)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(state.store.list("demo")[0].answers["i-0-0"], ["Because"]);
+}
+
+#[tokio::test]
+async fn forms_without_editable_hand_out_no_link() {
+    let (state, _) = state("fixed", HashMap::from([("demo".to_string(), demo_form())]));
+    let (_, body) = send(&state, submit("demo", "i-0-0=Because")).await;
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert!(body["edit_url"].is_null());
+}
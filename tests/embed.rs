@@ -0,0 +1,190 @@
+//! `?embed=1` pages may be framed by the configured origins, others not at all;
+//! admins get a snippet to paste.
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, HeaderMap, HeaderValue, Request, StatusCode};
+use common::{as_admin, fetch, get, post, with_admin};
+use std::collections::HashMap;
+use std::sync::Arc;
+use umfragetool_rust::server::{self, AppState};
+use umfragetool_rust::{demo_form, embed, Form};
+
+/// The demo form, with an answer to its first question required.
+fn form() -> Form {
+    let mut form = demo_form();
+    form.groups[0].questions[0].required = true;
+    form
+}
+
+const ORIGIN: &str = "https://intranet.example.org";
+
+fn state(name: &str) -> AppState {
+    let mut state = common::state(&format!("embed-{name}"), [("demo", form())]);
+    state.embed_origins = vec![ORIGIN.to_string()];
+    state
+}
+
+async fn send(state: AppState, request: Request<Body>) -> (StatusCode, HeaderMap, String) {
+    fetch(&Arc::new(state), request).await
+}
+
+fn submit(body: &str) -> Request<Body> {
+    post("/submit/demo", body)
+}
+
+fn frame_ancestors(headers: &HeaderMap) -> String {
+    let policy = headers[header::CONTENT_SECURITY_POLICY].to_str().unwrap();
+    let directives: Vec<&str> = policy
+        .split("; ")
+        .filter(|directive| directive.starts_with("frame-ancestors"))
+        .collect();
+    assert_eq!(directives.len(), 1, "{policy}");
+    directives[0].to_string()
+}
+
+#[tokio::test]
+async fn normal_pages_cannot_be_framed() {
+    let (_, headers, html) = send(state("normal"), get("/form/demo")).await;
+    assert_eq!(headers[header::X_FRAME_OPTIONS], "DENY");
+    assert_eq!(frame_ancestors(&headers), "frame-ancestors 'none'");
+    assert!(html.contains("<h1>"));
+    assert!(!html.contains("_embed"));
+}
+
+#[tokio::test]
+async fn embedded_form_allows_configured_origins() {
+    let (status, headers, html) = send(state("form"), get("/form/demo?embed=1")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(headers.get(header::X_FRAME_OPTIONS).is_none());
+    assert_eq!(
+        frame_ancestors(&headers),
+        format!("frame-ancestors 'self' {ORIGIN}")
+    );
+    let policy = headers[header::CONTENT_SECURITY_POLICY].to_str().unwrap();
+    assert!(policy.starts_with(server::default_csp(false).split("; frame").next().unwrap()));
+    assert!(!html.contains("<h1>"));
+    assert!(html.starts_with("<!DOCTYPE html><html"));
+    assert!(html.contains(r#"<body style="margin:0.5em;background:transparent">"#));
+    assert!(html.contains(r#"<input type="hidden" name="_embed" value="1">"#));
+}
+
+#[tokio::test]
+async fn embed_route_leaves_out_the_chrome() {
+    let with_intro = |name| {
+        let state = state(name);
+        let form = Form {
+            intro: Some("We would like to know...".into()),
+            ..form()
+        };
+        *state.forms.write().unwrap() = HashMap::from([("demo".to_string(), Arc::new(form))]);
+        state
+    };
+    let (status, headers, html) = send(with_intro("route"), get("/form/demo/embed")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(headers.get(header::X_FRAME_OPTIONS).is_none());
+    assert_eq!(
+        frame_ancestors(&headers),
+        format!("frame-ancestors 'self' {ORIGIN}")
+    );
+    assert!(!html.contains("<h1>"));
+    assert!(!html.contains("We would like to know"));
+    assert!(html.contains(r#"name="_embed" value="1""#));
+
+    let (_, headers, html) = send(with_intro("route-plain"), get("/form/demo")).await;
+    assert_eq!(headers[header::X_FRAME_OPTIONS], "DENY");
+    assert!(html.contains("We would like to know"));
+}
+
+#[tokio::test]
+async fn submission_flow_stays_embeddable() {
+    let (status, headers, html) = send(state("invalid"), submit("_embed=1")).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(headers.get(header::X_FRAME_OPTIONS).is_none());
+    assert!(!html.contains("<h1>"));
+    assert!(html.contains(r#"name="_embed" value="1""#));
+
+    let (status, headers, html) = send(state("valid"), submit("_embed=1&i-0-0=Because")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(headers.get(header::X_FRAME_OPTIONS).is_none());
+    assert!(frame_ancestors(&headers).contains(ORIGIN));
+    assert!(html.contains("Thank you"));
+
+    let (_, headers, _) = send(state("plain"), submit("i-0-0=Because")).await;
+    assert_eq!(headers[header::X_FRAME_OPTIONS], "DENY");
+}
+
+#[tokio::test]
+async fn configured_policy_gets_embedding_origins() {
+    let mut state = state("configured");
+    state.csp = Some(HeaderValue::from_static(
+        "default-src 'none'; frame-ancestors 'none'",
+    ));
+    let (_, headers, _) = send(state, get("/form/demo?embed=1")).await;
+    assert_eq!(
+        headers[header::CONTENT_SECURITY_POLICY],
+        format!("default-src 'none'; frame-ancestors 'self' {ORIGIN}").as_str()
+    );
+}
+
+fn admin_get(uri: &str) -> Request<Body> {
+    let mut request = as_admin(get(uri));
+    let headers = request.headers_mut();
+    headers.insert(
+        header::HOST,
+        HeaderValue::from_static("surveys.example.org"),
+    );
+    headers.insert("x-forwarded-proto", HeaderValue::from_static("https"));
+    request
+}
+
+#[tokio::test]
+async fn embed_snippet_needs_admin() {
+    let state = state("snippet-auth");
+    let state = with_admin(state);
+    let (status, _, _) = send(state, get("/form/demo/embed.html")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn embed_snippet_frames_the_embed_mode() {
+    let state = state("snippet");
+    let state = with_admin(state);
+    let (status, headers, snippet) = send(state, admin_get("/form/demo/embed.html")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::CONTENT_TYPE], "text/plain; charset=utf-8");
+    let form = form();
+    let title = umfragetool_rust::escape(form.text(&form.title));
+    assert!(snippet.starts_with(&format!(
+        r#"<iframe src="https://surveys.example.org/form/demo?embed=1" title="{title}" width="100%" height="{}""#,
+        embed::estimated_height(&form)
+    )));
+    assert!(snippet.contains(&format!(
+        r#"<noscript><a href="https://surveys.example.org/form/demo">{title}</a></noscript>"#
+    )));
+}
+
+#[test]
+fn height_follows_the_questions() {
+    let poll: Form = serde_yaml::from_str(
+        r#"
+title: Lunch?
+description: ""
+groups:
+  - title: Poll
+    description: ""
+    questions:
+      - title: Pizza or pasta?
+        spec:
+          type: single_choice
+          answers: [Pizza, Pasta]
+          custom_answer: false
+"#,
+    )
+    .unwrap();
+    let survey = form();
+    let short = embed::estimated_height(&poll);
+    assert!(short < 400, "{short}");
+    assert!(short < embed::estimated_height(&survey));
+}
@@ -0,0 +1,36 @@
+//! A rejected submission gets the form back with its answers, a summary
+//! linking to the problems and the offending questions marked.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::submit;
+use std::sync::Arc;
+use umfragetool_rust::{demo_form, Form};
+
+/// The demo form, with an answer to its first question required.
+fn form() -> Form {
+    let mut form = demo_form();
+    form.groups[0].questions[0].required = true;
+    form
+}
+
+#[tokio::test]
+async fn only_the_missing_answer_is_marked() {
+    let state = Arc::new(common::state("error-summary", [("demo", form())]));
+    let (status, html) = submit(&state, "demo", "i-0-1=42&i-0-3=Ravioli").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    assert_eq!(html.matches(r#"<div class="error""#).count(), 1, "{html}");
+    assert!(html.contains(r#"<div class="error" id="i-0-0-error">"#));
+    assert!(html.contains(r##"<div class="error-summary" role="alert" aria-live="assertive">"##));
+    assert!(html.contains(r##"<li><a href="#i-0-0-error">Why would you do this?</a>: "##));
+    assert_eq!(html.matches(r##"<a href="#i-"##).count(), 1);
+    // What was answered is still there.
+    assert!(
+        html.contains(r#"name="i-0-1" id="i-0-1" value="42""#),
+        "{html}"
+    );
+    assert!(html.contains(r#"value="Ravioli" checked"#), "{html}");
+    assert!(state.store.list("demo").is_empty());
+}
@@ -0,0 +1,99 @@
+//! Titles, descriptions and labels written by the form's author are shown as
+//! text, not markup.
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{get, post, send};
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::*;
+
+const FORM: &str = r#"
+title: Tags <script>alert(1)</script>
+description: Fish & <b>chips</b>
+groups:
+  - title: <i>Set</i>
+    description: <u>about</u>
+    questions:
+      - title: <em>Name</em>
+        help: <small>help</small>
+        required: true
+        spec:
+          type: text
+          is_long: false
+      - title: Favourite
+        spec:
+          type: single_choice
+          custom_answer: false
+          answers:
+            - <img src=x>
+      - title: Toppings
+        spec:
+          type: multiple_choice
+          custom_answer: false
+          answers:
+            - <s>cheese</s>
+      - title: Rating
+        spec:
+          type: discrete_numeric
+          bounds: [1, 3]
+          num_descriptions:
+            1: <sup>low</sup>
+"#;
+
+fn state(name: &str) -> Arc<AppState> {
+    let form = serde_yaml::from_str(FORM).unwrap();
+    Arc::new(common::state(&format!("escaping-{name}"), [("tags", form)]))
+}
+
+async fn body(state: &Arc<AppState>, request: Request<Body>, status: StatusCode) -> String {
+    let (actual, body) = send(state, request).await;
+    assert_eq!(actual, status);
+    body
+}
+
+fn assert_escaped(html: &str, texts: &[&str]) {
+    for text in texts {
+        assert!(!html.contains(text), "{text} left as markup");
+        assert!(html.contains(&escape(text)), "{text} missing");
+    }
+}
+
+#[tokio::test]
+async fn index_lists_titles_as_text() {
+    let state = state("index");
+    let html = body(&state, get("/"), StatusCode::OK).await;
+    assert_escaped(&html, &["<script>alert(1)</script>"]);
+}
+
+#[tokio::test]
+async fn form_page_shows_texts_as_text() {
+    let state = state("form");
+    let html = body(&state, get("/form/tags"), StatusCode::OK).await;
+    assert_escaped(
+        &html,
+        &[
+            "<script>alert(1)</script>",
+            "Fish & <b>chips</b>",
+            "<i>Set</i>",
+            "<u>about</u>",
+            "<em>Name</em>",
+            "<small>help</small>",
+            "<img src=x>",
+            "<s>cheese</s>",
+            "<sup>low</sup>",
+        ],
+    );
+}
+
+#[tokio::test]
+async fn error_summary_and_thank_you_page_show_titles_as_text() {
+    let state = state("submit");
+    let submit = |form: &str| post("/submit/tags", form);
+    let html = body(&state, submit(""), StatusCode::UNPROCESSABLE_ENTITY).await;
+    assert_escaped(&html, &["<em>Name</em>"]);
+    let html = body(&state, submit("i-0-0=Ada"), StatusCode::OK).await;
+    assert_escaped(&html, &["<script>alert(1)</script>"]);
+}
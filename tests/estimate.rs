@@ -0,0 +1,30 @@
+//! `estimated_minutes` tells respondents how long the form takes.
+
+use umfragetool_rust::*;
+
+#[test]
+fn estimate_only_when_set() {
+    let render = |estimated_minutes, lang: &str| {
+        let form = Form {
+            estimated_minutes,
+            ..demo_form()
+        };
+        let ctx = RenderContext {
+            lang: lang.to_string(),
+            ..Default::default()
+        };
+        form.render(ROOT_PREFIX.to_string(), &ctx)
+    };
+    let html = render(Some(5), "en");
+    assert!(
+        html.contains(
+            r#"<p>Hello, I am survey.</p><p class="estimate">Estimated time: ~5 minutes</p>"#
+        ),
+        "{html}"
+    );
+    assert!(render(Some(1), "en").contains(r#"<p class="estimate">Estimated time: ~1 minute</p>"#));
+    assert!(
+        render(Some(5), "de").contains(r#"<p class="estimate">Geschätzte Dauer: ~5 Minuten</p>"#)
+    );
+    assert!(!render(None, "en").contains("estimate"));
+}
@@ -0,0 +1,108 @@
+//! `filter=<question id>:<op>:<value>` restricts exports to the matching
+//! submissions, and malformed filters are refused with a reason.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{answered, as_admin, get, send, submit, with_admin};
+use std::sync::Arc;
+use umfragetool_rust::demo_form;
+use umfragetool_rust::filter::Filter;
+use umfragetool_rust::server::AppState;
+
+async fn state(name: &str) -> Arc<AppState> {
+    let state = Arc::new(with_admin(common::state(
+        &format!("filters-{name}"),
+        [("demo", demo_form())],
+    )));
+    for body in [
+        "i-0-0=Because&i-0-2=3&i-0-3=Pizza",
+        "i-0-0=Why+not&i-0-2=7&i-0-3=Ravioli&i-0-4=Pizza",
+        "i-0-0=Just+because&i-0-2=9&i-0-3=Pizza&i-0-4=MAOAM",
+    ] {
+        assert_eq!(submit(&state, "demo", body).await.0, StatusCode::OK);
+    }
+    state
+}
+
+/// The `i-0-0` answers of the filtered JSON export, sorted.
+async fn export(state: &Arc<AppState>, query: &str) -> (StatusCode, Vec<String>, String) {
+    let request = as_admin(get(&format!("/export/demo.json{query}")));
+    let (status, body) = send(state, request).await;
+    let mut reasons: Vec<String> = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|export| export.as_array().cloned())
+        .unwrap_or_default()
+        .iter()
+        .map(|submission| submission["answers"]["i-0-0"].as_str().unwrap().to_string())
+        .collect();
+    reasons.sort();
+    (status, reasons, body)
+}
+
+#[tokio::test]
+async fn exports_keep_what_all_filters_match() {
+    let state = state("export").await;
+    let (status, reasons, _) = export(&state, "?filter=i-0-3:eq:Pizza").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(reasons, ["Because", "Just because"]);
+    let (_, reasons, _) = export(&state, "?filter=i-0-3:eq:Pizza&filter=i-0-2:gte:5").await;
+    assert_eq!(reasons, ["Just because"]);
+    let (_, reasons, _) = export(&state, "?filter=i-0-0:contains:BECAUSE").await;
+    assert_eq!(reasons, ["Because", "Just because"]);
+    // Multiple choice matches on any of the selected options.
+    let (_, reasons, _) = export(&state, "?filter=i-0-4:eq:Pizza").await;
+    assert_eq!(reasons, ["Why not"]);
+    let (_, reasons, _) = export(&state, "?filter=i-0-2:lt:3").await;
+    assert!(reasons.is_empty());
+    let (_, reasons, _) = export(&state, "").await;
+    assert_eq!(reasons.len(), 3);
+}
+
+#[tokio::test]
+async fn malformed_filters_are_refused() {
+    let state = state("refused").await;
+    let (status, _, body) = export(&state, "?filter=i-9-9:eq:Pizza").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body,
+        "unknown question id 'i-9-9' in filter 'i-9-9:eq:Pizza'"
+    );
+}
+
+#[test]
+fn parser_explains_what_is_wrong() {
+    let form = demo_form();
+    let error = |expression| Filter::parse(expression, &form).unwrap_err();
+    assert_eq!(
+        error("i-0-3"),
+        "filter 'i-0-3' is not of the form <question id>:<op>:<value>"
+    );
+    assert!(error("i-0-3:like:Pizza").starts_with("unknown operator 'like'"));
+    assert_eq!(
+        error("i-0-3:gt:4"),
+        "operator 'gt' needs a numeric question, i-0-3 is single_choice"
+    );
+    assert_eq!(
+        error("i-0-2:contains:4"),
+        "operator 'contains' needs a text question, i-0-2 is discrete_numeric"
+    );
+    assert_eq!(
+        error("i-0-2:eq:four"),
+        "'four' is not a number (filter 'i-0-2:eq:four')"
+    );
+    // Values may themselves hold colons.
+    assert!(Filter::parse("i-0-0:eq:a:b", &form).is_ok());
+}
+
+#[test]
+fn numbers_compare_as_numbers_and_unanswered_never_matches() {
+    let form = demo_form();
+    let answers = answered(&[("i-0-2", &["4"])]);
+    let filter = |expression| Filter::parse(expression, &form).unwrap();
+    assert!(filter("i-0-2:eq:4").matches(&answers));
+    assert!(filter("i-0-2:lte:4.0").matches(&answers));
+    assert!(!filter("i-0-2:ne:4").matches(&answers));
+    assert!(!filter("i-0-3:ne:Pizza").matches(&answers));
+    assert!(!filter("i-0-1:gte:0").matches(&answers));
+}
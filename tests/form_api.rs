@@ -0,0 +1,265 @@
+//! `PUT` and `DELETE /api/form/:id` publish and unpublish forms without a
+//! restart, writing through to the forms directory.
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::{as_admin, get, send, submit, with_admin};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::store::SubmissionStore;
+use umfragetool_rust::{demo_form, load_form, Form, Limits};
+
+fn state(name: &str) -> (Arc<AppState>, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("umfragetool-form-api-{name}"));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut state = with_admin(AppState::new(
+        HashMap::new(),
+        SubmissionStore::open(&dir.join("responses.jsonl")).unwrap(),
+    ));
+    state.forms_dir = Some(dir.clone());
+    (Arc::new(state), dir)
+}
+
+fn put(form_id: &str, content_type: &str, body: String) -> Request<Body> {
+    as_admin(
+        Request::put(format!("/api/form/{form_id}"))
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap(),
+    )
+}
+
+fn titled(title: &str) -> Form {
+    Form {
+        title: title.into(),
+        ..demo_form()
+    }
+}
+
+#[tokio::test]
+async fn put_publishes_and_replaces_forms() {
+    let (state, dir) = state("put");
+    let yaml = serde_yaml::to_string(&titled("First")).unwrap();
+    let (status, _) = send(&state, put("lunch", "application/yaml", yaml)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, html) = send(&state, get("/form/lunch")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains("<h1>First</h1>"), "{html}");
+
+    let json = serde_json::to_string(&titled("Second")).unwrap();
+    let (status, body) = send(&state, put("lunch", "application/json", json)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, r#"{"warnings":[]}"#);
+    let (_, html) = send(&state, get("/form/lunch")).await;
+    assert!(html.contains("<h1>Second</h1>"), "{html}");
+    let written: Form =
+        serde_yaml::from_str(&std::fs::read_to_string(dir.join("lunch.yml")).unwrap()).unwrap();
+    assert_eq!(written.to_string(), titled("Second").to_string());
+    assert!(!dir.join(".lunch.yml.tmp").exists());
+}
+
+#[tokio::test]
+async fn definitions_are_read_like_form_files() {
+    let (state, dir) = state("resolve");
+    std::fs::create_dir_all(dir.join("sets")).unwrap();
+    std::fs::write(
+        dir.join("sets/food.yml"),
+        "title: Food\ndescription: ''\nquestions:\n  - template: main\n",
+    )
+    .unwrap();
+    let yaml = "\
+title: Lunch
+description: ''
+templates:
+  main:
+    title: Main course
+    spec: { type: single_choice, custom_answer: false, answers: [Pizza, Ravioli] }
+groups:
+  - include: sets/food.yml
+  - title: Seating
+    description: ''
+    questions:
+      - title: Where?
+        spec: { type: matrix, rows: [A, B] }
+";
+    let (status, body) = send(&state, put("lunch", "application/yaml", yaml.into())).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        body["warnings"],
+        serde_json::json!([
+            "question i-1-0 (Where?) has the unsupported type 'matrix' and is shown as a placeholder"
+        ])
+    );
+    let (_, html) = send(&state, get("/form/lunch")).await;
+    assert!(html.contains("Main course"), "{html}");
+    // What is written stands on its own.
+    let written = std::fs::read_to_string(dir.join("lunch.yml")).unwrap();
+    assert!(
+        !written.contains("include") && !written.contains("template"),
+        "{written}"
+    );
+
+    let (status, body) = send(
+        &state,
+        put("lunch", "application/yaml", yaml.replace("food", "drinks")),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body.contains("drinks.yml"), "{body}");
+}
+
+#[tokio::test]
+async fn invalid_definitions_change_nothing() {
+    let (state, dir) = state("invalid");
+    let yaml = serde_yaml::to_string(&titled("First")).unwrap();
+    send(&state, put("lunch", "application/yaml", yaml)).await;
+    let before = std::fs::read_to_string(dir.join("lunch.yml")).unwrap();
+
+    let (status, body) = send(&state, put("lunch", "application/yaml", "title: [".into())).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body.starts_with("could not parse form: "), "{body}");
+    let closed = Form {
+        opens_at: Some("2024-05-02T00:00:00Z".parse().unwrap()),
+        closes_at: Some("2024-05-01T00:00:00Z".parse().unwrap()),
+        ..titled("Broken")
+    };
+    let yaml = serde_yaml::to_string(&closed).unwrap();
+    let (status, body) = send(&state, put("lunch", "application/yaml", yaml)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body.contains("must be later than opens_at"), "{body}");
+    let (status, _) = send(&state, put("lunch.old", "application/yaml", before.clone())).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    assert_eq!(
+        std::fs::read_to_string(dir.join("lunch.yml")).unwrap(),
+        before
+    );
+    let (_, html) = send(&state, get("/form/lunch")).await;
+    assert!(html.contains("<h1>First</h1>"), "{html}");
+}
+
+#[tokio::test]
+async fn changes_need_admin() {
+    let (state, dir) = state("admin");
+    let request = Request::put("/api/form/lunch")
+        .header(header::CONTENT_TYPE, "application/yaml")
+        .body(Body::from(serde_yaml::to_string(&demo_form()).unwrap()))
+        .unwrap();
+    assert_eq!(send(&state, request).await.0, StatusCode::UNAUTHORIZED);
+    let request = Request::delete("/api/form/lunch")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(send(&state, request).await.0, StatusCode::UNAUTHORIZED);
+    assert!(!dir.join("lunch.yml").exists());
+}
+
+#[tokio::test]
+async fn delete_unpublishes_and_keeps_the_responses() {
+    let (state, dir) = state("delete");
+    let yaml = serde_yaml::to_string(&demo_form()).unwrap();
+    send(&state, put("lunch", "application/yaml", yaml)).await;
+    assert_eq!(
+        submit(&state, "lunch", "i-0-0=Because").await.0,
+        StatusCode::OK
+    );
+
+    let delete = || {
+        as_admin(
+            Request::delete("/api/form/lunch")
+                .body(Body::empty())
+                .unwrap(),
+        )
+    };
+    assert_eq!(send(&state, delete()).await.0, StatusCode::NO_CONTENT);
+    assert_eq!(
+        send(&state, get("/form/lunch")).await.0,
+        StatusCode::NOT_FOUND
+    );
+    assert!(!dir.join("lunch.yml").exists());
+    assert!(dir.join("lunch.yml.unpublished").exists());
+    assert_eq!(state.store.list("lunch").len(), 1);
+    assert_eq!(send(&state, delete()).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn changes_go_to_the_file_the_form_was_loaded_from() {
+    let (state, dir) = state("sources");
+    let json = serde_json::to_string_pretty(&demo_form()).unwrap();
+    std::fs::write(dir.join("lunch.json"), json).unwrap();
+    state
+        .sources
+        .lock()
+        .unwrap()
+        .insert("lunch".into(), dir.join("lunch.json"));
+    state
+        .forms
+        .write()
+        .unwrap()
+        .insert("lunch".into(), Arc::new(demo_form()));
+
+    let yaml = serde_yaml::to_string(&titled("Replaced")).unwrap();
+    let (status, _) = send(&state, put("lunch", "application/yaml", yaml)).await;
+    assert_eq!(status, StatusCode::OK);
+    let written: Form =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("lunch.json")).unwrap()).unwrap();
+    assert_eq!(written.title, titled("Replaced").title);
+    assert!(!dir.join("lunch.yml").exists());
+    assert!(!dir.join(".lunch.json.tmp").exists());
+
+    // New forms take the format they are sent in.
+    let json = serde_json::to_string(&titled("Dinner")).unwrap();
+    let (status, _) = send(&state, put("dinner", "application/json", json)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(dir.join("dinner.json").exists());
+
+    // Number keys and all, forms are written as TOML as well.
+    state
+        .sources
+        .lock()
+        .unwrap()
+        .insert("dinner".into(), dir.join("dinner.toml"));
+    let json = serde_json::to_string(&titled("Supper")).unwrap();
+    let (status, body) = send(&state, put("dinner", "application/json", json)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let written = load_form(&dir.join("dinner.toml"), &Limits::default()).unwrap();
+    assert_eq!(written.title, titled("Supper").title);
+
+    let delete = as_admin(
+        Request::delete("/api/form/lunch")
+            .body(Body::empty())
+            .unwrap(),
+    );
+    assert_eq!(send(&state, delete).await.0, StatusCode::NO_CONTENT);
+    assert!(!dir.join("lunch.json").exists());
+    assert!(dir.join("lunch.json.unpublished").exists());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_puts_leave_one_whole_definition() {
+    let (state, dir) = state("concurrent");
+    let tasks: Vec<_> = (0..16)
+        .map(|index| {
+            let state = state.clone();
+            tokio::spawn(async move {
+                let yaml = serde_yaml::to_string(&titled(&format!("Version {index}"))).unwrap();
+                send(&state, put("lunch", "application/yaml", yaml)).await.0
+            })
+        })
+        .collect();
+    for task in tasks {
+        assert!(task.await.unwrap().is_success());
+    }
+    let written: Form =
+        serde_yaml::from_str(&std::fs::read_to_string(dir.join("lunch.yml")).unwrap()).unwrap();
+    let (_, html) = send(&state, get("/form/lunch")).await;
+    assert!(
+        html.contains(&format!("<h1>{}</h1>", written.text(&written.title))),
+        "{html}"
+    );
+}
@@ -0,0 +1,56 @@
+//! `/form/:id/version` reports the version hash of the definition currently
+//! served, the one logged when it was loaded.
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::{as_admin, get, send, with_admin};
+use serde_json::Value;
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::{demo_form, Form};
+
+fn renamed() -> Form {
+    Form {
+        title: "Another survey".into(),
+        ..demo_form()
+    }
+}
+
+async fn version(state: &Arc<AppState>) -> Value {
+    let (status, body) = send(state, get("/form/demo/version")).await;
+    assert_eq!(status, StatusCode::OK);
+    serde_json::from_str(&body).unwrap()
+}
+
+#[test]
+fn different_definitions_hash_differently() {
+    assert_eq!(demo_form().version(), demo_form().version());
+    assert_ne!(demo_form().version(), renamed().version());
+}
+
+#[tokio::test]
+async fn endpoint_follows_the_served_definition() {
+    let forms_dir = std::env::temp_dir().join("umfragetool-form-version-forms");
+    std::fs::create_dir_all(&forms_dir).unwrap();
+    let mut state = with_admin(common::state("form-version", [("demo", demo_form())]));
+    state.forms_dir = Some(forms_dir);
+    let state = Arc::new(state);
+
+    assert_eq!(
+        version(&state).await,
+        serde_json::json!({ "form_id": "demo", "version": demo_form().version() })
+    );
+
+    let request = Request::put("/api/form/demo")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_string(&renamed()).unwrap()))
+        .unwrap();
+    let (status, body) = send(&state, as_admin(request)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(version(&state).await["version"], renamed().version());
+
+    let request = get("/form/unknown/version");
+    assert_eq!(send(&state, request).await.0, StatusCode::NOT_FOUND);
+}
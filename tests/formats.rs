@@ -0,0 +1,148 @@
+//! Form definitions load from YAML, TOML and JSON alike, chosen by the file
+//! extension. One id in two formats is refused.
+
+use std::path::PathBuf;
+use umfragetool_rust::{load_form, Limits, LoadError};
+
+const YAML: &str = "\
+title: Lunch
+description: What do you eat?
+closes_at: 2024-05-08T08:00:00Z
+groups:
+  - title: Food
+    description: ''
+    questions:
+      - title: How hungry are you?
+        required: true
+        spec:
+          type: discrete_numeric
+          bounds: [1, 5]
+          num_descriptions: { 1: not at all, 5: very }
+      - title: What do you want?
+        spec:
+          type: multiple_choice
+          custom_answer: true
+          answers: [Pizza, Ravioli]
+";
+
+const TOML: &str = r#"
+title = "Lunch"
+description = "What do you eat?"
+closes_at = 2024-05-08T08:00:00Z
+
+[[groups]]
+title = "Food"
+description = ""
+
+[[groups.questions]]
+title = "How hungry are you?"
+required = true
+spec = { type = "discrete_numeric", bounds = [1, 5], num_descriptions = { 1 = "not at all", 5 = "very" } }
+
+[[groups.questions]]
+title = "What do you want?"
+spec = { type = "multiple_choice", custom_answer = true, answers = ["Pizza", "Ravioli"] }
+"#;
+
+const JSON: &str = r#"{
+  "title": "Lunch",
+  "description": "What do you eat?",
+  "closes_at": "2024-05-08T08:00:00Z",
+  "groups": [{
+    "title": "Food",
+    "description": "",
+    "questions": [
+      {
+        "title": "How hungry are you?",
+        "required": true,
+        "spec": { "type": "discrete_numeric", "bounds": [1, 5], "num_descriptions": { "1": "not at all", "5": "very" } }
+      },
+      {
+        "title": "What do you want?",
+        "spec": { "type": "multiple_choice", "custom_answer": true, "answers": ["Pizza", "Ravioli"] }
+      }
+    ]
+  }]
+}"#;
+
+fn write(name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("umfragetool-formats-{name}"));
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn every_format_gives_the_same_form() {
+    let yaml = load_form(&write("lunch.yml", YAML), &Limits::default()).unwrap();
+    for (name, content) in [("lunch.toml", TOML), ("lunch.json", JSON)] {
+        let other = load_form(&write(name, content), &Limits::default()).unwrap();
+        assert_eq!(other.version(), yaml.version(), "{name}");
+        assert_eq!(other.to_string(), yaml.to_string(), "{name}");
+    }
+    assert_eq!(
+        yaml.closes_at.unwrap().to_rfc3339(),
+        "2024-05-08T08:00:00+00:00"
+    );
+}
+
+#[test]
+fn unknown_extensions_are_refused() {
+    let err = load_form(&write("lunch.ini", YAML), &Limits::default()).unwrap_err();
+    assert!(matches!(&err, LoadError::UnknownFormat(ext) if ext == "ini"));
+    assert_eq!(
+        err.to_string(),
+        "unsupported form format 'ini' (expected .yml, .yaml, .toml or .json)"
+    );
+}
+
+#[test]
+fn toml_errors_point_at_their_line() {
+    let broken = TOML
+        .replace("closes_at = 2024-05-08T08:00:00Z", "")
+        .replace("bounds = [1, 5]", r#"bounds = "low""#);
+    let err = load_form(&write("broken.toml", &broken), &Limits::default()).unwrap_err();
+    assert!(matches!(err, LoadError::Parse(_)));
+    assert!(err.to_string().contains(" at line "), "{err}");
+
+    // With dates in it, the value is converted first and the cause is named
+    // without a position.
+    let broken = TOML.replace("bounds = [1, 5]", r#"bounds = "low""#);
+    let err = load_form(&write("dated.toml", &broken), &Limits::default()).unwrap_err();
+    assert!(err.to_string().contains(r#"string "low""#), "{err}");
+}
+
+#[test]
+fn one_id_in_two_formats_is_refused_at_startup() {
+    let dir = std::env::temp_dir().join("umfragetool-formats-duplicate");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("lunch.yml"), YAML).unwrap();
+    std::fs::write(dir.join("lunch.json"), JSON).unwrap();
+    let mut server = std::process::Command::new(env!("CARGO_BIN_EXE_umfragetool-rust"))
+        .arg("--forms-dir")
+        .arg(&dir)
+        .arg("--store")
+        .arg(dir.join("responses.jsonl"))
+        .args(["--bind", "127.0.0.1:0"])
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    let status = loop {
+        if let Some(status) = server.try_wait().unwrap() {
+            break status;
+        }
+        if std::time::Instant::now() > deadline {
+            server.kill().unwrap();
+            panic!("the server started with lunch.yml and lunch.json");
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    };
+    let mut stderr = String::new();
+    std::io::Read::read_to_string(&mut server.stderr.take().unwrap(), &mut stderr).unwrap();
+    assert_eq!(status.code(), Some(1), "{stderr}");
+    assert!(
+        stderr.contains("form id 'lunch' is already used by"),
+        "{stderr}"
+    );
+}
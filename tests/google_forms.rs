@@ -0,0 +1,81 @@
+//! Converting a representative Google Forms API export.
+
+use umfragetool_rust::*;
+
+fn converted() -> (Form, Vec<String>) {
+    google_forms::convert(include_str!("fixtures/google_forms.json")).expect("fixture parses")
+}
+
+#[test]
+fn sections_become_question_sets() {
+    let (form, _) = converted();
+    assert_eq!(form.text(&form.title), "Team offsite");
+    assert_eq!(
+        form.text(&form.description),
+        "Help us plan the next offsite."
+    );
+    let titles: Vec<&str> = form
+        .groups
+        .iter()
+        .map(|group| form.text(&group.title))
+        .collect();
+    assert_eq!(titles, ["", "Activities"]);
+    assert_eq!(form.groups[0].questions.len(), 2);
+    assert_eq!(form.groups[1].questions.len(), 4);
+    assert!(form.validate(&Limits::default()).is_ok());
+}
+
+#[test]
+fn items_map_to_question_types() {
+    let (form, _) = converted();
+    let specs: Vec<&QuestionType> = form.questions().map(|(_, q)| &q.spec).collect();
+    assert!(matches!(
+        specs[0],
+        QuestionType::Text { is_long: false, .. }
+    ));
+    match specs[1] {
+        QuestionType::SingleChoice {
+            answers,
+            custom_answer,
+            ..
+        } => {
+            let labels: Vec<&str> = answers.iter().map(|a| form.text(&a.label)).collect();
+            assert_eq!(labels, ["Mountains", "Seaside"]);
+            assert!(custom_answer);
+        }
+        other => panic!("expected single choice, got {other:?}"),
+    }
+    assert!(matches!(
+        specs[2],
+        QuestionType::MultipleChoice {
+            custom_answer: false,
+            ..
+        }
+    ));
+    assert!(matches!(specs[3], QuestionType::SingleChoice { .. }));
+    match specs[4] {
+        QuestionType::DiscreteNumeric {
+            bounds,
+            num_descriptions,
+            ..
+        } => {
+            assert_eq!(*bounds, (1, 5));
+            assert_eq!(form.text(&num_descriptions[&1]), "Meh");
+            assert_eq!(form.text(&num_descriptions[&5]), "Very");
+        }
+        other => panic!("expected scale, got {other:?}"),
+    }
+    assert!(matches!(specs[5], QuestionType::Text { is_long: true, .. }));
+}
+
+#[test]
+fn unsupported_items_are_reported() {
+    let (_, warnings) = converted();
+    assert_eq!(
+        warnings,
+        [
+            "'Rate the options': questionGroupItem is not supported, skipped",
+            "'Upload a photo': fileUploadQuestion is not supported, skipped",
+        ]
+    );
+}
@@ -0,0 +1,190 @@
+//! Forms with texts in several languages.
+
+mod common;
+
+use common::{get, send};
+use std::collections::HashMap;
+use std::sync::Arc;
+use umfragetool_rust::*;
+
+const FORM: &str = r#"
+title: { de: Umfrage, en: Survey }
+description: Plain for everyone
+default_language: de
+groups:
+  - title: { de: Essen, en: Food }
+    description: ""
+    questions:
+      - title: { de: Lieblingsessen?, en: Favourite food? }
+        spec:
+          type: single_choice
+          custom_answer: false
+          answers:
+            - label: { de: Nudeln, en: Noodles }
+              value: noodles
+"#;
+
+fn form() -> Form {
+    serde_yaml::from_str(FORM).unwrap()
+}
+
+#[test]
+fn language_is_chosen_from_request() {
+    let form = form();
+    assert_eq!(form.languages(), ["de", "en"]);
+    assert_eq!(i18n::choose(&form, Some("en"), None), "en");
+    assert_eq!(i18n::choose(&form, Some("fr"), Some("en-GB")), "en");
+    assert_eq!(
+        i18n::choose(&form, None, Some("fr, en;q=0.8, de;q=0.5")),
+        "en"
+    );
+    assert_eq!(i18n::choose(&form, None, Some("fr")), "de");
+    assert_eq!(i18n::choose(&form, None, None), "de");
+}
+
+#[test]
+fn texts_fall_back_to_default_language() {
+    let title: Localized = serde_yaml::from_str("{ de: Umfrage, en: Survey }").unwrap();
+    assert_eq!(title.get("en", "de"), "Survey");
+    assert_eq!(title.get("fr", "de"), "Umfrage");
+    assert_eq!(Localized::from("Survey").get("de", "en"), "Survey");
+}
+
+#[test]
+fn texts_parse_plain_or_per_language() {
+    let plain: Localized = serde_yaml::from_str("Survey").unwrap();
+    assert!(matches!(plain, Localized::Plain(ref text) if text == "Survey"));
+    let translated: Localized =
+        serde_json::from_str(r#"{"de": "Umfrage", "en": "Survey"}"#).unwrap();
+    assert_eq!(translated.get("de", "en"), "Umfrage");
+    assert!(serde_yaml::from_str::<Localized>("[Survey]").is_err());
+}
+
+#[tokio::test]
+async fn lang_parameter_selects_translation() {
+    let state = Arc::new(common::state("i18n-lang", [("food", form())]));
+    for (lang, title, option) in [("de", "Umfrage", "Nudeln"), ("en", "Survey", "Noodles")] {
+        let (_, html) = send(&state, get(&format!("/form/food?lang={lang}"))).await;
+        assert!(html.contains(&format!("<h1>{title}</h1>")), "{html}");
+        assert!(html.contains(option));
+        assert!(html.contains(&format!(
+            r#"<a href="/form/food?lang={lang}" hreflang="{lang}" lang="{lang}" aria-current="true">"#
+        )));
+    }
+}
+
+#[test]
+fn switcher_links_every_language() {
+    let html = form().to_string();
+    assert!(html.contains(r#"<nav class="languages" aria-label="Language"><a href="?lang=de" hreflang="de" lang="de" aria-current="true">Deutsch</a> | <a href="?lang=en" hreflang="en" lang="en">English</a></nav>"#), "{html}");
+    assert!(!demo_form().to_string().contains("<nav"));
+}
+
+#[test]
+fn form_renders_in_chosen_language_and_stores_values() {
+    let form = form();
+    let ctx = RenderContext {
+        lang: "en".to_string(),
+        ..Default::default()
+    };
+    let html = form.render(ROOT_PREFIX.to_string(), &ctx);
+    assert!(html.contains(r#"<html lang="en""#));
+    for text in ["Survey", "Food", "Favourite food?", "Noodles", "Submit"] {
+        assert!(html.contains(text), "{text} missing");
+    }
+    assert!(html.contains(r#"value="noodles""#));
+    assert!(!html.contains("Nudeln"));
+}
+
+#[test]
+fn validation_messages_are_translated() {
+    let form = form();
+    let answers = HashMap::from([("i-0-0".to_string(), vec!["pizza".to_string()])]);
+    let errors = form.validate_submission_in(&answers, "de").unwrap_err();
+    assert_eq!(errors[0].message, "'pizza' ist keine der Optionen");
+}
+
+#[test]
+fn form_messages_replace_the_built_in_ones() {
+    let form = Form {
+        messages: serde_yaml::from_str(
+            r#"
+not_an_option: { de: "'{value}' gibt es heute nicht" }
+required: Bitte ausfüllen!
+"#,
+        )
+        .unwrap(),
+        ..form()
+    };
+    let answers = HashMap::from([("i-0-0".to_string(), vec!["pizza".to_string()])]);
+    let message = |lang| {
+        form.validate_submission_in(&answers, lang).unwrap_err()[0]
+            .message
+            .clone()
+    };
+    assert_eq!(message("de"), "'pizza' gibt es heute nicht");
+    // Only given in German, so English keeps the built-in message.
+    assert_eq!(message("en"), "'pizza' is not one of the options");
+
+    let ctx = RenderContext {
+        lang: "en".to_string(),
+        ..Default::default()
+    };
+    let html = form.render(ROOT_PREFIX.to_string(), &ctx);
+    assert!(
+        html.contains(r#"data-msg-required="Bitte ausfüllen!""#),
+        "{html}"
+    );
+    assert!(html.contains(r#"data-msg-match-format="please match the requested format""#));
+
+    let unknown: Result<Form, _> =
+        serde_yaml::from_str(&format!("{FORM}\nmessages:\n  too_short: Zu kurz\n"));
+    assert!(unknown.is_err());
+}
+
+#[test]
+fn numbers_are_parsed_in_both_locales() {
+    let table = [
+        ("1.234,5", Some(1234.5), Some(1234.5)),
+        ("1,234.5", Some(1234.5), Some(1234.5)),
+        ("3,5", Some(3.5), Some(3.5)),
+        ("3.5", Some(3.5), Some(3.5)),
+        ("1,234", Some(1234.0), Some(1.234)),
+        ("1.234", Some(1.234), Some(1234.0)),
+        ("1\u{2009}234,5", Some(1234.5), Some(1234.5)),
+        ("-0,5", Some(-0.5), Some(-0.5)),
+        ("12", Some(12.0), Some(12.0)),
+        ("", None, None),
+        ("abc", None, None),
+        ("1,2,3", None, None),
+        ("1.2.3,4", None, None),
+        ("3,5,", None, None),
+        ("1e5", None, None),
+    ];
+    for (input, en, de) in table {
+        assert_eq!(i18n::parse_number(input, "en"), en, "{input} in en");
+        assert_eq!(i18n::parse_number(input, "de"), de, "{input} in de");
+    }
+}
+
+#[test]
+fn numbers_are_normalized_before_validation() {
+    let form: Form = serde_yaml::from_str(
+        r#"
+title: Form
+description: ""
+groups:
+  - title: Set
+    description: ""
+    questions:
+      - title: How much?
+        spec: { type: continous_numeric, bounds: [0, 10] }
+"#,
+    )
+    .unwrap();
+    let mut answers = HashMap::from([("i-0-0".to_string(), vec!["3,5".to_string()])]);
+    assert!(form.validate_submission(&answers).is_err());
+    form.normalize_answers(&mut answers, "de");
+    assert_eq!(answers["i-0-0"], ["3.5"]);
+    assert!(form.validate_submission(&answers).is_ok());
+}
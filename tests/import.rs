@@ -0,0 +1,180 @@
+//! `import-responses` reads paper responses from CSV, validates each row
+//! like a live submission and stores the accepted ones as imported.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{as_admin, get, send, with_admin};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+use umfragetool_rust::import::read_responses;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::store::{Source, SubmissionStore};
+use umfragetool_rust::{demo_form, Form};
+
+/// The demo form, with an answer to its first question required.
+fn form() -> Form {
+    let mut form = demo_form();
+    form.groups[0].questions[0].required = true;
+    form
+}
+
+const RESPONSES: &str = "\
+why would you do this?,i-0-2,i-0-3,i-0-4,Clerk
+Because,7,Ravioli,Pizza; MAOAM,AB
+,3,Pizza,,AB
+Hunger,11,,,CD
+";
+
+fn mapping() -> HashMap<String, Option<String>> {
+    HashMap::from([("Clerk".to_string(), None)])
+}
+
+#[test]
+fn rows_are_matched_and_validated() {
+    let (rows, errors) = read_responses(&form(), RESPONSES.as_bytes(), &mapping()).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].line, 2);
+    assert_eq!(
+        rows[0].answers,
+        HashMap::from([
+            ("i-0-0".to_string(), vec!["Because".to_string()]),
+            ("i-0-2".to_string(), vec!["7".to_string()]),
+            ("i-0-3".to_string(), vec!["Ravioli".to_string()]),
+            (
+                "i-0-4".to_string(),
+                vec!["Pizza".to_string(), "MAOAM".to_string()]
+            ),
+        ])
+    );
+    let errors: Vec<_> = errors
+        .iter()
+        .map(|error| (error.line, error.message.as_str()))
+        .collect();
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    assert_eq!(errors[0].0, 3);
+    assert!(
+        errors[0].1.starts_with("Why would you do this?: "),
+        "{errors:?}"
+    );
+    assert_eq!(errors[1].0, 4);
+    assert!(errors[1].1.starts_with("What do you want?: "), "{errors:?}");
+}
+
+#[test]
+fn headers_have_to_name_one_question() {
+    let form = form();
+    let read = |csv: &str, mapping: &HashMap<String, Option<String>>| {
+        read_responses(&form, csv.as_bytes(), mapping).map(|(rows, _)| rows.len())
+    };
+    assert_eq!(
+        read("i-0-0,Clerk\nBecause,AB\n", &HashMap::new()).unwrap_err(),
+        "column 'Clerk' does not match any question"
+    );
+    assert_eq!(
+        read("i-0-0,What do you want?\nBecause,Pizza\n", &HashMap::new()).unwrap_err(),
+        "column 'What do you want?' matches several questions (i-0-2, i-0-3, i-0-4), map it to a question id"
+    );
+    let mapping = HashMap::from([("Food".to_string(), Some("i-0-3".to_string()))]);
+    assert_eq!(read("i-0-0,Food\nBecause,Pizza\n", &mapping), Ok(1));
+}
+
+#[test]
+fn labels_are_stored_by_value() {
+    let form: Form = serde_yaml::from_str(
+        r#"
+title: Lunch
+description: ""
+groups:
+  - title: Food
+    description: ""
+    questions:
+      - title: Main course
+        spec:
+          type: single_choice
+          custom_answer: false
+          answers: [{ label: Pizza, value: pz }, { label: Ravioli, value: rav }]
+"#,
+    )
+    .unwrap();
+    let (rows, errors) = read_responses(
+        &form,
+        "Main course\nRavioli\npz\nLasagne\n".as_bytes(),
+        &HashMap::new(),
+    )
+    .unwrap();
+    let values: Vec<_> = rows
+        .iter()
+        .map(|row| row.answers["i-0-0"][0].as_str())
+        .collect();
+    assert_eq!(values, ["rav", "pz"]);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line, 4);
+}
+
+fn dir() -> PathBuf {
+    let dir = std::env::temp_dir().join("umfragetool-import");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("demo.yml"),
+        serde_yaml::to_string(&form()).unwrap(),
+    )
+    .unwrap();
+    std::fs::write(dir.join("responses.csv"), RESPONSES).unwrap();
+    std::fs::write(dir.join("mapping.yml"), "Clerk: null\n").unwrap();
+    dir
+}
+
+fn import(dir: &std::path::Path, dry_run: bool) -> std::process::Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_umfragetool-rust"));
+    command
+        .current_dir(dir)
+        .args(["import-responses", "demo.yml", "responses.csv"])
+        .args(["--mapping", "mapping.yml", "--store", "responses.jsonl"]);
+    if dry_run {
+        command.arg("--dry-run");
+    }
+    command.output().unwrap()
+}
+
+#[tokio::test]
+async fn imported_rows_are_stored_and_exported() {
+    let dir = dir();
+    let output = import(&dir, true);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(stdout, "would import 1 responses, rejected 2 rows\n");
+    assert!(stderr.contains("responses.csv:3: "), "{stderr}");
+    assert!(stderr.contains("responses.csv:4: "), "{stderr}");
+    // Rejected rows fail the command, even when the others are fine.
+    assert!(!output.status.success());
+    assert!(!dir.join("responses.jsonl").exists());
+
+    let output = import(&dir, false);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "imported 1 responses, rejected 2 rows\n"
+    );
+    let store = SubmissionStore::open(&dir.join("responses.jsonl")).unwrap();
+    let submissions = store.list("demo");
+    assert_eq!(submissions.len(), 1);
+    assert_eq!(submissions[0].source, Source::Import);
+    assert_eq!(
+        submissions[0].form_version.as_deref(),
+        Some(form().version().as_str())
+    );
+
+    let state = AppState::new(HashMap::from([("demo".to_string(), form())]), store);
+    let request = as_admin(get("/export/demo.json"));
+    let (status, body) = send(&Arc::new(with_admin(state)), request).await;
+    assert_eq!(status, StatusCode::OK);
+    let export: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(export[0]["source"], "import");
+    assert_eq!(
+        export[0]["answers"]["i-0-4"],
+        serde_json::json!(["Pizza", "MAOAM"])
+    );
+}
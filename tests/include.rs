@@ -0,0 +1,170 @@
+//! `groups` entries `{ include: path }` are replaced by the question sets in
+//! that file, as if they were written out in the form.
+
+use std::path::{Path, PathBuf};
+use umfragetool_rust::{load_form, Limits, LoadError};
+
+const DEMOGRAPHICS: &str = "\
+title: About you
+description: ''
+questions:
+  - title: How old are you?
+    spec:
+      type: continous_numeric
+      bounds: [0, 120]
+";
+
+const FEEDBACK: &str = "\
+- title: Feedback
+  description: ''
+  questions:
+    - title: What went well?
+      spec:
+        type: text
+        is_long: true
+";
+
+/// A fresh directory holding `files`.
+fn dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("umfragetool-include-{name}"));
+    let _ = std::fs::remove_dir_all(&dir);
+    for (path, content) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    dir
+}
+
+fn form(groups: &str) -> String {
+    format!("title: Survey\ndescription: ''\ngroups:\n{groups}")
+}
+
+fn load(path: &Path) -> Result<umfragetool_rust::Form, LoadError> {
+    load_form(path, &Limits::default())
+}
+
+#[test]
+fn included_sets_are_the_same_as_inline_ones() {
+    let inline = form(
+        "  - title: About you
+    description: ''
+    questions:
+      - title: How old are you?
+        spec:
+          type: continous_numeric
+          bounds: [0, 120]
+  - title: Feedback
+    description: ''
+    questions:
+      - title: What went well?
+        spec:
+          type: text
+          is_long: true
+",
+    );
+    let dir = dir(
+        "inline",
+        &[
+            ("inline.yml", &inline),
+            (
+                "survey.yml",
+                &form("  - include: shared/demographics.yml\n  - include: shared/feedback.yml\n"),
+            ),
+            ("shared/demographics.yml", DEMOGRAPHICS),
+            ("shared/feedback.yml", FEEDBACK),
+        ],
+    );
+    let inline = load(&dir.join("inline.yml")).unwrap();
+    let included = load(&dir.join("survey.yml")).unwrap();
+    assert_eq!(included.groups.len(), 2);
+    assert_eq!(included.version(), inline.version());
+    assert_eq!(included.to_string(), inline.to_string());
+}
+
+#[test]
+fn includes_nest_relative_to_their_file() {
+    let dir = dir(
+        "nested",
+        &[
+            ("survey.json", r#"{"title": "Survey", "description": "", "groups": [{"include": "shared/all.yml"}]}"#),
+            ("shared/all.yml", "- include: parts/demographics.yml\n- include: ../feedback.toml\n"),
+            ("shared/parts/demographics.yml", DEMOGRAPHICS),
+            (
+                "feedback.toml",
+                "title = 'Feedback'\ndescription = ''\n[[questions]]\ntitle = 'What went well?'\nspec = { type = 'text', is_long = true }\n",
+            ),
+        ],
+    );
+    let form = load(&dir.join("survey.json")).unwrap();
+    let titles: Vec<_> = form
+        .groups
+        .iter()
+        .map(|group| group.title.get("en", "en"))
+        .collect();
+    assert_eq!(titles, ["About you", "Feedback"]);
+}
+
+#[test]
+fn errors_name_the_included_file() {
+    let dir = dir(
+        "missing",
+        &[
+            ("survey.yml", &form("  - include: outer.yml\n")),
+            ("outer.yml", "- include: gone.yml\n"),
+            ("broken.yml", &form("  - include: bad.yml\n")),
+            ("bad.yml", "title: No questions\ndescription: ''\n"),
+        ],
+    );
+    let err = load(&dir.join("survey.yml")).unwrap_err();
+    let message = err.to_string();
+    assert!(
+        matches!(&err, LoadError::Include(path, inner)
+            if path.ends_with("outer.yml") && matches!(**inner, LoadError::Include(..))),
+        "{message}"
+    );
+    assert!(message.contains("outer.yml: in "), "{message}");
+    assert!(message.contains("gone.yml: "), "{message}");
+
+    let message = load(&dir.join("broken.yml")).unwrap_err().to_string();
+    assert!(message.contains("bad.yml: "), "{message}");
+    assert!(message.contains("questions"), "{message}");
+}
+
+#[test]
+fn cycles_are_reported() {
+    let dir = dir(
+        "cycle",
+        &[
+            ("survey.yml", &form("  - include: a.yml\n")),
+            ("a.yml", "- include: b.yml\n"),
+            ("b.yml", "- include: a.yml\n"),
+        ],
+    );
+    let err = load(&dir.join("survey.yml")).unwrap_err();
+    let LoadError::IncludeCycle(chain) = &err else {
+        panic!("{err}");
+    };
+    let names: Vec<_> = chain
+        .iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, ["survey.yml", "a.yml", "b.yml", "a.yml"]);
+    assert!(err.to_string().contains("a.yml -> "), "{err}");
+}
+
+#[test]
+fn include_stands_alone() {
+    let dir = dir(
+        "extra",
+        &[
+            (
+                "survey.yml",
+                &form("  - include: part.yml\n    title: Mine\n"),
+            ),
+            ("part.yml", DEMOGRAPHICS),
+        ],
+    );
+    let err = load(&dir.join("survey.yml")).unwrap_err();
+    assert!(err.to_string().contains("other fields"), "{err}");
+}
@@ -0,0 +1,21 @@
+//! An `intro` is shown before the questions, which a button reveals.
+
+use umfragetool_rust::*;
+
+#[test]
+fn intro_page_only_when_set() {
+    let mut form = demo_form();
+    let html = form.to_string();
+    assert!(!html.contains(r#"id="intro""#));
+    assert!(!html.contains(r#"class="start""#));
+    assert!(!html.contains(" hidden>"));
+
+    form.intro = Some("By taking part you **agree**.\n\n<b>raw</b>".into());
+    let html = form.to_string();
+    assert!(
+        html.contains(r#"<section id="intro"><p>By taking part you <strong>agree</strong>.</p>"#)
+    );
+    assert!(html.contains("&lt;b&gt;raw&lt;/b&gt;"));
+    assert!(html.contains(r#"<button type="button" class="start""#));
+    assert!(html.contains(r#"<div class="content" id="questions" hidden>"#));
+}
@@ -0,0 +1,61 @@
+//! `/export/:form_id.json` is a single JSON array of the submissions, for
+//! tools that load one document.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{get, send, submit, with_admin, with_token};
+use std::sync::Arc;
+use umfragetool_rust::demo_form;
+use umfragetool_rust::server::AppState;
+
+async fn state(name: &str) -> Arc<AppState> {
+    let state = Arc::new(with_admin(common::state(
+        &format!("json-export-{name}"),
+        [("demo", demo_form())],
+    )));
+    for body in [
+        "i-0-0=Because&i-0-4=Pizza&i-0-4=MAOAM",
+        "i-0-0=Why+not&i-0-3=Ravioli&i-0-4=Ravioli",
+    ] {
+        assert_eq!(submit(&state, "demo", body).await.0, StatusCode::OK);
+    }
+    state
+}
+
+async fn export(state: &Arc<AppState>, token: Option<&str>) -> (StatusCode, String) {
+    let request = with_token(get("/export/demo.json"), token);
+    send(state, request).await
+}
+
+#[tokio::test]
+async fn submissions_come_back_as_one_array() {
+    let state = state("array").await;
+    let (status, body) = export(&state, Some("secret")).await;
+    assert_eq!(status, StatusCode::OK);
+    let export: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let mut answers: Vec<_> = export
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|submission| submission["answers"].clone())
+        .collect();
+    answers.sort_by_key(|answers| answers["i-0-0"].to_string());
+    assert_eq!(
+        answers,
+        [
+            serde_json::json!({ "i-0-0": "Because", "i-0-4": ["Pizza", "MAOAM"] }),
+            serde_json::json!({ "i-0-0": "Why not", "i-0-3": "Ravioli", "i-0-4": ["Ravioli"] }),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn export_needs_admin() {
+    let state = state("admin").await;
+    assert_eq!(export(&state, None).await.0, StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        export(&state, Some("wrong")).await.0,
+        StatusCode::UNAUTHORIZED
+    );
+}
@@ -0,0 +1,109 @@
+//! `stats/keywords` ranks the words and word pairs in the answers to a text
+//! question, leaving out the built-in and the form's own stopwords.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{accept_json, as_admin, get, send, submission, with_admin};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::{demo_form, Form};
+
+async fn state(name: &str, form: Form, answers: &[&str]) -> Arc<AppState> {
+    let state = with_admin(common::state(&format!("keywords-{name}"), [("demo", form)]));
+    for (index, answer) in answers.iter().enumerate() {
+        let submission = submission("demo", &index.to_string(), &[("i-0-0", &[answer])]);
+        state.store.insert(submission, None).await.unwrap();
+    }
+    Arc::new(state)
+}
+
+async fn keywords(state: &Arc<AppState>, query: &str) -> (StatusCode, Value) {
+    let request = as_admin(get(&format!("/form/demo/stats/keywords?{query}")));
+    let (status, body) = send(state, accept_json(request)).await;
+    (status, serde_json::from_str(&body).unwrap_or(Value::Null))
+}
+
+const ANSWERS: &[&str] = &[
+    "Kalte Pizza, kalte Pizza 🍕🍕 und teurer Kaffee.",
+    "The cold coffee was the WORST",
+    "",
+    "  !!! 🍕 ",
+];
+
+#[tokio::test]
+async fn words_and_pairs_are_ranked_without_stopwords() {
+    let state = state("ranked", demo_form(), ANSWERS).await;
+    let (status, keywords) = keywords(&state, "question=i-0-0&top=4").await;
+    assert_eq!(status, StatusCode::OK);
+    // Answers without any words are not counted.
+    assert_eq!(keywords["answers"], 2);
+    assert_eq!(
+        keywords["terms"],
+        json!([
+            { "term": "kalte", "count": 2 },
+            { "term": "pizza", "count": 2 },
+            { "term": "coffee", "count": 1 },
+            { "term": "cold", "count": 1 },
+        ])
+    );
+    assert_eq!(
+        keywords["bigrams"],
+        json!([
+            { "term": "kalte pizza", "count": 2 },
+            { "term": "cold coffee", "count": 1 },
+            { "term": "pizza kalte", "count": 1 },
+            { "term": "teurer kaffee", "count": 1 },
+        ])
+    );
+}
+
+#[tokio::test]
+async fn forms_add_their_own_stopwords() {
+    let form = Form {
+        stopwords: vec!["PIZZA".to_string(), "Kalte".to_string()],
+        ..demo_form()
+    };
+    let state = state("stopwords", form, ANSWERS).await;
+    let (_, keywords) = keywords(&state, "question=i-0-0").await;
+    let terms: Vec<_> = keywords["terms"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|term| term["term"].as_str().unwrap())
+        .collect();
+    assert_eq!(terms, ["coffee", "cold", "kaffee", "teurer", "worst"]);
+    let bigrams: Vec<_> = keywords["bigrams"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|term| term["term"].as_str().unwrap())
+        .collect();
+    assert_eq!(bigrams, ["cold coffee", "teurer kaffee"]);
+}
+
+#[tokio::test]
+async fn long_pastes_are_counted() {
+    let paste = "Mensa ".repeat(100_000);
+    let state = state("paste", demo_form(), &[&paste]).await;
+    let (status, keywords) = keywords(&state, "question=i-0-0&top=1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        keywords["terms"],
+        json!([{ "term": "mensa", "count": 100_000 }])
+    );
+    assert_eq!(
+        keywords["bigrams"],
+        json!([{ "term": "mensa mensa", "count": 99_999 }])
+    );
+}
+
+#[tokio::test]
+async fn only_text_questions_are_summarized() {
+    let state = state("kind", demo_form(), &[]).await;
+    let (status, _) = keywords(&state, "question=i-0-3").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = keywords(&state, "question=i-9-9").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
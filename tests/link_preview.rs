@@ -0,0 +1,68 @@
+//! Form pages describe themselves to link previews with OpenGraph tags, and
+//! `/favicon.ico` is always there.
+
+mod common;
+
+use axum::http::{header, StatusCode};
+use std::sync::Arc;
+use tower::ServiceExt;
+use umfragetool_rust::server::{self, AppState, Favicon};
+use umfragetool_rust::{demo_form, Form};
+
+fn state(name: &str, form: Form) -> AppState {
+    common::state(&format!("link-preview-{name}"), [("demo", form)])
+}
+
+async fn get(state: Arc<AppState>, uri: &str) -> (StatusCode, String, Vec<u8>) {
+    let response = server::router(state)
+        .oneshot(common::get(uri))
+        .await
+        .unwrap();
+    let status = response.status();
+    let content_type = response.headers()[header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, content_type, body.to_vec())
+}
+
+#[tokio::test]
+async fn head_carries_the_form_title_description_and_image() {
+    let form = Form {
+        title: "Lunch & more".into(),
+        description: "Tell us what you eat.".into(),
+        image: Some("https://example.org/lunch.png".to_string()),
+        ..demo_form()
+    };
+    let (status, _, body) = get(Arc::new(state("tags", form)), "/form/demo").await;
+    assert_eq!(status, StatusCode::OK);
+    let html = String::from_utf8(body).unwrap();
+    assert!(
+        html.contains(r#"<meta property="og:title" content="Lunch &amp; more">"#),
+        "{html}"
+    );
+    assert!(html.contains(r#"<meta property="og:description" content="Tell us what you eat.">"#));
+    assert!(html.contains(r#"<meta property="og:image" content="https://example.org/lunch.png">"#));
+    assert!(html.contains(r#"<link rel="icon" href="/favicon.ico">"#));
+
+    let (_, _, body) = get(Arc::new(state("no-image", demo_form())), "/form/demo").await;
+    assert!(!String::from_utf8(body).unwrap().contains("og:image"));
+}
+
+#[tokio::test]
+async fn favicon_is_bundled_or_configured() {
+    let (status, content_type, body) =
+        get(Arc::new(state("icon", demo_form())), "/favicon.ico").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "image/x-icon");
+    assert_eq!(body, Favicon::default().bytes);
+
+    let path = std::env::temp_dir().join("umfragetool-link-preview-icon.svg");
+    std::fs::write(&path, "<svg/>").unwrap();
+    let mut state = state("svg", demo_form());
+    state.favicon = Favicon::load(&path).unwrap();
+    let (_, content_type, body) = get(Arc::new(state), "/favicon.ico").await;
+    assert_eq!(content_type, "image/svg+xml");
+    assert_eq!(body, b"<svg/>");
+}
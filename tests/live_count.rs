@@ -0,0 +1,173 @@
+//! `/form/:id/events` streams the response count of forms that publish it,
+//! with the options of their `live_question`, and `/form/:id/live` shows it.
+
+mod common;
+
+use axum::http::{header, StatusCode};
+use axum::response::Response;
+use hyper::body::HttpBody;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+use umfragetool_rust::server::{self, AppState};
+use umfragetool_rust::{demo_form, Form, Limits};
+
+fn poll_form() -> Form {
+    Form {
+        public_count: true,
+        live_question: Some("i-0-3".to_string()),
+        ..demo_form()
+    }
+}
+
+fn state(name: &str, form: Form) -> AppState {
+    common::state(&format!("live-{name}"), [("demo", form)])
+}
+
+async fn get(state: &Arc<AppState>, uri: &str) -> Response {
+    let response = server::router(state.clone())
+        .oneshot(common::get(uri))
+        .await
+        .unwrap();
+    response.map(axum::body::boxed)
+}
+
+async fn submit(state: &Arc<AppState>, body: &str) {
+    assert_eq!(common::submit(state, "demo", body).await.0, StatusCode::OK);
+}
+
+/// The data of the next event on `stream`.
+async fn next_event(stream: &mut Response) -> Value {
+    let chunk = tokio::time::timeout(Duration::from_secs(5), stream.body_mut().data())
+        .await
+        .expect("no event")
+        .unwrap()
+        .unwrap();
+    let text = std::str::from_utf8(&chunk).unwrap();
+    let data = text.strip_prefix("data:").unwrap().trim();
+    serde_json::from_str(data).unwrap()
+}
+
+#[tokio::test]
+async fn events_follow_the_responses() {
+    let state = Arc::new(state("follow", poll_form()));
+    let mut stream = get(&state, "/form/demo/events").await;
+    assert_eq!(stream.status(), StatusCode::OK);
+    assert_eq!(stream.headers()[header::CONTENT_TYPE], "text/event-stream");
+    let event = next_event(&mut stream).await;
+    assert_eq!(event["responses"], 0);
+    assert_eq!(event["question"]["id"], "i-0-3");
+    assert_eq!(
+        event["question"]["counts"][0],
+        json!({ "value": "Pizza", "count": 0 })
+    );
+
+    submit(&state, "i-0-0=Because&i-0-3=Pizza").await;
+    let event = next_event(&mut stream).await;
+    assert_eq!(event["responses"], 1);
+    assert_eq!(
+        event["question"]["counts"][0],
+        json!({ "value": "Pizza", "count": 1 })
+    );
+
+    // Without a live question only the count is sent.
+    let state = Arc::new(state_without_question());
+    let mut stream = get(&state, "/form/demo/events").await;
+    assert_eq!(next_event(&mut stream).await, json!({ "responses": 0 }));
+}
+
+fn state_without_question() -> AppState {
+    state(
+        "count-only",
+        Form {
+            public_count: true,
+            ..demo_form()
+        },
+    )
+}
+
+#[tokio::test]
+async fn streams_are_capped_per_form_and_freed_on_disconnect() {
+    let mut state = state("cap", poll_form());
+    state.max_event_streams = 1;
+    let state = Arc::new(state);
+    let first = get(&state, "/form/demo/events").await;
+    assert_eq!(first.status(), StatusCode::OK);
+    let second = get(&state, "/form/demo/events").await;
+    assert_eq!(second.status(), StatusCode::SERVICE_UNAVAILABLE);
+    drop(first);
+    let third = get(&state, "/form/demo/events").await;
+    assert_eq!(third.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn only_published_counts_are_streamed() {
+    let state = Arc::new(state("private", demo_form()));
+    assert_eq!(
+        get(&state, "/form/demo/events").await.status(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        get(&state, "/form/demo/live").await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+async fn page(state: AppState) -> String {
+    let state = Arc::new(state);
+    submit(&state, "i-0-0=Because&i-0-3=Ravioli").await;
+    let response = get(&state, "/form/demo/live").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn live_page_shows_the_count_and_follows_the_stream() {
+    let html = page(state("page", poll_form())).await;
+    assert!(
+        html.contains(r#"<span id="responses">1</span> responses"#),
+        "{html}"
+    );
+    assert!(html.contains("<h2>What do you want?</h2>"), "{html}");
+    assert!(html.contains("<li>Ravioli: 1</li>"), "{html}");
+    assert!(html.contains("new EventSource("), "{html}");
+
+    let mut state = state("page-no-js", poll_form());
+    state.no_js = true;
+    let html = page(state).await;
+    assert!(html.contains(r#"<meta http-equiv="refresh""#), "{html}");
+    assert!(!html.contains("<script"), "{html}");
+}
+
+#[test]
+fn live_questions_have_to_be_published_options() {
+    let problems = |form: Form| {
+        form.validate(&Limits::default())
+            .map_err(|err| err.problems)
+            .unwrap_err()
+    };
+    assert_eq!(
+        problems(Form {
+            live_question: Some("i-0-3".to_string()),
+            ..demo_form()
+        }),
+        ["live_question i-0-3 is only shown with public_count"]
+    );
+    assert_eq!(
+        problems(Form {
+            live_question: Some("i-0-0".to_string()),
+            ..poll_form()
+        }),
+        ["live_question i-0-0 (Why would you do this?) is a text question; only choices and scales have options to count"]
+    );
+    assert_eq!(
+        problems(Form {
+            live_question: Some("i-9-9".to_string()),
+            ..poll_form()
+        }),
+        ["live_question i-9-9 is no question of the form"]
+    );
+    assert!(poll_form().validate(&Limits::default()).is_ok());
+}
@@ -0,0 +1,83 @@
+//! Golden-file snapshots of form definitions rendered to Markdown. After an
+//! intended change to the output, review and accept the new snapshots with
+//! `cargo insta review`.
+
+use umfragetool_rust::markdown::to_markdown;
+use umfragetool_rust::*;
+
+/// One question of every type, with the notes each of them can carry.
+const FORM: &str = r#"
+title: Lunch
+description: What we eat on Fridays.
+groups:
+  - title: Food
+    description: ""
+    questions:
+      - title: How hungry are you?
+        spec:
+          type: discrete_numeric
+          bounds: [1, 5]
+          num_descriptions: { 1: not at all, 5: very }
+          na_option: no idea
+      - title: How much would you pay?
+        spec: { type: continous_numeric, bounds: [0.5, 20] }
+      - title: How many courses?
+        spec: { type: continous_numeric }
+      - title: Main course
+        spec:
+          type: single_choice
+          custom_answer: true
+          answers:
+            - Pizza
+            - { label: Ravioli, value: rav, score: 2 }
+      - title: Toppings
+        spec: { type: multiple_choice, custom_answer: false, answers: [Cheese, Olives] }
+  - title: About you
+    description: Optional, as ever.
+    questions:
+      - title: Postcode
+        spec: { type: text, is_long: false, pattern: "[0-9]{5}" }
+      - title: Anything else?
+        spec: { type: text, is_long: true, max_length: 500 }
+      - title: Appetite
+        spec: { type: computed, expression: "{i-0-0} * 2" }
+      - title: Seating plan
+        spec: { type: matrix, rows: [A, B] }
+"#;
+
+#[test]
+fn every_question_type() {
+    let form: Form = serde_yaml::from_str(FORM).unwrap();
+    insta::assert_snapshot!(to_markdown(&form));
+}
+
+#[test]
+fn demo_form_markdown() {
+    insta::assert_snapshot!(to_markdown(&demo_form()));
+}
+
+#[test]
+fn markdown_in_titles_and_options_is_escaped() {
+    let form: Form = serde_yaml::from_str(
+        r##"
+title: "*Lunch* [poll]"
+description: "# not a heading"
+groups:
+  - title: Set_1
+    description: "1. not a list"
+    questions:
+      - title: "`code` <b>"
+        spec: { type: single_choice, custom_answer: false, answers: ["a|b", "- c"] }
+"##,
+    )
+    .unwrap();
+    let markdown = to_markdown(&form);
+    assert!(markdown.starts_with("# \\*Lunch\\* \\[poll\\]\n\n\\# not a heading\n\n"));
+    assert!(
+        markdown.contains("## Set\\_1\n\n1\\. not a list\n\n"),
+        "{markdown}"
+    );
+    assert!(markdown.contains("1. **\\`code\\` \\<b\\>**"), "{markdown}");
+    assert!(markdown.contains("   - a\\|b\n"), "{markdown}");
+    assert!(markdown.contains("   - - c\n"), "{markdown}");
+}
@@ -0,0 +1,107 @@
+//! `max_responses` closes a form once it has that many submissions; edits
+//! of the stored ones are still accepted.
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{accept_json, get, post, send};
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::{demo_form, Form};
+
+fn state(name: &str, form: Form) -> Arc<AppState> {
+    Arc::new(common::state(
+        &format!("max-responses-{name}"),
+        [("demo", form)],
+    ))
+}
+
+fn submit(body: &str) -> Request<Body> {
+    accept_json(post("/submit/demo", body))
+}
+
+#[tokio::test]
+async fn submission_after_the_cap_is_refused() {
+    let state = state(
+        "cap",
+        Form {
+            max_responses: Some(2),
+            ..demo_form()
+        },
+    );
+    for _ in 0..2 {
+        let (status, _) = send(&state, submit("i-0-0=Because")).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let (status, body) = send(&state, submit("i-0-0=Because")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body, r#"{"fields":[],"status":"error"}"#);
+    assert_eq!(state.store.list("demo").len(), 2);
+
+    let (status, html) = send(&state, get("/form/demo")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains("This survey is full"), "{html}");
+    assert!(!html.contains("<input"), "{html}");
+}
+
+#[tokio::test]
+async fn full_forms_take_edits_but_no_forged_ones() {
+    let state = state(
+        "edits",
+        Form {
+            max_responses: Some(1),
+            editable: true,
+            ..demo_form()
+        },
+    );
+    let (_, body) = send(&state, submit("i-0-0=Because")).await;
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let token = body["edit_url"]
+        .as_str()
+        .unwrap()
+        .rsplit('/')
+        .next()
+        .unwrap();
+
+    let (status, _) = send(&state, submit("i-0-0=Sneaky&_edit=forged")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&state, submit(&format!("i-0-0=Changed&_edit={token}"))).await;
+    assert_eq!(status, StatusCode::OK);
+    let stored = state.store.list("demo");
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].answers["i-0-0"], ["Changed"]);
+}
+
+#[tokio::test]
+async fn saved_sets_count_towards_the_cap() {
+    let state = state(
+        "groups",
+        Form {
+            max_responses: Some(1),
+            per_group_submit: true,
+            ..demo_form()
+        },
+    );
+    let save = |session: &str| {
+        post(
+            "/submit/demo/0",
+            &format!("i-0-0=Because&_session={session}"),
+        )
+    };
+    let session = || async {
+        let (_, html) = send(&state, get("/form/demo")).await;
+        regex::Regex::new(r#"name="_session" value="([^"]+)""#)
+            .unwrap()
+            .captures(&html)
+            .unwrap_or_else(|| panic!("no session in {html}"))[1]
+            .to_string()
+    };
+    let first = session().await;
+    let second = session().await;
+    assert_eq!(send(&state, save(&first)).await.0, StatusCode::NO_CONTENT);
+    // The session already stored keeps saving its sets.
+    assert_eq!(send(&state, save(&first)).await.0, StatusCode::NO_CONTENT);
+    assert_eq!(send(&state, save(&second)).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(state.store.list("demo").len(), 1);
+}
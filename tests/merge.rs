@@ -0,0 +1,101 @@
+//! Forms composed from the question sets of others keep their ids unique.
+
+use std::collections::HashSet;
+use umfragetool_rust::merge::DuplicateKey;
+use umfragetool_rust::{Form, Limits, QuestionType};
+
+fn form(yaml: &str) -> Form {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+const BASE: &str = r#"
+title: Base
+description: ""
+groups:
+  - title: About you
+    description: ""
+    questions:
+      - title: Age
+        key: age
+        spec: { type: continous_numeric }
+      - title: Name
+        spec: { type: text, is_long: false }
+"#;
+
+const FRAGMENT: &str = r#"
+title: Fragment
+description: ""
+groups:
+  - title: Demographics
+    description: ""
+    questions:
+      - title: Age?
+        key: age
+        spec: { type: continous_numeric }
+      - title: Height
+        spec: { type: continous_numeric }
+      - title: Age times height
+        show_if: "i-0-1:gt:0"
+        spec: { type: computed, expression: "{age} * {i-0-1}" }
+"#;
+
+fn ids(form: &Form) -> Vec<String> {
+    form.questions().map(|(id, _)| id).collect()
+}
+
+fn expression(form: &Form) -> &str {
+    form.questions()
+        .find_map(|(_, question)| match &question.spec {
+            QuestionType::Computed { expression } => Some(expression.as_str()),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn merged_groups_get_unique_ids() {
+    let mut merged = form(BASE);
+    merged.merge(form(FRAGMENT), DuplicateKey::Rename);
+    merged.merge(form(FRAGMENT), DuplicateKey::Rename);
+    assert_eq!(merged.groups.len(), 3);
+    let ids = ids(&merged);
+    assert_eq!(
+        ids,
+        ["age", "i-0-1", "age-2", "i-1-1", "i-1-2", "age-3", "i-2-1", "i-2-2"]
+    );
+    assert_eq!(ids.iter().collect::<HashSet<_>>().len(), ids.len());
+    assert_eq!(expression(&merged), "{age-2} * {i-1-1}");
+    let condition = merged.groups[1].questions[2].show_if.as_deref();
+    assert_eq!(condition, Some("i-1-1:gt:0"));
+    merged.validate(&Limits::default()).unwrap();
+}
+
+#[test]
+fn duplicate_keys_can_be_skipped() {
+    let mut merged = form(BASE);
+    merged.merge(form(FRAGMENT), DuplicateKey::Skip);
+    assert_eq!(merged.groups.len(), 2);
+    assert_eq!(ids(&merged), ["age", "i-0-1", "i-1-0", "i-1-1"]);
+    assert_eq!(merged.groups[1].questions[0].title.get("", ""), "Height");
+    // The shared question now answers for both sets.
+    assert_eq!(expression(&merged), "{age} * {i-1-0}");
+    merged.validate(&Limits::default()).unwrap();
+}
+
+#[test]
+fn emptied_sets_are_dropped() {
+    let mut merged = form(BASE);
+    let only_age = r#"
+title: Age only
+description: ""
+groups:
+  - title: Age
+    description: ""
+    questions:
+      - title: Age
+        key: age
+        spec: { type: continous_numeric }
+"#;
+    merged.merge(form(only_age), DuplicateKey::Skip);
+    assert_eq!(merged.groups.len(), 1);
+}
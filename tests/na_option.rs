@@ -0,0 +1,35 @@
+//! A `na_option` adds a "not applicable" choice to a scale, posted as its
+//! own value.
+
+mod common;
+
+use common::render_question;
+use std::collections::BTreeMap;
+use umfragetool_rust::*;
+
+#[test]
+fn na_option_adds_a_distinct_radio() {
+    let spec = |na_option| QuestionType::DiscreteNumeric {
+        bounds: (1, 3),
+        num_descriptions: BTreeMap::new(),
+        na_option,
+        columns: None,
+    };
+    let without = render_question(spec(None));
+    assert!(!without.contains(r#"value="na""#), "{without}");
+
+    let with = render_question(spec(Some("Not applicable".into())));
+    let na = format!(
+        r#"<input type="radio" name="{ROOT_PREFIX}-0-0" id="{ROOT_PREFIX}-0-0-na" value="na">"#
+    );
+    assert!(with.contains(&na), "{with}");
+    assert!(with.contains(&format!(
+        r#"<label for="{ROOT_PREFIX}-0-0-na">Not applicable</label>"#
+    )));
+    assert!(with.find(r#"value="3""#) < with.find(r#"value="na""#));
+
+    assert!(spec(Some("n/a".into()))
+        .validate_answer(&[NA.to_string()])
+        .is_ok());
+    assert!(spec(None).validate_answer(&[NA.to_string()]).is_err());
+}
@@ -0,0 +1,46 @@
+//! With `no_js`, pages work without a single script: no inline handlers,
+//! nothing hidden until a script shows it.
+
+mod common;
+
+use common::question;
+use std::collections::HashMap;
+use umfragetool_rust::*;
+
+#[test]
+fn no_js_renders_no_scripts() {
+    let mut form = demo_form();
+    form.quick_nav = true;
+    form.intro = Some("Welcome".into());
+    form.groups[0].questions.push(Question {
+        title: "Doubled".into(),
+        ..question(QuestionType::Computed {
+            expression: "i-0-1 * 2".to_string(),
+        })
+    });
+    // Fresh, with the intro, and re-rendered with errors.
+    let errors = [
+        HashMap::new(),
+        HashMap::from([(format!("{ROOT_PREFIX}-0-0"), "wrong".to_string())]),
+    ];
+    for errors in errors {
+        let with_js = RenderContext {
+            errors: errors.clone(),
+            ..Default::default()
+        };
+        let html = form.render(ROOT_PREFIX.to_string(), &with_js);
+        assert!(html.contains("<script") && html.contains("onkeyup"));
+
+        let ctx = RenderContext {
+            errors,
+            no_js: true,
+            ..Default::default()
+        };
+        let html = form.render(ROOT_PREFIX.to_string(), &ctx);
+        for forbidden in ["<script", "onkeyup", "onclick", " hidden>"] {
+            assert!(!html.contains(forbidden), "{forbidden} in no-JS output");
+        }
+        // The custom answer text box is submitted itself.
+        assert!(html.contains(r#"<input type="text" name="i-0-3" id="i-0-3-t""#));
+    }
+}
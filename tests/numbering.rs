@@ -0,0 +1,38 @@
+//! `numbering` puts a number before each question title, counted through
+//! the form or per set.
+
+mod common;
+
+use common::question;
+use umfragetool_rust::*;
+
+#[test]
+fn numbering_prefixes_titles() {
+    let mut form = demo_form();
+    form.groups.push(QuestionSet {
+        title: "Set 2".into(),
+        description: "".into(),
+        questions: vec![Question {
+            title: "Anything else?".into(),
+            ..question(QuestionType::Text {
+                is_long: false,
+                pattern: None,
+                max_length: None,
+                warn_at: None,
+            })
+        }],
+        show_if: None,
+        collapsible: false,
+    });
+    assert!(!form.to_string().contains(r#"class="number""#));
+
+    form.numbering = Numbering::PerForm;
+    let html = form.to_string();
+    assert!(html.contains(r#"<span class="number">1.</span> Why would you do this?"#));
+    assert!(html.contains(r#"<span class="number">6.</span> Anything else?"#));
+
+    form.numbering = Numbering::PerGroup;
+    let html = form.to_string();
+    assert!(html.contains(r#"<span class="number">1.5</span> What do you want?"#));
+    assert!(html.contains(r#"<span class="number">2.1</span> Anything else?"#));
+}
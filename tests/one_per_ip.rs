@@ -0,0 +1,130 @@
+//! `limit: one_per_ip` turns away a second submission from the same client
+//! address, recognised by its hash.
+
+mod common;
+
+use axum::extract::ConnectInfo;
+use axum::http::StatusCode;
+use common::{post, send};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::{demo_form, ClientIp, Form, Limits, Privacy, ResponseLimit};
+
+fn limited() -> Form {
+    Form {
+        limit: ResponseLimit::OnePerIp,
+        privacy: Privacy {
+            client_ip: ClientIp::Hashed,
+        },
+        ..demo_form()
+    }
+}
+
+fn state(name: &str, trust_proxy: bool) -> Arc<AppState> {
+    let mut state = common::state(&format!("one-per-ip-{name}"), [("demo", limited())]);
+    state.trust_proxy = trust_proxy;
+    Arc::new(state)
+}
+
+async fn submit(
+    state: &Arc<AppState>,
+    peer: &str,
+    forwarded_for: Option<&str>,
+) -> (StatusCode, String) {
+    let mut request = post("/submit/demo", "i-0-0=Because");
+    if let Some(forwarded_for) = forwarded_for {
+        let forwarded_for = forwarded_for.parse().unwrap();
+        request
+            .headers_mut()
+            .insert("x-forwarded-for", forwarded_for);
+    }
+    let ip: IpAddr = peer.parse().unwrap();
+    request
+        .extensions_mut()
+        .insert(ConnectInfo(SocketAddr::new(ip, 1234)));
+    send(state, request).await
+}
+
+#[tokio::test]
+async fn second_submission_from_an_address_is_refused() {
+    let state = state("refused", false);
+    let (status, _) = submit(&state, "192.0.2.1", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, html) = submit(&state, "192.0.2.1", None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert!(html.contains("has already been submitted"), "{html}");
+    // Mapped IPv4 addresses are the same client.
+    let (status, _) = submit(&state, "::ffff:192.0.2.1", None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = submit(&state, "192.0.2.2", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(state.store.list("demo").len(), 2);
+}
+
+#[tokio::test]
+async fn ipv6_addresses_count_by_their_prefix() {
+    let state = state("ipv6", false);
+    let (status, _) = submit(&state, "2001:db8:1:2::10", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = submit(&state, "2001:db8:1:2:aaaa::20", None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = submit(&state, "2001:db8:1:3::10", None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn forwarded_for_is_only_trusted_when_asked_to() {
+    let state = self::state("untrusted", false);
+    let (status, _) = submit(&state, "10.0.0.1", Some("192.0.2.1")).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = submit(&state, "10.0.0.1", Some("192.0.2.2")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let state = self::state("trusted", true);
+    let (status, _) = submit(&state, "10.0.0.1", Some("192.0.2.1")).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = submit(&state, "10.0.0.1", Some("192.0.2.2")).await;
+    assert_eq!(status, StatusCode::OK);
+    // The proxy appends the address it saw; earlier entries are the client's word.
+    let (status, _) = submit(&state, "10.0.0.1", Some("198.51.100.7, 192.0.2.1")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn concurrent_submissions_store_one() {
+    let state = state("concurrent", false);
+    let results = tokio::join!(
+        submit(&state, "192.0.2.1", None),
+        submit(&state, "192.0.2.1", None),
+        submit(&state, "192.0.2.1", None),
+    );
+    let accepted = [results.0, results.1, results.2]
+        .iter()
+        .filter(|(status, _)| *status == StatusCode::OK)
+        .count();
+    assert_eq!(accepted, 1);
+    assert_eq!(state.store.list("demo").len(), 1);
+}
+
+#[test]
+fn limit_needs_hashed_addresses() {
+    let form = Form {
+        privacy: Privacy::default(),
+        ..limited()
+    };
+    let err = form.validate(&Limits::default()).unwrap_err();
+    assert!(err.to_string().contains("one_per_ip"), "{err}");
+    assert!(limited().validate(&Limits::default()).is_ok());
+}
+
+#[test]
+fn limit_needs_a_single_submit() {
+    let form = Form {
+        per_group_submit: true,
+        ..limited()
+    };
+    let err = form.validate(&Limits::default()).unwrap_err();
+    assert!(err.to_string().contains("per_group_submit"), "{err}");
+}
@@ -0,0 +1,40 @@
+//! `--open` points the browser at the address the server is bound to.
+
+use std::net::SocketAddr;
+use umfragetool_rust::browser;
+
+#[test]
+fn url_follows_the_loopback_address() {
+    let addr = |addr: &str| addr.parse::<SocketAddr>().unwrap();
+    assert_eq!(
+        browser::url(addr("127.0.0.1:3000"), Some("survey")).unwrap(),
+        "http://127.0.0.1:3000/form/survey"
+    );
+    assert_eq!(
+        browser::url(addr("[::1]:8080"), Some("survey")).unwrap(),
+        "http://[::1]:8080/form/survey"
+    );
+    assert_eq!(
+        browser::url(addr("127.0.0.1:3000"), None).unwrap(),
+        "http://127.0.0.1:3000/"
+    );
+}
+
+#[test]
+fn other_addresses_are_not_opened() {
+    for bind in ["0.0.0.0:3000", "192.168.1.5:3000", "[::]:3000"] {
+        assert_eq!(browser::url(bind.parse().unwrap(), Some("survey")), None);
+    }
+}
+
+#[tokio::test]
+async fn url_uses_the_bound_port() {
+    let app = axum::Router::new();
+    let server = axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+    let bound = server.local_addr();
+    assert_ne!(bound.port(), 0);
+    assert_eq!(
+        browser::url(bound, Some("demo")).unwrap(),
+        format!("http://127.0.0.1:{}/form/demo", bound.port())
+    );
+}
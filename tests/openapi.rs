@@ -0,0 +1,151 @@
+//! `/api/openapi.json` is a valid OpenAPI 3 document describing every JSON
+//! route, and `/api/docs` shows it to admins.
+
+mod common;
+
+use axum::http::{header, HeaderMap, StatusCode};
+use common::{fetch, store_path, with_admin, with_token};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use umfragetool_rust::demo_form;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::store::SubmissionStore;
+
+/// Routes that serve pages, files or streams rather than JSON, and so are
+/// not part of the described API.
+const NOT_API: &[&str] = &[
+    "/",
+    "/favicon.ico",
+    "/admin/backup.tar.gz",
+    "/draft/:form_id",
+    "/form/:form_id",
+    "/form/:form_id/admin/response/:submission_id/delete",
+    "/form/:form_id/admin/responses",
+    "/form/:form_id/badge.svg",
+    "/form/:form_id/delete/:token",
+    "/form/:form_id/edit/:token",
+    "/form/:form_id/embed",
+    "/form/:form_id/embed.html",
+    "/form/:form_id/events",
+    "/form/:form_id/live",
+    "/form/:form_id/result/:token",
+    "/form/:form_id/resume/:token",
+    "/form/:form_id/stats/live",
+    "/form/:form_id/view",
+];
+
+async fn get(uri: &str, token: Option<&str>) -> (StatusCode, HeaderMap, String) {
+    let state = with_admin(AppState::new(
+        HashMap::from([("demo".to_string(), demo_form())]),
+        SubmissionStore::open(&store_path("openapi")).unwrap(),
+    ));
+    fetch(&Arc::new(state), with_token(common::get(uri), token)).await
+}
+
+async fn document() -> Value {
+    let (status, _, body) = get("/api/openapi.json", None).await;
+    assert_eq!(status, StatusCode::OK);
+    serde_json::from_str(&body).unwrap()
+}
+
+fn refs<'a>(value: &'a Value, found: &mut Vec<&'a str>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::String(target)) = map.get("$ref") {
+                found.push(target);
+            }
+            map.values().for_each(|value| refs(value, found));
+        }
+        Value::Array(values) => values.iter().for_each(|value| refs(value, found)),
+        _ => {}
+    }
+}
+
+#[tokio::test]
+async fn document_is_valid_openapi() {
+    let document = document().await;
+    let parsed: openapiv3::OpenAPI = serde_json::from_value(document.clone()).unwrap();
+    assert_eq!(parsed.openapi, "3.0.3");
+    let schemas = &document["components"]["schemas"];
+    let mut found = Vec::new();
+    refs(&document, &mut found);
+    for target in found {
+        let name = target
+            .strip_prefix("#/components/schemas/")
+            .unwrap_or_else(|| panic!("{target} points outside the components"));
+        assert!(schemas.get(name).is_some(), "{target} is not defined");
+    }
+
+    // Question types are told apart by their `type` tag.
+    let variants = schemas["QuestionType"]["oneOf"].as_array().unwrap();
+    let tags: BTreeSet<&str> = variants
+        .iter()
+        .map(|variant| {
+            assert!(variant["required"]
+                .as_array()
+                .unwrap()
+                .contains(&"type".into()));
+            variant["properties"]["type"]["enum"][0].as_str().unwrap()
+        })
+        .collect();
+    assert!(
+        tags.contains("single_choice") && tags.contains("discrete_numeric"),
+        "{tags:?}"
+    );
+    assert!(schemas["Answer"].is_object());
+}
+
+#[tokio::test]
+async fn every_route_is_documented() {
+    let route = Regex::new(r#"\.route\(\s*"([^"]+)""#).unwrap();
+    let mut routes = BTreeSet::new();
+    for entry in std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/src")).unwrap() {
+        let source = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+        routes.extend(
+            route
+                .captures_iter(&source)
+                .map(|found| found[1].to_string()),
+        );
+    }
+    let parameter = Regex::new(r":(\w+)").unwrap();
+    let document = document().await;
+    let paths = document["paths"].as_object().unwrap();
+    for route in &routes {
+        let documented = paths.contains_key(&*parameter.replace_all(route, "{$1}"));
+        let excluded = NOT_API.contains(&route.as_str());
+        assert!(
+            documented != excluded,
+            "{route} has to be either documented or listed in NOT_API"
+        );
+    }
+    for path in paths.keys() {
+        let route = path.replace('{', ":").replace('}', "");
+        assert!(
+            routes.contains(&route),
+            "{path} is documented but not served"
+        );
+    }
+}
+
+#[tokio::test]
+async fn docs_are_for_admins() {
+    let (status, _, _) = get("/api/docs", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, headers, html) = get("/api/docs", Some("secret")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains("SwaggerUIBundle({dom_id:"), "{html}");
+    let csp = headers[header::CONTENT_SECURITY_POLICY].to_str().unwrap();
+    assert!(
+        csp.contains("script-src 'unsafe-inline' https://cdn.jsdelivr.net/npm/swagger-ui-dist@5/"),
+        "{csp}"
+    );
+}
+
+#[tokio::test]
+async fn health_answers_while_the_server_runs() {
+    let (status, _, body) = get("/api/health", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, r#"{"status":"ok"}"#);
+}
@@ -0,0 +1,69 @@
+//! Text questions with a `pattern` only take answers matching it as a whole,
+//! in the browser and on the server.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{accept_json, post, send};
+use std::sync::Arc;
+use umfragetool_rust::*;
+
+fn form(pattern: &str) -> Form {
+    serde_yaml::from_str(&format!(
+        r#"
+title: Address
+description: ""
+groups:
+  - title: Where
+    description: ""
+    questions:
+      - title: Postal code
+        spec: {{ type: text, is_long: false, pattern: '{pattern}' }}
+"#
+    ))
+    .unwrap()
+}
+
+fn spec(form: &Form) -> &QuestionType {
+    &form.groups[0].questions[0].spec
+}
+
+#[test]
+fn answers_have_to_match_as_a_whole() {
+    let form = form("[0-9]{5}");
+    assert!(form.validate(&Limits::default()).is_ok());
+    assert!(spec(&form).validate_answer(&["12345".to_string()]).is_ok());
+    for invalid in ["1234", "123456", "abcde", "12345 "] {
+        assert!(
+            spec(&form).validate_answer(&[invalid.to_string()]).is_err(),
+            "{invalid}"
+        );
+    }
+    let html = form.render(ROOT_PREFIX.to_string(), &RenderContext::default());
+    assert!(html.contains(r#"pattern="[0-9]{5}""#), "{html}");
+}
+
+#[test]
+fn invalid_patterns_are_reported_at_load() {
+    let err = form("[0-9").validate(&Limits::default()).unwrap_err();
+    assert_eq!(err.problems.len(), 1);
+    assert!(
+        err.problems[0].starts_with("question i-0-0 (Postal code) has an invalid pattern"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn mismatches_are_refused_with_the_field() {
+    let state = Arc::new(common::state("pattern", [("address", form("[0-9]{5}"))]));
+    let submit = |body: &str| send(&state, accept_json(post("/submit/address", body)));
+
+    let (status, body) = submit("i-0-0=1234x").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["fields"][0]["id"], "i-0-0");
+    assert!(state.store.list("address").is_empty());
+
+    assert_eq!(submit("i-0-0=10115").await.0, StatusCode::OK);
+    assert_eq!(state.store.list("address")[0].answers["i-0-0"], ["10115"]);
+}
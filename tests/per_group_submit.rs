@@ -0,0 +1,155 @@
+//! With `per_group_submit`, each question set is saved on its own and the
+//! parts of a session are merged into one submission.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{fresh_store, get, post, send, store_path, submission};
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::store::SubmissionStore;
+use umfragetool_rust::Form;
+
+const FORM: &str = r#"
+title: Steps
+description: ""
+per_group_submit: true
+groups:
+  - title: About you
+    description: ""
+    questions:
+      - title: Name
+        key: name
+        required: true
+        spec: { type: text, is_long: false }
+  - title: Feedback
+    description: ""
+    questions:
+      - title: Rating
+        key: rating
+        spec: { type: discrete_numeric, bounds: [1, 5], num_descriptions: {} }
+"#;
+
+fn state(name: &str) -> Arc<AppState> {
+    let form: Form = serde_yaml::from_str(FORM).unwrap();
+    Arc::new(common::state(name, [("steps", form)]))
+}
+
+/// The signed `_session` of a freshly served form page.
+async fn session(state: &Arc<AppState>) -> String {
+    let (_, html) = send(state, get("/form/steps")).await;
+    regex::Regex::new(r#"name="_session" value="([^"]+)""#)
+        .unwrap()
+        .captures(&html)
+        .unwrap_or_else(|| panic!("no session in {html}"))[1]
+        .to_string()
+}
+
+async fn save(state: &Arc<AppState>, group: usize, body: &str) -> StatusCode {
+    send(state, post(&format!("/submit/steps/{group}"), body))
+        .await
+        .0
+}
+
+#[tokio::test]
+async fn parts_of_a_session_are_merged() {
+    let state = state("per-group-merged");
+    let session = session(&state).await;
+    let first = format!("name=Kim&_session={session}");
+    assert_eq!(save(&state, 0, &first).await, StatusCode::NO_CONTENT);
+    let second = format!("rating=4&name=ignored&_session={session}");
+    assert_eq!(save(&state, 1, &second).await, StatusCode::NO_CONTENT);
+
+    let stored = state.store.list("steps");
+    assert_eq!(stored.len(), 1);
+    assert_eq!(session.split_once('.').unwrap().0, stored[0].id);
+    // Answers to questions of other sets are not taken from a set's post.
+    assert_eq!(stored[0].answers["name"], ["Kim"]);
+    assert_eq!(stored[0].answers["rating"], ["4"]);
+
+    // Every save is a line of its own; the last one wins on load.
+    assert_eq!(
+        std::fs::read_to_string(store_path("per-group-merged"))
+            .unwrap()
+            .lines()
+            .count(),
+        2
+    );
+    let reopened = SubmissionStore::open(&store_path("per-group-merged")).unwrap();
+    assert_eq!(reopened.list("steps"), stored);
+}
+
+#[tokio::test]
+async fn parts_are_checked_on_their_own() {
+    let state = state("per-group-checked");
+    let missing = format!("_session={}", session(&state).await);
+    assert_eq!(
+        save(&state, 0, &missing).await,
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+    // The required name belongs to the other set.
+    assert_eq!(save(&state, 1, &missing).await, StatusCode::NO_CONTENT);
+    assert_eq!(save(&state, 0, "name=Kim").await, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        save(&state, 0, "name=Kim&_session=../other").await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(save(&state, 2, &missing).await, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn answers_to_sets_no_longer_asked_are_dropped() {
+    let mut form: Form = serde_yaml::from_str(FORM).unwrap();
+    form.groups[1].show_if = Some("name:eq:Kim".to_string());
+    let state = Arc::new(common::state("per-group-hidden", [("steps", form)]));
+    let session = session(&state).await;
+    let save_name = |name: &str| format!("name={name}&_session={session}");
+    assert_eq!(
+        save(&state, 0, &save_name("Kim")).await,
+        StatusCode::NO_CONTENT
+    );
+    let rating = format!("rating=4&_session={session}");
+    assert_eq!(save(&state, 1, &rating).await, StatusCode::NO_CONTENT);
+    assert_eq!(state.store.list("steps")[0].answers["rating"], ["4"]);
+
+    // The rating was saved earlier, but the new name hides its set.
+    assert_eq!(
+        save(&state, 0, &save_name("Lee")).await,
+        StatusCode::NO_CONTENT
+    );
+    let stored = &state.store.list("steps")[0];
+    assert_eq!(stored.answers["name"], ["Lee"]);
+    assert!(
+        !stored.answers.contains_key("rating"),
+        "{:?}",
+        stored.answers
+    );
+}
+
+#[tokio::test]
+async fn sessions_have_to_be_issued_by_the_server() {
+    let state = state("per-group-issued");
+    let session = session(&state).await;
+    let first = format!("name=Kim&_session={session}");
+    assert_eq!(save(&state, 0, &first).await, StatusCode::NO_CONTENT);
+
+    // The bare id, as shown in edit links, does not add to the submission.
+    let id = session.split_once('.').unwrap().0;
+    let forged = format!("rating=1&_session={id}");
+    assert_eq!(save(&state, 1, &forged).await, StatusCode::BAD_REQUEST);
+    let forged = format!("rating=1&_session={id}.00");
+    assert_eq!(save(&state, 1, &forged).await, StatusCode::BAD_REQUEST);
+    assert!(!state.store.list("steps")[0].answers.contains_key("rating"));
+}
+
+#[tokio::test]
+async fn ids_are_only_unique_within_a_form() {
+    let store = fresh_store("per-group-ids");
+    for form_id in ["steps", "other"] {
+        let submission = submission(form_id, "same", &[("name", &[form_id])]);
+        store.insert(submission, None).await.unwrap();
+    }
+    let reopened = SubmissionStore::open(&store_path("per-group-ids")).unwrap();
+    assert_eq!(reopened.list("steps")[0].answers["name"], ["steps"]);
+    assert_eq!(reopened.list("other")[0].answers["name"], ["other"]);
+}
@@ -0,0 +1,155 @@
+//! The presenter WebSocket sends the aggregates of a form, then what changed
+//! as responses come in, and everything again when the form is reloaded.
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::{as_admin, send, submit, with_admin};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::{Error, Message};
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use umfragetool_rust::demo_form;
+use umfragetool_rust::server::{self, AppState};
+use umfragetool_rust::watch::FormWatcher;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+fn state(name: &str) -> Arc<AppState> {
+    Arc::new(with_admin(common::state(
+        &format!("presenter-{name}"),
+        [("demo", demo_form())],
+    )))
+}
+
+async fn serve(state: &Arc<AppState>) -> SocketAddr {
+    let server = axum::Server::bind(&"127.0.0.1:0".parse().unwrap())
+        .serve(server::router(state.clone()).into_make_service_with_connect_info::<SocketAddr>());
+    let address = server.local_addr();
+    tokio::spawn(server);
+    address
+}
+
+async fn connect(address: SocketAddr, token: &str) -> Result<Socket, Error> {
+    let mut request = format!("ws://{address}/form/demo/stats/live")
+        .into_client_request()
+        .unwrap();
+    request.headers_mut().insert(
+        header::AUTHORIZATION,
+        format!("Bearer {token}").parse().unwrap(),
+    );
+    connect_async(request).await.map(|(socket, _)| socket)
+}
+
+async fn next(socket: &mut Socket) -> Option<Value> {
+    let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+        .await
+        .expect("no message from the server");
+    match message? {
+        Ok(Message::Text(text)) => Some(serde_json::from_str(&text).unwrap()),
+        _ => None,
+    }
+}
+
+fn ids(message: &Value) -> Vec<&str> {
+    message["questions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|question| question["id"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn snapshot_then_what_changed() {
+    let state = state("updates");
+    let address = serve(&state).await;
+    let mut socket = connect(address, "secret").await.unwrap();
+
+    let snapshot = next(&mut socket).await.unwrap();
+    assert_eq!(snapshot["type"], "snapshot");
+    assert_eq!(snapshot["form_id"], "demo");
+    assert_eq!(snapshot["version"], demo_form().version());
+    assert_eq!(snapshot["responses"], 0);
+    assert_eq!(
+        ids(&snapshot),
+        ["i-0-0", "i-0-1", "i-0-2", "i-0-3", "i-0-4"]
+    );
+
+    assert_eq!(
+        submit(&state, "demo", "i-0-0=Because&i-0-3=Pizza").await.0,
+        StatusCode::OK
+    );
+    let update = next(&mut socket).await.unwrap();
+    assert_eq!(update["type"], "update");
+    assert_eq!(update["responses"], 1);
+    // Only the questions the response answered changed.
+    assert_eq!(ids(&update), ["i-0-0", "i-0-3"]);
+    assert_eq!(update["questions"][1]["answered"], 1);
+    assert_eq!(
+        update["questions"][1]["counts"][0],
+        json!({ "value": "Pizza", "count": 1 })
+    );
+
+    // Deleting a response is a change as well.
+    let id = state.store.list("demo")[0].id.clone();
+    let request = Request::delete(format!("/api/form/demo/response/{id}"))
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        send(&state, as_admin(request)).await.0,
+        StatusCode::NO_CONTENT
+    );
+    let update = next(&mut socket).await.unwrap();
+    assert_eq!(update["responses"], 0);
+    assert_eq!(ids(&update), ["i-0-0", "i-0-3"]);
+}
+
+#[tokio::test]
+async fn reloads_resend_everything_and_removal_ends_the_stream() {
+    let state = state("reload");
+    let dir = std::env::temp_dir().join("umfragetool-presenter-forms");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("demo.yml");
+    std::fs::write(&file, serde_yaml::to_string(&demo_form()).unwrap()).unwrap();
+    let mut watcher = FormWatcher::new(vec![("demo".to_string(), file.clone())]);
+    let address = serve(&state).await;
+    let mut socket = connect(address, "secret").await.unwrap();
+    assert_eq!(next(&mut socket).await.unwrap()["type"], "snapshot");
+
+    let mut form = demo_form();
+    form.groups[0].questions.truncate(2);
+    std::fs::write(&file, serde_yaml::to_string(&form).unwrap()).unwrap();
+    watcher.poll(&state, |_, _| ());
+    let snapshot = next(&mut socket).await.unwrap();
+    assert_eq!(snapshot["type"], "snapshot");
+    assert_eq!(snapshot["version"], form.version());
+    assert_eq!(ids(&snapshot), ["i-0-0", "i-0-1"]);
+
+    let request = Request::delete("/api/form/demo")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        send(&state, as_admin(request)).await.0,
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(next(&mut socket).await, Some(json!({ "type": "removed" })));
+    assert_eq!(next(&mut socket).await, None);
+}
+
+#[tokio::test]
+async fn presenters_need_the_admin_token() {
+    let state = state("auth");
+    let address = serve(&state).await;
+    match connect(address, "guess").await {
+        Err(Error::Http(response)) => assert_eq!(response.status(), StatusCode::UNAUTHORIZED),
+        other => panic!("connected without the admin token: {:?}", other.is_ok()),
+    }
+}
@@ -0,0 +1,87 @@
+//! Previews render the form for its authors and never store submissions.
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{as_admin, get, post, send, with_admin};
+use std::sync::Arc;
+use umfragetool_rust::server::{self, AppState};
+use umfragetool_rust::{demo_form, Form};
+
+/// The demo form, with an answer to its first question required.
+fn form() -> Form {
+    let mut form = demo_form();
+    form.groups[0].questions[0].required = true;
+    form
+}
+
+fn state(name: &str) -> Arc<AppState> {
+    Arc::new(with_admin(common::state(
+        &format!("preview-{name}"),
+        [("demo", form())],
+    )))
+}
+
+fn submit(body: String) -> Request<Body> {
+    post("/submit/demo", &body)
+}
+
+#[tokio::test]
+async fn preview_needs_admin_or_token() {
+    let state = state("auth");
+    let token = server::preview_token(&state.signer, "demo");
+
+    let (status, _) = send(&state, get("/form/demo?preview=1")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&state, get("/form/demo?preview=1&token=x.00")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, html) = send(&state, get(&format!("/form/demo?preview=1&token={token}"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains(r#"<p class="preview" role="status">PREVIEW"#));
+    assert!(html.contains(&format!(r#"name="_preview" value="{token}""#)));
+
+    let admin = as_admin(get("/form/demo?preview=1"));
+    let (status, html) = send(&state, admin).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains(r#"class="preview""#));
+
+    let (_, html) = send(&state, get("/form/demo")).await;
+    assert!(!html.contains("_preview"));
+}
+
+#[tokio::test]
+async fn preview_submissions_are_validated_and_discarded() {
+    let state = state("submit");
+    let token = server::preview_token(&state.signer, "demo");
+
+    let (status, html) = send(&state, submit(format!("_preview={token}"))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(html.contains(r#"class="preview""#));
+    assert!(html.contains(&format!(r#"name="_preview" value="{token}""#)));
+
+    let (status, html) = send(&state, submit(format!("_preview={token}&i-0-0=Because"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains("Thank you"), "{html}");
+    assert!(html.contains("This was a preview, nothing was saved."));
+    assert!(state.store.list("demo").is_empty());
+}
+
+#[tokio::test]
+async fn forged_preview_field_is_refused() {
+    let state = state("forged");
+    for forged in [
+        "1",
+        "preview.00",
+        &server::preview_token(&state.signer, "other"),
+    ] {
+        let (status, _) = send(&state, submit(format!("_preview={forged}&i-0-0=Because"))).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{forged}");
+    }
+    assert!(state.store.list("demo").is_empty());
+
+    let (status, _) = send(&state, submit("i-0-0=Because".to_string())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(state.store.list("demo").len(), 1);
+}
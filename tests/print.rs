@@ -0,0 +1,98 @@
+//! `?print=1` serves a paper version of the form: marks to tick and lines to
+//! write on, with no inputs and no way to submit.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{get, send, store_path};
+use std::collections::HashMap;
+use std::sync::Arc;
+use umfragetool_rust::print::to_print_html;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::store::SubmissionStore;
+use umfragetool_rust::*;
+
+async fn page(uri: &str) -> (StatusCode, String) {
+    let state = AppState::new(
+        HashMap::from([("demo".to_string(), demo_form())]),
+        SubmissionStore::open(&store_path("print")).unwrap(),
+    );
+    send(&Arc::new(state), get(uri)).await
+}
+
+#[tokio::test]
+async fn print_mode_has_nothing_to_fill_in_online() {
+    let (status, html) = page("/form/demo?print=1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(html, to_print_html(&demo_form()));
+    for interactive in [
+        "<form",
+        "<input",
+        "<textarea",
+        "<select",
+        "<button",
+        "/submit",
+    ] {
+        assert!(!html.contains(interactive), "{interactive} in {html}");
+    }
+    // Each set starts on a page of its own.
+    assert!(html.contains(".group{break-before:page}"), "{html}");
+
+    let (_, online) = page("/form/demo").await;
+    assert!(online.contains("<form"), "{online}");
+}
+
+#[test]
+fn each_question_type_gets_its_marks() {
+    let html = to_print_html(&demo_form());
+    let question = |number: usize| {
+        let start = html.find(&format!("<h3>{number}. ")).unwrap();
+        let end = html[start..]
+            .find(r#"<div class="question">"#)
+            .map_or(html.len(), |end| start + end);
+        &html[start..end]
+    };
+    // Long text: a paragraph of full-width lines.
+    assert_eq!(question(1).matches(r#"<div class="line">"#).count(), 5);
+    // Continuous numeric: one short line.
+    assert_eq!(question(2).matches(r#"class="line short""#).count(), 1);
+    // The scale as a row of numbered boxes, with the end descriptions.
+    let scale = question(3);
+    assert_eq!(scale.matches(r#"<span class="box"></span>"#).count(), 10);
+    assert!(scale.contains("<br>1<br>NOPE!</div>"), "{scale}");
+    assert!(scale.contains("<br>10<br>YESSSSH!!!!</div>"), "{scale}");
+    // Single choice ticks circles, multiple choice boxes, and both leave
+    // room for another answer.
+    let single = question(4);
+    assert_eq!(single.matches(r#"class="box circle""#).count(), 4);
+    assert!(single.contains("Other:"), "{single}");
+    let multiple = question(5);
+    assert_eq!(multiple.matches(r#"<span class="box"></span>"#).count(), 4);
+    assert!(!multiple.contains("circle"), "{multiple}");
+}
+
+#[test]
+fn short_text_gets_a_short_line_and_computed_is_left_out() {
+    let form: Form = serde_yaml::from_str(
+        r#"
+title: Lunch
+description: ""
+groups:
+  - title: Food
+    description: ""
+    questions:
+      - title: Name
+        spec: { type: text, is_long: false }
+      - title: Appetite
+        spec: { type: computed, expression: "{i-0-0} * 2" }
+      - title: Later
+        spec: { type: text, is_long: false }
+"#,
+    )
+    .unwrap();
+    let html = to_print_html(&form);
+    assert_eq!(html.matches(r#"<div class="line short">"#).count(), 2);
+    assert!(!html.contains("Appetite"), "{html}");
+    // Numbering skips what respondents do not answer.
+    assert!(html.contains("<h3>2. Later</h3>"), "{html}");
+}
@@ -0,0 +1,78 @@
+//! `privacy.client_ip` decides what is kept of the respondent's address; the
+//! address itself is never written.
+
+mod common;
+
+use axum::extract::ConnectInfo;
+use axum::http::StatusCode;
+use common::{post, send, store_path};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::store::ClientMeta;
+use umfragetool_rust::{demo_form, ClientIp, Form, Privacy};
+
+fn path(name: &str) -> PathBuf {
+    store_path(&format!("privacy-{name}"))
+}
+
+/// The state after two submissions from `203.0.113.57` to a form keeping
+/// `client_ip`.
+async fn submitted(name: &str, client_ip: ClientIp) -> Arc<AppState> {
+    let form = Form {
+        privacy: Privacy { client_ip },
+        ..demo_form()
+    };
+    let state = Arc::new(common::state(&format!("privacy-{name}"), [("demo", form)]));
+    for port in [1234, 4321] {
+        let mut request = post("/submit/demo", "i-0-0=Because");
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([203, 0, 113, 57], port))));
+        assert_eq!(send(&state, request).await.0, StatusCode::OK);
+    }
+    state
+}
+
+#[tokio::test]
+async fn nothing_is_kept_by_default() {
+    let state = submitted("none", ClientIp::default()).await;
+    assert!(state
+        .store
+        .list("demo")
+        .iter()
+        .all(|submission| submission.client.is_none()));
+    let stored = std::fs::read_to_string(path("none")).unwrap();
+    assert!(!stored.contains("203.0.113"), "{stored}");
+}
+
+#[tokio::test]
+async fn hashed_addresses_match_without_being_stored() {
+    let state = submitted("hashed", ClientIp::Hashed).await;
+    let hashes: Vec<String> = state
+        .store
+        .list("demo")
+        .into_iter()
+        .map(|submission| match submission.client {
+            Some(ClientMeta::IpHash(hash)) => hash,
+            other => panic!("{other:?}"),
+        })
+        .collect();
+    assert_eq!(hashes[0], hashes[1]);
+    let stored = std::fs::read_to_string(path("hashed")).unwrap();
+    assert!(!stored.contains("203.0.113"), "{stored}");
+}
+
+#[tokio::test]
+async fn truncated_addresses_keep_the_network() {
+    let state = submitted("truncated", ClientIp::Truncated).await;
+    for submission in state.store.list("demo") {
+        assert!(matches!(
+            submission.client,
+            Some(ClientMeta::IpPrefix(prefix)) if prefix == "203.0.113.0/24"
+        ));
+    }
+    let stored = std::fs::read_to_string(path("truncated")).unwrap();
+    assert!(!stored.contains("203.0.113.57"), "{stored}");
+}
@@ -0,0 +1,78 @@
+//! Questions with a `key` are submitted and stored under it.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{answered, submit};
+use std::sync::Arc;
+use umfragetool_rust::*;
+
+fn form(age_first: bool) -> Form {
+    let age = r#"
+      - title: How old are you?
+        key: age
+        spec: { type: continous_numeric }"#;
+    let name = r#"
+      - title: What is your name?
+        spec: { type: text, is_long: false }"#;
+    let questions = if age_first {
+        format!("{age}{name}")
+    } else {
+        format!("{name}{age}")
+    };
+    let yaml = format!(
+        r#"
+title: Form
+description: ""
+groups:
+  - title: Set
+    description: ""
+    questions:{questions}
+"#
+    );
+    let form: Form = serde_yaml::from_str(&yaml).unwrap();
+    form.validate(&Limits::default()).unwrap();
+    form
+}
+
+#[test]
+fn key_replaces_positional_id() {
+    for age_first in [true, false] {
+        let form = form(age_first);
+        let ids: Vec<String> = form.questions().map(|(id, _)| id).collect();
+        let name_id = if age_first { "i-0-1" } else { "i-0-0" };
+        assert!(ids.contains(&"age".to_string()) && ids.contains(&name_id.to_string()));
+
+        let html = form.to_string();
+        assert!(html.contains(r#"name="age" id="age""#));
+        assert!(html.contains(r#"<label for="age">How old are you?</label>"#));
+
+        let answers = answered(&[("age", &["old"])]);
+        let errors = form.validate_submission(&answers).unwrap_err();
+        assert_eq!(errors[0].id, "age");
+    }
+}
+
+#[tokio::test]
+async fn answer_is_stored_under_key() {
+    let state = Arc::new(common::state("question-key", [("f", form(false))]));
+    let (status, _) = submit(&state, "f", "age=42&i-0-0=Ada").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let submissions = state.store.list("f");
+    assert_eq!(submissions[0].answers["age"], ["42"]);
+    assert_eq!(submissions[0].answers["i-0-0"], ["Ada"]);
+}
+
+#[test]
+fn keys_are_checked() {
+    let mut form = form(true);
+    form.groups[0].questions[1].key = Some("age".to_string());
+    let problems = form.validate(&Limits::default()).unwrap_err().problems;
+    assert!(problems[0].contains("the same key 'age'"), "{problems:?}");
+
+    for key in ["_age", "i-1-0", "what age", ""] {
+        form.groups[0].questions[1].key = Some(key.to_string());
+        assert!(form.validate(&Limits::default()).is_err(), "{key}");
+    }
+}
@@ -0,0 +1,31 @@
+//! The first field is focused; with `quick_nav`, Enter moves on to the
+//! next one.
+
+use umfragetool_rust::*;
+
+#[test]
+fn first_field_is_focused_and_enter_navigation_opt_in() {
+    let render = |quick_nav, no_js| {
+        let form = Form {
+            quick_nav,
+            ..demo_form()
+        };
+        let ctx = RenderContext {
+            no_js,
+            ..Default::default()
+        };
+        form.render(ROOT_PREFIX.to_string(), &ctx)
+    };
+    let html = render(false, false);
+    assert_eq!(html.matches(" autofocus").count(), 1, "{html}");
+    assert!(html.contains(r#"<textarea name="i-0-0" id="i-0-0" autofocus"#));
+    assert!(!html.contains(r#"e.key!=="Enter""#));
+
+    let html = render(true, false);
+    assert!(
+        html.contains(r#"document.addEventListener("keydown""#),
+        "{html}"
+    );
+    assert!(html.contains(r#"e.key!=="Enter"||t.tagName!=="INPUT""#));
+    assert!(!render(true, true).contains("<script"));
+}
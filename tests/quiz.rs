@@ -0,0 +1,180 @@
+//! Quiz forms score submissions and keep their correct answers to themselves.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{accept_json, answered, get, post, send, submit};
+use std::sync::Arc;
+use umfragetool_rust::quiz::{self, Correctness, Score};
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::*;
+
+const FORM: &str = r#"
+title: Quiz
+description: ""
+quiz: true
+show_score: per_question
+groups:
+  - title: Questions
+    description: ""
+    questions:
+      - title: Capital of France?
+        correct: paris
+        spec:
+          type: single_choice
+          custom_answer: false
+          answers:
+            - { label: Paris, value: paris }
+            - { label: Lyon, value: lyon }
+      - title: Which are primes?
+        correct: ["2", "3", "5"]
+        points: 3
+        spec: { type: multiple_choice, answers: ["2", "3", "4", "5"], custom_answer: false }
+      - title: 6 times 7?
+        correct: 42
+        explanation: Six sevens make forty-two.
+        spec: { type: continous_numeric }
+      - title: Anything else?
+        spec: { type: text, is_long: false }
+"#;
+
+fn form() -> Form {
+    let form: Form = serde_yaml::from_str(FORM).unwrap();
+    form.validate(&Limits::default()).unwrap();
+    form
+}
+
+#[test]
+fn answers_are_graded() {
+    let form = form();
+    let (score, results) = quiz::grade(
+        &form,
+        &answered(&[
+            ("i-0-0", &["paris"]),
+            ("i-0-1", &["2", "3", "5"]),
+            ("i-0-2", &["42"]),
+        ]),
+    );
+    assert_eq!(
+        score,
+        Score {
+            points: 5.0,
+            max: 5.0
+        }
+    );
+    assert_eq!(results.len(), 3);
+
+    let (score, results) = quiz::grade(
+        &form,
+        &answered(&[("i-0-0", &["lyon"]), ("i-0-1", &["2", "3", "4"])]),
+    );
+    assert_eq!(score.points, 1.0);
+    let correctness: Vec<Correctness> = results.iter().map(|r| r.correctness()).collect();
+    assert_eq!(
+        correctness,
+        [Correctness::Wrong, Correctness::Partly, Correctness::Wrong]
+    );
+}
+
+#[test]
+fn multiple_choice_gets_partial_credit() {
+    let form = form();
+    for (chosen, points) in [
+        (&["2", "3", "5"][..], 3.0),
+        (&["2", "3"], 2.0),
+        (&["2", "3", "5", "4"], 2.0),
+        (&["4"], 0.0),
+        (&["2", "4"], 0.0),
+        (&[], 0.0),
+    ] {
+        let (_, results) = quiz::grade(&form, &answered(&[("i-0-1", chosen)]));
+        assert_eq!(results[1].points, points, "{chosen:?}");
+    }
+}
+
+#[test]
+fn correct_answers_are_never_served() {
+    let form = form();
+    assert!(!form.to_string().contains("correct"));
+    let public = quiz::public_definition(&form).to_string();
+    assert!(!public.contains("correct"), "{public}");
+    assert!(public.contains("\"points\":3"));
+}
+
+#[test]
+fn correct_answers_have_to_fit_the_question() {
+    for (correct, kind) in [
+        ("paris", "continous_numeric"),
+        ("berlin", "single_choice"),
+        ("[]", "multiple_choice"),
+        ("yes", "text\n          is_long: false"),
+    ] {
+        let yaml = FORM.replacen(
+            "correct: paris\n        spec:\n          type: single_choice",
+            &format!("correct: {correct}\n        spec:\n          type: {kind}"),
+            1,
+        );
+        let form: Form = serde_yaml::from_str(&yaml).unwrap_or_else(|err| panic!("{err}"));
+        assert!(
+            form.validate(&Limits::default()).is_err(),
+            "{correct} {kind}"
+        );
+    }
+}
+
+fn state(name: &str) -> Arc<AppState> {
+    Arc::new(common::state(&format!("quiz-{name}"), [("quiz", form())]))
+}
+
+#[tokio::test]
+async fn score_is_stored_and_shown() {
+    let state = state("submit");
+    let (status, html) = submit(&state, "quiz", "i-0-0=paris&i-0-1=2&i-0-1=3&i-0-2=41").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains("You scored 3 of 5 points."), "{html}");
+    assert!(html.contains(r#"<li class="partly"><h3>Which are primes?</h3><p class="answer">2, 3</p><p class="verdict">partly correct</p></li>"#));
+    assert!(html.contains(r#"<li class="wrong"><h3>6 times 7?</h3><p class="answer">41</p><p class="verdict">wrong</p><p class="explanation">Six sevens make forty-two.</p></li>"#));
+    assert!(html.contains(r#"<li class="ungraded"><h3>Anything else?</h3><p class="answer">(no answer)</p><p class="verdict">not graded</p></li>"#));
+
+    let submissions = state.store.list("quiz");
+    assert_eq!(
+        submissions[0].score,
+        Some(Score {
+            points: 3.0,
+            max: 5.0
+        })
+    );
+}
+
+#[tokio::test]
+async fn api_serves_quiz_without_answers() {
+    let (status, body) = send(&state("api"), get("/api/form/quiz")).await;
+    assert_eq!(status, StatusCode::OK);
+    let definition: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(definition["title"], "Quiz");
+    assert!(definition["groups"][0]["questions"][0]
+        .get("correct")
+        .is_none());
+    assert!(definition["groups"][0]["questions"][2]
+        .get("explanation")
+        .is_none());
+}
+
+#[tokio::test]
+async fn result_can_be_revisited() {
+    let state = state("result");
+    let request = accept_json(post("/submit/quiz", "i-0-0=lyon&i-0-3=no"));
+    let (_, body) = send(&state, request).await;
+    let submitted: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(submitted["score"]["points"], 0.0);
+    let url = submitted["result_url"].as_str().unwrap();
+
+    let (status, html) = send(&state, get(url)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains("You scored 0 of 5 points."), "{html}");
+    assert!(html.contains(r#"<p class="answer">Lyon</p><p class="verdict">wrong</p>"#));
+    assert!(html.contains(r#"<p class="answer">no</p><p class="verdict">not graded</p>"#));
+
+    let tampered = format!("{url}x");
+    assert_eq!(send(&state, get(&tampered)).await.0, StatusCode::NOT_FOUND);
+}
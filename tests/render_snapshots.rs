@@ -0,0 +1,163 @@
+//! Golden-file snapshots of the rendered HTML. After an intended change to
+//! the output, review and accept the new snapshots with `cargo insta review`.
+
+mod common;
+
+use common::{answers, question, render_question};
+use std::collections::{BTreeMap, HashMap};
+use umfragetool_rust::*;
+
+/// Like `render_question`, for a required question with help text whose
+/// answer was rejected.
+fn render_invalid_question(spec: QuestionType) -> String {
+    let question = Question {
+        help: Some("Some help".into()),
+        required: true,
+        ..question(spec)
+    };
+    let id = format!("{ROOT_PREFIX}-0-0");
+    let ctx = RenderContext {
+        errors: HashMap::from([(id.clone(), "wrong".to_string())]),
+        ..Default::default()
+    };
+    question.render(id, &ctx)
+}
+
+#[test]
+fn demo_form() {
+    let ctx = RenderContext {
+        form_id: "demo".to_string(),
+        session: "session".to_string(),
+        ..Default::default()
+    };
+    insta::assert_snapshot!(umfragetool_rust::demo_form().render(ROOT_PREFIX.to_string(), &ctx));
+}
+
+#[test]
+fn discrete_numeric() {
+    insta::assert_snapshot!(render_question(QuestionType::DiscreteNumeric {
+        bounds: (1, 5),
+        num_descriptions: BTreeMap::from([(1, "low".into()), (5, "high".into())]),
+        na_option: None,
+        columns: None,
+    }));
+}
+
+#[test]
+fn continous_numeric() {
+    insta::assert_snapshot!(render_question(QuestionType::ContinousNumeric {
+        step: None,
+        bounds: None,
+        unit: None,
+        unit_position: UnitPosition::Suffix,
+    }));
+}
+
+#[test]
+fn continous_numeric_bounded() {
+    insta::assert_snapshot!(render_question(QuestionType::ContinousNumeric {
+        step: None,
+        bounds: Some((0.0, 2.5)),
+        unit: None,
+        unit_position: UnitPosition::Suffix,
+    }));
+}
+
+#[test]
+fn single_choice() {
+    insta::assert_snapshot!(render_question(QuestionType::SingleChoice {
+        answers: answers(),
+        custom_answer: true,
+        columns: None,
+    }));
+}
+
+#[test]
+fn multiple_choice() {
+    insta::assert_snapshot!(render_question(QuestionType::MultipleChoice {
+        answers: answers(),
+        custom_answer: false,
+        columns: None,
+    }));
+}
+
+#[test]
+fn text_short() {
+    insta::assert_snapshot!(render_question(QuestionType::Text {
+        is_long: false,
+        pattern: Some(Pattern::from("[0-9]{5}".to_string())),
+        max_length: None,
+        warn_at: None,
+    }));
+}
+
+#[test]
+fn text_long() {
+    insta::assert_snapshot!(render_question(QuestionType::Text {
+        is_long: true,
+        pattern: None,
+        max_length: None,
+        warn_at: None,
+    }));
+}
+
+/// The marker, help text and ARIA attributes of a required question, before
+/// anything was submitted.
+#[test]
+fn required_with_help() {
+    let question = Question {
+        help: Some("There are no wrong answers.".into()),
+        required: true,
+        ..question(QuestionType::Text {
+            is_long: true,
+            pattern: None,
+            max_length: None,
+            warn_at: None,
+        })
+    };
+    insta::assert_snapshot!(
+        question.render(format!("{ROOT_PREFIX}-0-0"), &RenderContext::default())
+    );
+}
+
+#[test]
+fn invalid_text() {
+    insta::assert_snapshot!(render_invalid_question(QuestionType::Text {
+        is_long: false,
+        pattern: None,
+        max_length: None,
+        warn_at: None,
+    }));
+}
+
+#[test]
+fn invalid_single_choice() {
+    insta::assert_snapshot!(render_invalid_question(QuestionType::SingleChoice {
+        answers: answers(),
+        custom_answer: false,
+        columns: None,
+    }));
+}
+
+#[test]
+fn invalid_multiple_choice() {
+    insta::assert_snapshot!(render_invalid_question(QuestionType::MultipleChoice {
+        answers: answers(),
+        custom_answer: false,
+        columns: None,
+    }));
+}
+
+#[test]
+fn computed() {
+    insta::assert_snapshot!(render_question(QuestionType::Computed {
+        expression: "i-0-1 * 2".to_string(),
+    }));
+}
+
+#[test]
+fn unknown() {
+    insta::assert_snapshot!(render_question(QuestionType::Unknown {
+        raw_type: "signature".to_string(),
+    }));
+}
@@ -0,0 +1,32 @@
+//! `show_reset` adds a button clearing all answers.
+
+use umfragetool_rust::*;
+
+#[test]
+fn reset_button_only_when_enabled() {
+    let render = |show_reset, no_js| {
+        let form = Form {
+            show_reset,
+            ..demo_form()
+        };
+        let ctx = RenderContext {
+            no_js,
+            ..Default::default()
+        };
+        form.render(ROOT_PREFIX.to_string(), &ctx)
+    };
+    let html = render(true, false);
+    assert!(
+        html.contains(r#"<input type="submit" value="Submit"><input type="reset" value="Clear answers"></form>"#),
+        "{html}"
+    );
+    assert!(html.contains(r#"document.addEventListener("reset""#));
+    // Without scripts the browser's reset is all there is.
+    let html = render(true, true);
+    assert!(html.contains(r#"<input type="reset""#));
+    assert!(!html.contains("<script"));
+
+    let html = render(false, false);
+    assert!(!html.contains(r#"type="reset""#), "{html}");
+    assert!(!html.contains(r#"addEventListener("reset""#));
+}
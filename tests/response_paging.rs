@@ -0,0 +1,70 @@
+//! `GET /api/form/:id/responses` pages through a form's submissions in either
+//! order, with the total alongside.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{get, send, submission, with_admin, with_token};
+use serde_json::Value;
+use std::sync::Arc;
+use umfragetool_rust::demo_form;
+use umfragetool_rust::server::AppState;
+
+async fn state(name: &str) -> Arc<AppState> {
+    let state = with_admin(common::state(
+        &format!("response-paging-{name}"),
+        [("demo", demo_form())],
+    ));
+    for (index, form_id) in ["demo", "demo", "other", "demo", "demo", "demo"]
+        .into_iter()
+        .enumerate()
+    {
+        let submission = submission(form_id, &index.to_string(), &[]);
+        state.store.insert(submission, None).await.unwrap();
+    }
+    Arc::new(state)
+}
+
+async fn page(state: &Arc<AppState>, query: &str, token: Option<&str>) -> (StatusCode, Value) {
+    let request = with_token(get(&format!("/api/form/demo/responses{query}")), token);
+    let (status, body) = send(state, request).await;
+    (status, serde_json::from_str(&body).unwrap_or(Value::Null))
+}
+
+fn ids(page: &Value) -> Vec<&str> {
+    page["responses"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|response| response["id"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn pages_follow_offset_limit_and_order() {
+    let state = state("pages").await;
+    let (status, first) = page(&state, "?limit=2", Some("secret")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ids(&first), ["0", "1"]);
+    assert_eq!(first["total"], 5);
+    assert_eq!(first["offset"], 0);
+    assert_eq!(first["limit"], 2);
+
+    let (_, second) = page(&state, "?offset=2&limit=2", Some("secret")).await;
+    assert_eq!(ids(&second), ["3", "4"]);
+    let (_, last) = page(&state, "?offset=4&limit=2", Some("secret")).await;
+    assert_eq!(ids(&last), ["5"]);
+    let (_, newest) = page(&state, "?limit=2&order=desc", Some("secret")).await;
+    assert_eq!(ids(&newest), ["5", "4"]);
+    let (_, all) = page(&state, "", Some("secret")).await;
+    assert_eq!(ids(&all), ["0", "1", "3", "4", "5"]);
+    assert_eq!(all["limit"], 50);
+    let (_, capped) = page(&state, "?limit=100000", Some("secret")).await;
+    assert_eq!(capped["limit"], 1000);
+}
+
+#[tokio::test]
+async fn paging_needs_admin() {
+    let state = state("admin").await;
+    assert_eq!(page(&state, "", None).await.0, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,41 @@
+//! Serializing a form gives the same bytes every time, so definitions written
+//! back (PUT, imports, backups) diff cleanly.
+
+use std::collections::BTreeMap;
+use umfragetool_rust::{demo_form, Form, QuestionType};
+
+fn scale() -> Form {
+    let mut form = demo_form();
+    form.groups[0].questions[2].spec = QuestionType::DiscreteNumeric {
+        bounds: (-5, 5),
+        num_descriptions: (-5..=5)
+            .rev()
+            .map(|value| (value, format!("Point {value}").into()))
+            .collect::<BTreeMap<_, _>>(),
+        na_option: None,
+        columns: None,
+    };
+    form
+}
+
+#[test]
+fn repeated_serializations_are_identical() {
+    let yaml = serde_yaml::to_string(&scale()).unwrap();
+    for _ in 0..20 {
+        assert_eq!(serde_yaml::to_string(&scale()).unwrap(), yaml);
+    }
+    let json = serde_json::to_string(&scale()).unwrap();
+    assert_eq!(serde_json::to_string(&scale()).unwrap(), json);
+}
+
+#[test]
+fn descriptions_are_written_in_order() {
+    let yaml = serde_yaml::to_string(&scale()).unwrap();
+    let positions: Vec<usize> = (-5..=5)
+        .map(|value| yaml.find(&format!("Point {value}")).unwrap())
+        .collect();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]), "{yaml}");
+
+    let reloaded: Form = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(serde_yaml::to_string(&reloaded).unwrap(), yaml);
+}
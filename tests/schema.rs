@@ -0,0 +1,25 @@
+//! Keeps `form_schema()` in line with what the model actually accepts.
+
+use umfragetool_rust::*;
+
+fn validate(instance: &serde_json::Value) {
+    let schema = serde_json::to_value(form_schema()).unwrap();
+    let validator = jsonschema::validator_for(&schema).expect("schema compiles");
+    let errors: Vec<String> = validator
+        .iter_errors(instance)
+        .map(|error| format!("{}: {error}", error.instance_path))
+        .collect();
+    assert!(errors.is_empty(), "{errors:#?}");
+}
+
+#[test]
+fn sample_definition_matches_schema() {
+    let yaml: serde_yaml::Value =
+        serde_yaml::from_str(include_str!("../test.yml")).expect("sample parses");
+    validate(&serde_json::to_value(yaml).unwrap());
+}
+
+#[test]
+fn serialized_form_matches_schema() {
+    validate(&serde_json::to_value(demo_form()).unwrap());
+}
@@ -0,0 +1,201 @@
+//! Conditional questions and question sets: answers to questions that were
+//! not asked are dropped.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::submit;
+use std::collections::HashMap;
+use std::sync::Arc;
+use umfragetool_rust::*;
+
+const FORM: &str = r#"
+title: Team
+description: ""
+groups:
+  - title: You
+    description: ""
+    questions:
+      - title: Role
+        key: role
+        required: true
+        spec: { type: single_choice, answers: [manager, staff], custom_answer: false }
+      - title: Team size
+        key: team
+        required: true
+        show_if: "role:eq:manager"
+        spec: { type: continous_numeric }
+      - title: How do you keep up?
+        key: large-team
+        show_if: "team:gt:5"
+        spec: { type: text, is_long: true }
+"#;
+
+fn form() -> Form {
+    let form: Form = serde_yaml::from_str(FORM).unwrap();
+    form.validate(&Limits::default()).unwrap();
+    form
+}
+
+/// `FORM` with a set only managers are asked.
+fn with_manager_set(per_group_submit: bool) -> Form {
+    let set = r#"
+  - title: Managers
+    description: ""
+    show_if: "role:eq:manager"
+    questions:
+      - title: Budget
+        key: budget
+        required: true
+        spec: { type: continous_numeric }
+      - title: Hiring?
+        key: hiring
+        show_if: "budget:gt:1000"
+        spec: { type: text, is_long: false }
+  - title: Everyone
+    description: ""
+    questions:
+      - title: Anything else?
+        key: else
+        spec: { type: text, is_long: true }
+"#;
+    let mut form: Form = serde_yaml::from_str(&format!("{FORM}{set}")).unwrap();
+    form.per_group_submit = per_group_submit;
+    form.validate(&Limits::default()).unwrap();
+    form
+}
+
+fn answers(pairs: &[(&str, &str)]) -> HashMap<String, Vec<String>> {
+    pairs
+        .iter()
+        .map(|(id, value)| (id.to_string(), vec![value.to_string()]))
+        .collect()
+}
+
+#[test]
+fn answers_to_unmet_conditions_are_dropped() {
+    let form = form();
+    let mut given = answers(&[
+        ("role", "staff"),
+        ("team", "12"),
+        ("large-team", "Meetings"),
+    ]);
+    assert!(form.validate_submission(&given).is_ok());
+    form.drop_hidden_answers(&mut given);
+    assert_eq!(given, answers(&[("role", "staff")]));
+
+    let mut given = answers(&[
+        ("role", "manager"),
+        ("team", "3"),
+        ("large-team", "Meetings"),
+    ]);
+    form.drop_hidden_answers(&mut given);
+    assert_eq!(given, answers(&[("role", "manager"), ("team", "3")]));
+}
+
+#[test]
+fn met_conditions_make_questions_required() {
+    let errors = form()
+        .validate_submission(&answers(&[("role", "manager")]))
+        .unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].id, "team");
+}
+
+#[test]
+fn conditions_have_to_refer_to_earlier_questions() {
+    let mut form: Form = serde_yaml::from_str(FORM).unwrap();
+    form.groups[0].questions[0].show_if = Some("team:gt:1".to_string());
+    form.groups[0].questions[1].show_if = Some("role:is:manager".to_string());
+    let problems = form.validate(&Limits::default()).unwrap_err().problems;
+    assert_eq!(problems.len(), 2, "{problems:?}");
+    assert!(problems[0].contains("depends on team, which is not asked before it"));
+    assert!(problems[1].contains("invalid show_if"));
+}
+
+#[test]
+fn conditional_questions_are_marked() {
+    let form = form();
+    let html = form.render(ROOT_PREFIX.to_string(), &RenderContext::default());
+    assert!(html.contains(r#"<div class="conditional" data-show-if="role:eq:manager">"#));
+    assert!(
+        html.contains(r#"<input type="text" inputmode="decimal" lang="en" name="team" id="team">"#)
+    );
+    assert!(html.contains(r#"getAttribute("data-show-if")"#));
+
+    let no_js = RenderContext {
+        no_js: true,
+        ..Default::default()
+    };
+    let html = form.render(ROOT_PREFIX.to_string(), &no_js);
+    assert!(!html.contains("<script"));
+}
+
+#[test]
+fn unmet_set_conditions_skip_the_whole_set() {
+    let form = with_manager_set(false);
+    // Its required questions need no answer then.
+    let mut given = answers(&[("role", "staff"), ("hiring", "Soon"), ("else", "No")]);
+    assert!(form.validate_submission(&given).is_ok());
+    form.drop_hidden_answers(&mut given);
+    assert_eq!(given, answers(&[("role", "staff"), ("else", "No")]));
+
+    let errors = form
+        .validate_submission(&answers(&[("role", "manager"), ("team", "3")]))
+        .unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].id, "budget");
+    let mut given = answers(&[
+        ("role", "manager"),
+        ("team", "3"),
+        ("budget", "5000"),
+        ("hiring", "Soon"),
+    ]);
+    form.drop_hidden_answers(&mut given);
+    assert_eq!(given.len(), 4);
+}
+
+#[test]
+fn set_conditions_have_to_refer_to_earlier_sets() {
+    let mut form = with_manager_set(false);
+    form.groups[1].show_if = Some("budget:gt:0".to_string());
+    form.groups[2].show_if = Some("role:is:manager".to_string());
+    let problems = form.validate(&Limits::default()).unwrap_err().problems;
+    assert_eq!(problems.len(), 2, "{problems:?}");
+    assert!(problems[0].contains(
+        "question set 1 (Managers) depends on budget, which is not asked in an earlier set"
+    ));
+    assert!(problems[1].contains("question set 2 (Everyone) has an invalid show_if"));
+}
+
+#[test]
+fn conditional_sets_are_marked() {
+    let html = with_manager_set(false).render(ROOT_PREFIX.to_string(), &RenderContext::default());
+    assert!(html.contains(
+        r#"<section aria-labelledby="i-1-title" class="conditional" data-show-if="role:eq:manager">"#
+    ));
+    // Browsers must not insist on answers to a set that may be hidden.
+    assert!(html.contains(r#"name="budget" id="budget">"#), "{html}");
+    assert!(!html.contains(r#"name="budget" id="budget" required"#));
+    assert!(html.contains(r#"<section aria-labelledby="i-2-title">"#));
+
+    // With a submit per set, the set's save button goes with it.
+    let ctx = RenderContext {
+        form_id: "team".to_string(),
+        ..Default::default()
+    };
+    let html = with_manager_set(true).render(ROOT_PREFIX.to_string(), &ctx);
+    assert!(
+        html.contains(r#"action="/submit/team/1" data-show-if="role:eq:manager">"#),
+        "{html}"
+    );
+}
+
+#[tokio::test]
+async fn stored_submission_omits_hidden_answers() {
+    let state = Arc::new(common::state("show-if", [("team", form())]));
+    let (status, _) = submit(&state, "team", "role=staff&team=12").await;
+    assert_eq!(status, StatusCode::OK);
+    let stored = state.store.list("team");
+    assert_eq!(stored[0].answers, answers(&[("role", "staff")]));
+}
@@ -0,0 +1,221 @@
+//! Shuffled questions and options keep the order a respondent first saw on
+//! every page they get: reloads, error pages and resumed drafts.
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::fetch;
+use regex::Regex;
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::*;
+
+fn question(title: &str, spec: QuestionType) -> Question {
+    Question {
+        title: title.into(),
+        key: None,
+        help: None,
+        required: false,
+        correct: None,
+        points: None,
+        explanation: None,
+        show_if: None,
+        spec,
+    }
+}
+
+fn shuffled() -> Form {
+    let text = || QuestionType::Text {
+        is_long: false,
+        pattern: None,
+        max_length: None,
+        warn_at: None,
+    };
+    let mut questions: Vec<Question> = (0..6)
+        .map(|index| question(&format!("Question {index}"), text()))
+        .collect();
+    questions[0].required = true;
+    questions.push(question(
+        "Pick one",
+        QuestionType::SingleChoice {
+            answers: (0..8)
+                .map(|index| Answer::from(format!("Option {index}").as_str()))
+                .collect(),
+            custom_answer: false,
+            columns: None,
+        },
+    ));
+    Form {
+        title: "Shuffled".into(),
+        groups: vec![QuestionSet {
+            title: "Set".into(),
+            description: "".into(),
+            questions,
+            show_if: None,
+            collapsible: false,
+        }],
+        shuffle_questions: true,
+        shuffle_answers: true,
+        drafts: true,
+        ..Default::default()
+    }
+}
+
+/// Ids of the questions and options in the order the page shows them.
+fn order(html: &str) -> Vec<String> {
+    Regex::new(r#"<(?:input|textarea)[^>]* id="(i-[0-9-]+)""#)
+        .unwrap()
+        .captures_iter(html)
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
+#[test]
+fn order_depends_only_on_the_seed() {
+    let order = shuffled_order(7, "i-0", 20);
+    assert_eq!(order, shuffled_order(7, "i-0", 20));
+    assert_ne!(order, shuffled_order(8, "i-0", 20));
+    assert_ne!(order, shuffled_order(7, "i-1", 20));
+    let mut sorted = order.clone();
+    sorted.sort();
+    assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+}
+
+#[test]
+fn rendering_follows_the_seed() {
+    let render = |seed| {
+        let ctx = RenderContext {
+            seed,
+            ..Default::default()
+        };
+        order(&shuffled().render(ROOT_PREFIX.to_string(), &ctx))
+    };
+    let first = render(Some(1));
+    assert_eq!(first, render(Some(1)));
+    assert_ne!(first, render(Some(2)));
+    // Without a seed, and for forms that do not shuffle, the declared order.
+    let declared = render(None);
+    assert_eq!(declared[..3], ["i-0-0", "i-0-1", "i-0-2"]);
+    let form = Form {
+        shuffle_questions: false,
+        shuffle_answers: false,
+        ..shuffled()
+    };
+    let ctx = RenderContext {
+        seed: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(order(&form.render(ROOT_PREFIX.to_string(), &ctx)), declared);
+}
+
+fn state(name: &str) -> Arc<AppState> {
+    Arc::new(common::state(
+        &format!("shuffle-{name}"),
+        [("demo", shuffled())],
+    ))
+}
+
+fn get(uri: &str, cookie: Option<&str>) -> Request<Body> {
+    with_cookie(common::get(uri), cookie)
+}
+
+fn post(uri: &str, body: &str, cookie: Option<&str>) -> Request<Body> {
+    with_cookie(common::post(uri, body), cookie)
+}
+
+fn with_cookie(mut request: Request<Body>, cookie: Option<&str>) -> Request<Body> {
+    if let Some(cookie) = cookie {
+        let cookie = cookie.parse().unwrap();
+        request.headers_mut().insert(header::COOKIE, cookie);
+    }
+    request
+}
+
+/// The posted hidden fields of a page.
+fn hidden(html: &str) -> String {
+    Regex::new(r#"<input type="hidden" name="([^"]+)" value="([^"]*)">"#)
+        .unwrap()
+        .captures_iter(html)
+        .map(|captures| format!("{}={}", &captures[1], &captures[2]))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[tokio::test]
+async fn session_keeps_its_order() {
+    let state = state("session");
+    let (_, headers, html) = fetch(&state, get("/form/demo", None)).await;
+    let cookie = headers[header::SET_COOKIE].to_str().unwrap();
+    assert!(cookie.contains("; HttpOnly; SameSite=Lax"), "{cookie}");
+    let cookie = cookie.split(';').next().unwrap().to_string();
+    let first = order(&html);
+
+    // Reloading with the cookie shows the same order and sets no new one.
+    let (_, headers, html) = fetch(&state, get("/form/demo", Some(&cookie))).await;
+    assert_eq!(order(&html), first);
+    assert!(!headers.contains_key(header::SET_COOKIE));
+    // A forged cookie counts as none.
+    let (_, headers, _) = fetch(&state, get("/form/demo", Some("umfragetool_seed=1.00"))).await;
+    assert!(headers.contains_key(header::SET_COOKIE));
+
+    // So does the page marking problems, even without the cookie.
+    let fields = hidden(&html);
+    let (status, _, html) = fetch(&state, post("/submit/demo", &fields, None)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(order(&html), first);
+
+    // And a resumed draft.
+    let (_, _, html) = fetch(
+        &state,
+        post("/draft/demo", &format!("{fields}&i-0-1=later"), None),
+    )
+    .await;
+    let resume = Regex::new(r#"href="(/form/demo/resume/[^"?]+)"#)
+        .unwrap()
+        .captures(&html)
+        .unwrap()[1]
+        .to_string();
+    let (_, _, html) = fetch(&state, get(&resume, None)).await;
+    assert_eq!(order(&html), first);
+
+    let (status, _, _) = fetch(
+        &state,
+        post("/submit/demo", &format!("{fields}&i-0-0=now"), None),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let seed = state.store.list("demo")[0].shuffle_seed.unwrap();
+    let ctx = RenderContext {
+        seed: Some(seed),
+        ..Default::default()
+    };
+    assert_eq!(
+        order(&shuffled().render(ROOT_PREFIX.to_string(), &ctx)),
+        first
+    );
+}
+
+#[tokio::test]
+async fn sets_saved_on_their_own_keep_the_seed() {
+    let form = Form {
+        per_group_submit: true,
+        ..shuffled()
+    };
+    let state = Arc::new(common::state("shuffle-per-group", [("demo", form)]));
+    let (_, _, html) = fetch(&state, get("/form/demo", None)).await;
+    let first = order(&html);
+
+    let fields = format!("{}&i-0-0=now", hidden(&html));
+    let (status, _, _) = fetch(&state, post("/submit/demo/0", &fields, None)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let seed = state.store.list("demo")[0].shuffle_seed.unwrap();
+    let ctx = RenderContext {
+        seed: Some(seed),
+        ..Default::default()
+    };
+    assert_eq!(
+        order(&shuffled().render(ROOT_PREFIX.to_string(), &ctx)),
+        first
+    );
+}
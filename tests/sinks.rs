@@ -0,0 +1,119 @@
+//! Every stored submission is handed to the sinks of the state in the
+//! background: neither a failing nor a slow sink costs the respondent their
+//! submission or holds up their response.
+
+mod common;
+
+use axum::async_trait;
+use axum::http::StatusCode;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use umfragetool_rust::demo_form;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::sinks::{JsonLinesFile, SinkError, SubmissionSink};
+use umfragetool_rust::store::{Submission, SubmissionStore};
+
+/// Remembers the submissions it was handed.
+#[derive(Clone, Default)]
+struct MockSink {
+    received: Arc<Mutex<Vec<Submission>>>,
+}
+
+#[async_trait]
+impl SubmissionSink for MockSink {
+    async fn handle(&self, submission: &Submission) -> Result<(), SinkError> {
+        self.received.lock().unwrap().push(submission.clone());
+        Ok(())
+    }
+}
+
+struct Broken;
+
+/// Never done with a submission.
+struct Stuck;
+
+#[async_trait]
+impl SubmissionSink for Stuck {
+    async fn handle(&self, _: &Submission) -> Result<(), SinkError> {
+        std::future::pending().await
+    }
+}
+
+#[async_trait]
+impl SubmissionSink for Broken {
+    async fn handle(&self, _: &Submission) -> Result<(), SinkError> {
+        Err(SinkError::Rejected("always".to_string()))
+    }
+}
+
+async fn submit(state: &Arc<AppState>, body: &str) -> StatusCode {
+    common::submit(state, "demo", body).await.0
+}
+
+#[tokio::test]
+async fn sinks_receive_stored_submissions() {
+    let dir = std::env::temp_dir().join("umfragetool-sinks");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let copy = dir.join("copy.jsonl");
+    let mock = MockSink::default();
+    let mut state = AppState::new(
+        HashMap::from([("demo".to_string(), demo_form())]),
+        SubmissionStore::open(&dir.join("submissions.jsonl")).unwrap(),
+    );
+    state.sinks = Arc::new(vec![
+        Box::new(Broken),
+        Box::new(mock.clone()),
+        Box::new(JsonLinesFile { path: copy.clone() }),
+    ]);
+    let state = Arc::new(state);
+
+    assert_eq!(submit(&state, "i-0-0=Because").await, StatusCode::OK);
+    assert_eq!(submit(&state, "i-0-0=Why not").await, StatusCode::OK);
+
+    let stored = state.store.list("demo");
+    assert_eq!(stored.len(), 2);
+    let copied = || -> Vec<Submission> {
+        std::fs::read_to_string(&copy)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    };
+    // The copy is the last sink, so the others are done once it has both.
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while std::fs::read_to_string(&copy)
+            .unwrap_or_default()
+            .lines()
+            .count()
+            < 2
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let by_id = |mut submissions: Vec<Submission>| {
+        submissions.sort_by(|a, b| a.id.cmp(&b.id));
+        submissions
+    };
+    assert_eq!(
+        by_id(mock.received.lock().unwrap().clone()),
+        by_id(stored.clone())
+    );
+    assert_eq!(by_id(copied()), by_id(stored));
+}
+
+#[tokio::test]
+async fn slow_sinks_do_not_hold_up_the_response() {
+    let mut state = common::state("sinks-stuck", [("demo", demo_form())]);
+    state.sinks = Arc::new(vec![Box::new(Stuck)]);
+    let state = Arc::new(state);
+
+    let status = tokio::time::timeout(Duration::from_secs(5), submit(&state, "i-0-0=Because"))
+        .await
+        .expect("the response waited for the sink");
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(state.store.list("demo").len(), 1);
+}
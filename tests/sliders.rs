@@ -0,0 +1,46 @@
+//! Bounded numbers are sliders with a step and ticks fitting their range,
+//! and their ends labelled.
+
+mod common;
+
+use common::render_question;
+use umfragetool_rust::*;
+
+#[test]
+fn slider_steps_and_ticks_fit_the_range() {
+    assert_eq!(slider_step(0.0, 1.0), "0.01");
+    assert_eq!(slider_step(-500.0, 500.0), "10");
+    assert_eq!(slider_step(0.0, 0.1), "0.001");
+    assert_eq!(slider_step(0.0, 2.5), "0.05");
+    // No positions to step between, and no division by zero.
+    assert_eq!(slider_step(3.0, 3.0), "any");
+    assert_eq!(slider_step(5.0, 1.0), "any");
+    assert!(slider_ticks(3.0, 3.0).is_empty());
+
+    assert_eq!(
+        slider_ticks(0.0, 1.0),
+        ["0", "0.1", "0.2", "0.3", "0.4", "0.5", "0.6", "0.7", "0.8", "0.9", "1"]
+    );
+    assert_eq!(
+        slider_ticks(-500.0, 500.0),
+        ["-500", "-400", "-300", "-200", "-100", "0", "100", "200", "300", "400", "500"]
+    );
+    assert_eq!(slider_ticks(0.0, 0.1)[..3], ["0", "0.01", "0.02"]);
+    assert_eq!(slider_ticks(0.0, 2.5), ["0", "0.5", "1", "1.5", "2", "2.5"]);
+    for (min, max) in [(0.0, 7.0), (1.0, 13.0), (-0.3, 0.4), (0.0, 1e6)] {
+        let count = slider_ticks(min, max).len();
+        assert!((5..=11).contains(&count), "{min} to {max}: {count} ticks");
+    }
+}
+
+#[test]
+fn slider_ends_are_labelled_with_the_unit() {
+    let html = render_question(QuestionType::ContinousNumeric {
+        bounds: Some((0.0, 100.0)),
+        unit: Some("km".to_string()),
+        unit_position: UnitPosition::Suffix,
+        step: Some(5.0),
+    });
+    assert!(html.contains(r#"<span class="slider-min">0<span class="unit">km</span></span><input type="range" min="0" max="100" step="5" list="i-0-0-ticks""#), "{html}");
+    assert!(html.contains(r#"<span class="slider-max">100<span class="unit">km</span></span><datalist id="i-0-0-ticks"><option value="0"></option><option value="10"></option>"#), "{html}");
+}
@@ -0,0 +1,158 @@
+//! Forms outside their open window show a page of their own, or a built-in
+//! notice, and refuse submissions.
+
+mod common;
+
+use axum::http::StatusCode;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use common::{get, send, stop_clock, submission};
+use std::sync::Arc;
+use umfragetool_rust::{demo_form, Form, Limits, PageLink, StatusPage};
+
+fn opens_at() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap()
+}
+
+fn window(form: Form) -> Form {
+    Form {
+        opens_at: Some(opens_at()),
+        closes_at: Some(opens_at() + Duration::days(7)),
+        ..form
+    }
+}
+
+fn custom() -> Form {
+    window(Form {
+        not_open_page: Some(StatusPage {
+            title: "Coming <soon>".into(),
+            body: "Starts on **May 1st**. <script>alert(1)</script>".into(),
+            link: None,
+        }),
+        closed_page: Some(StatusPage {
+            title: serde_yaml::from_str("{ en: All done, de: Vorbei }").unwrap(),
+            body: "Thanks to everyone who took part.".into(),
+            link: Some(PageLink {
+                label: "See the results".into(),
+                url: "/results?survey=demo&year=2024".to_string(),
+            }),
+        }),
+        ..demo_form()
+    })
+}
+
+async fn page_at(form: Form, name: &str, now: DateTime<Utc>, responses: usize) -> String {
+    let mut state = common::state(&format!("status-{name}"), [("demo", form)]);
+    stop_clock(&mut state, now);
+    for index in 0..responses {
+        let submission = submission("demo", &index.to_string(), &[("i-0-0", &["Because"])]);
+        state.store.insert(submission, None).await.unwrap();
+    }
+    let (status, html) = send(&Arc::new(state), get("/form/demo")).await;
+    assert_eq!(status, StatusCode::OK);
+    html
+}
+
+#[tokio::test]
+async fn built_in_notices_without_custom_pages() {
+    let before = opens_at() - Duration::hours(1);
+    let html = page_at(window(demo_form()), "default-before", before, 0).await;
+    assert!(
+        html.contains("<p>This survey is not open yet.</p>"),
+        "{html}"
+    );
+    let after = opens_at() + Duration::days(8);
+    let html = page_at(window(demo_form()), "default-after", after, 2).await;
+    assert!(html.contains("<p>This survey is closed.</p>"), "{html}");
+    assert!(!html.contains("class=\"count\""));
+}
+
+#[tokio::test]
+async fn custom_pages_are_escaped_markdown() {
+    let before = opens_at() - Duration::hours(1);
+    let html = page_at(custom(), "custom-before", before, 0).await;
+    assert!(html.contains("<h1>Coming &lt;soon&gt;</h1>"), "{html}");
+    assert!(html.contains("<strong>May 1st</strong>"), "{html}");
+    assert!(!html.contains("<script>"), "{html}");
+    assert!(!html.contains("not open yet"));
+
+    let after = opens_at() + Duration::days(8);
+    let html = page_at(custom(), "custom-after", after, 0).await;
+    assert!(html.contains("<h1>All done</h1>"), "{html}");
+    assert!(html.contains("<p>Thanks to everyone who took part.</p>"));
+    assert!(
+        html.contains(r#"<p><a href="/results?survey=demo&amp;year=2024">See the results</a></p>"#)
+    );
+}
+
+#[tokio::test]
+async fn closed_page_shows_a_public_count() {
+    let after = opens_at() + Duration::days(8);
+    let counted = || Form {
+        public_count: true,
+        ..custom()
+    };
+    let html = page_at(counted(), "count-custom", after, 3).await;
+    assert!(
+        html.contains(r#"<p class="count">It received 3 responses.</p>"#),
+        "{html}"
+    );
+    let html = page_at(
+        window(Form {
+            public_count: true,
+            ..demo_form()
+        }),
+        "count-one",
+        after,
+        1,
+    )
+    .await;
+    assert!(
+        html.contains(
+            "<p>This survey is closed.</p><p class=\"count\">It received 1 response.</p>"
+        ),
+        "{html}"
+    );
+
+    // The count is the final one, so an open form does not show it here.
+    let before = opens_at() - Duration::hours(1);
+    let html = page_at(counted(), "count-before", before, 3).await;
+    assert!(!html.contains("class=\"count\""));
+}
+
+#[tokio::test]
+async fn submissions_are_only_taken_within_the_window() {
+    let mut state = common::state("status-submit", [("demo", window(demo_form()))]);
+    let now = stop_clock(&mut state, opens_at() - Duration::hours(1));
+    let state = Arc::new(state);
+    let submit = || async { common::submit(&state, "demo", "i-0-0=Because").await.0 };
+
+    assert_eq!(submit().await, StatusCode::FORBIDDEN);
+    *now.lock().unwrap() = opens_at() + Duration::days(1);
+    assert_eq!(submit().await, StatusCode::OK);
+    *now.lock().unwrap() = opens_at() + Duration::days(7);
+    assert_eq!(submit().await, StatusCode::FORBIDDEN);
+    assert_eq!(state.store.list("demo").len(), 1);
+}
+
+#[tokio::test]
+async fn open_form_is_served_within_the_window() {
+    let html = page_at(window(demo_form()), "open", opens_at(), 0).await;
+    assert!(
+        html.contains(r#"<form method="post" action="/submit/demo">"#),
+        "{html}"
+    );
+    assert!(!html.contains("not open yet") && !html.contains("is closed"));
+}
+
+#[test]
+fn link_must_be_absolute_or_root_relative() {
+    let mut form = custom();
+    let link = form
+        .closed_page
+        .as_mut()
+        .and_then(|page| page.link.as_mut());
+    link.unwrap().url = "javascript:alert(1)".to_string();
+    let err = form.validate(&Limits::default()).unwrap_err();
+    assert!(err.to_string().contains("closed_page.link.url"), "{err}");
+    assert!(custom().validate(&Limits::default()).is_ok());
+}
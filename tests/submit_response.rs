@@ -0,0 +1,122 @@
+//! What `/submit` answers with: JSON for clients that accept it, pages for
+//! browsers, or a redirect to the form's `redirect_after`.
+
+mod common;
+
+use axum::http::{header, StatusCode};
+use common::{fetch, post};
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::{demo_form, Form, Limits};
+
+/// The demo form, with an answer to its first question required.
+fn form() -> Form {
+    let mut form = demo_form();
+    form.groups[0].questions[0].required = true;
+    form
+}
+
+fn state(name: &str, form: Form) -> Arc<AppState> {
+    Arc::new(common::state(
+        &format!("submit-response-{name}"),
+        [("demo", form)],
+    ))
+}
+
+async fn submit(
+    state: &Arc<AppState>,
+    accept: Option<&str>,
+    body: &str,
+) -> (StatusCode, String, String) {
+    let mut request = post("/submit/demo", body);
+    if let Some(accept) = accept {
+        let accept = accept.parse().unwrap();
+        request.headers_mut().insert(header::ACCEPT, accept);
+    }
+    let (status, headers, body) = fetch(state, request).await;
+    let content_type = headers[header::CONTENT_TYPE].to_str().unwrap().to_string();
+    (status, content_type, body)
+}
+
+#[tokio::test]
+async fn json_clients_get_the_outcome_as_json() {
+    let state = state("json", form());
+    let (status, content_type, body) =
+        submit(&state, Some("application/json"), "i-0-0=Because").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/json");
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["status"], "ok");
+    assert_eq!(json["submission_id"], state.store.list("demo")[0].id);
+
+    let (status, content_type, body) = submit(&state, Some("application/json"), "i-0-2=11").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(content_type, "application/json");
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["status"], "error");
+    let fields: Vec<&str> = json["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| field["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, ["i-0-0", "i-0-2"]);
+    assert_eq!(state.store.list("demo").len(), 1);
+}
+
+#[tokio::test]
+async fn browsers_get_pages() {
+    let state = state("html", form());
+    for accept in [None, Some("text/html,application/xhtml+xml,*/*;q=0.8")] {
+        let (status, content_type, body) = submit(&state, accept, "i-0-0=Because").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "text/html; charset=utf-8");
+        assert!(body.contains("<html"), "{body}");
+
+        let (status, content_type, body) = submit(&state, accept, "").await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(content_type, "text/html; charset=utf-8");
+        assert!(body.contains(r#"<form method="post""#), "{body}");
+    }
+}
+
+#[tokio::test]
+async fn redirect_after_sends_browsers_on() {
+    let state = state(
+        "redirect",
+        Form {
+            redirect_after: Some("https://example.org/thanks?from=survey".to_string()),
+            ..form()
+        },
+    );
+    let (status, headers, _) = fetch(&state, post("/submit/demo", "i-0-0=Because")).await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    assert_eq!(
+        headers[header::LOCATION],
+        "https://example.org/thanks?from=survey"
+    );
+    assert_eq!(state.store.list("demo").len(), 1);
+
+    // Scripts are told where to go, and problems are shown, not skipped.
+    let (_, _, body) = submit(&state, Some("application/json"), "i-0-0=Again").await;
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        json["redirect_url"],
+        "https://example.org/thanks?from=survey"
+    );
+    let (status, _, _) = submit(&state, None, "").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[test]
+fn redirect_has_to_be_absolute_or_root_relative() {
+    let form = |url: &str| Form {
+        redirect_after: Some(url.to_string()),
+        ..form()
+    };
+    let err = form("javascript:alert(1)")
+        .validate(&Limits::default())
+        .unwrap_err();
+    assert!(err.to_string().contains("redirect_after"), "{err}");
+    assert!(form("/done").validate(&Limits::default()).is_ok());
+}
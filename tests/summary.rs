@@ -0,0 +1,110 @@
+//! `summary.json` aggregates the stored answers per question.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{fresh_store, send, submission, with_admin, with_token};
+use std::collections::HashMap;
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+
+async fn state(name: &str) -> Arc<AppState> {
+    let store = fresh_store(&format!("summary-{name}"));
+    let answers: [&[(&str, &[&str])]; 3] = [
+        &[
+            ("i-0-0", &["Because"]),
+            ("i-0-1", &["1.5"]),
+            ("i-0-4", &["Pizza", "MAOAM"]),
+        ],
+        &[
+            ("i-0-0", &["Why not"]),
+            ("i-0-1", &["2.5"]),
+            ("i-0-4", &["Pizza"]),
+        ],
+        &[("i-0-1", &["5"]), ("i-0-4", &["Gnocchi"])],
+    ];
+    for (idx, answers) in answers.iter().enumerate() {
+        let submission = submission("demo", &idx.to_string(), answers);
+        store.insert(submission, None).await.unwrap();
+    }
+    let forms = HashMap::from([("demo".to_string(), umfragetool_rust::demo_form())]);
+    Arc::new(with_admin(AppState::new(forms, store)))
+}
+
+async fn get(state: Arc<AppState>, token: Option<&str>) -> (StatusCode, String) {
+    let request = with_token(common::get("/form/demo/summary.json"), token);
+    send(&state, request).await
+}
+
+#[tokio::test]
+async fn summary_needs_admin() {
+    let (status, _) = get(state("auth").await, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn summary_counts_choices_and_averages_numbers() {
+    let (status, body) = get(state("counts").await, Some("secret")).await;
+    assert_eq!(status, StatusCode::OK);
+    let summary: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(summary["responses"], 3);
+    let question = |id: &str| {
+        summary["questions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|question| question["id"] == id)
+            .unwrap()
+            .clone()
+    };
+
+    let text = question("i-0-0");
+    assert_eq!(text["answered"], 2);
+    assert!(text.get("counts").is_none());
+
+    let number = question("i-0-1");
+    assert_eq!(number["mean"], 3.0);
+    assert_eq!(number["min"], 1.5);
+    assert_eq!(number["max"], 5.0);
+
+    let choices = question("i-0-4");
+    assert_eq!(choices["answered"], 3);
+    assert_eq!(
+        choices["counts"],
+        serde_json::json!([
+            { "value": "Pizza", "count": 2 },
+            { "value": "Ravioli", "count": 0 },
+            { "value": "MAOAM", "count": 1 },
+            { "value": "Gnocchi", "count": 1 },
+        ])
+    );
+}
+
+#[tokio::test]
+async fn versions_are_counted_and_mixing_them_is_flagged() {
+    let state = state("versions").await;
+    let (_, body) = get(state.clone(), Some("secret")).await;
+    let summary: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        summary["versions"],
+        serde_json::json!([{ "value": "unversioned", "count": 3 }])
+    );
+    assert!(summary.get("warning").is_none());
+
+    for (id, version) in [("0", "aaaa"), ("1", "aaaa"), ("2", "bbbb")] {
+        let mut submission = state.store.get("demo", id).unwrap();
+        submission.form_version = Some(version.to_string());
+        state.store.update(submission).await.unwrap();
+    }
+    let (_, body) = get(state, Some("secret")).await;
+    let summary: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        summary["versions"],
+        serde_json::json!([
+            { "value": "aaaa", "count": 2 },
+            { "value": "bbbb", "count": 1 },
+        ])
+    );
+    let warning = summary["warning"].as_str().unwrap();
+    assert!(warning.contains("2 versions of the form"), "{warning}");
+}
@@ -0,0 +1,147 @@
+//! Questions and question sets declared under `templates` and used by name
+//! load as if they were written out.
+
+use std::path::PathBuf;
+use umfragetool_rust::{load_form, Form, Limits, LoadError, QuestionType};
+
+const TEMPLATES: &str = "\
+templates:
+  age:
+    title: How old are you?
+    required: true
+    spec:
+      type: continous_numeric
+      bounds: [0, 120]
+  comment:
+    title: Anything else?
+    spec:
+      type: text
+      is_long: true
+  wrap_up:
+    title: Finally
+    description: ''
+    questions:
+      - template: comment
+";
+
+fn write(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("umfragetool-templates-{name}"));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for (path, content) in files {
+        std::fs::write(dir.join(path), content).unwrap();
+    }
+    dir.join(files[0].0)
+}
+
+fn load(name: &str, groups: &str) -> Result<Form, LoadError> {
+    let form = format!("title: Survey\ndescription: ''\n{TEMPLATES}groups:\n{groups}");
+    load_form(&write(name, &[("form.yml", &form)]), &Limits::default())
+}
+
+#[test]
+fn references_expand_with_their_overrides() {
+    let form = load(
+        "expand",
+        "  - title: You
+    description: ''
+    questions:
+      - template: age
+      - template: age
+        title: How old is your oldest child?
+        spec: { bounds: [0, 60] }
+  - template: wrap_up
+    title: Last words
+",
+    )
+    .unwrap();
+    let questions: Vec<_> = form.questions().map(|(_, question)| question).collect();
+    assert_eq!(questions.len(), 3);
+    assert_eq!(form.text(&questions[0].title), "How old are you?");
+    assert_eq!(
+        form.text(&questions[1].title),
+        "How old is your oldest child?"
+    );
+    // Fields not overridden come from the template.
+    assert!(questions[1].required);
+    assert!(matches!(
+        questions[1].spec,
+        QuestionType::ContinousNumeric {
+            bounds: Some((0.0, 60.0)),
+            ..
+        }
+    ));
+    assert_eq!(form.text(&form.groups[1].title), "Last words");
+    assert_eq!(form.text(&questions[2].title), "Anything else?");
+
+    let inline = load_form(
+        &write(
+            "inline",
+            &[(
+                "form.yml",
+                "title: Survey
+description: ''
+groups:
+  - title: Finally
+    description: ''
+    questions:
+      - title: Anything else?
+        spec: { type: text, is_long: true }
+",
+            )],
+        ),
+        &Limits::default(),
+    )
+    .unwrap();
+    let used = load("same", "  - template: wrap_up\n").unwrap();
+    assert_eq!(used.version(), inline.version());
+}
+
+#[test]
+fn included_files_use_the_form_templates() {
+    let path = write(
+        "include",
+        &[
+            (
+                "form.yml",
+                &format!(
+                    "title: Survey\ndescription: ''\n{TEMPLATES}groups:\n  - include: shared.yml\n"
+                ),
+            ),
+            (
+                "shared.yml",
+                "title: Shared\ndescription: ''\nquestions:\n  - template: age\n",
+            ),
+        ],
+    );
+    let form = load_form(&path, &Limits::default()).unwrap();
+    assert_eq!(form.groups[0].questions.len(), 1);
+}
+
+#[test]
+fn mismatched_references_are_errors() {
+    let message = |groups: &str| load("errors", groups).unwrap_err().to_string();
+    let set = |question: &str| {
+        format!("  - title: You\n    description: ''\n    questions:\n      - {question}\n")
+    };
+
+    let err = message(&set("template: height"));
+    assert!(
+        err.contains("groups[0].questions[0]: unknown template 'height'"),
+        "{err}"
+    );
+    let err = message(&set("{ template: comment, spec: { answers: [a, b] } }"));
+    assert!(
+        err.contains("spec.answers: text questions have no such field"),
+        "{err}"
+    );
+    let err = message(&set("{ template: comment, spec: { type: single_choice } }"));
+    assert!(err.contains("cannot change the type"), "{err}");
+    let err = message(&set("template: wrap_up"));
+    assert!(err.contains("is a question set, not a question"), "{err}");
+    let err = message("  - template: age\n");
+    assert!(
+        err.contains("groups[0]: template 'age' is a question, not a question set"),
+        "{err}"
+    );
+}
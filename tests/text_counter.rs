@@ -0,0 +1,51 @@
+//! Text with a `max_length` counts its characters, like the browser does,
+//! and warns from `warn_at` of the limit on.
+
+mod common;
+
+use common::{question, render_question};
+use std::collections::HashMap;
+use umfragetool_rust::*;
+
+#[test]
+fn text_counter_warns_near_the_limit() {
+    let spec = |warn_at| QuestionType::Text {
+        is_long: true,
+        pattern: None,
+        max_length: Some(50),
+        warn_at,
+    };
+    let html = render_question(spec(None));
+    assert!(
+        html.contains(r#"aria-describedby="i-0-0-counter""#),
+        "{html}"
+    );
+    assert!(html.contains(r#" maxlength="50""#));
+    assert!(
+        html.contains("c.classList.toggle('near-limit',n>=45)"),
+        "{html}"
+    );
+    assert!(html.contains(r#"<small class="counter" id="i-0-0-counter">0/50</small>"#));
+    let html = render_question(spec(Some(0.5)));
+    assert!(html.contains("n>=25"), "{html}");
+
+    let question = question(spec(None));
+    let ctx = RenderContext {
+        values: HashMap::from([("i-0-0".to_string(), vec!["x".repeat(46)])]),
+        no_js: true,
+        ..Default::default()
+    };
+    let html = question.render("i-0-0".to_string(), &ctx);
+    assert!(html.contains(r#"<small class="counter near-limit" id="i-0-0-counter">46/50</small>"#));
+    assert!(!html.contains("oninput"));
+
+    // The server counts like the browser: the emoji is two of the 50.
+    let text = format!("{}😀", "x".repeat(48));
+    assert!(spec(None)
+        .validate_answer(std::slice::from_ref(&text))
+        .is_ok());
+    let err = spec(None)
+        .validate_answer(&[format!("{text}x")])
+        .unwrap_err();
+    assert_eq!(err, "please use at most 50 characters");
+}
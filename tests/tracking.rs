@@ -0,0 +1,80 @@
+//! `track_params` carries allowlisted URL parameters from the form link into
+//! the stored submission, the stats and the exports.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{as_admin, get, send, with_admin};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::{demo_form, Form};
+
+fn state(name: &str) -> Arc<AppState> {
+    let form = Form {
+        track_params: vec!["source".to_string(), "utm_campaign".to_string()],
+        ..demo_form()
+    };
+    let state = with_admin(common::state(&format!("tracking-{name}"), [("demo", form)]));
+    Arc::new(state)
+}
+
+async fn submit(state: &Arc<AppState>, body: &str) {
+    let body = format!("i-0-0=Because&{body}");
+    assert_eq!(common::submit(state, "demo", &body).await.0, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn allowlisted_parameters_are_carried_through() {
+    let state = state("carried");
+    let uri = "/form/demo?source=news%22letter&utm_medium=mail&utm_campaign=".to_string()
+        + &"x".repeat(300);
+    let (_, html) = send(&state, get(&uri)).await;
+    assert!(
+        html.contains(r#"name="_track.source" value="news&quot;letter""#),
+        "{html}"
+    );
+    assert!(!html.contains("utm_medium"));
+    assert!(html.contains(&format!(
+        r#"name="_track.utm_campaign" value="{}""#,
+        "x".repeat(100)
+    )));
+
+    // Posted parameters outside the allowlist are dropped as well.
+    submit(&state, "_track.source=newsletter&_track.utm_medium=mail").await;
+    assert_eq!(
+        state.store.list("demo")[0].tracking,
+        BTreeMap::from([("source".to_string(), "newsletter".to_string())])
+    );
+}
+
+#[tokio::test]
+async fn stats_count_submissions_per_value() {
+    let state = state("stats");
+    submit(
+        &state,
+        "_track.source=newsletter&_track.utm_campaign=spring",
+    )
+    .await;
+    submit(&state, "_track.source=newsletter").await;
+    submit(&state, "_track.source=intranet").await;
+    submit(&state, "").await;
+
+    let request = as_admin(get("/form/demo/summary.json"));
+    let (_, body) = send(&state, request).await;
+    let summary: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        summary["tracking"],
+        serde_json::json!({
+            "source": [
+                { "value": "newsletter", "count": 2 },
+                { "value": "intranet", "count": 1 },
+            ],
+            "utm_campaign": [{ "value": "spring", "count": 1 }],
+        })
+    );
+    assert_eq!(
+        summary["sources"],
+        serde_json::json!([{ "value": "web", "count": 4 }])
+    );
+}
@@ -0,0 +1,34 @@
+//! A `unit` is shown before or after a number input, and never posted.
+
+mod common;
+
+use common::render_question;
+use umfragetool_rust::*;
+
+#[test]
+fn unit_goes_on_its_side() {
+    let spec = |unit: Option<&str>, unit_position| QuestionType::ContinousNumeric {
+        step: None,
+        bounds: None,
+        unit: unit.map(str::to_string),
+        unit_position,
+    };
+    let without = render_question(spec(None, UnitPosition::Prefix));
+    assert!(!without.contains(r#"class="unit""#), "{without}");
+
+    let unit = r#"<span class="unit">&lt;€&gt;</span>"#;
+    let suffix = render_question(spec(Some("<€>"), UnitPosition::Suffix));
+    assert!(
+        suffix.contains(&format!(r#"{ROOT_PREFIX}-0-0">{unit}"#)),
+        "{suffix}"
+    );
+    assert_eq!(suffix.matches(r#"class="unit""#).count(), 1);
+    let prefix = render_question(spec(Some("<€>"), UnitPosition::Prefix));
+    assert!(prefix.contains(&format!(r#"{unit}<input"#)), "{prefix}");
+    assert_eq!(prefix.matches(r#"class="unit""#).count(), 1);
+
+    // The unit is only shown: the posted value is the number alone.
+    assert!(spec(Some("€"), UnitPosition::Suffix)
+        .validate_answer(&["5".to_string()])
+        .is_ok());
+}
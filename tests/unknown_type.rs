@@ -0,0 +1,51 @@
+//! Question types this build does not know load as placeholders instead of
+//! failing the whole form.
+
+use umfragetool_rust::*;
+
+const FORM: &str = r#"
+title: Newer form
+description: ""
+groups:
+  - title: Mixed
+    description: ""
+    questions:
+      - title: Name
+        spec: { type: text, is_long: false }
+      - title: Grid
+        spec: { type: matrix, rows: [A, B], columns: [1, 2, 3] }
+      - title: Lunch
+        spec: { type: single_choice, custom_answer: false, answers: [Pizza, Ravioli] }
+"#;
+
+#[test]
+fn other_questions_still_load_and_render() {
+    let form: Form = serde_yaml::from_str(FORM).unwrap();
+    assert!(matches!(
+        &form.groups[0].questions[1].spec,
+        QuestionType::Unknown { raw_type } if raw_type == "matrix"
+    ));
+    assert!(form.validate(&Limits::default()).is_ok());
+    assert_eq!(
+        form.warnings(),
+        ["question i-0-1 (Grid) has the unsupported type 'matrix' and is shown as a placeholder"]
+    );
+
+    let html = form.render(ROOT_PREFIX.to_string(), &RenderContext::default());
+    assert!(
+        html.contains(r#"<input type="text" name="i-0-0" id="i-0-0""#),
+        "{html}"
+    );
+    assert!(html.contains(r#"<p class="unsupported">(unsupported question type: matrix)</p>"#));
+    assert!(html.contains(r#"value="Ravioli""#), "{html}");
+}
+
+#[test]
+fn other_questions_are_still_answered() {
+    let form: Form = serde_yaml::from_str(FORM).unwrap();
+    let answers = std::collections::HashMap::from([
+        ("i-0-0".to_string(), vec!["Ada".to_string()]),
+        ("i-0-2".to_string(), vec!["Pizza".to_string()]),
+    ]);
+    assert!(form.validate_submission(&answers).is_ok());
+}
@@ -0,0 +1,81 @@
+//! Load-time checks of form definitions.
+
+use std::collections::BTreeMap;
+use umfragetool_rust::*;
+
+fn form_with(spec: QuestionType) -> Form {
+    Form {
+        title: "Form".into(),
+        groups: vec![QuestionSet {
+            title: "Set".into(),
+            description: Default::default(),
+            questions: vec![Question {
+                title: "How many?".into(),
+                key: None,
+                help: None,
+                required: false,
+                correct: None,
+                points: None,
+                explanation: None,
+                show_if: None,
+                spec,
+            }],
+            show_if: None,
+            collapsible: false,
+        }],
+        ..Default::default()
+    }
+}
+
+fn discrete(min: i8, max: i8) -> QuestionType {
+    QuestionType::DiscreteNumeric {
+        bounds: (min, max),
+        num_descriptions: BTreeMap::new(),
+        na_option: None,
+        columns: None,
+    }
+}
+
+#[test]
+fn wide_discrete_range_is_rejected() {
+    let err = form_with(discrete(-100, 99))
+        .validate(&Limits::default())
+        .unwrap_err();
+    assert_eq!(err.problems.len(), 1);
+    assert!(err.problems[0].contains("How many?"), "{err}");
+    assert!(err.problems[0].contains("200 options"), "{err}");
+}
+
+#[test]
+fn discrete_span_limit_is_configurable() {
+    let limits = Limits {
+        max_discrete_span: 300,
+        ..Limits::default()
+    };
+    assert!(form_with(discrete(-128, 127)).validate(&limits).is_ok());
+    assert!(form_with(discrete(1, 20))
+        .validate(&Limits::default())
+        .is_ok());
+}
+
+#[test]
+fn too_many_questions_and_sets_are_counted_in_the_error() {
+    let mut form = form_with(discrete(1, 5));
+    form.groups = (0..4)
+        .map(|_| form_with(discrete(1, 5)).groups.remove(0))
+        .collect();
+    let limits = Limits {
+        max_groups: 3,
+        max_questions: 2,
+        ..Limits::default()
+    };
+    let err = form.validate(&limits).unwrap_err();
+    assert_eq!(
+        err.problems,
+        [
+            "form has 4 question sets, the limit is 3 (see --max-groups)",
+            "form has 4 questions, the limit is 2 (see --max-questions)",
+        ]
+    );
+    assert!(form.validate(&Limits::default()).is_ok());
+}
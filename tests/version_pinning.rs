@@ -0,0 +1,57 @@
+//! Submissions record the version of the form they answered, and the store
+//! keeps the structure of every version it has seen.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::store_path;
+use std::sync::Arc;
+use umfragetool_rust::demo_form;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::store::SubmissionStore;
+
+async fn submit(state: &Arc<AppState>) {
+    assert_eq!(
+        common::submit(state, "demo", "i-0-0=Because").await.0,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn edited_forms_start_a_new_version() {
+    let state = Arc::new(common::state("version-pinning", [("demo", demo_form())]));
+    let old = demo_form().version();
+    submit(&state).await;
+    submit(&state).await;
+
+    let mut edited = demo_form();
+    edited.groups[0].questions[0].title = "Why, really?".into();
+    let new = edited.version();
+    assert_ne!(new, old);
+    state
+        .forms
+        .write()
+        .unwrap()
+        .insert("demo".to_string(), Arc::new(edited));
+    submit(&state).await;
+
+    let pinned: Vec<_> = state
+        .store
+        .list("demo")
+        .into_iter()
+        .map(|submission| submission.form_version.unwrap())
+        .collect();
+    assert_eq!(pinned, [old.clone(), old.clone(), new.clone()]);
+
+    // Each version is kept once, with the titles it had, across reloads.
+    let reopened = SubmissionStore::open(&store_path("version-pinning")).unwrap();
+    let versions = reopened.versions("demo");
+    assert_eq!(versions.len(), 2);
+    let title = |version: &str| {
+        let version = versions.iter().find(|v| v.version == version).unwrap();
+        version.questions[0].title.clone()
+    };
+    assert_eq!(title(&old), "Why would you do this?");
+    assert_eq!(title(&new), "Why, really?");
+    assert_eq!(versions[0].questions[0].kind, "text");
+}
@@ -0,0 +1,64 @@
+//! `/form/:id/view` shows forms and stored answers without letting them change.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{as_admin, get, send, submit, with_admin};
+use scraper::{Html, Selector};
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+
+fn state(name: &str) -> Arc<AppState> {
+    Arc::new(with_admin(common::state(
+        &format!("view-{name}"),
+        [("demo", umfragetool_rust::demo_form())],
+    )))
+}
+
+fn assert_read_only(html: &str) {
+    let document = Html::parse_document(html);
+    let controls = Selector::parse("input, textarea, select").unwrap();
+    let mut count = 0;
+    for control in document.select(&controls) {
+        count += 1;
+        assert!(
+            control.value().attr("disabled").is_some(),
+            "{}",
+            control.html()
+        );
+        assert_ne!(control.value().attr("type"), Some("submit"));
+        assert_ne!(control.value().attr("type"), Some("hidden"));
+    }
+    assert!(count > 0);
+    assert!(document
+        .select(&Selector::parse("button, [autofocus]").unwrap())
+        .next()
+        .is_none());
+}
+
+#[tokio::test]
+async fn view_disables_every_control() {
+    let (status, html) = send(&state("blank"), get("/form/demo/view")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_read_only(&html);
+}
+
+#[tokio::test]
+async fn admins_view_stored_answers() {
+    let state = state("stored");
+    let (status, _) = submit(&state, "demo", "i-0-0=Because&i-0-2=7").await;
+    assert_eq!(status, StatusCode::OK);
+    let id = state.store.list("demo")[0].id.clone();
+
+    let uri = format!("/form/demo/view?response={id}");
+    let (status, _) = send(&state, get(&uri)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&state, as_admin(get("/form/demo/view?response=nope"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, html) = send(&state, as_admin(get(&uri))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_read_only(&html);
+    assert!(html.contains(">Because</textarea>"));
+    assert!(html.contains(r#"value="7" checked"#));
+}
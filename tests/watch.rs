@@ -0,0 +1,56 @@
+//! With `--watch`, edited form files are served anew without a restart.
+
+mod common;
+
+use common::{get, send};
+use std::collections::HashMap;
+use std::sync::Arc;
+use umfragetool_rust::server::AppState;
+use umfragetool_rust::store::SubmissionStore;
+use umfragetool_rust::watch::FormWatcher;
+use umfragetool_rust::{load_form, Limits};
+
+fn definition(title: &str) -> String {
+    format!(
+        "title: {title}\ndescription: ''\ngroups:\n  - title: Set\n    description: ''\n    questions:\n      - title: Why?\n        spec: {{ type: text, is_long: false }}\n"
+    )
+}
+
+#[tokio::test]
+async fn edited_files_are_served_anew() {
+    let dir = std::env::temp_dir().join("umfragetool-watch");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("survey.yml");
+    std::fs::write(&path, definition("First draft")).unwrap();
+    let form = load_form(&path, &Limits::default()).unwrap();
+    let state = Arc::new(AppState::new(
+        HashMap::from([("survey".to_string(), form)]),
+        SubmissionStore::open(&dir.join("submissions.jsonl")).unwrap(),
+    ));
+    let mut watcher = FormWatcher::new(vec![("survey".to_string(), path.clone())]);
+    let mut reloaded = Vec::new();
+    let mut poll = |watcher: &mut FormWatcher| {
+        watcher.poll(&state, |id, form| {
+            reloaded.push((id.to_string(), form.text(&form.title).to_string()))
+        })
+    };
+
+    poll(&mut watcher);
+    std::fs::write(&path, definition("Second draft")).unwrap();
+    poll(&mut watcher);
+    // Unchanged files are not loaded again.
+    poll(&mut watcher);
+    assert_eq!(
+        reloaded,
+        [("survey".to_string(), "Second draft".to_string())]
+    );
+
+    let (_, html) = send(&state, get("/form/survey")).await;
+    assert!(html.contains("Second draft"), "{html}");
+
+    std::fs::write(&path, "title: [unclosed").unwrap();
+    watcher.poll(&state, |_, _| panic!("broken files are not served"));
+    let form = state.form("survey").unwrap();
+    assert_eq!(form.text(&form.title), "Second draft");
+}
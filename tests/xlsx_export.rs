@@ -0,0 +1,274 @@
+//! The spreadsheet export, read back: typed cells on "Responses", the
+//! aggregates on "Summary".
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{accept_json, as_admin, get, post, send, with_admin};
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::sync::Arc;
+use tower::ServiceExt;
+use umfragetool_rust::server::{self, AppState};
+use umfragetool_rust::store::ClientMeta;
+use umfragetool_rust::{demo_form, ClientIp, Form, Privacy};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    Text(String),
+    Number(f64),
+    /// Seconds since the epoch.
+    Date(i64),
+}
+
+/// A sheet of the workbook, by name, as rows of cells keyed by column letter.
+/// Panes frozen below the header row are checked on the way.
+fn sheet(xlsx: &[u8], name: &str) -> Vec<HashMap<String, Cell>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(xlsx)).unwrap();
+    let mut file = |path: &str| {
+        let mut content = String::new();
+        archive
+            .by_name(path)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    };
+    let sheets: Vec<String> = Regex::new(r#"<sheet name="([^"]+)""#)
+        .unwrap()
+        .captures_iter(&file("xl/workbook.xml"))
+        .map(|captures| captures[1].to_string())
+        .collect();
+    let index = sheets.iter().position(|sheet| sheet == name).unwrap() + 1;
+    let strings: Vec<String> = Regex::new(r"<si><t[^>]*>(.*?)</t></si>")
+        .unwrap()
+        .captures_iter(&file("xl/sharedStrings.xml"))
+        .map(|captures| captures[1].to_string())
+        .collect();
+    // Styles with a number format of their own are the dates.
+    let dates: Vec<bool> = Regex::new(r#"<xf numFmtId="(\d+)"[^>]*xfId"#)
+        .unwrap()
+        .captures_iter(&file("xl/styles.xml"))
+        .map(|captures| &captures[1] != "0")
+        .collect();
+    let xml = file(&format!("xl/worksheets/sheet{index}.xml"));
+    assert!(xml
+        .contains(r#"<pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/>"#));
+    let cell =
+        Regex::new(r#"<c r="([A-Z]+)\d+"(?: s="(\d+)")?(?: t="(\w+)")?>(?:<v>(.*?)</v>)?</c>"#)
+            .unwrap();
+    Regex::new(r"<row [^>]*>(.*?)</row>")
+        .unwrap()
+        .captures_iter(&xml)
+        .map(|row| {
+            cell.captures_iter(&row[1])
+                .filter_map(|c| {
+                    let value = c.get(4)?.as_str();
+                    let style: usize = c.get(2).map_or(0, |s| s.as_str().parse().unwrap());
+                    let cell = match c.get(3).map(|t| t.as_str()) {
+                        Some("s") => Cell::Text(strings[value.parse::<usize>().unwrap()].clone()),
+                        _ if dates[style] => {
+                            let days: f64 = value.parse().unwrap();
+                            Cell::Date(((days - 25569.0) * 86400.0).round() as i64)
+                        }
+                        _ => Cell::Number(value.parse().unwrap()),
+                    };
+                    Some((c[1].to_string(), cell))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn text(value: &str) -> Cell {
+    Cell::Text(value.to_string())
+}
+
+async fn state(name: &str, form: Form) -> Arc<AppState> {
+    Arc::new(with_admin(common::state(
+        &format!("xlsx-{name}"),
+        [("demo", form)],
+    )))
+}
+
+/// Submits `body` and returns the JSON response.
+async fn submit(state: &Arc<AppState>, body: &str) -> serde_json::Value {
+    let (status, body) = send(state, accept_json(post("/submit/demo", body))).await;
+    assert_eq!(status, StatusCode::OK);
+    serde_json::from_str(&body).unwrap()
+}
+
+async fn export(state: &Arc<AppState>) -> Vec<u8> {
+    let request = as_admin(get("/form/demo/results.xlsx"));
+    let response = server::router(state.clone())
+        .oneshot(request)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    hyper::body::to_bytes(response.into_body())
+        .await
+        .unwrap()
+        .to_vec()
+}
+
+/// The rows below the header, keyed by the header of their column.
+fn by_header(sheet: &[HashMap<String, Cell>]) -> Vec<HashMap<String, Cell>> {
+    let headers: HashMap<&String, String> = sheet[0]
+        .iter()
+        .map(|(col, header)| match header {
+            Cell::Text(header) => (col, header.clone()),
+            other => panic!("header {other:?}"),
+        })
+        .collect();
+    sheet[1..]
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|(col, cell)| (headers[col].clone(), cell.clone()))
+                .collect()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn cells_keep_their_types() {
+    let state = state("types", demo_form()).await;
+    submit(&state, "i-0-0=Grüße&i-0-1=1.5&i-0-4=Pizza&i-0-4=MAOAM").await;
+    submit(&state, "i-0-0=Why not&i-0-2=3").await;
+    let xlsx = export(&state).await;
+
+    let responses = sheet(&xlsx, "Responses");
+    assert_eq!(responses[0]["A"], text("Submission"));
+    assert_eq!(responses[0]["B"], text("Submitted at"));
+    let responses = by_header(&responses);
+    assert_eq!(responses.len(), 2);
+    let stored = state.store.list("demo");
+    for (row, submission) in responses.iter().zip(&stored) {
+        assert_eq!(row["Submission"], text(&submission.id));
+        assert_eq!(
+            row["Submitted at"],
+            Cell::Date(submission.submitted_at.unwrap().timestamp())
+        );
+        assert!(!row.contains_key("Updated at"));
+        // Forms keep nothing of the address unless they say so.
+        assert!(!row.keys().any(|header| header.starts_with("Client")));
+    }
+    let first = &responses[0];
+    assert_eq!(first["Why would you do this?"], text("Grüße"));
+    assert_eq!(first["How much is the fish?"], Cell::Number(1.5));
+    assert_eq!(first["What do you want?"], text("Pizza; MAOAM"));
+    assert_eq!(responses[1]["What do you want?"], Cell::Number(3.0));
+    assert!(!responses[1].contains_key("How much is the fish?"));
+
+    let summary = sheet(&xlsx, "Summary");
+    assert_eq!(summary[0]["A"], text("Question"));
+    assert_eq!(summary.len(), demo_form().questions().count() + 1);
+    assert_eq!(summary[1]["B"], Cell::Number(2.0));
+    assert_eq!(summary[2]["C"], Cell::Number(1.5));
+}
+
+#[tokio::test]
+async fn edits_show_when_they_were_made() {
+    let state = state(
+        "edits",
+        Form {
+            editable: true,
+            ..demo_form()
+        },
+    )
+    .await;
+    let submitted = submit(&state, "i-0-0=Because").await;
+    let token = submitted["edit_url"]
+        .as_str()
+        .unwrap()
+        .rsplit('/')
+        .next()
+        .unwrap();
+    submit(&state, &format!("i-0-0=Changed&_edit={token}")).await;
+    let xlsx = export(&state).await;
+
+    let responses = by_header(&sheet(&xlsx, "Responses"));
+    let stored = &state.store.list("demo")[0];
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0]["Why would you do this?"], text("Changed"));
+    assert_eq!(
+        responses[0]["Updated at"],
+        Cell::Date(stored.updated_at.unwrap().timestamp())
+    );
+}
+
+#[tokio::test]
+async fn client_column_is_named_after_what_is_kept() {
+    for (client_ip, header) in [
+        (ClientIp::Hashed, "Client IP hash"),
+        (ClientIp::Truncated, "Client IP prefix"),
+    ] {
+        let state = state(
+            header,
+            Form {
+                privacy: Privacy { client_ip },
+                ..demo_form()
+            },
+        )
+        .await;
+        submit(&state, "i-0-0=Because").await;
+        let xlsx = export(&state).await;
+        let responses = by_header(&sheet(&xlsx, "Responses"));
+        let (ClientMeta::IpHash(stored) | ClientMeta::IpPrefix(stored)) =
+            state.store.list("demo")[0].client.clone().unwrap();
+        assert_eq!(responses[0][header], text(&stored));
+        assert_ne!(responses[0][header], text("127.0.0.1"));
+    }
+}
+
+#[tokio::test]
+async fn durations_are_numbers() {
+    let state = state("duration", demo_form()).await;
+    let (_, html) = send(&state, get("/form/demo")).await;
+    let started = &Regex::new(r#"name="_started" value="([^"]+)""#)
+        .unwrap()
+        .captures(&html)
+        .unwrap()[1];
+    submit(&state, &format!("i-0-0=Because&_started={started}")).await;
+    submit(&state, "i-0-0=Untimed").await;
+    let xlsx = export(&state).await;
+
+    let responses = by_header(&sheet(&xlsx, "Responses"));
+    let secs = state.store.list("demo")[0].duration_secs.unwrap();
+    assert_eq!(responses[0]["Duration (s)"], Cell::Number(secs as f64));
+    assert!(!responses[1].contains_key("Duration (s)"));
+}
+
+#[tokio::test]
+async fn tracked_parameters_get_a_column_each() {
+    let state = state(
+        "tracking",
+        Form {
+            track_params: vec!["source".to_string(), "utm_campaign".to_string()],
+            ..demo_form()
+        },
+    )
+    .await;
+    submit(&state, "i-0-0=Because&_track.source=newsletter").await;
+    let xlsx = export(&state).await;
+
+    let responses = sheet(&xlsx, "Responses");
+    let headers: Vec<&Cell> = ["D", "E", "F", "G"]
+        .iter()
+        .map(|col| &responses[0][*col])
+        .collect();
+    assert_eq!(
+        headers,
+        [
+            &text("Duration (s)"),
+            &text("Source"),
+            &text("source"),
+            &text("utm_campaign")
+        ]
+    );
+    let responses = by_header(&responses);
+    assert_eq!(responses[0]["Source"], text("web"));
+    assert_eq!(responses[0]["source"], text("newsletter"));
+    assert!(!responses[0].contains_key("utm_campaign"));
+}